@@ -1,3 +1,11 @@
 fn main() {
-    tauri_build::build()
-}
\ No newline at end of file
+    // The inline `bridge` plugin is the only IPC surface remote tabs get
+    // (see src/telemetry/channel.rs); this generates `bridge:allow-ingest`.
+    tauri_build::try_build(
+        tauri_build::Attributes::new().plugin(
+            "bridge",
+            tauri_build::InlinedPlugin::new().commands(&["ingest"]),
+        ),
+    )
+    .expect("failed to run tauri-build");
+}
@@ -0,0 +1,753 @@
+//! Adblock Plus / EasyList filter syntax: parsing and matching.
+//!
+//! Supported: network rules with `||`, `|` and `^` anchors, `*` wildcards,
+//! `@@` exceptions and the options `third-party`/`first-party`, `domain=`,
+//! resource types (negatable with `~`), `match-case`, `important`,
+//! `elemhide` and `generichide`; element-hiding rules `##` and `#@#`.
+//! Rules using anything else (regular expressions, `$redirect`, `$csp`,
+//! procedural or scriptlet cosmetics, …) are skipped and counted, so a list
+//! never blocks more than we understand.
+//!
+//! [`FilterSet::check`] matches one request and is what Rust applies itself
+//! (navigations, and every request on WebView2). [`content_rules`] turns the
+//! set into WebKit's compiled filter format, and [`Cosmetics`] resolves the
+//! element hiding for one host.
+
+mod content_rules;
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::OnceLock;
+
+use serde::Serialize;
+use url::Url;
+
+pub use content_rules::{RuleList, COSMETIC_LIST, NETWORK_LIST};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ResourceType {
+    Document,
+    Subdocument,
+    Script,
+    Image,
+    Stylesheet,
+    Xmlhttprequest,
+    Font,
+    Media,
+    Object,
+    Ping,
+    Websocket,
+    Popup,
+    Other,
+}
+
+impl ResourceType {
+    const ALL: [ResourceType; 13] = [
+        ResourceType::Document,
+        ResourceType::Subdocument,
+        ResourceType::Script,
+        ResourceType::Image,
+        ResourceType::Stylesheet,
+        ResourceType::Xmlhttprequest,
+        ResourceType::Font,
+        ResourceType::Media,
+        ResourceType::Object,
+        ResourceType::Ping,
+        ResourceType::Websocket,
+        ResourceType::Popup,
+        ResourceType::Other,
+    ];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "document" | "doc" => ResourceType::Document,
+            "subdocument" | "frame" => ResourceType::Subdocument,
+            "script" => ResourceType::Script,
+            "image" => ResourceType::Image,
+            "stylesheet" | "css" => ResourceType::Stylesheet,
+            "xmlhttprequest" | "xhr" => ResourceType::Xmlhttprequest,
+            "font" => ResourceType::Font,
+            "media" => ResourceType::Media,
+            "object" => ResourceType::Object,
+            "ping" | "beacon" => ResourceType::Ping,
+            "websocket" => ResourceType::Websocket,
+            "popup" => ResourceType::Popup,
+            "other" => ResourceType::Other,
+            _ => return None,
+        })
+    }
+
+    fn bit(self) -> u16 {
+        1 << (self as u16)
+    }
+}
+
+/// Rules without type options apply to everything except documents and popups.
+const DEFAULT_TYPES: u16 = !(1 << ResourceType::Document as u16 | 1 << ResourceType::Popup as u16);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Literal(String),
+    /// `*`
+    Any,
+    /// `^`: a character that can't be part of a host or path segment, or the end.
+    Separator,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Anchor {
+    None,
+    /// `|` at the start: the URL starts here.
+    Start,
+    /// `||`: the start of the host or of one of its labels.
+    Domain,
+}
+
+#[derive(Debug, Clone)]
+pub struct NetworkRule {
+    /// The rule as written in the list.
+    pub text: String,
+    pub exception: bool,
+    anchor: Anchor,
+    tokens: Vec<Token>,
+    end_anchor: bool,
+    match_case: bool,
+    important: bool,
+    types: u16,
+    third_party: Option<bool>,
+    domains: Vec<String>,
+    not_domains: Vec<String>,
+    /// `@@…$elemhide`: no element hiding on matching pages.
+    elemhide: bool,
+    /// `@@…$generichide`: no generic element hiding on matching pages.
+    generichide: bool,
+}
+
+#[derive(Debug, Clone)]
+struct CosmeticRule {
+    selector: String,
+    domains: Vec<String>,
+    not_domains: Vec<String>,
+    exception: bool,
+}
+
+enum Parsed {
+    Network(NetworkRule),
+    Cosmetic(CosmeticRule),
+    Skip,
+    Unsupported,
+}
+
+/// Procedural and extended selectors the page's CSS engine can't apply.
+const UNSUPPORTED_SELECTORS: &[&str] = &[
+    ":-abp-",
+    ":has-text(",
+    ":xpath(",
+    ":upward(",
+    ":remove(",
+    ":style(",
+    ":matches-",
+    ":min-text-length(",
+    ":watch-attr(",
+    ":others(",
+    ":if(",
+    ":if-not(",
+    ":contains(",
+];
+
+fn split_domains(list: &str, separator: char) -> (Vec<String>, Vec<String>) {
+    let mut domains = Vec::new();
+    let mut not_domains = Vec::new();
+    for domain in list.split(separator).map(str::trim).filter(|d| !d.is_empty()) {
+        match domain.strip_prefix('~') {
+            Some(d) => not_domains.push(d.to_ascii_lowercase()),
+            None => domains.push(domain.to_ascii_lowercase()),
+        }
+    }
+    (domains, not_domains)
+}
+
+fn parse_line(line: &str) -> Parsed {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('!') || line.starts_with('[') {
+        return Parsed::Skip;
+    }
+    for (marker, exception) in [("#@#", true), ("##", false)] {
+        if let Some(at) = line.find(marker) {
+            let selector = line[at + marker.len()..].trim();
+            // Braces would let a selector escape its rule in the generated CSS.
+            if selector.is_empty()
+                || selector.contains(['{', '}'])
+                || selector.starts_with('+')
+                || selector.starts_with('^')
+                || UNSUPPORTED_SELECTORS.iter().any(|s| selector.contains(s))
+            {
+                return Parsed::Unsupported;
+            }
+            let (domains, not_domains) = split_domains(&line[..at], ',');
+            return Parsed::Cosmetic(CosmeticRule {
+                selector: selector.to_string(),
+                domains,
+                not_domains,
+                exception,
+            });
+        }
+    }
+    // Other cosmetic and HTML-filtering syntaxes: #?#, #$#, #@$#, $$, …
+    if line.contains("#?#") || line.contains("#$#") || line.contains("#@$#") || line.contains("#@?#") || line.contains("$$") {
+        return Parsed::Unsupported;
+    }
+    match parse_network(line) {
+        Some(rule) => Parsed::Network(rule),
+        None => Parsed::Unsupported,
+    }
+}
+
+fn parse_network(line: &str) -> Option<NetworkRule> {
+    let (exception, body) = match line.strip_prefix("@@") {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    // Regular-expression rules.
+    if body.starts_with('/') && body.len() > 1 && body[1..].contains('/') && !body.starts_with("//") {
+        let end = body.rfind('/')?;
+        if end > 0 && (end == body.len() - 1 || body[end + 1..].starts_with('$')) {
+            return None;
+        }
+    }
+    let (pattern, options) = match body.rfind('$') {
+        Some(at) if !body[at + 1..].contains('/') => (&body[..at], Some(&body[at + 1..])),
+        _ => (body, None),
+    };
+
+    let mut rule = NetworkRule {
+        text: line.to_string(),
+        exception,
+        anchor: Anchor::None,
+        tokens: Vec::new(),
+        end_anchor: false,
+        match_case: false,
+        important: false,
+        types: DEFAULT_TYPES,
+        third_party: None,
+        domains: Vec::new(),
+        not_domains: Vec::new(),
+        elemhide: false,
+        generichide: false,
+    };
+
+    if let Some(options) = options {
+        let mut include: u16 = 0;
+        let mut exclude: u16 = 0;
+        for option in options.split(',').map(str::trim).filter(|o| !o.is_empty()) {
+            let (negated, name) = match option.strip_prefix('~') {
+                Some(name) => (true, name),
+                None => (false, option),
+            };
+            if let Some(list) = name.strip_prefix("domain=") {
+                let (domains, not_domains) = split_domains(list, '|');
+                rule.domains = domains;
+                rule.not_domains = not_domains;
+                continue;
+            }
+            match name {
+                "third-party" | "3p" => rule.third_party = Some(!negated),
+                "first-party" | "1p" => rule.third_party = Some(negated),
+                "match-case" => rule.match_case = true,
+                "important" => rule.important = true,
+                "all" => include = u16::MAX,
+                "elemhide" | "ehide" if exception => rule.elemhide = true,
+                "generichide" | "ghide" if exception => rule.generichide = true,
+                _ => match ResourceType::parse(name) {
+                    Some(ty) if negated => exclude |= ty.bit(),
+                    Some(ty) => include |= ty.bit(),
+                    None => return None,
+                },
+            }
+        }
+        if include != 0 {
+            rule.types = include;
+        }
+        rule.types &= !exclude;
+        // Cosmetic-only exceptions don't apply to requests.
+        if rule.elemhide || rule.generichide {
+            rule.types = if include != 0 { rule.types } else { 0 };
+        }
+    }
+
+    let mut pattern = pattern;
+    if let Some(rest) = pattern.strip_prefix("||") {
+        rule.anchor = Anchor::Domain;
+        pattern = rest;
+    } else if let Some(rest) = pattern.strip_prefix('|') {
+        rule.anchor = Anchor::Start;
+        pattern = rest;
+    }
+    if let Some(rest) = pattern.strip_suffix('|') {
+        rule.end_anchor = true;
+        pattern = rest;
+    }
+    let pattern = if rule.match_case { pattern.to_string() } else { pattern.to_ascii_lowercase() };
+    let mut literal = String::new();
+    for c in pattern.chars() {
+        let token = match c {
+            '*' => Token::Any,
+            '^' => Token::Separator,
+            _ => {
+                literal.push(c);
+                continue;
+            }
+        };
+        if !literal.is_empty() {
+            rule.tokens.push(Token::Literal(std::mem::take(&mut literal)));
+        }
+        if !(token == Token::Any && rule.tokens.last() == Some(&Token::Any)) {
+            rule.tokens.push(token);
+        }
+    }
+    if !literal.is_empty() {
+        rule.tokens.push(Token::Literal(literal));
+    }
+    // A bare `*` or empty pattern only makes sense narrowed by options.
+    if rule.tokens.iter().all(|t| *t == Token::Any) && rule.domains.is_empty() && rule.types == DEFAULT_TYPES {
+        return None;
+    }
+    Some(rule)
+}
+
+fn is_separator(c: u8) -> bool {
+    !(c.is_ascii_alphanumeric() || matches!(c, b'_' | b'-' | b'.' | b'%'))
+}
+
+/// Does `tokens` match `url` starting at byte `at`?
+fn match_from(tokens: &[Token], url: &[u8], at: usize, end_anchor: bool) -> bool {
+    match tokens.split_first() {
+        None => !end_anchor || at == url.len(),
+        Some((Token::Literal(lit), rest)) => {
+            url[at..].starts_with(lit.as_bytes()) && match_from(rest, url, at + lit.len(), end_anchor)
+        }
+        Some((Token::Separator, rest)) => {
+            if at == url.len() {
+                // `^` also matches the end of the address.
+                rest.iter().all(|t| *t == Token::Any || *t == Token::Separator)
+            } else {
+                is_separator(url[at]) && match_from(rest, url, at + 1, end_anchor)
+            }
+        }
+        Some((Token::Any, rest)) => {
+            if rest.is_empty() {
+                return true;
+            }
+            (at..=url.len()).any(|i| match_from(rest, url, i, end_anchor))
+        }
+    }
+}
+
+/// `host` is `domain` or one of its subdomains.
+fn on_domain(host: &str, domain: &str) -> bool {
+    host == domain || host.strip_suffix(domain).is_some_and(|rest| rest.ends_with('.'))
+}
+
+/// Approximates the registrable domain without a public-suffix list: the
+/// last two labels, or three under short second-level labels like `co.uk`.
+pub(crate) fn base_domain(host: &str) -> &str {
+    let labels: Vec<&str> = host.rsplitn(4, '.').collect();
+    let keep = match labels.as_slice() {
+        [tld, sld, ..] if tld.len() == 2 && sld.len() <= 3 && labels.len() >= 3 => 3,
+        _ => 2,
+    };
+    if labels.len() <= keep {
+        return host;
+    }
+    let tail: usize = labels[..keep].iter().map(|l| l.len()).sum::<usize>() + keep - 1;
+    &host[host.len() - tail..]
+}
+
+/// A request as the matcher sees it.
+pub struct Request<'a> {
+    pub url: &'a Url,
+    /// Host of the document making the request.
+    pub source_host: Option<&'a str>,
+    pub resource_type: ResourceType,
+}
+
+impl NetworkRule {
+    fn applies_to(&self, req: &Request<'_>, host: &str) -> bool {
+        if self.types & req.resource_type.bit() == 0 {
+            return false;
+        }
+        if let Some(third_party) = self.third_party {
+            let is_third = req.source_host.is_some_and(|src| base_domain(src) != base_domain(host));
+            if third_party != is_third {
+                return false;
+            }
+        }
+        let source = req.source_host.unwrap_or(host);
+        if self.not_domains.iter().any(|d| on_domain(source, d)) {
+            return false;
+        }
+        self.domains.is_empty() || self.domains.iter().any(|d| on_domain(source, d))
+    }
+
+    fn matches_url(&self, url: &str, host_start: usize, host: &str) -> bool {
+        let lowered;
+        let url = if self.match_case {
+            url
+        } else {
+            lowered = url.to_ascii_lowercase();
+            &lowered
+        };
+        let bytes = url.as_bytes();
+        match self.anchor {
+            Anchor::Start => match_from(&self.tokens, bytes, 0, self.end_anchor),
+            Anchor::Domain => {
+                let starts = std::iter::once(0).chain(host.match_indices('.').map(|(i, _)| i + 1));
+                starts
+                    .map(|i| host_start + i)
+                    .any(|at| match_from(&self.tokens, bytes, at, self.end_anchor))
+            }
+            Anchor::None => (0..=bytes.len()).any(|at| match_from(&self.tokens, bytes, at, self.end_anchor)),
+        }
+    }
+
+    /// `||host^` with nothing after it: the host an `$elemhide` or
+    /// `$generichide` exception turns hiding off for.
+    fn host_only(&self) -> Option<&str> {
+        if self.anchor != Anchor::Domain || self.match_case {
+            return None;
+        }
+        match self.tokens.as_slice() {
+            [Token::Literal(host)] | [Token::Literal(host), Token::Separator] if !self.end_anchor => {
+                let host = host.as_str();
+                host.bytes()
+                    .all(|c| c.is_ascii_alphanumeric() || c == b'.' || c == b'-')
+                    .then_some(host)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Element hiding, indexed by domain.
+#[derive(Debug, Default)]
+pub struct Cosmetics {
+    /// Selectors hidden everywhere → the domains excepted from each.
+    generic: BTreeMap<String, BTreeSet<String>>,
+    /// Domain → selectors hidden on it and its subdomains.
+    specific: BTreeMap<String, BTreeSet<String>>,
+    /// Domain → selectors not hidden on it and its subdomains.
+    exceptions: BTreeMap<String, BTreeSet<String>>,
+    /// Hosts with element hiding turned off by an `$elemhide` exception.
+    no_elemhide: BTreeSet<String>,
+    /// Hosts with generic element hiding turned off by a `$generichide` exception.
+    no_generichide: BTreeSet<String>,
+}
+
+/// `host` and each domain above it: `a.b.example`, `b.example`, `example`.
+fn suffixes(host: &str) -> impl Iterator<Item = &str> {
+    std::iter::successors(Some(host), |h| h.find('.').map(|dot| &h[dot + 1..])).filter(|h| !h.is_empty())
+}
+
+impl Cosmetics {
+    /// Selectors to hide on pages of `host`.
+    pub fn hidden_on(&self, host: &str) -> Vec<&str> {
+        if suffixes(host).any(|h| self.no_elemhide.contains(h)) {
+            return Vec::new();
+        }
+        let unhidden: BTreeSet<&str> = suffixes(host)
+            .filter_map(|h| self.exceptions.get(h))
+            .flatten()
+            .map(String::as_str)
+            .collect();
+        let mut hidden: BTreeSet<&str> = suffixes(host)
+            .filter_map(|h| self.specific.get(h))
+            .flatten()
+            .map(String::as_str)
+            .collect();
+        if !suffixes(host).any(|h| self.no_generichide.contains(h)) {
+            hidden.extend(self.generic.keys().map(String::as_str));
+        }
+        hidden.into_iter().filter(|s| !unhidden.contains(s)).collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParseStats {
+    pub network: u32,
+    pub cosmetic: u32,
+    pub unsupported: u32,
+}
+
+/// The compiled rules of every enabled list.
+#[derive(Debug, Default)]
+pub struct FilterSet {
+    rules: Vec<NetworkRule>,
+    /// Rules anchored to a host, keyed by that host.
+    by_host: HashMap<String, Vec<usize>>,
+    /// Everything else, checked against every request.
+    generic: Vec<usize>,
+    cosmetics: Vec<CosmeticRule>,
+    /// `cosmetics` by domain, built on first use.
+    cosmetic_index: OnceLock<Cosmetics>,
+}
+
+/// Parse one list's text into `set`.
+pub fn parse_into(set: &mut FilterSet, text: &str) -> ParseStats {
+    let mut stats = ParseStats::default();
+    set.cosmetic_index = OnceLock::new();
+    for line in text.lines() {
+        match parse_line(line) {
+            Parsed::Network(rule) => {
+                stats.network += 1;
+                set.push_rule(rule);
+            }
+            Parsed::Cosmetic(rule) => {
+                stats.cosmetic += 1;
+                set.cosmetics.push(rule);
+            }
+            Parsed::Unsupported => stats.unsupported += 1,
+            Parsed::Skip => {}
+        }
+    }
+    stats
+}
+
+/// Parse `text` on its own, for counting a list's rules.
+pub fn parse_stats(text: &str) -> ParseStats {
+    parse_into(&mut FilterSet::default(), text)
+}
+
+impl FilterSet {
+    fn push_rule(&mut self, rule: NetworkRule) {
+        let index = self.rules.len();
+        let key = match (rule.anchor, rule.tokens.first()) {
+            (Anchor::Domain, Some(Token::Literal(lit))) => {
+                // Only a complete host can be looked up by host.
+                let end = lit.find(['/', ':', '?']).unwrap_or(lit.len());
+                let complete = end < lit.len() || matches!(rule.tokens.get(1), Some(Token::Separator) | None);
+                complete.then(|| lit[..end].to_string())
+            }
+            _ => None,
+        };
+        match key {
+            Some(host) if !host.is_empty() => self.by_host.entry(host).or_default().push(index),
+            _ => self.generic.push(index),
+        }
+        self.rules.push(rule);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty() && self.cosmetics.is_empty()
+    }
+
+    /// The rule deciding `req`: the blocking rule if it is blocked, otherwise
+    /// the exception that let it through, otherwise `None`.
+    pub fn check(&self, req: &Request<'_>) -> Option<&NetworkRule> {
+        let host = req.url.host_str()?.to_ascii_lowercase();
+        let url = req.url.as_str();
+        let host_start = url.find(&host)?;
+
+        let mut candidates: Vec<usize> = Vec::new();
+        let mut suffix = host.as_str();
+        loop {
+            if let Some(rules) = self.by_host.get(suffix) {
+                candidates.extend(rules);
+            }
+            match suffix.find('.') {
+                Some(dot) => suffix = &suffix[dot + 1..],
+                None => break,
+            }
+        }
+        candidates.extend(&self.generic);
+
+        let mut block = None;
+        let mut allow = None;
+        for rule in candidates.into_iter().map(|i| &self.rules[i]) {
+            if !rule.applies_to(req, &host) || !rule.matches_url(url, host_start, &host) {
+                continue;
+            }
+            match (rule.exception, rule.important) {
+                (false, true) => return Some(rule),
+                (false, false) => block = block.or(Some(rule)),
+                (true, _) => allow = allow.or(Some(rule)),
+            }
+        }
+        allow.or(block)
+    }
+
+    /// Element hiding by domain.
+    pub fn cosmetics(&self) -> &Cosmetics {
+        self.cosmetic_index.get_or_init(|| {
+            let mut index = Cosmetics::default();
+            for rule in self.rules.iter().filter(|r| r.elemhide || r.generichide) {
+                if let Some(host) = rule.host_only() {
+                    let hosts = if rule.elemhide { &mut index.no_elemhide } else { &mut index.no_generichide };
+                    hosts.insert(host.to_string());
+                }
+            }
+            let mut unhidden = BTreeSet::new();
+            for rule in &self.cosmetics {
+                let target = if rule.exception { &mut index.exceptions } else { &mut index.specific };
+                for domain in &rule.domains {
+                    target.entry(domain.clone()).or_default().insert(rule.selector.clone());
+                }
+                // `~domain` on a hiding rule is an exception for that domain.
+                if !rule.exception {
+                    for domain in &rule.not_domains {
+                        index.exceptions.entry(domain.clone()).or_default().insert(rule.selector.clone());
+                    }
+                    if rule.domains.is_empty() {
+                        index.generic.entry(rule.selector.clone()).or_default();
+                    }
+                } else if rule.domains.is_empty() {
+                    // `#@#selector` with no domains lifts the rule everywhere.
+                    unhidden.insert(rule.selector.clone());
+                }
+            }
+            index.generic.retain(|selector, _| !unhidden.contains(selector));
+            for (domain, selectors) in &index.exceptions {
+                for selector in selectors {
+                    if let Some(excepted) = index.generic.get_mut(selector) {
+                        excepted.insert(domain.clone());
+                    }
+                }
+            }
+            index
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(text: &str) -> (FilterSet, ParseStats) {
+        let mut set = FilterSet::default();
+        let stats = parse_into(&mut set, text);
+        (set, stats)
+    }
+
+    fn check<'a>(set: &'a FilterSet, url: &str, source: Option<&str>, ty: ResourceType) -> Option<&'a str> {
+        let url = Url::parse(url).unwrap();
+        let req = Request {
+            url: &url,
+            source_host: source,
+            resource_type: ty,
+        };
+        set.check(&req).map(|rule| rule.text.as_str())
+    }
+
+    #[test]
+    fn counts_rules_by_kind() {
+        let (_, stats) = set(
+            "! Title: Test\n\
+             [Adblock Plus 2.0]\n\
+             ||ads.example^\n\
+             @@||cdn.example^$script\n\
+             ||tracker.example/pixel.gif\n\
+             /banner/*\n\
+             ||bad.example/landing^$document\n\
+             /ads[0-9]+/\n\
+             ||x.example^$redirect=noop.js\n\
+             ##.ad\n\
+             example.com#@#.ad\n\
+             example.com#?#.ad:has-text(Sponsored)\n",
+        );
+        assert_eq!(stats.network, 5);
+        assert_eq!(stats.cosmetic, 2);
+        assert_eq!(stats.unsupported, 3);
+    }
+
+    #[test]
+    fn parses_anchors_and_options() {
+        let rule =
+            parse_network("@@|https://a.example/x*y^|$script,~third-party,domain=b.example|~c.b.example").unwrap();
+        assert!(rule.exception);
+        assert_eq!(rule.anchor, Anchor::Start);
+        assert!(rule.end_anchor);
+        assert_eq!(
+            rule.tokens,
+            [
+                Token::Literal("https://a.example/x".into()),
+                Token::Any,
+                Token::Literal("y".into()),
+                Token::Separator
+            ]
+        );
+        assert_eq!(rule.types, ResourceType::Script.bit());
+        assert_eq!(rule.third_party, Some(false));
+        assert_eq!(rule.domains, ["b.example"]);
+        assert_eq!(rule.not_domains, ["c.b.example"]);
+
+        let rule = parse_network("||Ads.Example^$~image,important").unwrap();
+        assert_eq!(rule.anchor, Anchor::Domain);
+        assert!(rule.important);
+        assert_eq!(rule.types, DEFAULT_TYPES & !ResourceType::Image.bit());
+        assert_eq!(rule.host_only(), Some("ads.example"));
+
+        assert!(parse_network("||a.example^$unknown-option").is_none());
+        assert!(parse_network("*").is_none());
+        assert!(matches!(parse_line("example.com##div {color: red}"), Parsed::Unsupported));
+        assert!(matches!(parse_line("! comment"), Parsed::Skip));
+    }
+
+    #[test]
+    fn matches_requests() {
+        let (set, _) = set(
+            "||ads.example^\n\
+             @@||ads.example^$script,domain=news.example\n\
+             ||track.example^$third-party\n\
+             ||bad.example/landing^$document\n\
+             ||forced.example^$important\n\
+             @@||forced.example^\n",
+        );
+        let image = ResourceType::Image;
+        assert_eq!(check(&set, "https://x.ads.example/a.png", Some("site.example"), image), Some("||ads.example^"));
+        assert_eq!(check(&set, "https://notads.example/a.png", Some("site.example"), image), None);
+        // An exception that applies wins over a plain block.
+        let script = check(&set, "https://ads.example/a.js", Some("www.news.example"), ResourceType::Script);
+        assert_eq!(script, Some("@@||ads.example^$script,domain=news.example"));
+        assert_eq!(check(&set, "https://track.example/p", Some("track.example"), image), None);
+        let third_party = check(&set, "https://track.example/p", Some("site.example"), image);
+        assert_eq!(third_party, Some("||track.example^$third-party"));
+        // `$important` beats exceptions.
+        let forced = check(&set, "https://forced.example/", Some("site.example"), image);
+        assert_eq!(forced, Some("||forced.example^$important"));
+        // Plain rules don't block documents; `$document` ones do.
+        assert_eq!(check(&set, "https://ads.example/", None, ResourceType::Document), None);
+        assert_eq!(
+            check(&set, "https://bad.example/landing?id=1", None, ResourceType::Document),
+            Some("||bad.example/landing^$document")
+        );
+        assert_eq!(check(&set, "https://bad.example/landingpage", None, ResourceType::Document), None);
+    }
+
+    #[test]
+    fn hides_per_host() {
+        let (set, _) = set(
+            "##.ad\n\
+             ##.banner\n\
+             ~shop.example##.promo\n\
+             news.example##.sidebar-ad\n\
+             sports.news.example#@#.ad\n\
+             #@#.banner\n\
+             @@||clean.example^$elemhide\n\
+             @@||own.example^$generichide\n\
+             own.example##.own-ad\n",
+        );
+        let cosmetics = set.cosmetics();
+        assert_eq!(cosmetics.hidden_on("www.news.example"), [".ad", ".promo", ".sidebar-ad"]);
+        assert_eq!(cosmetics.hidden_on("sports.news.example"), [".promo", ".sidebar-ad"]);
+        assert_eq!(cosmetics.hidden_on("shop.example"), [".ad"]);
+        assert!(cosmetics.hidden_on("a.clean.example").is_empty());
+        assert_eq!(cosmetics.hidden_on("own.example"), [".own-ad"]);
+    }
+
+    #[test]
+    fn base_domain_approximation() {
+        assert_eq!(base_domain("a.b.example.com"), "example.com");
+        assert_eq!(base_domain("www.bbc.co.uk"), "bbc.co.uk");
+        assert_eq!(base_domain("example"), "example");
+    }
+}
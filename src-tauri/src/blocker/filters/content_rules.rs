@@ -0,0 +1,381 @@
+//! The filter set as WebKit content rule lists: the JSON `WKContentRuleList`
+//! (macOS) and `WebKitUserContentFilter` (Linux) compile into the engine's own
+//! request filter and element hiding.
+//!
+//! Network rules become `block` actions matching a regular expression built
+//! from the pattern. Exceptions follow as `ignore-previous-rules`, then the
+//! `$important` blocks, which no exception may lift. Element hiding goes in a
+//! second list of `css-display-none` actions, so a selector WebKit refuses
+//! costs the hiding but not the blocking. Both lists end by lifting
+//! everything on the sites blocking is turned off for.
+//!
+//! `$document` is left to Rust, which checks navigations itself. Frames are
+//! matched as child-frame documents (`load-context`, WebKit 2.32 / Safari
+//! 14.1 and later). Where WebKit's format can't say what a rule says, the
+//! rule is approximated or left out:
+//! - `^` matches one separator character, or the end when it ends the rule.
+//! - A trigger takes `if-domain` or `unless-domain`, not both, so a rule with
+//!   both `domain=` and `domain=~` entries keeps only the former, and a
+//!   `#@#` exception on a subdomain of a site-specific rule's domain doesn't
+//!   lift that rule there.
+//! - Rules with non-ASCII patterns, or domains that aren't plain host names
+//!   (`example.*`), are dropped.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde_json::{json, Map, Value};
+
+use super::{suffixes, Anchor, Cosmetics, FilterSet, NetworkRule, ResourceType, Token};
+
+/// Store identifiers of the two lists.
+pub const NETWORK_LIST: &str = "brsr-network";
+pub const COSMETIC_LIST: &str = "brsr-cosmetic";
+
+/// Selectors joined into one `css-display-none` action.
+const SELECTORS_PER_RULE: usize = 250;
+
+/// What `^` matches: anything but a letter, a digit or `_.%-`.
+const SEPARATOR: &str = "[^a-zA-Z0-9_.%-]";
+
+/// One list in WebKit's JSON format, ready to compile.
+#[derive(Debug, Clone)]
+pub struct RuleList {
+    pub id: &'static str,
+    pub json: String,
+    pub rules: usize,
+}
+
+fn url_filter(rule: &NetworkRule) -> Option<String> {
+    let mut filter = String::new();
+    match rule.anchor {
+        Anchor::Domain => filter.push_str(r"^[^:]+://([^/]+\.)?"),
+        Anchor::Start => filter.push('^'),
+        Anchor::None => {}
+    }
+    for (i, token) in rule.tokens.iter().enumerate() {
+        match token {
+            Token::Literal(literal) if !literal.is_ascii() => return None,
+            Token::Literal(literal) => {
+                for c in literal.chars() {
+                    if r"\.*+?^$|()[]{}".contains(c) {
+                        filter.push('\\');
+                    }
+                    filter.push(c);
+                }
+            }
+            Token::Any => filter.push_str(".*"),
+            Token::Separator if i + 1 == rule.tokens.len() => {
+                let rest = if rule.end_anchor { "" } else { ".*" };
+                filter.push_str(&format!("({}{})?$", SEPARATOR, rest));
+                return Some(filter);
+            }
+            Token::Separator => filter.push_str(SEPARATOR),
+        }
+    }
+    if rule.end_anchor {
+        filter.push('$');
+    }
+    if filter.is_empty() {
+        filter.push_str(".*");
+    }
+    Some(filter)
+}
+
+/// WebKit's resource types for `types`, and whether frames are among them.
+fn resource_types(types: u16) -> (Vec<&'static str>, bool) {
+    let mut names = Vec::new();
+    for ty in ResourceType::ALL.into_iter().filter(|t| types & t.bit() != 0) {
+        let name = match ty {
+            ResourceType::Document | ResourceType::Subdocument => continue,
+            ResourceType::Script => "script",
+            ResourceType::Image => "image",
+            ResourceType::Stylesheet => "style-sheet",
+            ResourceType::Font => "font",
+            ResourceType::Media => "media",
+            ResourceType::Popup => "popup",
+            // "raw" is every request not made by an element, in every WebKit
+            // version; the finer types came later.
+            ResourceType::Xmlhttprequest
+            | ResourceType::Websocket
+            | ResourceType::Ping
+            | ResourceType::Object
+            | ResourceType::Other => "raw",
+        };
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    (names, types & ResourceType::Subdocument.bit() != 0)
+}
+
+fn plain_domain(domain: &str) -> bool {
+    !domain.is_empty()
+        && domain
+            .bytes()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == b'.' || c == b'-')
+}
+
+/// `domains` with their subdomains, as WebKit writes them; `None` if one of
+/// them can't be written.
+fn with_subdomains<'a>(domains: impl IntoIterator<Item = &'a String>) -> Option<Vec<String>> {
+    domains
+        .into_iter()
+        .map(|d| plain_domain(d).then(|| format!("*{}", d)))
+        .collect()
+}
+
+fn rule(trigger: Map<String, Value>, action: Value) -> Value {
+    json!({ "trigger": trigger, "action": action })
+}
+
+fn network_triggers(rule: &NetworkRule) -> Vec<Map<String, Value>> {
+    let Some(filter) = url_filter(rule) else {
+        return Vec::new();
+    };
+    let mut trigger = Map::new();
+    trigger.insert("url-filter".into(), json!(filter));
+    if rule.match_case {
+        trigger.insert("url-filter-is-case-sensitive".into(), json!(true));
+    }
+    if let Some(third_party) = rule.third_party {
+        let load = if third_party { "third-party" } else { "first-party" };
+        trigger.insert("load-type".into(), json!([load]));
+    }
+    let domains = if !rule.domains.is_empty() {
+        with_subdomains(&rule.domains).map(|d| ("if-domain", d))
+    } else if !rule.not_domains.is_empty() {
+        with_subdomains(&rule.not_domains).map(|d| ("unless-domain", d))
+    } else {
+        Some(("", Vec::new()))
+    };
+    match domains {
+        None => return Vec::new(),
+        Some((key, domains)) if !domains.is_empty() => {
+            trigger.insert(key.into(), json!(domains));
+        }
+        Some(_) => {}
+    }
+
+    let (types, frames) = resource_types(rule.types);
+    let mut triggers = Vec::new();
+    if frames {
+        let mut frame = trigger.clone();
+        frame.insert("resource-type".into(), json!(["document"]));
+        frame.insert("load-context".into(), json!(["child-frame"]));
+        triggers.push(frame);
+    }
+    if !types.is_empty() {
+        trigger.insert("resource-type".into(), json!(types));
+        triggers.push(trigger);
+    }
+    triggers
+}
+
+fn any_url() -> Map<String, Value> {
+    let mut trigger = Map::new();
+    trigger.insert("url-filter".into(), json!(".*"));
+    trigger
+}
+
+/// Lift everything before it on `domains` and their subdomains.
+fn lift_on<'a>(domains: impl IntoIterator<Item = &'a String>) -> Option<Value> {
+    let domains: Vec<String> = domains.into_iter().filter(|d| plain_domain(d)).map(|d| format!("*{}", d)).collect();
+    if domains.is_empty() {
+        return None;
+    }
+    let mut trigger = any_url();
+    trigger.insert("if-domain".into(), json!(domains));
+    Some(rule(trigger, json!({ "type": "ignore-previous-rules" })))
+}
+
+fn hide(trigger: Map<String, Value>, selectors: &[&str]) -> Value {
+    rule(trigger, json!({ "type": "css-display-none", "selector": selectors.join(", ") }))
+}
+
+/// Generic hiding, lifted where `$generichide` says; then site-specific
+/// hiding; then both lifted where `$elemhide` says.
+fn cosmetic_rules(cosmetics: &Cosmetics) -> Vec<Value> {
+    let mut rules = Vec::new();
+
+    // Generic selectors, batched by the domains excepted from them.
+    let mut batches: BTreeMap<Vec<String>, Vec<&str>> = BTreeMap::new();
+    for (selector, excepted) in &cosmetics.generic {
+        if let Some(unless) = with_subdomains(excepted) {
+            batches.entry(unless).or_default().push(selector);
+        }
+    }
+    for (unless, selectors) in &batches {
+        for chunk in selectors.chunks(SELECTORS_PER_RULE) {
+            let mut trigger = any_url();
+            if !unless.is_empty() {
+                trigger.insert("unless-domain".into(), json!(unless));
+            }
+            rules.push(hide(trigger, chunk));
+        }
+    }
+    rules.extend(lift_on(&cosmetics.no_generichide));
+
+    // Site-specific selectors, less the exceptions on the site or above it.
+    for (domain, selectors) in cosmetics.specific.iter().filter(|(domain, _)| plain_domain(domain)) {
+        let unhidden: BTreeSet<&String> = suffixes(domain).filter_map(|d| cosmetics.exceptions.get(d)).flatten().collect();
+        let hidden: Vec<&str> = selectors.iter().filter(|s| !unhidden.contains(s)).map(String::as_str).collect();
+        for chunk in hidden.chunks(SELECTORS_PER_RULE) {
+            let mut trigger = any_url();
+            trigger.insert("if-domain".into(), json!([format!("*{}", domain)]));
+            rules.push(hide(trigger, chunk));
+        }
+    }
+    rules.extend(lift_on(&cosmetics.no_elemhide));
+    rules
+}
+
+impl FilterSet {
+    /// The set as WebKit content rule lists, with nothing applying on
+    /// `disabled_sites`. Lists with no rules are left out.
+    pub fn content_rules(&self, disabled_sites: &BTreeSet<String>) -> Vec<RuleList> {
+        let mut blocks = Vec::new();
+        let mut exceptions = Vec::new();
+        let mut important = Vec::new();
+        for network in &self.rules {
+            let (target, action) = match (network.exception, network.important) {
+                (true, _) => (&mut exceptions, "ignore-previous-rules"),
+                (false, true) => (&mut important, "block"),
+                (false, false) => (&mut blocks, "block"),
+            };
+            for trigger in network_triggers(network) {
+                target.push(rule(trigger, json!({ "type": action })));
+            }
+        }
+        let mut network: Vec<Value> = blocks.into_iter().chain(exceptions).chain(important).collect();
+        let mut cosmetic = cosmetic_rules(self.cosmetics());
+
+        let mut lists = Vec::new();
+        for (id, rules) in [(NETWORK_LIST, &mut network), (COSMETIC_LIST, &mut cosmetic)] {
+            if rules.is_empty() {
+                continue;
+            }
+            // Nothing applies on the sites blocking is off for.
+            rules.extend(lift_on(disabled_sites));
+            lists.push(RuleList {
+                id,
+                rules: rules.len(),
+                json: Value::Array(std::mem::take(rules)).to_string(),
+            });
+        }
+        lists
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocker::filters::{parse_into, parse_network};
+
+    fn filter(line: &str) -> String {
+        url_filter(&parse_network(line).unwrap()).unwrap()
+    }
+
+    fn lists(text: &str, disabled: &[&str]) -> Vec<(&'static str, Vec<Value>)> {
+        let mut set = FilterSet::default();
+        parse_into(&mut set, text);
+        let disabled = disabled.iter().map(|s| s.to_string()).collect();
+        set.content_rules(&disabled)
+            .into_iter()
+            .map(|list| (list.id, serde_json::from_str(&list.json).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn patterns_become_regular_expressions() {
+        assert_eq!(filter("||ads.example^"), r"^[^:]+://([^/]+\.)?ads\.example([^a-zA-Z0-9_.%-].*)?$");
+        assert_eq!(filter("|https://a.example/x*y|"), r"^https://a\.example/x.*y$");
+        assert_eq!(filter("/banner/*.gif?"), r"/banner/.*\.gif\?");
+        assert_eq!(filter("&ad_type=^x"), "&ad_type=[^a-zA-Z0-9_.%-]x");
+        assert_eq!(filter("$script,domain=a.example"), ".*");
+        assert!(url_filter(&parse_network("||пример.example^").unwrap()).is_none());
+    }
+
+    #[test]
+    fn orders_blocks_exceptions_and_important() {
+        let lists = lists(
+            "||important.example^$important\n\
+             @@||cdn.example^$script,domain=news.example\n\
+             ||ads.example^$third-party,~image,~media,~font,~stylesheet,~xmlhttprequest,~other,~object,~ping,~websocket\n\
+             ||bad.example^$document\n\
+             ||wild.example^$domain=example.*\n",
+            &["quiet.example"],
+        );
+        assert_eq!(lists.len(), 1);
+        let (id, rules) = &lists[0];
+        assert_eq!(*id, NETWORK_LIST);
+        let summary: Vec<(String, &str)> = rules
+            .iter()
+            .map(|r| (r["action"]["type"].as_str().unwrap().to_string(), r["trigger"]["url-filter"].as_str().unwrap()))
+            .map(|(action, filter)| (action, filter.split("example").next().unwrap()))
+            .collect();
+        // Frames get a trigger of their own; the `$document` rule and the one
+        // with a wildcard domain are left out.
+        assert_eq!(
+            summary,
+            [
+                ("block".into(), r"^[^:]+://([^/]+\.)?ads\."),
+                ("block".into(), r"^[^:]+://([^/]+\.)?ads\."),
+                ("ignore-previous-rules".into(), r"^[^:]+://([^/]+\.)?cdn\."),
+                ("block".into(), r"^[^:]+://([^/]+\.)?important\."),
+                ("block".into(), r"^[^:]+://([^/]+\.)?important\."),
+                ("ignore-previous-rules".into(), ".*"),
+            ]
+        );
+        assert_eq!(rules[0]["trigger"]["load-context"], json!(["child-frame"]));
+        assert_eq!(rules[1]["trigger"]["resource-type"], json!(["script"]));
+        assert_eq!(rules[1]["trigger"]["load-type"], json!(["third-party"]));
+        assert_eq!(rules[2]["trigger"]["if-domain"], json!(["*news.example"]));
+        assert_eq!(rules[5]["trigger"]["if-domain"], json!(["*quiet.example"]));
+    }
+
+    #[test]
+    fn hiding_is_scoped_by_domain() {
+        let lists = lists(
+            "##.ad\n\
+             ##.banner\n\
+             ~shop.example##.promo\n\
+             news.example##.sidebar-ad\n\
+             news.example##.ad-slot\n\
+             news.example#@#.ad-slot\n\
+             @@||clean.example^$elemhide\n\
+             clean.example##.never\n\
+             @@||own.example^$generichide\n\
+             own.example##.own-ad\n",
+            &[],
+        );
+        // The `$elemhide` and `$generichide` exceptions block nothing, so
+        // there's no network list.
+        assert_eq!(lists.len(), 1);
+        let (id, rules) = &lists[0];
+        assert_eq!(*id, COSMETIC_LIST);
+        // (selector, which domain condition, its domains); no selector is a lift.
+        let hiding: Vec<(Option<&str>, Option<&str>, &Value)> = rules
+            .iter()
+            .map(|r| {
+                let trigger = r["trigger"].as_object().unwrap();
+                let (key, domains) = match trigger.iter().find(|(key, _)| key.ends_with("-domain")) {
+                    Some((key, domains)) => (key.split('-').next(), domains),
+                    None => (None, &Value::Null),
+                };
+                (r["action"]["selector"].as_str(), key, domains)
+            })
+            .collect();
+        assert_eq!(
+            hiding,
+            [
+                (Some(".ad, .banner"), None, &json!(null)),
+                (Some(".promo"), Some("unless"), &json!(["*shop.example"])),
+                (None, Some("if"), &json!(["*own.example"])),
+                (Some(".never"), Some("if"), &json!(["*clean.example"])),
+                (Some(".sidebar-ad"), Some("if"), &json!(["*news.example"])),
+                (Some(".own-ad"), Some("if"), &json!(["*own.example"])),
+                (None, Some("if"), &json!(["*clean.example"])),
+            ]
+        );
+    }
+}
@@ -0,0 +1,17 @@
+// Element hiding for one document on WebView2, which can't hide elements
+// itself; see src-tauri/src/blocker/native.rs. Runs as the document starts
+// loading, before it has a root to attach to. The caller declares `CSS`
+// right before this file.
+
+const style = document.createElement('style');
+style.textContent = CSS;
+const attach = () => {
+  const root = document.head || document.documentElement;
+  if (!root) return false;
+  root.appendChild(style);
+  return true;
+};
+if (!attach()) {
+  const waiting = new MutationObserver(() => { if (attach()) waiting.disconnect(); });
+  waiting.observe(document, { childList: true });
+}
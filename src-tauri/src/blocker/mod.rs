@@ -0,0 +1,457 @@
+//! Ad and tracker blocking from Adblock Plus / EasyList filter lists.
+//!
+//! Lists are added from local files and copied under `filter-lists/` in the
+//! app data dir; which ones are enabled, and the sites blocking is turned off
+//! for, live in `content-blocker.json`. The enabled lists are compiled into a
+//! [`filters::FilterSet`] that is applied in two places:
+//!
+//! - Rust checks every top-level navigation through the navigation policy,
+//!   which only `$document` rules block.
+//! - The engine filters subresources and hides elements ([`native`]): WebKit
+//!   from the set compiled into its content rule lists, WebView2 by asking
+//!   Rust about each request.
+//!
+//! Blocked requests are counted per tab. WebView2 counts them as it blocks
+//! them; WebKit doesn't say what it blocked, so there the injected script
+//! reports loads that failed and Rust counts the ones the rules block.
+//!
+//! Changes to the lists, the on/off switch or the disabled sites are handed to
+//! the engine at once and reach open tabs without rebuilding them.
+
+pub mod filters;
+mod native;
+
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+use ts_rs::TS;
+
+use crate::error::BrsrError;
+use crate::scripts;
+use crate::storage;
+use crate::tabs::{now_ms, TabRegistry};
+use crate::telemetry::event::BlockedRequest;
+use filters::{FilterSet, ParseStats, Request, ResourceType};
+
+pub use native::attach;
+
+const CONFIG_FILE: &str = "content-blocker.json";
+const LISTS_DIR: &str = "filter-lists";
+/// Element hiding for one document on WebView2; see [`native`].
+#[cfg_attr(not(windows), allow(dead_code))]
+const HIDE: &str = include_str!("hide.js");
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilterList {
+    pub id: String,
+    /// From the list's `! Title:` header, or the file name.
+    pub title: String,
+    /// The file the list was added from; `reload_filter_list` reads it again.
+    pub source: String,
+    pub enabled: bool,
+    pub network_rules: u32,
+    pub cosmetic_rules: u32,
+    /// Rules using syntax we don't support, skipped.
+    pub unsupported_rules: u32,
+    pub updated_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BlockerConfig {
+    pub enabled: bool,
+    pub lists: Vec<FilterList>,
+    /// Hosts (and their subdomains) nothing is blocked on.
+    pub disabled_sites: BTreeSet<String>,
+}
+
+impl Default for BlockerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            lists: Vec::new(),
+            disabled_sites: BTreeSet::new(),
+        }
+    }
+}
+
+/// Blocked requests in one tab.
+#[derive(Debug, Clone, Copy, Default, Serialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct BlockedCounts {
+    /// On the current document.
+    pub page: u32,
+    /// Since the tab was opened.
+    pub total: u32,
+}
+
+fn on_site(host: &str, site: &str) -> bool {
+    host == site || host.ends_with(&format!(".{}", site))
+}
+
+fn list_title(text: &str, path: &Path) -> String {
+    text.lines()
+        .take(50)
+        .find_map(|line| line.strip_prefix("! Title:").map(|t| t.trim().to_string()))
+        .filter(|t| !t.is_empty())
+        .or_else(|| path.file_stem().map(|s| s.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| "Filter list".into())
+}
+
+/// Managed state: filter lists, the compiled rules and per-tab counts.
+pub struct ContentBlocker {
+    config: Mutex<BlockerConfig>,
+    config_path: Option<PathBuf>,
+    lists_dir: Option<PathBuf>,
+    filters: Mutex<Arc<FilterSet>>,
+    counts: Mutex<HashMap<String, BlockedCounts>>,
+}
+
+impl ContentBlocker {
+    pub fn load(app: &tauri::AppHandle) -> Self {
+        let config_path = storage::data_file(app, CONFIG_FILE)
+            .map_err(|e| eprintln!("[Rust] Content blocker settings will not persist: {}", e))
+            .ok();
+        let lists_dir = storage::data_file(app, LISTS_DIR)
+            .and_then(|dir| fs::create_dir_all(&dir).map(|_| dir.clone()).map_err(|e| BrsrError::io(&dir, e)))
+            .map_err(|e| eprintln!("[Rust] Filter lists unavailable: {}", e))
+            .ok();
+        let config = config_path
+            .as_deref()
+            .map(storage::load_json::<BlockerConfig>)
+            .unwrap_or_default();
+        let blocker = Self {
+            config: Mutex::new(config),
+            config_path,
+            lists_dir,
+            filters: Mutex::new(Arc::new(FilterSet::default())),
+            counts: Mutex::new(HashMap::new()),
+        };
+        blocker.compile(app);
+        blocker
+    }
+
+    fn list_path(&self, id: &str) -> Result<PathBuf, BrsrError> {
+        self.lists_dir
+            .as_ref()
+            .map(|dir| dir.join(format!("{}.txt", id)))
+            .ok_or_else(|| BrsrError::unsupported("Filter lists"))
+    }
+
+    /// Rebuild the compiled rules from the enabled lists and install them.
+    fn compile(&self, app: &tauri::AppHandle) {
+        let config = self.config.lock().unwrap().clone();
+        let mut set = FilterSet::default();
+        for list in config.lists.iter().filter(|l| l.enabled) {
+            match self.list_path(&list.id).and_then(|p| fs::read_to_string(&p).map_err(|e| BrsrError::io(&p, e))) {
+                Ok(text) => {
+                    filters::parse_into(&mut set, &text);
+                }
+                Err(e) => eprintln!("[Rust] Could not read filter list '{}': {}", list.title, e),
+            }
+        }
+        *self.filters.lock().unwrap() = Arc::new(set);
+        self.install(app);
+    }
+
+    /// Hand the rules, as they apply now, to the engine.
+    fn install(&self, app: &tauri::AppHandle) {
+        let config = self.config();
+        let filters = self.filters.lock().unwrap().clone();
+        // WebView2 asks about each request instead.
+        let lists = if config.enabled && !filters.is_empty() && cfg!(any(target_os = "macos", target_os = "linux")) {
+            filters.content_rules(&config.disabled_sites)
+        } else {
+            Vec::new()
+        };
+        for list in &lists {
+            println!("[Rust] Content rules '{}': {} rules, {} bytes", list.id, list.rules, list.json.len());
+        }
+        native::install(app, lists);
+    }
+
+    fn update(&self, f: impl FnOnce(&mut BlockerConfig) -> Result<(), BrsrError>) -> Result<BlockerConfig, BrsrError> {
+        let mut config = self.config.lock().unwrap();
+        let mut next = config.clone();
+        f(&mut next)?;
+        if let Some(path) = &self.config_path {
+            storage::save_json(path, &next)?;
+        }
+        *config = next.clone();
+        Ok(next)
+    }
+
+    pub fn config(&self) -> BlockerConfig {
+        self.config.lock().unwrap().clone()
+    }
+
+    /// Whether blocking is on for pages of `host`.
+    fn applies_on(&self, host: &str) -> bool {
+        let config = self.config.lock().unwrap();
+        config.enabled && !config.disabled_sites.iter().any(|site| on_site(host, site))
+    }
+
+    /// The rule blocking a top-level navigation to `url`, if any.
+    pub fn check_document(&self, url: &url::Url) -> Option<String> {
+        let host = url.host_str()?.to_ascii_lowercase();
+        if !self.applies_on(&host) {
+            return None;
+        }
+        let filters = self.filters.lock().unwrap().clone();
+        let request = Request {
+            url,
+            source_host: None,
+            resource_type: ResourceType::Document,
+        };
+        filters
+            .check(&request)
+            .filter(|rule| !rule.exception)
+            .map(|rule| rule.text.clone())
+    }
+
+    /// Whether the rules block a `resource_type` request for `url` made by
+    /// the page at `page`.
+    pub fn blocks(&self, page: &str, url: &str, resource_type: ResourceType) -> bool {
+        let (Ok(page), Ok(url)) = (url::Url::parse(page), url::Url::parse(url)) else {
+            return false;
+        };
+        if !matches!(url.scheme(), "http" | "https" | "ws" | "wss") {
+            return false;
+        }
+        let source_host = page.host_str().map(str::to_ascii_lowercase);
+        if !source_host.as_deref().is_some_and(|host| self.applies_on(host)) {
+            return false;
+        }
+        let filters = self.filters.lock().unwrap().clone();
+        let request = Request {
+            url: &url,
+            source_host: source_host.as_deref(),
+            resource_type,
+        };
+        filters.check(&request).is_some_and(|rule| !rule.exception)
+    }
+
+    /// A load failed in tab `label`, showing `page`: count it if the rules
+    /// block it. Not every failure is the engine's filter, so this is as
+    /// close as WebKit lets us get.
+    pub fn confirm_blocked(&self, label: &str, page: &str, request: &BlockedRequest) {
+        let resource_type = ResourceType::parse(&request.resource_type).unwrap_or(ResourceType::Other);
+        if self.blocks(page, &request.url, resource_type) {
+            self.record_blocked(label);
+        }
+    }
+
+    /// The script hiding elements on the document at `url`, if any hide.
+    #[cfg_attr(not(windows), allow(dead_code))]
+    pub fn hiding_script(&self, url: &str) -> Option<String> {
+        let host = url::Url::parse(url).ok()?.host_str()?.to_ascii_lowercase();
+        if !self.applies_on(&host) {
+            return None;
+        }
+        let filters = self.filters.lock().unwrap().clone();
+        let selectors = filters.cosmetics().hidden_on(&host);
+        if selectors.is_empty() {
+            return None;
+        }
+        // One rule per selector, so an invalid one only drops itself.
+        let css: String = selectors
+            .iter()
+            .map(|s| format!("{} {{ display: none !important; }}\n", s))
+            .collect();
+        let css = serde_json::to_string(&css).expect("css serializes");
+        Some(format!("(() => {{\nconst CSS = {};\n{}\n}})();\n", css, HIDE))
+    }
+
+    pub fn record_blocked(&self, label: &str) {
+        let mut counts = self.counts.lock().unwrap();
+        let entry = counts.entry(label.to_string()).or_default();
+        entry.page += 1;
+        entry.total += 1;
+    }
+
+    /// A new document started in `label`.
+    pub fn reset_page(&self, label: &str) {
+        if let Some(entry) = self.counts.lock().unwrap().get_mut(label) {
+            entry.page = 0;
+        }
+    }
+
+    pub fn counts(&self, label: &str) -> BlockedCounts {
+        self.counts.lock().unwrap().get(label).copied().unwrap_or_default()
+    }
+
+    pub fn remove(&self, label: &str) {
+        self.counts.lock().unwrap().remove(label);
+    }
+
+    /// Copy `source` into the lists dir as `id` and count its rules.
+    fn import(&self, id: &str, source: &Path) -> Result<(String, ParseStats), BrsrError> {
+        let text = fs::read_to_string(source).map_err(|e| BrsrError::io(source, e))?;
+        let stats = filters::parse_stats(&text);
+        if stats.network == 0 && stats.cosmetic == 0 {
+            return Err(BrsrError::invalid(format!("{} has no filter rules we understand", source.display())));
+        }
+        storage::write_atomic(&self.list_path(id)?, text.as_bytes())?;
+        Ok((list_title(&text, source), stats))
+    }
+}
+
+#[tauri::command]
+pub fn get_content_blocker(blocker: tauri::State<'_, ContentBlocker>) -> Result<BlockerConfig, BrsrError> {
+    Ok(blocker.config())
+}
+
+/// Add a filter list from a local file.
+#[tauri::command]
+pub async fn add_filter_list(
+    window: tauri::Window,
+    blocker: tauri::State<'_, ContentBlocker>,
+    path: String,
+) -> Result<FilterList, BrsrError> {
+    let id = format!("list-{}", now_ms());
+    let (title, stats) = blocker.import(&id, Path::new(&path))?;
+    let list = FilterList {
+        id,
+        title,
+        source: path,
+        enabled: true,
+        network_rules: stats.network,
+        cosmetic_rules: stats.cosmetic,
+        unsupported_rules: stats.unsupported,
+        updated_at: now_ms(),
+    };
+    blocker.update(|config| {
+        config.lists.push(list.clone());
+        Ok(())
+    })?;
+    println!("[Rust] Added filter list '{}' ({} network, {} cosmetic rules)", list.title, stats.network, stats.cosmetic);
+    blocker.compile(window.app_handle());
+    Ok(list)
+}
+
+/// Read a list again from the file it was added from.
+#[tauri::command]
+pub async fn reload_filter_list(
+    window: tauri::Window,
+    blocker: tauri::State<'_, ContentBlocker>,
+    id: String,
+) -> Result<FilterList, BrsrError> {
+    let source = blocker
+        .config()
+        .lists
+        .into_iter()
+        .find(|l| l.id == id)
+        .map(|l| l.source)
+        .ok_or_else(|| BrsrError::not_found("filter list", &id))?;
+    let (title, stats) = blocker.import(&id, Path::new(&source))?;
+    let config = blocker.update(|config| {
+        if let Some(list) = config.lists.iter_mut().find(|l| l.id == id) {
+            list.title = title;
+            list.network_rules = stats.network;
+            list.cosmetic_rules = stats.cosmetic;
+            list.unsupported_rules = stats.unsupported;
+            list.updated_at = now_ms();
+        }
+        Ok(())
+    })?;
+    blocker.compile(window.app_handle());
+    config
+        .lists
+        .into_iter()
+        .find(|l| l.id == id)
+        .ok_or_else(|| BrsrError::not_found("filter list", &id))
+}
+
+#[tauri::command]
+pub async fn remove_filter_list(
+    window: tauri::Window,
+    blocker: tauri::State<'_, ContentBlocker>,
+    id: String,
+) -> Result<BlockerConfig, BrsrError> {
+    let config = blocker.update(|config| {
+        let before = config.lists.len();
+        config.lists.retain(|l| l.id != id);
+        if config.lists.len() == before {
+            return Err(BrsrError::not_found("filter list", &id));
+        }
+        Ok(())
+    })?;
+    if let Ok(path) = blocker.list_path(&id) {
+        let _ = fs::remove_file(path);
+    }
+    blocker.compile(window.app_handle());
+    Ok(config)
+}
+
+#[tauri::command]
+pub async fn set_filter_list_enabled(
+    window: tauri::Window,
+    blocker: tauri::State<'_, ContentBlocker>,
+    id: String,
+    enabled: bool,
+) -> Result<BlockerConfig, BrsrError> {
+    let config = blocker.update(|config| {
+        let list = config
+            .lists
+            .iter_mut()
+            .find(|l| l.id == id)
+            .ok_or_else(|| BrsrError::not_found("filter list", &id))?;
+        list.enabled = enabled;
+        Ok(())
+    })?;
+    blocker.compile(window.app_handle());
+    Ok(config)
+}
+
+/// Turn content blocking on or off everywhere.
+#[tauri::command]
+pub async fn set_content_blocking(
+    window: tauri::Window,
+    blocker: tauri::State<'_, ContentBlocker>,
+    enabled: bool,
+) -> Result<BlockerConfig, BrsrError> {
+    let config = blocker.update(|config| {
+        config.enabled = enabled;
+        Ok(())
+    })?;
+    blocker.install(window.app_handle());
+    Ok(config)
+}
+
+/// Turn blocking on or off for a site and its subdomains.
+#[tauri::command]
+pub async fn set_site_blocking(
+    window: tauri::Window,
+    blocker: tauri::State<'_, ContentBlocker>,
+    host: String,
+    enabled: bool,
+) -> Result<BlockerConfig, BrsrError> {
+    let host = scripts::normalize_host(&host)?;
+    let config = blocker.update(|config| {
+        if enabled {
+            config.disabled_sites.remove(&host);
+        } else {
+            config.disabled_sites.insert(host.clone());
+        }
+        Ok(())
+    })?;
+    blocker.install(window.app_handle());
+    Ok(config)
+}
+
+#[tauri::command]
+pub fn get_blocked_counts(
+    registry: tauri::State<'_, TabRegistry>,
+    blocker: tauri::State<'_, ContentBlocker>,
+    label: String,
+) -> Result<BlockedCounts, BrsrError> {
+    if !registry.contains(&label) {
+        return Err(BrsrError::not_found("tab", &label));
+    }
+    Ok(blocker.counts(&label))
+}
@@ -0,0 +1,423 @@
+//! Handing the filter set to the engine, so it blocks subresources and hides
+//! elements itself:
+//!
+//! - WKWebView (macOS): the content rule lists (see `filters::content_rules`)
+//!   are compiled by `WKContentRuleListStore` and added to each tab's
+//!   `WKUserContentController`.
+//! - WebKitGTK (Linux): the same lists, compiled by a
+//!   `WebKitUserContentFilterStore` under `content-filters/` in the app data
+//!   dir and added to each tab's `WebKitUserContentManager`.
+//!
+//!   EasyList with EasyPrivacy makes about 108,000 network rules (20 MB of
+//!   JSON) and 11,000 hiding rules (2 MB). WebKit takes up to 150,000 rules
+//!   per list, older versions 50,000; a list it refuses is logged and left
+//!   out, the other one still applies.
+//! - WebView2 (Windows): there is no compiled format, so every request of a
+//!   tab goes through `WebResourceRequested` and the blocker's
+//!   [`FilterSet::check`](super::filters::FilterSet::check); a blocked one gets
+//!   an empty 403 response. Element hiding is a style sheet of the selectors
+//!   for the document's host, added as it starts loading (`ContentLoading`).
+//!   Generic selectors apply on every host: with EasyList that is some 20,000
+//!   of them, close to 1 MB of CSS in each document.
+//!
+//! Everything here runs on the main thread. Lists are installed again when
+//! the rules, the on/off switch or the disabled sites change, and open tabs,
+//! private ones included, switch to them without being rebuilt.
+
+use tauri::Manager;
+
+use super::filters::RuleList;
+use crate::tabs::TabRegistry;
+
+/// Where WebKitGTK keeps the compiled lists.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+const FILTERS_DIR: &str = "content-filters";
+
+/// Put `lists` in place of the previous ones in every tab, once all of them
+/// are compiled; an empty `lists` removes them. WebView2 has nothing to
+/// install: it asks the blocker about each request.
+pub fn install(app: &tauri::AppHandle, lists: Vec<RuleList>) {
+    platform::install(app, lists);
+}
+
+/// Apply content blocking to the new tab webview `webview`.
+pub fn attach(app: &tauri::AppHandle, webview: &tauri::Webview) {
+    platform::attach(app, webview);
+}
+
+#[cfg_attr(not(any(target_os = "macos", target_os = "linux")), allow(dead_code))]
+fn tab_webviews(app: &tauri::AppHandle) -> Vec<tauri::Webview> {
+    let registry = app.state::<TabRegistry>();
+    app.webviews()
+        .into_values()
+        .filter(|webview| registry.contains(webview.label()))
+        .collect()
+}
+
+/// The lists of one install, compiled one by one. The last to finish hands
+/// them all over, unless a newer install started in the meantime.
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+mod batch {
+    use std::cell::{Cell, RefCell};
+    use std::rc::Rc;
+
+    thread_local! {
+        static LATEST: Cell<u64> = const { Cell::new(0) };
+    }
+
+    pub struct Batch<T> {
+        generation: u64,
+        left: usize,
+        compiled: Vec<T>,
+    }
+
+    impl<T> Batch<T> {
+        /// A batch of `count` lists, superseding earlier ones.
+        pub fn start(count: usize) -> Rc<RefCell<Self>> {
+            let generation = LATEST.with(|latest| {
+                latest.set(latest.get() + 1);
+                latest.get()
+            });
+            Rc::new(RefCell::new(Self {
+                generation,
+                left: count,
+                compiled: Vec::new(),
+            }))
+        }
+
+        /// One list is done (`None` if it failed). Returns the batch's lists
+        /// when it was the last and the batch is still the latest.
+        pub fn done(&mut self, compiled: Option<T>) -> Option<Vec<T>> {
+            self.compiled.extend(compiled);
+            self.left = self.left.saturating_sub(1);
+            let latest = LATEST.with(Cell::get) == self.generation;
+            (self.left == 0 && latest).then(|| std::mem::take(&mut self.compiled))
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::cell::RefCell;
+    use std::ffi::CStr;
+    use std::os::raw::c_char;
+
+    use block::ConcreteBlock;
+    use objc::runtime::Object;
+    use objc::{class, msg_send, sel, sel_impl};
+    use objc_foundation::{INSString, NSString};
+
+    use super::batch::Batch;
+    use super::tab_webviews;
+    use crate::blocker::filters::RuleList;
+
+    /// A compiled `WKContentRuleList`, retained until dropped.
+    struct Compiled(*mut Object);
+
+    impl Drop for Compiled {
+        fn drop(&mut self) {
+            unsafe {
+                let _: () = msg_send![self.0, release];
+            }
+        }
+    }
+
+    thread_local! {
+        /// The lists in use.
+        static LISTS: RefCell<Vec<Compiled>> = const { RefCell::new(Vec::new()) };
+    }
+
+    unsafe fn to_string(string: *mut Object) -> Option<String> {
+        if string.is_null() {
+            return None;
+        }
+        let bytes: *const c_char = msg_send![string, UTF8String];
+        (!bytes.is_null()).then(|| CStr::from_ptr(bytes).to_string_lossy().into_owned())
+    }
+
+    /// Swap the rule lists of the WKWebView `view` for the ones in use.
+    unsafe fn apply(view: *mut Object) {
+        let configuration: *mut Object = msg_send![view, configuration];
+        let controller: *mut Object = msg_send![configuration, userContentController];
+        let _: () = msg_send![controller, removeAllContentRuleLists];
+        LISTS.with(|lists| {
+            for list in lists.borrow().iter() {
+                let _: () = msg_send![controller, addContentRuleList: list.0];
+            }
+        });
+    }
+
+    fn apply_to(webview: &tauri::Webview) {
+        let result = webview.with_webview(|platform| unsafe { apply(platform.inner() as *mut Object) });
+        if let Err(e) = result {
+            eprintln!("[Rust] Could not apply content rules to '{}': {}", webview.label(), e);
+        }
+    }
+
+    fn replace(app: &tauri::AppHandle, compiled: Vec<Compiled>) {
+        LISTS.with(|lists| *lists.borrow_mut() = compiled);
+        for webview in tab_webviews(app) {
+            apply_to(&webview);
+        }
+    }
+
+    pub fn install(app: &tauri::AppHandle, lists: Vec<RuleList>) {
+        let handle = app.clone();
+        let result = app.run_on_main_thread(move || unsafe {
+            let batch = Batch::start(lists.len());
+            if lists.is_empty() {
+                replace(&handle, Vec::new());
+                return;
+            }
+            let store: *mut Object = msg_send![class!(WKContentRuleListStore), defaultStore];
+            for list in lists {
+                let app = handle.clone();
+                let batch = batch.clone();
+                let id = list.id;
+                let compiled = ConcreteBlock::new(move |compiled: *mut Object, error: *mut Object| {
+                    let compiled = if compiled.is_null() {
+                        let reason: *mut Object = msg_send![error, localizedDescription];
+                        let reason = to_string(reason).unwrap_or_default();
+                        eprintln!("[Rust] WebKit refused content rules '{}': {}", id, reason);
+                        None
+                    } else {
+                        let _: *mut Object = msg_send![compiled, retain];
+                        Some(Compiled(compiled))
+                    };
+                    if let Some(all) = batch.borrow_mut().done(compiled) {
+                        replace(&app, all);
+                    }
+                })
+                .copy();
+                let _: () = msg_send![
+                    store,
+                    compileContentRuleListForIdentifier: NSString::from_str(list.id)
+                    encodedContentRuleList: NSString::from_str(&list.json)
+                    completionHandler: &*compiled
+                ];
+            }
+        });
+        if let Err(e) = result {
+            eprintln!("[Rust] Could not install content rules: {}", e);
+        }
+    }
+
+    pub fn attach(_app: &tauri::AppHandle, webview: &tauri::Webview) {
+        apply_to(webview);
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::cell::RefCell;
+
+    use webkit2gtk::gio::Cancellable;
+    use webkit2gtk::glib::Bytes;
+    use webkit2gtk::{UserContentFilter, UserContentFilterStore, UserContentManagerExt, WebViewExt};
+
+    use super::batch::Batch;
+    use super::{tab_webviews, FILTERS_DIR};
+    use crate::blocker::filters::RuleList;
+    use crate::storage;
+
+    thread_local! {
+        /// The compiled filters in use.
+        static FILTERS: RefCell<Vec<UserContentFilter>> = const { RefCell::new(Vec::new()) };
+    }
+
+    fn apply_to(webview: &tauri::Webview) {
+        let result = webview.with_webview(|platform| {
+            let Some(manager) = platform.inner().user_content_manager() else {
+                return;
+            };
+            manager.remove_all_filters();
+            FILTERS.with(|filters| {
+                for filter in filters.borrow().iter() {
+                    manager.add_filter(filter);
+                }
+            });
+        });
+        if let Err(e) = result {
+            eprintln!("[Rust] Could not apply content filters to '{}': {}", webview.label(), e);
+        }
+    }
+
+    fn replace(app: &tauri::AppHandle, compiled: Vec<UserContentFilter>) {
+        FILTERS.with(|filters| *filters.borrow_mut() = compiled);
+        for webview in tab_webviews(app) {
+            apply_to(&webview);
+        }
+    }
+
+    pub fn install(app: &tauri::AppHandle, lists: Vec<RuleList>) {
+        let dir = match storage::data_file(app, FILTERS_DIR) {
+            Ok(dir) => dir,
+            Err(e) => {
+                eprintln!("[Rust] Content filters unavailable: {}", e);
+                return;
+            }
+        };
+        let handle = app.clone();
+        let result = app.run_on_main_thread(move || {
+            let batch = Batch::start(lists.len());
+            if lists.is_empty() {
+                replace(&handle, Vec::new());
+                return;
+            }
+            let store = UserContentFilterStore::new(&dir.to_string_lossy());
+            for list in lists {
+                let app = handle.clone();
+                let batch = batch.clone();
+                let id = list.id;
+                let source = Bytes::from_owned(list.json.into_bytes());
+                store.save(id, &source, None::<&Cancellable>, move |compiled| {
+                    let compiled = compiled
+                        .map_err(|e| eprintln!("[Rust] WebKit refused content rules '{}': {}", id, e))
+                        .ok();
+                    if let Some(all) = batch.borrow_mut().done(compiled) {
+                        replace(&app, all);
+                    }
+                });
+            }
+        });
+        if let Err(e) = result {
+            eprintln!("[Rust] Could not install content filters: {}", e);
+        }
+    }
+
+    pub fn attach(_app: &tauri::AppHandle, webview: &tauri::Webview) {
+        apply_to(webview);
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use tauri::Manager;
+    use webview2_com::Microsoft::Web::WebView2::Win32::{
+        ICoreWebView2Controller, ICoreWebView2ExecuteScriptCompletedHandler, ICoreWebView2_2,
+        COREWEBVIEW2_WEB_RESOURCE_CONTEXT, COREWEBVIEW2_WEB_RESOURCE_CONTEXT_ALL,
+        COREWEBVIEW2_WEB_RESOURCE_CONTEXT_DOCUMENT, COREWEBVIEW2_WEB_RESOURCE_CONTEXT_FETCH,
+        COREWEBVIEW2_WEB_RESOURCE_CONTEXT_FONT, COREWEBVIEW2_WEB_RESOURCE_CONTEXT_IMAGE,
+        COREWEBVIEW2_WEB_RESOURCE_CONTEXT_MEDIA, COREWEBVIEW2_WEB_RESOURCE_CONTEXT_PING,
+        COREWEBVIEW2_WEB_RESOURCE_CONTEXT_SCRIPT, COREWEBVIEW2_WEB_RESOURCE_CONTEXT_STYLESHEET,
+        COREWEBVIEW2_WEB_RESOURCE_CONTEXT_WEBSOCKET, COREWEBVIEW2_WEB_RESOURCE_CONTEXT_XML_HTTP_REQUEST,
+    };
+    use webview2_com::{take_pwstr, ContentLoadingEventHandler, WebResourceRequestedEventHandler};
+    use windows::core::{Interface, HSTRING, PWSTR};
+    use windows::Win32::System::Com::IStream;
+
+    use crate::blocker::filters::{ResourceType, RuleList};
+    use crate::blocker::ContentBlocker;
+    use crate::tabs::TabRegistry;
+
+    fn resource_type(context: COREWEBVIEW2_WEB_RESOURCE_CONTEXT) -> ResourceType {
+        match context {
+            // Top-level documents are told apart by the caller.
+            COREWEBVIEW2_WEB_RESOURCE_CONTEXT_DOCUMENT => ResourceType::Subdocument,
+            COREWEBVIEW2_WEB_RESOURCE_CONTEXT_SCRIPT => ResourceType::Script,
+            COREWEBVIEW2_WEB_RESOURCE_CONTEXT_IMAGE => ResourceType::Image,
+            COREWEBVIEW2_WEB_RESOURCE_CONTEXT_STYLESHEET => ResourceType::Stylesheet,
+            COREWEBVIEW2_WEB_RESOURCE_CONTEXT_FONT => ResourceType::Font,
+            COREWEBVIEW2_WEB_RESOURCE_CONTEXT_MEDIA => ResourceType::Media,
+            COREWEBVIEW2_WEB_RESOURCE_CONTEXT_XML_HTTP_REQUEST | COREWEBVIEW2_WEB_RESOURCE_CONTEXT_FETCH => {
+                ResourceType::Xmlhttprequest
+            }
+            COREWEBVIEW2_WEB_RESOURCE_CONTEXT_WEBSOCKET => ResourceType::Websocket,
+            COREWEBVIEW2_WEB_RESOURCE_CONTEXT_PING => ResourceType::Ping,
+            _ => ResourceType::Other,
+        }
+    }
+
+    pub fn install(_app: &tauri::AppHandle, _lists: Vec<RuleList>) {}
+
+    unsafe fn hook(app: tauri::AppHandle, label: String, controller: ICoreWebView2Controller) -> windows::core::Result<()> {
+        let core = controller.CoreWebView2()?;
+        let environment = core.cast::<ICoreWebView2_2>()?.Environment()?;
+        core.AddWebResourceRequestedFilter(&HSTRING::from("*"), COREWEBVIEW2_WEB_RESOURCE_CONTEXT_ALL)?;
+        let mut token = 0;
+
+        let requests_app = app.clone();
+        let requested = WebResourceRequestedEventHandler::create(Box::new(move |_, args| {
+            let Some(args) = args else {
+                return Ok(());
+            };
+            let mut uri = PWSTR::null();
+            args.Request()?.Uri(&mut uri)?;
+            let uri = take_pwstr(uri);
+            let mut context = COREWEBVIEW2_WEB_RESOURCE_CONTEXT::default();
+            args.ResourceContext(&mut context)?;
+            // The navigation policy set the tab's URL as its navigation started.
+            let Some(page) = requests_app.state::<TabRegistry>().get(&label).map(|tab| tab.url) else {
+                return Ok(());
+            };
+            if context == COREWEBVIEW2_WEB_RESOURCE_CONTEXT_DOCUMENT && uri == page {
+                return Ok(());
+            }
+            let blocker = requests_app.state::<ContentBlocker>();
+            if blocker.blocks(&page, &uri, resource_type(context)) {
+                blocker.record_blocked(&label);
+                let response =
+                    environment.CreateWebResourceResponse(None::<&IStream>, 403, &HSTRING::from("Blocked"), &HSTRING::new())?;
+                args.SetResponse(&response)?;
+            }
+            Ok(())
+        }));
+        core.add_WebResourceRequested(&requested, &mut token)?;
+
+        let loading = ContentLoadingEventHandler::create(Box::new(move |sender, _| {
+            let Some(sender) = sender else {
+                return Ok(());
+            };
+            let mut source = PWSTR::null();
+            sender.Source(&mut source)?;
+            if let Some(script) = app.state::<ContentBlocker>().hiding_script(&take_pwstr(source)) {
+                sender.ExecuteScript(&HSTRING::from(script), None::<&ICoreWebView2ExecuteScriptCompletedHandler>)?;
+            }
+            Ok(())
+        }));
+        core.add_ContentLoading(&loading, &mut token)?;
+        Ok(())
+    }
+
+    pub fn attach(app: &tauri::AppHandle, webview: &tauri::Webview) {
+        let app = app.clone();
+        let label = webview.label().to_string();
+        let result = webview.with_webview(move |platform| {
+            if let Err(e) = unsafe { hook(app, label, platform.controller()) } {
+                eprintln!("[Rust] Content blocking unavailable in this tab: {}", e);
+            }
+        });
+        if let Err(e) = result {
+            eprintln!("[Rust] Could not hook content blocking into '{}': {}", webview.label(), e);
+        }
+    }
+}
+
+/// No engine filter here; only navigations are checked.
+#[cfg(not(any(target_os = "macos", target_os = "linux", windows)))]
+mod platform {
+    use crate::blocker::filters::RuleList;
+
+    pub fn install(_app: &tauri::AppHandle, _lists: Vec<RuleList>) {}
+
+    pub fn attach(_app: &tauri::AppHandle, _webview: &tauri::Webview) {}
+}
+
+#[cfg(all(test, any(target_os = "macos", target_os = "linux")))]
+mod tests {
+    use super::batch::Batch;
+
+    #[test]
+    fn only_the_latest_install_lands() {
+        let first = Batch::start(2);
+        let second = Batch::start(1);
+        assert_eq!(first.borrow_mut().done(Some(1)), None);
+        assert_eq!(second.borrow_mut().done(Some(3)), Some(vec![3]));
+        // Finished, but superseded.
+        assert_eq!(first.borrow_mut().done(Some(2)), None);
+
+        let failed = Batch::<u8>::start(2);
+        assert_eq!(failed.borrow_mut().done(None), None);
+        assert_eq!(failed.borrow_mut().done(Some(4)), Some(vec![4]));
+    }
+}
@@ -0,0 +1,436 @@
+//! Netscape bookmark file format (`<!DOCTYPE NETSCAPE-Bookmark-file-1>`), the
+//! interchange format Chrome, Firefox and Safari all import and export.
+//!
+//! The format is loose HTML: `<DT><H3>` opens a folder whose contents follow in
+//! the next `<DL>`, `<DT><A HREF=…>` is a bookmark. The parser below is a
+//! tolerant tag scanner rather than a full HTML parser, which is what every
+//! browser's importer does too.
+
+use super::{BookmarkTreeNode, NodeKind, BOOKMARKS_BAR_ID};
+
+/// A parsed entry, before it is given ids and stored.
+#[derive(Debug, Clone)]
+pub enum ImportedItem {
+    Folder {
+        title: String,
+        add_date: Option<u64>,
+        toolbar: bool,
+        children: Vec<ImportedItem>,
+    },
+    Bookmark {
+        title: String,
+        url: String,
+        add_date: Option<u64>,
+        tags: Vec<String>,
+        icon: Option<String>,
+    },
+}
+
+struct Tag {
+    name: String,
+    closing: bool,
+    attrs: Vec<(String, String)>,
+}
+
+impl Tag {
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// Parse a bookmark file into a tree of folders and bookmarks.
+pub fn parse(html: &str) -> Vec<ImportedItem> {
+    // Folder contents are collected on a stack; a `<DL>` right after an `<H3>`
+    // opens that folder, and `</DL>` closes it.
+    let mut stack: Vec<(Option<ImportedItem>, Vec<ImportedItem>)> = vec![(None, Vec::new())];
+    let mut pending_folder: Option<ImportedItem> = None;
+    let mut pos = 0;
+
+    while let Some((tag, end)) = next_tag(html, pos) {
+        pos = end;
+        match (tag.name.as_str(), tag.closing) {
+            ("H3", false) => {
+                let (text, after) = text_until(html, pos, "</h3");
+                pos = after;
+                if let Some(folder) = pending_folder.take() {
+                    // An H3 with no DL following it is an empty folder.
+                    stack.last_mut().unwrap().1.push(folder);
+                }
+                pending_folder = Some(ImportedItem::Folder {
+                    title: decode_entities(text.trim()),
+                    add_date: tag.attr("ADD_DATE").and_then(parse_epoch_seconds),
+                    toolbar: tag.attr("PERSONAL_TOOLBAR_FOLDER").is_some_and(|v| v.eq_ignore_ascii_case("true")),
+                    children: Vec::new(),
+                });
+            }
+            ("DL", false) => stack.push((pending_folder.take(), Vec::new())),
+            ("DL", true) => {
+                if let Some(folder) = pending_folder.take() {
+                    stack.last_mut().unwrap().1.push(folder);
+                }
+                if stack.len() > 1 {
+                    let (folder, items) = stack.pop().unwrap();
+                    let parent = &mut stack.last_mut().unwrap().1;
+                    match folder {
+                        Some(ImportedItem::Folder { title, add_date, toolbar, .. }) => parent.push(ImportedItem::Folder {
+                            title,
+                            add_date,
+                            toolbar,
+                            children: items,
+                        }),
+                        // A DL without a heading (the top-level list): splice its items in.
+                        _ => parent.extend(items),
+                    }
+                }
+            }
+            ("A", false) => {
+                let (text, after) = text_until(html, pos, "</a");
+                pos = after;
+                let Some(url) = tag.attr("HREF").map(decode_entities).filter(|u| !u.is_empty()) else {
+                    continue;
+                };
+                if let Some(folder) = pending_folder.take() {
+                    stack.last_mut().unwrap().1.push(folder);
+                }
+                let title = decode_entities(text.trim());
+                stack.last_mut().unwrap().1.push(ImportedItem::Bookmark {
+                    title: if title.is_empty() { url.clone() } else { title },
+                    url,
+                    add_date: tag.attr("ADD_DATE").and_then(parse_epoch_seconds),
+                    tags: tag
+                        .attr("TAGS")
+                        .map(|t| t.split(',').map(|s| decode_entities(s.trim())).filter(|s| !s.is_empty()).collect())
+                        .unwrap_or_default(),
+                    icon: tag.attr("ICON_URI").or(tag.attr("ICON")).map(decode_entities),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    // Unbalanced files: fold whatever is still open back into the root.
+    if let Some(folder) = pending_folder.take() {
+        stack.last_mut().unwrap().1.push(folder);
+    }
+    while stack.len() > 1 {
+        let (folder, items) = stack.pop().unwrap();
+        let parent = &mut stack.last_mut().unwrap().1;
+        match folder {
+            Some(ImportedItem::Folder { title, add_date, toolbar, .. }) => parent.push(ImportedItem::Folder {
+                title,
+                add_date,
+                toolbar,
+                children: items,
+            }),
+            _ => parent.extend(items),
+        }
+    }
+    stack.pop().map(|(_, items)| items).unwrap_or_default()
+}
+
+/// Render the bookmark tree as a Netscape bookmark file.
+pub fn render(roots: &[BookmarkTreeNode]) -> String {
+    let mut out = String::from(
+        "<!DOCTYPE NETSCAPE-Bookmark-file-1>\n\
+         <!-- This is an automatically generated file.\n     It will be read and overwritten.\n     DO NOT EDIT! -->\n\
+         <META HTTP-EQUIV=\"Content-Type\" CONTENT=\"text/html; charset=UTF-8\">\n\
+         <TITLE>Bookmarks</TITLE>\n\
+         <H1>Bookmarks</H1>\n\
+         <DL><p>\n",
+    );
+    for root in roots {
+        render_node(root, 1, &mut out);
+    }
+    out.push_str("</DL><p>\n");
+    out
+}
+
+fn render_node(item: &BookmarkTreeNode, depth: usize, out: &mut String) {
+    let indent = "    ".repeat(depth);
+    let node = &item.node;
+    let add_date = node.created_at / 1000;
+    match node.kind {
+        NodeKind::Folder => {
+            let toolbar = if node.id == BOOKMARKS_BAR_ID {
+                " PERSONAL_TOOLBAR_FOLDER=\"true\""
+            } else {
+                ""
+            };
+            out.push_str(&format!(
+                "{indent}<DT><H3 ADD_DATE=\"{}\" LAST_MODIFIED=\"{}\"{}>{}</H3>\n{indent}<DL><p>\n",
+                add_date,
+                node.updated_at / 1000,
+                toolbar,
+                escape(&node.title),
+            ));
+            for child in &item.children {
+                render_node(child, depth + 1, out);
+            }
+            out.push_str(&format!("{indent}</DL><p>\n"));
+        }
+        NodeKind::Bookmark => {
+            let mut attrs = format!("HREF=\"{}\" ADD_DATE=\"{}\"", escape(node.url.as_deref().unwrap_or("")), add_date);
+            if !node.tags.is_empty() {
+                attrs.push_str(&format!(" TAGS=\"{}\"", escape(&node.tags.join(","))));
+            }
+            match node.favicon_url.as_deref() {
+                Some(icon) if icon.starts_with("data:") => attrs.push_str(&format!(" ICON=\"{}\"", escape(icon))),
+                Some(icon) => attrs.push_str(&format!(" ICON_URI=\"{}\"", escape(icon))),
+                None => {}
+            }
+            out.push_str(&format!("{indent}<DT><A {}>{}</A>\n", attrs, escape(&node.title)));
+        }
+    }
+}
+
+/// Find the next tag at or after `from`. Comments and doctypes are skipped.
+fn next_tag(html: &str, mut from: usize) -> Option<(Tag, usize)> {
+    loop {
+        let start = from + html.get(from..)?.find('<')?;
+        let rest = &html[start + 1..];
+        if let Some(comment) = rest.strip_prefix("!--") {
+            from = comment.find("-->").map(|i| start + 4 + i + 3).unwrap_or(html.len());
+            continue;
+        }
+        let end = start + 1 + find_tag_end(rest)?;
+        let inner = &html[start + 1..end];
+        if inner.starts_with('!') || inner.starts_with('?') {
+            from = end + 1;
+            continue;
+        }
+        let (closing, inner) = match inner.strip_prefix('/') {
+            Some(i) => (true, i),
+            None => (false, inner),
+        };
+        let name_end = inner.find(|c: char| c.is_whitespace() || c == '/').unwrap_or(inner.len());
+        let name = inner[..name_end].to_ascii_uppercase();
+        let attrs = parse_attrs(&inner[name_end..]);
+        return Some((Tag { name, closing, attrs }, end + 1));
+    }
+}
+
+/// Index of the `>` closing a tag, skipping over quoted attribute values.
+fn find_tag_end(s: &str) -> Option<usize> {
+    let mut quote: Option<char> = None;
+    for (i, c) in s.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (None, '>') => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn parse_attrs(s: &str) -> Vec<(String, String)> {
+    let mut attrs = Vec::new();
+    let mut rest = s.trim_start();
+    while !rest.is_empty() {
+        let name_end = rest.find(|c: char| c == '=' || c.is_whitespace() || c == '/').unwrap_or(rest.len());
+        let name = rest[..name_end].to_string();
+        rest = rest[name_end..].trim_start();
+        let value = if let Some(after_eq) = rest.strip_prefix('=') {
+            let after_eq = after_eq.trim_start();
+            match after_eq.chars().next() {
+                Some(q @ ('"' | '\'')) => {
+                    let body = &after_eq[1..];
+                    let close = body.find(q).unwrap_or(body.len());
+                    rest = body.get(close + 1..).unwrap_or("");
+                    body[..close].to_string()
+                }
+                _ => {
+                    let end = after_eq.find(char::is_whitespace).unwrap_or(after_eq.len());
+                    rest = &after_eq[end..];
+                    after_eq[..end].to_string()
+                }
+            }
+        } else {
+            if name.is_empty() {
+                // Stray `/` or similar; skip a character to make progress.
+                rest = rest.get(1..).unwrap_or("");
+            }
+            String::new()
+        };
+        if !name.is_empty() {
+            attrs.push((name, value));
+        }
+        rest = rest.trim_start();
+    }
+    attrs
+}
+
+/// Text from `from` up to the (case-insensitive) `closing` tag; returns the text
+/// and the position after the closing tag.
+fn text_until<'a>(html: &'a str, from: usize, closing: &str) -> (&'a str, usize) {
+    let lower = html[from..].to_ascii_lowercase();
+    match lower.find(closing) {
+        Some(i) => {
+            let after = html[from + i..].find('>').map(|j| from + i + j + 1).unwrap_or(html.len());
+            (&html[from..from + i], after)
+        }
+        None => (&html[from..], html.len()),
+    }
+}
+
+fn parse_epoch_seconds(value: &str) -> Option<u64> {
+    let secs: u64 = value.trim().parse().ok()?;
+    // Some exporters write microseconds; anything past year ~5000 in seconds is not seconds.
+    let ms = if secs > 100_000_000_000 { secs / 1000 } else { secs * 1000 };
+    Some(ms)
+}
+
+fn decode_entities(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let Some(semi) = rest.find(';').filter(|&i| i <= 10) else {
+            out.push('&');
+            rest = &rest[1..];
+            continue;
+        };
+        let entity = &rest[1..semi];
+        let decoded = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" | "#39" => Some('\''),
+            "nbsp" => Some('\u{a0}'),
+            _ => entity
+                .strip_prefix("#x")
+                .or_else(|| entity.strip_prefix("#X"))
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[semi + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bookmarks::BookmarkNode;
+
+    fn node(id: u64, kind: NodeKind, title: &str, url: Option<&str>) -> BookmarkNode {
+        BookmarkNode {
+            id,
+            parent_id: None,
+            kind,
+            title: title.to_string(),
+            url: url.map(str::to_string),
+            tags: Vec::new(),
+            favicon_url: None,
+            created_at: 1_700_000_000_000,
+            updated_at: 1_700_000_000_000,
+        }
+    }
+
+    fn leaf(node: BookmarkNode) -> BookmarkTreeNode {
+        BookmarkTreeNode { node, children: Vec::new() }
+    }
+
+    #[test]
+    fn round_trips_a_rendered_tree() {
+        let mut tagged = node(3, NodeKind::Bookmark, "Tom & \"Jerry\" <3", Some("https://a.test/?x=1&y=2"));
+        tagged.tags = vec!["one".into(), "two".into()];
+        tagged.favicon_url = Some("https://a.test/favicon.ico".into());
+        let mut inline_icon = node(5, NodeKind::Bookmark, "Inline", Some("https://b.test/"));
+        inline_icon.favicon_url = Some("data:image/png;base64,AAAA".into());
+        let roots = vec![
+            BookmarkTreeNode {
+                node: node(BOOKMARKS_BAR_ID, NodeKind::Folder, "Bookmarks Bar", None),
+                children: vec![leaf(tagged)],
+            },
+            BookmarkTreeNode {
+                node: node(2, NodeKind::Folder, "Other", None),
+                children: vec![
+                    BookmarkTreeNode {
+                        node: node(4, NodeKind::Folder, "Nested", None),
+                        children: vec![leaf(inline_icon)],
+                    },
+                    leaf(node(6, NodeKind::Folder, "Empty", None)),
+                ],
+            },
+        ];
+
+        let items = parse(&render(&roots));
+        assert_eq!(items.len(), 2);
+        let ImportedItem::Folder { title, toolbar, children, add_date } = &items[0] else { panic!("not a folder") };
+        assert_eq!((title.as_str(), *toolbar, *add_date), ("Bookmarks Bar", true, Some(1_700_000_000_000)));
+        let ImportedItem::Bookmark { title, url, tags, icon, .. } = &children[0] else { panic!("not a bookmark") };
+        assert_eq!(title, "Tom & \"Jerry\" <3");
+        assert_eq!(url, "https://a.test/?x=1&y=2");
+        assert_eq!(tags, &["one", "two"]);
+        assert_eq!(icon.as_deref(), Some("https://a.test/favicon.ico"));
+
+        let ImportedItem::Folder { toolbar, children, .. } = &items[1] else { panic!("not a folder") };
+        assert!(!toolbar);
+        assert_eq!(children.len(), 2);
+        let ImportedItem::Folder { title, children: nested, .. } = &children[0] else { panic!("not a folder") };
+        assert_eq!(title, "Nested");
+        let ImportedItem::Bookmark { icon, .. } = &nested[0] else { panic!("not a bookmark") };
+        assert_eq!(icon.as_deref(), Some("data:image/png;base64,AAAA"));
+        assert!(matches!(
+            &children[1],
+            ImportedItem::Folder { title, children, .. } if title == "Empty" && children.is_empty()
+        ));
+    }
+
+    #[test]
+    fn parses_loose_exports() {
+        let html = r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<!-- <DT><A HREF="https://commented.test/">no</A> -->
+<dl><p>
+  <dt><h3 add_date='1600000000'>Folder</h3>
+  <dl><p>
+    <dt><a href="https://a.test/" ADD_DATE="1600000000000000">&#65;&#x42;</a>
+    <dt><a href="">no url</a>
+    <dt><a href=https://bare.test/></a>
+  </dl><p>
+  <dt><h3>No list</h3>
+  <dt><a href="https://top.test/" TAGS=" x , ,y ">Top</a>
+"#;
+        let items = parse(html);
+        assert_eq!(items.len(), 3);
+        let ImportedItem::Folder { children, add_date, .. } = &items[0] else { panic!("not a folder") };
+        assert_eq!(*add_date, Some(1_600_000_000_000));
+        assert_eq!(children.len(), 2);
+        assert!(matches!(
+            &children[0],
+            ImportedItem::Bookmark { title, add_date: Some(1_600_000_000_000), .. } if title == "AB"
+        ));
+        // An empty title falls back to the URL.
+        assert!(matches!(&children[1], ImportedItem::Bookmark { title, .. } if title == "https://bare.test/"));
+        assert!(matches!(
+            &items[1],
+            ImportedItem::Folder { title, children, .. } if title == "No list" && children.is_empty()
+        ));
+        assert!(matches!(&items[2], ImportedItem::Bookmark { tags, .. } if tags == &["x", "y"]));
+    }
+}
@@ -0,0 +1,578 @@
+//! Bookmarks: a tree of folders and bookmarks with tags and favicons, persisted
+//! as JSON under the app data dir, with Netscape HTML import/export (see `html`).
+//!
+//! Nodes are kept in a flat list; a node's position among its siblings is its
+//! order in that list. Two root folders always exist: the bookmarks bar and
+//! "Other Bookmarks".
+
+mod html;
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::blocker::filters::base_domain;
+use crate::error::BrsrError;
+use crate::storage;
+use crate::tabs::now_ms;
+use crate::telemetry::event::PageInfo;
+
+const BOOKMARKS_FILE: &str = "bookmarks.json";
+
+pub const BOOKMARKS_BAR_ID: u64 = 1;
+pub const OTHER_BOOKMARKS_ID: u64 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NodeKind {
+    Folder,
+    Bookmark,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BookmarkNode {
+    pub id: u64,
+    /// `None` only for the two root folders.
+    pub parent_id: Option<u64>,
+    pub kind: NodeKind,
+    pub title: String,
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub favicon_url: Option<String>,
+    pub created_at: u64,
+    pub updated_at: u64,
+}
+
+/// A node with its children, as returned by `bookmarks_tree`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BookmarkTreeNode {
+    #[serde(flatten)]
+    pub node: BookmarkNode,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<BookmarkTreeNode>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BookmarkData {
+    next_id: u64,
+    nodes: Vec<BookmarkNode>,
+}
+
+impl Default for BookmarkData {
+    fn default() -> Self {
+        let now = now_ms();
+        let root = |id, title: &str| BookmarkNode {
+            id,
+            parent_id: None,
+            kind: NodeKind::Folder,
+            title: title.into(),
+            url: None,
+            tags: Vec::new(),
+            favicon_url: None,
+            created_at: now,
+            updated_at: now,
+        };
+        Self {
+            next_id: OTHER_BOOKMARKS_ID + 1,
+            nodes: vec![root(BOOKMARKS_BAR_ID, "Bookmarks Bar"), root(OTHER_BOOKMARKS_ID, "Other Bookmarks")],
+        }
+    }
+}
+
+/// Fields `bookmark_update` may change; absent fields are left alone.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BookmarkChanges {
+    pub title: Option<String>,
+    pub url: Option<String>,
+    pub tags: Option<Vec<String>>,
+    pub favicon_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSummary {
+    pub folders: usize,
+    pub bookmarks: usize,
+}
+
+impl BookmarkData {
+    fn get(&self, id: u64) -> Option<&BookmarkNode> {
+        self.nodes.iter().find(|n| n.id == id)
+    }
+
+    fn position(&self, id: u64) -> Result<usize, BrsrError> {
+        self.nodes
+            .iter()
+            .position(|n| n.id == id)
+            .ok_or_else(|| BrsrError::not_found("bookmark", id))
+    }
+
+    fn children(&self, parent: u64) -> impl Iterator<Item = &BookmarkNode> {
+        self.nodes.iter().filter(move |n| n.parent_id == Some(parent))
+    }
+
+    fn require_folder(&self, id: u64) -> Result<(), BrsrError> {
+        match self.get(id) {
+            Some(n) if n.kind == NodeKind::Folder => Ok(()),
+            Some(_) => Err(BrsrError::invalid(format!("Bookmark {} is not a folder", id))),
+            None => Err(BrsrError::not_found("folder", id)),
+        }
+    }
+
+    /// `id` and everything beneath it.
+    fn subtree(&self, id: u64) -> HashSet<u64> {
+        let mut ids = HashSet::from([id]);
+        let mut frontier = vec![id];
+        while let Some(parent) = frontier.pop() {
+            for child in self.children(parent) {
+                if ids.insert(child.id) {
+                    frontier.push(child.id);
+                }
+            }
+        }
+        ids
+    }
+
+    /// Place `node` under its parent at sibling `index` (appended when `None` or out of range).
+    fn place(&mut self, node: BookmarkNode, index: Option<usize>) {
+        let parent = node.parent_id;
+        let sibling_positions: Vec<usize> = self
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, n)| n.parent_id == parent)
+            .map(|(i, _)| i)
+            .collect();
+        let at = match index.and_then(|i| sibling_positions.get(i)) {
+            Some(&pos) => pos,
+            None => sibling_positions.last().map(|&p| p + 1).unwrap_or(self.nodes.len()),
+        };
+        self.nodes.insert(at, node);
+    }
+
+    fn add(
+        &mut self,
+        parent_id: u64,
+        kind: NodeKind,
+        title: String,
+        url: Option<String>,
+        tags: Vec<String>,
+        index: Option<usize>,
+    ) -> Result<BookmarkNode, BrsrError> {
+        self.require_folder(parent_id)?;
+        if kind == NodeKind::Bookmark {
+            let url = url.as_deref().ok_or_else(|| BrsrError::invalid("A bookmark needs a URL"))?;
+            url::Url::parse(url).map_err(|e| BrsrError::invalid_url(url, e))?;
+        }
+        let now = now_ms();
+        let node = BookmarkNode {
+            id: self.next_id,
+            parent_id: Some(parent_id),
+            kind,
+            title,
+            url,
+            tags: normalize_tags(tags),
+            favicon_url: None,
+            created_at: now,
+            updated_at: now,
+        };
+        self.next_id += 1;
+        self.place(node.clone(), index);
+        Ok(node)
+    }
+
+    fn update(&mut self, id: u64, changes: BookmarkChanges) -> Result<BookmarkNode, BrsrError> {
+        let pos = self.position(id)?;
+        let node = &mut self.nodes[pos];
+        if let Some(url) = changes.url {
+            if node.kind != NodeKind::Bookmark {
+                return Err(BrsrError::invalid("Folders have no URL"));
+            }
+            url::Url::parse(&url).map_err(|e| BrsrError::invalid_url(&url, e))?;
+            node.url = Some(url);
+        }
+        if let Some(title) = changes.title {
+            node.title = title;
+        }
+        if let Some(tags) = changes.tags {
+            node.tags = normalize_tags(tags);
+        }
+        if let Some(favicon) = changes.favicon_url {
+            node.favicon_url = (!favicon.is_empty()).then_some(favicon);
+        }
+        node.updated_at = now_ms();
+        Ok(node.clone())
+    }
+
+    fn move_node(&mut self, id: u64, parent_id: u64, index: Option<usize>) -> Result<BookmarkNode, BrsrError> {
+        if id == BOOKMARKS_BAR_ID || id == OTHER_BOOKMARKS_ID {
+            return Err(BrsrError::conflict("Root folders cannot be moved"));
+        }
+        self.require_folder(parent_id)?;
+        if self.subtree(id).contains(&parent_id) {
+            return Err(BrsrError::conflict("Cannot move a folder into itself"));
+        }
+        let pos = self.position(id)?;
+        let mut node = self.nodes.remove(pos);
+        node.parent_id = Some(parent_id);
+        node.updated_at = now_ms();
+        self.place(node.clone(), index);
+        Ok(node)
+    }
+
+    /// Remove a node and its descendants; returns how many nodes were removed.
+    fn remove(&mut self, id: u64) -> Result<usize, BrsrError> {
+        if id == BOOKMARKS_BAR_ID || id == OTHER_BOOKMARKS_ID {
+            return Err(BrsrError::conflict("Root folders cannot be deleted"));
+        }
+        self.position(id)?;
+        let doomed = self.subtree(id);
+        self.nodes.retain(|n| !doomed.contains(&n.id));
+        Ok(doomed.len())
+    }
+
+    fn tree_of(&self, id: u64) -> Option<BookmarkTreeNode> {
+        let node = self.get(id)?.clone();
+        let children = self.children(id).filter_map(|c| self.tree_of(c.id)).collect();
+        Some(BookmarkTreeNode { node, children })
+    }
+
+    fn tree(&self) -> Vec<BookmarkTreeNode> {
+        self.nodes
+            .iter()
+            .filter(|n| n.parent_id.is_none())
+            .filter_map(|n| self.tree_of(n.id))
+            .collect()
+    }
+
+    fn search(&self, text: Option<&str>, tag: Option<&str>) -> Vec<BookmarkNode> {
+        let text = text.map(str::to_lowercase).filter(|t| !t.is_empty());
+        let tag = tag.map(str::to_lowercase);
+        self.nodes
+            .iter()
+            .filter(|n| n.kind == NodeKind::Bookmark)
+            .filter(|n| tag.as_ref().is_none_or(|t| n.tags.contains(t)))
+            .filter(|n| {
+                text.as_ref().is_none_or(|t| {
+                    n.title.to_lowercase().contains(t)
+                        || n.url.as_deref().is_some_and(|u| u.to_lowercase().contains(t))
+                        || n.tags.iter().any(|tag| tag.contains(t))
+                })
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Attach a favicon to every bookmark of `url`. Returns whether anything changed.
+    fn set_favicon_for_url(&mut self, url: &str, favicon: &str) -> bool {
+        let mut changed = false;
+        for node in self.nodes.iter_mut() {
+            if node.url.as_deref() == Some(url) && node.favicon_url.as_deref() != Some(favicon) {
+                node.favicon_url = Some(favicon.to_string());
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    fn import(&mut self, parent_id: u64, items: Vec<html::ImportedItem>, summary: &mut ImportSummary) {
+        for item in items {
+            let (added, add_date) = match item {
+                html::ImportedItem::Folder { title, add_date, toolbar, children } => {
+                    let title = if toolbar && title.is_empty() { "Bookmarks Bar".into() } else { title };
+                    let Ok(folder) = self.add(parent_id, NodeKind::Folder, title, None, Vec::new(), None) else {
+                        continue;
+                    };
+                    summary.folders += 1;
+                    self.import(folder.id, children, summary);
+                    (folder, add_date)
+                }
+                html::ImportedItem::Bookmark { title, url, add_date, tags, icon } => {
+                    let Ok(mut node) = self.add(parent_id, NodeKind::Bookmark, title, Some(url), tags, None) else {
+                        continue;
+                    };
+                    summary.bookmarks += 1;
+                    node.favicon_url = icon;
+                    (node, add_date)
+                }
+            };
+            // Keep the original dates and icon from the file.
+            if let Ok(pos) = self.position(added.id) {
+                let stored = &mut self.nodes[pos];
+                stored.favicon_url = added.favicon_url;
+                if let Some(created) = add_date {
+                    stored.created_at = created;
+                }
+            }
+        }
+    }
+}
+
+fn normalize_tags(tags: Vec<String>) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim().to_lowercase();
+        if !tag.is_empty() && !out.contains(&tag) {
+            out.push(tag);
+        }
+    }
+    out
+}
+
+/// Managed state: the bookmark tree and where it is persisted.
+pub struct Bookmarks {
+    data: Mutex<BookmarkData>,
+    path: Option<PathBuf>,
+}
+
+impl Bookmarks {
+    pub fn load<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Self {
+        let path = storage::data_file(app, BOOKMARKS_FILE)
+            .map_err(|e| eprintln!("[Rust] Bookmarks will not persist: {}", e))
+            .ok();
+        let data = path.as_deref().map(storage::load_json).unwrap_or_default();
+        Self {
+            data: Mutex::new(data),
+            path,
+        }
+    }
+
+    /// Run `f` against a copy of the data and, once that copy is saved, keep
+    /// it. Nothing changes if either fails.
+    fn mutate<T>(&self, f: impl FnOnce(&mut BookmarkData) -> Result<T, BrsrError>) -> Result<T, BrsrError> {
+        let mut data = self.data.lock().unwrap();
+        let mut next = data.clone();
+        let out = f(&mut next)?;
+        if let Some(path) = &self.path {
+            storage::save_json(path, &next)?;
+        }
+        *data = next;
+        Ok(out)
+    }
+
+    /// Pick up a page's favicon from the script's `page-info` telemetry event.
+    /// `page_url` is the tab's URL as Rust tracks it; the page's own report of
+    /// where it is isn't trusted to pick which bookmarks change.
+    pub fn handle_page_info(&self, page_url: &str, info: &PageInfo) {
+        let Ok(page) = Url::parse(page_url) else {
+            return;
+        };
+        if let Some(icon) = info.icons.iter().find_map(|icon| page_icon(&page, icon)) {
+            self.note_page_icon(page_url, &icon);
+        }
+    }
+
+    /// Runs on every page load, so only writes the file when a bookmark's icon changed.
+    fn note_page_icon(&self, url: &str, icon: &str) {
+        let stale = |n: &BookmarkNode| n.url.as_deref() == Some(url) && n.favicon_url.as_deref() != Some(icon);
+        if !self.data.lock().unwrap().nodes.iter().any(stale) {
+            return;
+        }
+        if let Err(e) = self.mutate(|data| Ok(data.set_favicon_for_url(url, icon))) {
+            eprintln!("[Rust] Failed to store bookmark favicon: {}", e);
+        }
+    }
+}
+
+/// `icon` if it may stand for `page` in the UI: an image `data:` URL, or an
+/// http(s) URL on the page's own site.
+fn page_icon(page: &Url, icon: &str) -> Option<String> {
+    let icon = Url::parse(icon).ok()?;
+    let allowed = match icon.scheme() {
+        "data" => icon.path().starts_with("image/"),
+        "http" | "https" => match (icon.host_str(), page.host_str()) {
+            (Some(icon_host), Some(page_host)) => {
+                base_domain(&icon_host.to_ascii_lowercase()) == base_domain(&page_host.to_ascii_lowercase())
+            }
+            _ => false,
+        },
+        _ => false,
+    };
+    allowed.then(|| icon.to_string())
+}
+
+#[tauri::command]
+pub fn bookmarks_tree(bookmarks: tauri::State<'_, Bookmarks>) -> Result<Vec<BookmarkTreeNode>, BrsrError> {
+    Ok(bookmarks.data.lock().unwrap().tree())
+}
+
+#[tauri::command]
+pub fn bookmark_create(
+    bookmarks: tauri::State<'_, Bookmarks>,
+    parent_id: Option<u64>,
+    title: String,
+    url: String,
+    tags: Option<Vec<String>>,
+    index: Option<usize>,
+) -> Result<BookmarkNode, BrsrError> {
+    bookmarks.mutate(|data| {
+        data.add(
+            parent_id.unwrap_or(OTHER_BOOKMARKS_ID),
+            NodeKind::Bookmark,
+            title,
+            Some(url),
+            tags.unwrap_or_default(),
+            index,
+        )
+    })
+}
+
+#[tauri::command]
+pub fn bookmark_create_folder(
+    bookmarks: tauri::State<'_, Bookmarks>,
+    parent_id: Option<u64>,
+    title: String,
+    index: Option<usize>,
+) -> Result<BookmarkNode, BrsrError> {
+    bookmarks.mutate(|data| {
+        data.add(parent_id.unwrap_or(OTHER_BOOKMARKS_ID), NodeKind::Folder, title, None, Vec::new(), index)
+    })
+}
+
+#[tauri::command]
+pub fn bookmark_update(
+    bookmarks: tauri::State<'_, Bookmarks>,
+    id: u64,
+    changes: BookmarkChanges,
+) -> Result<BookmarkNode, BrsrError> {
+    bookmarks.mutate(|data| data.update(id, changes))
+}
+
+#[tauri::command]
+pub fn bookmark_move(
+    bookmarks: tauri::State<'_, Bookmarks>,
+    id: u64,
+    parent_id: u64,
+    index: Option<usize>,
+) -> Result<BookmarkNode, BrsrError> {
+    bookmarks.mutate(|data| data.move_node(id, parent_id, index))
+}
+
+#[tauri::command]
+pub fn bookmark_delete(bookmarks: tauri::State<'_, Bookmarks>, id: u64) -> Result<usize, BrsrError> {
+    bookmarks.mutate(|data| data.remove(id))
+}
+
+#[tauri::command]
+pub fn bookmarks_search(
+    bookmarks: tauri::State<'_, Bookmarks>,
+    text: Option<String>,
+    tag: Option<String>,
+) -> Result<Vec<BookmarkNode>, BrsrError> {
+    Ok(bookmarks.data.lock().unwrap().search(text.as_deref(), tag.as_deref()))
+}
+
+/// Bookmarks for `url`, so the top bar can show a filled star.
+#[tauri::command]
+pub fn bookmarks_for_url(bookmarks: tauri::State<'_, Bookmarks>, url: String) -> Result<Vec<BookmarkNode>, BrsrError> {
+    let data = bookmarks.data.lock().unwrap();
+    Ok(data.nodes.iter().filter(|n| n.url.as_deref() == Some(url.as_str())).cloned().collect())
+}
+
+/// Import a Netscape bookmark file into a new "Imported" folder.
+#[tauri::command]
+pub fn bookmarks_import_html(
+    bookmarks: tauri::State<'_, Bookmarks>,
+    path: String,
+    parent_id: Option<u64>,
+) -> Result<ImportSummary, BrsrError> {
+    let contents = std::fs::read_to_string(&path).map_err(|e| BrsrError::io(path.as_ref(), e))?;
+    let items = html::parse(&contents);
+    bookmarks.mutate(|data| {
+        let folder = data.add(
+            parent_id.unwrap_or(OTHER_BOOKMARKS_ID),
+            NodeKind::Folder,
+            "Imported".into(),
+            None,
+            Vec::new(),
+            None,
+        )?;
+        let mut summary = ImportSummary::default();
+        data.import(folder.id, items, &mut summary);
+        println!("[Rust] Imported {} bookmarks in {} folders from {}", summary.bookmarks, summary.folders, path);
+        Ok(summary)
+    })
+}
+
+#[tauri::command]
+pub fn bookmarks_export_html(bookmarks: tauri::State<'_, Bookmarks>, path: String) -> Result<(), BrsrError> {
+    let tree = bookmarks.data.lock().unwrap().tree();
+    storage::write_atomic(std::path::Path::new(&path), html::render(&tree).as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bookmarks() -> Bookmarks {
+        Bookmarks {
+            data: Mutex::new(BookmarkData::default()),
+            path: None,
+        }
+    }
+
+    #[test]
+    fn failed_changes_leave_the_tree_alone() {
+        let bookmarks = bookmarks();
+        let result: Result<(), BrsrError> = bookmarks.mutate(|data| {
+            data.add(OTHER_BOOKMARKS_ID, NodeKind::Folder, "half".into(), None, Vec::new(), None)?;
+            Err(BrsrError::invalid("stop"))
+        });
+        assert!(result.is_err());
+        assert_eq!(bookmarks.data.lock().unwrap().nodes.len(), 2);
+    }
+
+    #[test]
+    fn only_same_site_image_icons_are_kept() {
+        let page = Url::parse("https://www.bank.example/login").unwrap();
+        for icon in ["https://static.bank.example/i.png", "data:image/png;base64,AAAA"] {
+            assert_eq!(page_icon(&page, icon).as_deref(), Some(icon));
+        }
+        for icon in [
+            "https://tracker.test/i.png",
+            "data:text/html,<script></script>",
+            "javascript:alert(1)",
+            "file:///etc/passwd",
+        ] {
+            assert_eq!(page_icon(&page, icon), None, "{}", icon);
+        }
+    }
+
+    #[test]
+    fn page_icons_go_on_the_tabs_url() {
+        let bookmarks = bookmarks();
+        let url = "https://www.bank.example/";
+        bookmarks
+            .mutate(|data| {
+                data.add(OTHER_BOOKMARKS_ID, NodeKind::Bookmark, "Bank".into(), Some(url.into()), Vec::new(), None)
+            })
+            .unwrap();
+        let info = |page: &str, icon: &str| PageInfo {
+            title: String::new(),
+            url: page.into(),
+            lang: None,
+            metas: Default::default(),
+            icons: vec![icon.into()],
+            theme_color: None,
+            links: Vec::new(),
+            headings: Vec::new(),
+        };
+        // A page on another site claiming to be the bank changes nothing.
+        bookmarks.handle_page_info("https://evil.test/", &info(url, "https://evil.test/i.png"));
+        bookmarks.handle_page_info(url, &info(url, "https://evil.test/i.png"));
+        let favicon = |b: &Bookmarks| b.data.lock().unwrap().nodes[2].favicon_url.clone();
+        assert_eq!(favicon(&bookmarks), None);
+        bookmarks.handle_page_info(url, &info("https://evil.test/", "https://www.bank.example/i.png"));
+        assert_eq!(favicon(&bookmarks).as_deref(), Some("https://www.bank.example/i.png"));
+    }
+}
@@ -0,0 +1,786 @@
+//! Downloads started from tabs.
+//!
+//! Every tab's `on_download` hook lands in [`intercept`]. For http(s) URLs the
+//! webview's own download is cancelled and the file is fetched by
+//! [`transfer`] instead, with the tab's cookies and URL as referrer, so brsr
+//! can show progress and speed, pause, resume, cancel and retry. `blob:` and
+//! `data:` URLs only exist inside the page, so those are left to the webview.
+//! So is anything a second GET wouldn't fetch again: the answer to a form the
+//! tab just posted (the injected script reports those), or a URL that looks
+//! signed for one use. These are still saved into the downloads folder and
+//! listed, but can't be paused or retried.
+//!
+//! The target is the configured folder (the OS downloads folder by default)
+//! unless "ask where to save" is on, in which case a save dialog picks it.
+//! Finished and interrupted downloads are kept in `downloads.json`, except
+//! those from private tabs; the folder settings in `download-settings.json`.
+//!
+//! The main window hears about changes through `download-started`,
+//! `download-progress`, `download-changed` and `download-removed`.
+
+mod transfer;
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, AtomicU8, Ordering};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use tauri::webview::DownloadEvent;
+use tauri::{Emitter, Manager};
+use ts_rs::TS;
+use url::Url;
+
+use crate::error::BrsrError;
+use crate::storage;
+use crate::tabs::{now_ms, TabRegistry};
+
+const SETTINGS_FILE: &str = "download-settings.json";
+const HISTORY_FILE: &str = "downloads.json";
+/// Oldest finished downloads are dropped from the history past this.
+const MAX_HISTORY: usize = 500;
+
+pub const STARTED_EVENT: &str = "download-started";
+pub const PROGRESS_EVENT: &str = "download-progress";
+pub const CHANGED_EVENT: &str = "download-changed";
+pub const REMOVED_EVENT: &str = "download-removed";
+
+/// Query parameters of signed URLs, which often work once or for a short time.
+const SIGNED_QUERY_KEYS: &[&str] = &[
+    "signature",
+    "sig",
+    "token",
+    "expires",
+    "x-amz-signature",
+    "x-amz-credential",
+    "x-goog-signature",
+    "key-pair-id",
+];
+
+/// Values of a transfer's control flag.
+const RUN: u8 = 0;
+const PAUSE: u8 = 1;
+const CANCEL: u8 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "kebab-case")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub enum DownloadState {
+    InProgress,
+    /// Paused by the user, or interrupted by quitting.
+    Paused,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct DownloadItem {
+    pub id: u32,
+    pub url: String,
+    /// Page the download started from, sent as the referrer.
+    pub referrer: Option<String>,
+    /// Tab the download started from; its cookies go with the request.
+    pub label: Option<String>,
+    pub path: String,
+    pub file_name: String,
+    pub state: DownloadState,
+    pub received_bytes: f64,
+    /// Unknown until the server sends a length, and for some servers never.
+    pub total_bytes: Option<f64>,
+    pub error: Option<String>,
+    /// Saved by the webview itself: `blob:` and `data:` URLs, and requests we
+    /// can't make again.
+    pub native: bool,
+    /// Started from a private tab: listed until the last one closes, never saved.
+    #[serde(default)]
+    pub private: bool,
+    pub started_at: f64,
+    pub finished_at: Option<f64>,
+}
+
+/// Payload of `download-progress`, sent a few times a second per transfer.
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct DownloadProgress {
+    pub id: u32,
+    pub received_bytes: f64,
+    pub total_bytes: Option<f64>,
+    pub bytes_per_second: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase", default)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct DownloadSettings {
+    /// Where downloads go; the OS downloads folder when unset.
+    pub directory: Option<String>,
+    /// Show a save dialog for every download instead.
+    pub ask_where_to_save: bool,
+}
+
+/// What `downloads.json` holds.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct HistoryFile {
+    /// Ids are never reused, so a late event for a removed download can't
+    /// land on a newer one.
+    next_id: u32,
+    items: Vec<DownloadItem>,
+}
+
+/// Managed state: download history, settings and the running transfers.
+pub struct DownloadManager {
+    items: Mutex<Vec<DownloadItem>>,
+    next_id: AtomicU32,
+    settings: Mutex<DownloadSettings>,
+    history_path: Option<PathBuf>,
+    settings_path: Option<PathBuf>,
+    /// Control flags of running transfers, by id.
+    controls: Mutex<HashMap<u32, Arc<AtomicU8>>>,
+    /// Where each tab's current document last posted a form.
+    form_posts: Mutex<HashMap<String, Url>>,
+}
+
+/// `path` with ` (1)`, ` (2)`, … before the extension until nothing is there.
+fn unique_path(path: PathBuf, taken: &[PathBuf]) -> PathBuf {
+    let free = |p: &Path| !p.exists() && !partial_path(p).exists() && !taken.iter().any(|t| t == p);
+    if free(&path) {
+        return path;
+    }
+    let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    let ext = path.extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_default();
+    (1..)
+        .map(|n| path.with_file_name(format!("{} ({}){}", stem, n, ext)))
+        .find(|p| free(p))
+        .expect("some numbered name is free")
+}
+
+/// Extension of the partial file next to `path` while it downloads.
+fn partial_extension(path: &Path) -> String {
+    match path.extension() {
+        Some(ext) => format!("{}.part", ext.to_string_lossy()),
+        None => "part".to_string(),
+    }
+}
+
+fn partial_path(path: &Path) -> PathBuf {
+    path.with_extension(partial_extension(path))
+}
+
+/// File name for `url`: the webview's suggestion, else the last path segment.
+fn suggested_name(url: &Url, suggestion: &Path) -> String {
+    let from_url = || {
+        url.path_segments()
+            .and_then(|mut s| s.next_back().map(str::to_string))
+            .filter(|s| !s.is_empty())
+    };
+    let name = suggestion
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .filter(|n| !n.is_empty())
+        .or_else(from_url)
+        .unwrap_or_else(|| "download".to_string());
+    // Never let a name climb out of the folder.
+    name.replace(['/', '\\'], "_")
+}
+
+/// Whether `url` carries a signature, so fetching it again may fail or get
+/// something else.
+fn looks_signed(url: &Url) -> bool {
+    url.query_pairs()
+        .any(|(key, _)| SIGNED_QUERY_KEYS.iter().any(|k| key.eq_ignore_ascii_case(k)))
+}
+
+/// `url` without its fragment, which never reaches the server.
+fn without_fragment(url: &Url) -> Url {
+    let mut url = url.clone();
+    url.set_fragment(None);
+    url
+}
+
+impl DownloadManager {
+    pub fn load<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Self {
+        let history_path = storage::data_file(app, HISTORY_FILE)
+            .map_err(|e| eprintln!("[Rust] Download history will not persist: {}", e))
+            .ok();
+        let settings_path = storage::data_file(app, SETTINGS_FILE)
+            .map_err(|e| eprintln!("[Rust] Download settings will not persist: {}", e))
+            .ok();
+        let HistoryFile { next_id, mut items } = history_path.as_deref().map(storage::load_json).unwrap_or_default();
+        let next_id = items.iter().map(|i| i.id + 1).max().unwrap_or(1).max(next_id);
+        // Whatever was running when the app quit can be resumed from its partial file.
+        for item in items.iter_mut().filter(|i| i.state == DownloadState::InProgress) {
+            item.state = if item.native {
+                DownloadState::Failed
+            } else {
+                DownloadState::Paused
+            };
+        }
+        let settings = settings_path.as_deref().map(storage::load_json).unwrap_or_default();
+        Self {
+            items: Mutex::new(items),
+            next_id: AtomicU32::new(next_id),
+            settings: Mutex::new(settings),
+            history_path,
+            settings_path,
+            controls: Mutex::new(HashMap::new()),
+            form_posts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The tab `label` is posting a form to `action`; see `forms.js`.
+    pub fn form_posted(&self, label: &str, action: &str) {
+        if let Ok(action) = Url::parse(action) {
+            self.form_posts.lock().unwrap().insert(label.to_string(), without_fragment(&action));
+        }
+    }
+
+    /// The tab `label` has a new document (or is gone), so its posts are
+    /// answered.
+    pub fn reset_page(&self, label: &str) {
+        self.form_posts.lock().unwrap().remove(label);
+    }
+
+    /// Whether a plain GET of `url`, as [`transfer`] makes it, would fetch
+    /// what the tab `label` asked for.
+    fn replayable(&self, label: &str, url: &Url) -> bool {
+        matches!(url.scheme(), "http" | "https")
+            && !looks_signed(url)
+            && self.form_posts.lock().unwrap().get(label) != Some(&without_fragment(url))
+    }
+
+    pub fn settings(&self) -> DownloadSettings {
+        self.settings.lock().unwrap().clone()
+    }
+
+    fn replace_settings(&self, next: DownloadSettings) -> Result<(), BrsrError> {
+        if let Some(path) = &self.settings_path {
+            storage::save_json(path, &next)?;
+        }
+        *self.settings.lock().unwrap() = next;
+        Ok(())
+    }
+
+    /// The folder new downloads go to.
+    fn directory<R: tauri::Runtime>(&self, app: &tauri::AppHandle<R>) -> Result<PathBuf, BrsrError> {
+        match self.settings().directory {
+            Some(dir) => Ok(PathBuf::from(dir)),
+            None => app
+                .path()
+                .download_dir()
+                .map_err(BrsrError::from),
+        }
+    }
+
+    /// A free path for `name` in the downloads folder.
+    fn target_for<R: tauri::Runtime>(&self, app: &tauri::AppHandle<R>, name: &str) -> Result<PathBuf, BrsrError> {
+        let dir = self.directory(app)?;
+        std::fs::create_dir_all(&dir).map_err(|e| BrsrError::io(&dir, e))?;
+        let taken: Vec<PathBuf> = self
+            .items
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|i| matches!(i.state, DownloadState::InProgress | DownloadState::Paused))
+            .map(|i| PathBuf::from(&i.path))
+            .collect();
+        Ok(unique_path(dir.join(name), &taken))
+    }
+
+    pub fn list(&self) -> Vec<DownloadItem> {
+        self.items.lock().unwrap().clone()
+    }
+
+    pub fn get(&self, id: u32) -> Option<DownloadItem> {
+        self.items.lock().unwrap().iter().find(|i| i.id == id).cloned()
+    }
+
+    fn update<F: FnOnce(&mut DownloadItem)>(&self, id: u32, f: F) -> Option<DownloadItem> {
+        let mut items = self.items.lock().unwrap();
+        let item = items.iter_mut().find(|i| i.id == id)?;
+        f(item);
+        Some(item.clone())
+    }
+
+    fn save(&self) {
+        let Some(path) = &self.history_path else {
+            return;
+        };
+        let file = HistoryFile {
+            next_id: self.next_id.load(Ordering::SeqCst),
+            items: self.items.lock().unwrap().iter().filter(|i| !i.private).cloned().collect(),
+        };
+        if let Err(e) = storage::save_json(path, &file) {
+            eprintln!("[Rust] Failed to save download history: {}", e);
+        }
+    }
+
+    fn add<R: tauri::Runtime>(&self, app: &tauri::AppHandle<R>, mut item: DownloadItem) -> DownloadItem {
+        {
+            let mut items = self.items.lock().unwrap();
+            item.id = self.next_id.fetch_add(1, Ordering::SeqCst);
+            items.push(item.clone());
+            // Trim the oldest finished entries; running ones always stay.
+            while items.len() > MAX_HISTORY {
+                let Some(oldest) = items
+                    .iter()
+                    .position(|i| !matches!(i.state, DownloadState::InProgress | DownloadState::Paused))
+                else {
+                    break;
+                };
+                items.remove(oldest);
+            }
+        }
+        println!("[Rust] Download {} started: {} -> {}", item.id, item.url, item.path);
+        self.save();
+        let _ = app.emit_to("main", STARTED_EVENT, &item);
+        item
+    }
+
+    fn changed<R: tauri::Runtime>(&self, app: &tauri::AppHandle<R>, item: &DownloadItem) {
+        self.save();
+        let _ = app.emit_to("main", CHANGED_EVENT, item);
+    }
+
+    /// Start (or continue) the transfer for `id` in the background.
+    fn start<R: tauri::Runtime>(&self, app: &tauri::AppHandle<R>, id: u32) {
+        let control = Arc::new(AtomicU8::new(RUN));
+        self.controls.lock().unwrap().insert(id, control.clone());
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            let result = transfer::run(&app, id, &control).await;
+            app.state::<DownloadManager>().finish(&app, id, result, control.load(Ordering::SeqCst));
+        });
+    }
+
+    fn finish<R: tauri::Runtime>(
+        &self,
+        app: &tauri::AppHandle<R>,
+        id: u32,
+        result: Result<transfer::Outcome, BrsrError>,
+        control: u8,
+    ) {
+        self.controls.lock().unwrap().remove(&id);
+        let Some(item) = self.get(id) else {
+            return;
+        };
+        let path = PathBuf::from(&item.path);
+        let (state, error) = match result {
+            Ok(transfer::Outcome::Completed) => (DownloadState::Completed, None),
+            Ok(transfer::Outcome::Stopped) if control == CANCEL => {
+                let _ = std::fs::remove_file(partial_path(&path));
+                (DownloadState::Cancelled, None)
+            }
+            Ok(transfer::Outcome::Stopped) => (DownloadState::Paused, None),
+            Err(e) => {
+                eprintln!("[Rust] Download {} failed: {}", id, e);
+                (DownloadState::Failed, Some(e.to_string()))
+            }
+        };
+        let finished = !matches!(state, DownloadState::Paused);
+        if let Some(item) = self.update(id, |item| {
+            item.state = state;
+            item.error = error;
+            if state == DownloadState::Cancelled {
+                item.received_bytes = 0.0;
+            }
+            item.finished_at = finished.then(|| now_ms() as f64);
+        }) {
+            println!("[Rust] Download {} is now {:?}", id, item.state);
+            self.changed(app, &item);
+            self.settled(app, &item);
+        }
+    }
+
+    /// `item` stopped running. If it is private and the last private tab
+    /// closed while it ran, forget it now rather than at the next close.
+    fn settled<R: tauri::Runtime>(&self, app: &tauri::AppHandle<R>, item: &DownloadItem) {
+        if item.private && item.state != DownloadState::InProgress && !app.state::<TabRegistry>().any_private() {
+            self.discard_private(app);
+        }
+    }
+
+    /// Ask the running transfer `id` to stop with `flag`.
+    fn signal(&self, id: u32, flag: u8) -> bool {
+        match self.controls.lock().unwrap().get(&id) {
+            Some(control) => {
+                control.store(flag, Ordering::SeqCst);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Restart the transfer of a download in one of `from`, continuing its partial file.
+    fn restart<R: tauri::Runtime>(&self, app: &tauri::AppHandle<R>, id: u32, from: &[DownloadState]) -> Result<(), BrsrError> {
+        let item = self.get(id).ok_or_else(|| BrsrError::not_found("download", id))?;
+        if item.native {
+            return Err(BrsrError::unsupported("Restarting downloads saved by the page itself"));
+        }
+        if !from.contains(&item.state) {
+            return Err(BrsrError::conflict(format!("Download {} is {:?}", id, item.state)));
+        }
+        if let Some(item) = self.update(id, |item| {
+            item.state = DownloadState::InProgress;
+            item.error = None;
+            item.finished_at = None;
+        }) {
+            self.changed(app, &item);
+        }
+        self.start(app, id);
+        Ok(())
+    }
+
+    /// Begin a download of `url` into `path`, fetched by us.
+    fn begin<R: tauri::Runtime>(&self, app: &tauri::AppHandle<R>, url: &Url, label: &str, path: PathBuf) {
+        let item = self.add(app, new_item(app, url, label, path, false));
+        self.start(app, item.id);
+    }
+
+    /// Forget downloads from private tabs, once the last one has closed.
+    /// Completed files stay where the user saved them; partial ones go.
+    /// Running ones are left to finish and go when they stop.
+    pub fn discard_private<R: tauri::Runtime>(&self, app: &tauri::AppHandle<R>) {
+        let mut removed = Vec::new();
+        self.items.lock().unwrap().retain(|i| {
+            let keep = !i.private || i.state == DownloadState::InProgress;
+            if !keep {
+                if i.state == DownloadState::Paused {
+                    let _ = std::fs::remove_file(partial_path(Path::new(&i.path)));
+                }
+                removed.push(i.id);
+            }
+            keep
+        });
+        for id in removed {
+            let _ = app.emit_to("main", REMOVED_EVENT, serde_json::json!({ "id": id }));
+        }
+    }
+
+    /// The webview finished saving a `blob:`/`data:` download.
+    fn native_finished<R: tauri::Runtime>(&self, app: &tauri::AppHandle<R>, url: &Url, path: Option<PathBuf>, success: bool) {
+        let id = self
+            .items
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .find(|i| i.native && i.state == DownloadState::InProgress && i.url == url.as_str())
+            .map(|i| i.id);
+        let Some(id) = id else {
+            return;
+        };
+        let received = path
+            .as_deref()
+            .and_then(|p| std::fs::metadata(p).ok())
+            .map(|m| m.len() as f64);
+        if let Some(item) = self.update(id, |item| {
+            if let Some(path) = &path {
+                item.path = path.to_string_lossy().into_owned();
+            }
+            if let Some(received) = received {
+                item.received_bytes = received;
+                item.total_bytes = Some(received);
+            }
+            item.state = if success {
+                DownloadState::Completed
+            } else {
+                DownloadState::Failed
+            };
+            item.finished_at = Some(now_ms() as f64);
+        }) {
+            self.changed(app, &item);
+            self.settled(app, &item);
+        }
+    }
+}
+
+/// A new download of `url` into `path`, started from the tab `label`.
+fn new_item<R: tauri::Runtime>(app: &tauri::AppHandle<R>, url: &Url, label: &str, path: PathBuf, native: bool) -> DownloadItem {
+    let tab = app.state::<TabRegistry>().get(label);
+    DownloadItem {
+        id: 0,
+        url: url.to_string(),
+        referrer: tab.as_ref().map(|tab| tab.url.clone()),
+        label: Some(label.to_string()),
+        private: tab.is_none_or(|tab| tab.private),
+        file_name: path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        path: path.to_string_lossy().into_owned(),
+        state: DownloadState::InProgress,
+        received_bytes: 0.0,
+        total_bytes: None,
+        error: None,
+        native,
+        started_at: now_ms() as f64,
+        finished_at: None,
+    }
+}
+
+/// The `on_download` hook of every tab. Returns whether the webview should
+/// carry on with the download itself.
+pub fn intercept<R: tauri::Runtime>(webview: &tauri::Webview<R>, event: DownloadEvent<'_>) -> bool {
+    let app = webview.app_handle();
+    let manager = app.state::<DownloadManager>();
+    let label = webview.label();
+    match event {
+        DownloadEvent::Requested { url, destination } => {
+            let name = suggested_name(&url, destination);
+            if !manager.replayable(label, &url) {
+                // Only the webview can get at these, so it saves them, into our folder.
+                return match manager.target_for(app, &name) {
+                    Ok(path) => {
+                        *destination = path.clone();
+                        manager.add(app, new_item(app, &url, label, path, true));
+                        true
+                    }
+                    Err(e) => {
+                        eprintln!("[Rust] Download of {} not saved: {}", url, e);
+                        false
+                    }
+                };
+            }
+            if manager.settings().ask_where_to_save {
+                ask_and_begin(app, url, label.to_string(), name);
+            } else {
+                match manager.target_for(app, &name) {
+                    Ok(path) => manager.begin(app, &url, label, path),
+                    Err(e) => eprintln!("[Rust] Download of {} not started: {}", url, e),
+                }
+            }
+            false
+        }
+        DownloadEvent::Finished { url, path, success } => {
+            manager.native_finished(app, &url, path, success);
+            true
+        }
+        _ => true,
+    }
+}
+
+/// Let the user pick where `url` goes, then start it.
+fn ask_and_begin<R: tauri::Runtime>(app: &tauri::AppHandle<R>, url: Url, label: String, name: String) {
+    use tauri_plugin_dialog::DialogExt;
+    let mut dialog = app.dialog().file().set_file_name(&name);
+    if let Ok(dir) = app.state::<DownloadManager>().directory(app) {
+        dialog = dialog.set_directory(dir);
+    }
+    let app = app.clone();
+    dialog.save_file(move |path| {
+        let Some(path) = path.and_then(|p| p.as_path().map(Path::to_path_buf)) else {
+            println!("[Rust] Download of {} cancelled in the save dialog", url);
+            return;
+        };
+        app.state::<DownloadManager>().begin(&app, &url, &label, path);
+    });
+}
+
+fn reveal(path: &Path) -> Result<(), BrsrError> {
+    use std::process::Command;
+    #[cfg(target_os = "macos")]
+    let result = Command::new("open").arg("-R").arg(path).spawn();
+    #[cfg(target_os = "windows")]
+    let result = Command::new("explorer").arg(format!("/select,{}", path.display())).spawn();
+    // No common way to select a file; open its folder instead.
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let result = Command::new("xdg-open")
+        .arg(path.parent().unwrap_or(Path::new("/")))
+        .spawn();
+    result.map(|_| ()).map_err(|e| BrsrError::platform(format!("Failed to reveal {}: {}", path.display(), e)))
+}
+
+#[tauri::command]
+pub fn list_downloads(manager: tauri::State<'_, DownloadManager>) -> Result<Vec<DownloadItem>, BrsrError> {
+    Ok(manager.list())
+}
+
+/// Stop a download and delete what was received. Paused downloads are cancelled straight away.
+#[tauri::command]
+pub fn cancel_download(
+    app: tauri::AppHandle,
+    manager: tauri::State<'_, DownloadManager>,
+    id: u32,
+) -> Result<(), BrsrError> {
+    if manager.signal(id, CANCEL) {
+        return Ok(());
+    }
+    let item = manager.get(id).ok_or_else(|| BrsrError::not_found("download", id))?;
+    if item.state != DownloadState::Paused {
+        return Err(BrsrError::conflict(format!("Download {} is not running", id)));
+    }
+    let _ = std::fs::remove_file(partial_path(Path::new(&item.path)));
+    if let Some(item) = manager.update(id, |item| {
+        item.state = DownloadState::Cancelled;
+        item.received_bytes = 0.0;
+        item.finished_at = Some(now_ms() as f64);
+    }) {
+        manager.changed(&app, &item);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn pause_download(manager: tauri::State<'_, DownloadManager>, id: u32) -> Result<(), BrsrError> {
+    if manager.signal(id, PAUSE) {
+        Ok(())
+    } else {
+        Err(BrsrError::conflict(format!("Download {} is not running", id)))
+    }
+}
+
+/// Continue a paused download where it stopped, if the server allows it.
+#[tauri::command]
+pub fn resume_download(
+    app: tauri::AppHandle,
+    manager: tauri::State<'_, DownloadManager>,
+    id: u32,
+) -> Result<(), BrsrError> {
+    manager.restart(&app, id, &[DownloadState::Paused])
+}
+
+/// Try a failed or cancelled download again.
+#[tauri::command]
+pub fn retry_download(
+    app: tauri::AppHandle,
+    manager: tauri::State<'_, DownloadManager>,
+    id: u32,
+) -> Result<(), BrsrError> {
+    manager.restart(&app, id, &[DownloadState::Failed, DownloadState::Cancelled])
+}
+
+/// Open a completed download with its default app.
+#[tauri::command]
+pub fn open_download(
+    app: tauri::AppHandle,
+    manager: tauri::State<'_, DownloadManager>,
+    id: u32,
+) -> Result<(), BrsrError> {
+    use tauri_plugin_opener::OpenerExt;
+    let item = manager.get(id).ok_or_else(|| BrsrError::not_found("download", id))?;
+    if item.state != DownloadState::Completed || !Path::new(&item.path).exists() {
+        return Err(BrsrError::conflict(format!("'{}' is not available", item.file_name)));
+    }
+    app.opener().open_path(&item.path, None::<&str>).map_err(BrsrError::platform)
+}
+
+/// Show a download in the file manager.
+#[tauri::command]
+pub fn reveal_download(manager: tauri::State<'_, DownloadManager>, id: u32) -> Result<(), BrsrError> {
+    let item = manager.get(id).ok_or_else(|| BrsrError::not_found("download", id))?;
+    let path = PathBuf::from(&item.path);
+    if path.exists() {
+        reveal(&path)
+    } else {
+        reveal(&partial_path(&path))
+    }
+}
+
+/// Drop a finished download from the history. The file stays on disk.
+#[tauri::command]
+pub fn remove_download(
+    app: tauri::AppHandle,
+    manager: tauri::State<'_, DownloadManager>,
+    id: u32,
+) -> Result<(), BrsrError> {
+    {
+        let mut items = manager.items.lock().unwrap();
+        let index = items
+            .iter()
+            .position(|i| i.id == id)
+            .ok_or_else(|| BrsrError::not_found("download", id))?;
+        if items[index].state == DownloadState::InProgress {
+            return Err(BrsrError::conflict(format!("Download {} is still running", id)));
+        }
+        if items[index].state == DownloadState::Paused {
+            let _ = std::fs::remove_file(partial_path(Path::new(&items[index].path)));
+        }
+        items.remove(index);
+    }
+    manager.save();
+    let _ = app.emit_to("main", REMOVED_EVENT, serde_json::json!({ "id": id }));
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_download_settings(manager: tauri::State<'_, DownloadManager>) -> Result<DownloadSettings, BrsrError> {
+    Ok(manager.settings())
+}
+
+#[tauri::command]
+pub fn set_download_settings(
+    manager: tauri::State<'_, DownloadManager>,
+    settings: DownloadSettings,
+) -> Result<DownloadSettings, BrsrError> {
+    if let Some(dir) = &settings.directory {
+        if !Path::new(dir).is_absolute() {
+            return Err(BrsrError::invalid(format!("'{}' is not an absolute path", dir)));
+        }
+    }
+    manager.replace_settings(settings.clone())?;
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partial_files_keep_the_extension() {
+        assert_eq!(partial_extension(Path::new("a/report.pdf")), "pdf.part");
+        assert_eq!(partial_path(Path::new("a/report.pdf")), Path::new("a/report.pdf.part"));
+        assert_eq!(partial_path(Path::new("a/README")), Path::new("a/README.part"));
+        assert_eq!(partial_path(Path::new("a/x.tar.gz")), Path::new("a/x.tar.gz.part"));
+    }
+
+    #[test]
+    fn names_come_from_the_suggestion_then_the_url() {
+        let url = Url::parse("https://example.test/files/report.pdf?x=1").unwrap();
+        assert_eq!(suggested_name(&url, Path::new("/tmp/Annual.pdf")), "Annual.pdf");
+        assert_eq!(suggested_name(&url, Path::new("")), "report.pdf");
+        let bare = Url::parse("https://example.test/files/").unwrap();
+        assert_eq!(suggested_name(&bare, Path::new("")), "download");
+        let escaping = Url::parse("https://example.test/a%2F..%5Cb").unwrap();
+        assert_eq!(suggested_name(&escaping, Path::new("")), "a%2F..%5Cb");
+    }
+
+    #[test]
+    fn unique_paths_skip_files_partials_and_running_downloads() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("a.zip");
+        assert_eq!(unique_path(target.clone(), &[]), target);
+        std::fs::write(&target, b"").unwrap();
+        assert_eq!(unique_path(target.clone(), &[]), dir.path().join("a (1).zip"));
+        std::fs::write(dir.path().join("a (1).zip.part"), b"").unwrap();
+        let taken = [dir.path().join("a (2).zip")];
+        assert_eq!(unique_path(target, &taken), dir.path().join("a (3).zip"));
+        assert_eq!(unique_path(dir.path().join("notes"), &[]), dir.path().join("notes"));
+    }
+
+    #[test]
+    fn posted_and_signed_urls_are_not_replayed() {
+        let manager = DownloadManager {
+            items: Mutex::new(Vec::new()),
+            next_id: AtomicU32::new(1),
+            settings: Mutex::new(DownloadSettings::default()),
+            history_path: None,
+            settings_path: None,
+            controls: Mutex::new(HashMap::new()),
+            form_posts: Mutex::new(HashMap::new()),
+        };
+        let plain = Url::parse("https://example.test/export").unwrap();
+        assert!(manager.replayable("t", &plain));
+        assert!(!manager.replayable("t", &Url::parse("blob:https://example.test/1").unwrap()));
+        assert!(!manager.replayable("t", &Url::parse("https://s3.test/f?X-Amz-Signature=ab").unwrap()));
+        manager.form_posted("t", "https://example.test/export#top");
+        assert!(!manager.replayable("t", &plain));
+        assert!(manager.replayable("other", &plain));
+        manager.reset_page("t");
+        assert!(manager.replayable("t", &plain));
+    }
+}
@@ -0,0 +1,119 @@
+//! Fetching one download over HTTP, continuing a partial file when the server
+//! supports ranges.
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::time::{Duration, Instant};
+
+use reqwest::header::{COOKIE, RANGE, REFERER};
+use reqwest::StatusCode;
+use tauri::{Emitter, Manager};
+use tokio::io::AsyncWriteExt;
+
+use super::{partial_path, DownloadManager, DownloadProgress, PROGRESS_EVENT, RUN};
+use crate::error::BrsrError;
+
+/// How often `download-progress` goes out per transfer.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+pub enum Outcome {
+    Completed,
+    /// Paused or cancelled; the control flag says which.
+    Stopped,
+}
+
+/// The `Cookie` header the tab `label` would send to `url`.
+fn cookie_header<R: tauri::Runtime>(app: &tauri::AppHandle<R>, label: &str, url: &url::Url) -> Option<String> {
+    let cookies = app.get_webview(label)?.cookies_for_url(url.clone()).ok()?;
+    let header = cookies
+        .iter()
+        .map(|c| format!("{}={}", c.name(), c.value()))
+        .collect::<Vec<_>>()
+        .join("; ");
+    (!header.is_empty()).then_some(header)
+}
+
+/// Download `id` into its partial file, then move it into place.
+pub async fn run<R: tauri::Runtime>(app: &tauri::AppHandle<R>, id: u32, control: &AtomicU8) -> Result<Outcome, BrsrError> {
+    let manager = app.state::<DownloadManager>();
+    let item = manager.get(id).ok_or_else(|| BrsrError::not_found("download", id))?;
+    let url = url::Url::parse(&item.url).map_err(|e| BrsrError::invalid_url(&item.url, e))?;
+    let path = PathBuf::from(&item.path);
+    let partial = partial_path(&path);
+    let offset = tokio::fs::metadata(&partial).await.map(|m| m.len()).unwrap_or(0);
+
+    let mut request = reqwest::Client::new().get(url.clone());
+    if offset > 0 {
+        request = request.header(RANGE, format!("bytes={}-", offset));
+    }
+    if let Some(referrer) = &item.referrer {
+        request = request.header(REFERER, referrer);
+    }
+    // Tabs that are still open lend their cookies, so signed-in downloads work.
+    if let Some(cookies) = item.label.as_deref().and_then(|label| cookie_header(app, label, &url)) {
+        request = request.header(COOKIE, cookies);
+    }
+    let mut response = request.send().await.map_err(|e| BrsrError::network(&url, e))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(BrsrError::network(&url, format!("the server responded {}", status)));
+    }
+    // A 200 to a range request means starting over.
+    let resumed = offset > 0 && status == StatusCode::PARTIAL_CONTENT;
+    let mut received = if resumed { offset } else { 0 };
+    let total = response.content_length().map(|len| len + received);
+
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await.map_err(|e| BrsrError::io(parent, e))?;
+    }
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(resumed)
+        .truncate(!resumed)
+        .open(&partial)
+        .await
+        .map_err(|e| BrsrError::io(&partial, e))?;
+
+    let mut last_report = Instant::now();
+    let mut since_report = 0u64;
+    loop {
+        if control.load(Ordering::SeqCst) != RUN {
+            file.flush().await.map_err(|e| BrsrError::io(&partial, e))?;
+            return Ok(Outcome::Stopped);
+        }
+        let Some(chunk) = response.chunk().await.map_err(|e| BrsrError::network(&url, e))? else {
+            break;
+        };
+        file.write_all(&chunk).await.map_err(|e| BrsrError::io(&partial, e))?;
+        received += chunk.len() as u64;
+        since_report += chunk.len() as u64;
+
+        let elapsed = last_report.elapsed();
+        if elapsed >= PROGRESS_INTERVAL {
+            let progress = DownloadProgress {
+                id,
+                received_bytes: received as f64,
+                total_bytes: total.map(|t| t as f64),
+                bytes_per_second: since_report as f64 / elapsed.as_secs_f64(),
+            };
+            manager.update(id, |item| {
+                item.received_bytes = progress.received_bytes;
+                item.total_bytes = progress.total_bytes;
+            });
+            let _ = app.emit_to("main", PROGRESS_EVENT, &progress);
+            last_report = Instant::now();
+            since_report = 0;
+        }
+    }
+    file.flush().await.map_err(|e| BrsrError::io(&partial, e))?;
+    drop(file);
+    tokio::fs::rename(&partial, &path)
+        .await
+        .map_err(|e| BrsrError::io(&path, e))?;
+    manager.update(id, |item| {
+        item.received_bytes = received as f64;
+        item.total_bytes = Some(received as f64);
+    });
+    Ok(Outcome::Completed)
+}
@@ -0,0 +1,154 @@
+//! The error type every command returns.
+//!
+//! Errors reach the frontend as `{ code, message, ...details }`. `code` is
+//! stable and what the UI should branch on; `message` is for people and may
+//! change; the details depend on the code (see [`BrsrError`]).
+
+use std::fmt;
+use std::path::Path;
+
+use serde::ser::SerializeMap;
+use serde::{Serialize, Serializer};
+
+use crate::navigation::NavigationError;
+
+#[derive(Debug, Clone)]
+pub enum BrsrError {
+    /// No `kind` (tab, download, profile, ...) with this id exists.
+    NotFound { kind: &'static str, id: String },
+    /// Input that should have been a URL isn't one, or not one we can use.
+    InvalidUrl { url: String, reason: String },
+    /// An argument is malformed or out of range.
+    InvalidInput { message: String },
+    /// The request clashes with the current state, e.g. deleting the profile
+    /// that is open, or pausing a download that has finished.
+    Conflict { message: String },
+    /// Navigation policy, a URL scheme rule or a privacy rule refused it.
+    Policy { url: Option<String>, reason: String },
+    /// Not available on this platform or in this build.
+    Unsupported { feature: String },
+    /// The OS, the webview or Tauri failed.
+    Platform { message: String },
+    /// Reading or writing a file failed.
+    Io { path: Option<String>, message: String },
+    /// A request to a server failed.
+    Network { url: String, message: String },
+}
+
+impl BrsrError {
+    pub fn not_found(kind: &'static str, id: impl fmt::Display) -> Self {
+        Self::NotFound { kind, id: id.to_string() }
+    }
+
+    pub fn invalid_url(url: impl Into<String>, reason: impl fmt::Display) -> Self {
+        Self::InvalidUrl { url: url.into(), reason: reason.to_string() }
+    }
+
+    pub fn invalid(message: impl Into<String>) -> Self {
+        Self::InvalidInput { message: message.into() }
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::Conflict { message: message.into() }
+    }
+
+    pub fn unsupported(feature: impl Into<String>) -> Self {
+        Self::Unsupported { feature: feature.into() }
+    }
+
+    pub fn platform(message: impl fmt::Display) -> Self {
+        Self::Platform { message: message.to_string() }
+    }
+
+    pub fn io(path: &Path, error: impl fmt::Display) -> Self {
+        Self::Io { path: Some(path.display().to_string()), message: error.to_string() }
+    }
+
+    pub fn network(url: impl fmt::Display, error: impl fmt::Display) -> Self {
+        Self::Network { url: url.to_string(), message: error.to_string() }
+    }
+
+    /// The stable code sent to the frontend.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::NotFound { .. } => "notFound",
+            Self::InvalidUrl { .. } => "invalidUrl",
+            Self::InvalidInput { .. } => "invalidInput",
+            Self::Conflict { .. } => "conflict",
+            Self::Policy { .. } => "policy",
+            Self::Unsupported { .. } => "unsupported",
+            Self::Platform { .. } => "platform",
+            Self::Io { .. } => "io",
+            Self::Network { .. } => "network",
+        }
+    }
+}
+
+impl fmt::Display for BrsrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound { kind, id } => write!(f, "No {} '{}'", kind, id),
+            Self::InvalidUrl { url, reason } => write!(f, "Invalid URL '{}': {}", url, reason),
+            Self::InvalidInput { message } | Self::Conflict { message } | Self::Platform { message } => {
+                f.write_str(message)
+            }
+            Self::Policy { url: Some(url), reason } => write!(f, "'{}' is not allowed: {}", url, reason),
+            Self::Policy { url: None, reason } => write!(f, "Not allowed: {}", reason),
+            Self::Unsupported { feature } => write!(f, "{} is not supported here", feature),
+            Self::Io { path: Some(path), message } => write!(f, "{}: {}", path, message),
+            Self::Io { path: None, message } => f.write_str(message),
+            Self::Network { url, message } => write!(f, "Request to {} failed: {}", url, message),
+        }
+    }
+}
+
+impl std::error::Error for BrsrError {}
+
+impl Serialize for BrsrError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("code", self.code())?;
+        map.serialize_entry("message", &self.to_string())?;
+        match self {
+            Self::NotFound { kind, id } => {
+                map.serialize_entry("kind", kind)?;
+                map.serialize_entry("id", id)?;
+            }
+            Self::InvalidUrl { url, reason } => {
+                map.serialize_entry("url", url)?;
+                map.serialize_entry("reason", reason)?;
+            }
+            Self::Policy { url, reason } => {
+                map.serialize_entry("url", url)?;
+                map.serialize_entry("reason", reason)?;
+            }
+            Self::Unsupported { feature } => map.serialize_entry("feature", feature)?,
+            Self::Io { path, .. } => map.serialize_entry("path", path)?,
+            Self::Network { url, .. } => map.serialize_entry("url", url)?,
+            Self::InvalidInput { .. } | Self::Conflict { .. } | Self::Platform { .. } => {}
+        }
+        map.end()
+    }
+}
+
+impl From<tauri::Error> for BrsrError {
+    fn from(e: tauri::Error) -> Self {
+        Self::platform(e)
+    }
+}
+
+impl From<NavigationError> for BrsrError {
+    fn from(e: NavigationError) -> Self {
+        match e {
+            NavigationError::InvalidUrl { input, reason } => Self::InvalidUrl { url: input, reason },
+            NavigationError::DisallowedScheme { url, scheme } => Self::Policy {
+                url: Some(url),
+                reason: format!("tabs can't load '{}:' URLs", scheme),
+            },
+            NavigationError::Webview { label, message } => Self::Platform {
+                message: format!("Webview '{}' error: {}", label, message),
+            },
+            NavigationError::Unsupported { operation } => Self::Unsupported { feature: operation },
+        }
+    }
+}
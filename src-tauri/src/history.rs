@@ -0,0 +1,490 @@
+//! Persistent browsing history.
+//!
+//! Every committed page load is recorded as a visit in a SQLite database under
+//! the app data dir. URLs and titles are indexed with FTS5 for the omnibox, and
+//! text queries are ranked by frecency: a Firefox-style score that weighs each
+//! recent visit by how it happened (typed > link > reload) and how long ago.
+
+use std::path::Path;
+use std::sync::Mutex;
+
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::error::BrsrError;
+use crate::storage;
+use crate::tabs::now_ms;
+
+const DB_FILE: &str = "history.sqlite3";
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+const SCHEMA: &str = r#"
+PRAGMA journal_mode = WAL;
+PRAGMA foreign_keys = ON;
+
+CREATE TABLE IF NOT EXISTS urls (
+    id            INTEGER PRIMARY KEY,
+    url           TEXT NOT NULL UNIQUE,
+    title         TEXT NOT NULL DEFAULT '',
+    visit_count   INTEGER NOT NULL DEFAULT 0,
+    last_visit_at INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE TABLE IF NOT EXISTS visits (
+    id            INTEGER PRIMARY KEY,
+    url_id        INTEGER NOT NULL REFERENCES urls(id) ON DELETE CASCADE,
+    visited_at    INTEGER NOT NULL,
+    transition    TEXT NOT NULL,
+    webview_label TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS visits_url_id ON visits(url_id);
+CREATE INDEX IF NOT EXISTS visits_visited_at ON visits(visited_at);
+
+CREATE VIRTUAL TABLE IF NOT EXISTS urls_fts USING fts5(
+    url, title, content = 'urls', content_rowid = 'id'
+);
+CREATE TRIGGER IF NOT EXISTS urls_ai AFTER INSERT ON urls BEGIN
+    INSERT INTO urls_fts(rowid, url, title) VALUES (new.id, new.url, new.title);
+END;
+CREATE TRIGGER IF NOT EXISTS urls_ad AFTER DELETE ON urls BEGIN
+    INSERT INTO urls_fts(urls_fts, rowid, url, title) VALUES ('delete', old.id, old.url, old.title);
+END;
+CREATE TRIGGER IF NOT EXISTS urls_au AFTER UPDATE OF url, title ON urls BEGIN
+    INSERT INTO urls_fts(urls_fts, rowid, url, title) VALUES ('delete', old.id, old.url, old.title);
+    INSERT INTO urls_fts(rowid, url, title) VALUES (new.id, new.url, new.title);
+END;
+"#;
+
+/// How a visit came about. Stored as text and used to weight frecency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Transition {
+    /// Followed a link or a page-initiated navigation.
+    Link,
+    /// Entered in the omnibox.
+    Typed,
+    Reload,
+    BackForward,
+    /// `pushState`/fragment navigation within the same document.
+    SameDocument,
+}
+
+impl Transition {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Link => "link",
+            Self::Typed => "typed",
+            Self::Reload => "reload",
+            Self::BackForward => "back_forward",
+            Self::SameDocument => "same_document",
+        }
+    }
+}
+
+/// Frecency contribution of one visit, before recency weighting.
+const TRANSITION_BONUS_SQL: &str = "CASE v.transition \
+    WHEN 'typed' THEN 2.0 \
+    WHEN 'link' THEN 1.0 \
+    WHEN 'same_document' THEN 0.5 \
+    WHEN 'back_forward' THEN 0.5 \
+    ELSE 0.1 END";
+
+/// Inclusive millisecond timestamp range.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeRange {
+    pub start: Option<u64>,
+    pub end: Option<u64>,
+}
+
+impl TimeRange {
+    fn bounds(&self) -> (i64, i64) {
+        (
+            self.start.map(|t| t as i64).unwrap_or(0),
+            self.end.map(|t| t as i64).unwrap_or(i64::MAX),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryItem {
+    pub url: String,
+    pub title: String,
+    pub visit_count: u32,
+    pub last_visit_at: u64,
+    pub frecency: f64,
+}
+
+/// What `history_delete` removes.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum HistoryDeleteTarget {
+    Url { url: String },
+    /// Every URL on `host` or its subdomains.
+    Host { host: String },
+    Range { range: TimeRange },
+}
+
+/// Managed state wrapping the history database.
+pub struct HistoryStore {
+    conn: Mutex<Connection>,
+}
+
+impl HistoryStore {
+    pub fn open(path: &Path) -> Result<Self, BrsrError> {
+        let conn = Connection::open(path).map_err(|e| BrsrError::io(path, e))?;
+        Self::init(conn)
+    }
+
+    pub fn open_in_memory() -> Result<Self, BrsrError> {
+        Self::init(Connection::open_in_memory().map_err(db_error("Failed to open in-memory history"))?)
+    }
+
+    /// Open the on-disk store, falling back to an in-memory one so browsing keeps working.
+    pub fn load<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Self {
+        storage::data_file(app, DB_FILE)
+            .and_then(|path| Self::open(&path))
+            .unwrap_or_else(|e| {
+                eprintln!("[Rust] History will not persist: {}", e);
+                Self::open_in_memory().expect("in-memory SQLite must open")
+            })
+    }
+
+    fn init(conn: Connection) -> Result<Self, BrsrError> {
+        conn.execute_batch(SCHEMA).map_err(db_error("Failed to initialise history schema"))?;
+        Ok(Self { conn: Mutex::new(conn) })
+    }
+
+    /// Record one visit. Only web and file URLs are kept.
+    pub fn record_visit(&self, url: &str, title: &str, transition: Transition, label: &str) -> Result<(), BrsrError> {
+        if !(url.starts_with("http://") || url.starts_with("https://") || url.starts_with("file://")) {
+            return Ok(());
+        }
+        let now = now_ms() as i64;
+        let conn = self.conn.lock().unwrap();
+        let run = || -> rusqlite::Result<()> {
+            conn.execute(
+                "INSERT INTO urls (url, title, visit_count, last_visit_at) VALUES (?1, ?2, 1, ?3)
+                 ON CONFLICT(url) DO UPDATE SET
+                    visit_count = visit_count + 1,
+                    last_visit_at = excluded.last_visit_at,
+                    title = CASE WHEN excluded.title = '' THEN title ELSE excluded.title END",
+                params![url, title, now],
+            )?;
+            let url_id: i64 = conn.query_row("SELECT id FROM urls WHERE url = ?1", [url], |r| r.get(0))?;
+            conn.execute(
+                "INSERT INTO visits (url_id, visited_at, transition, webview_label) VALUES (?1, ?2, ?3, ?4)",
+                params![url_id, now, transition.as_str(), label],
+            )?;
+            Ok(())
+        };
+        run().map_err(db_error("Failed to record visit"))
+    }
+
+    /// Titles usually arrive after the load commits; attach them to the stored URL.
+    pub fn set_title(&self, url: &str, title: &str) -> Result<(), BrsrError> {
+        if title.trim().is_empty() {
+            return Ok(());
+        }
+        self.conn
+            .lock()
+            .unwrap()
+            .execute("UPDATE urls SET title = ?2 WHERE url = ?1 AND title != ?2", params![url, title])
+            .map(|_| ())
+            .map_err(db_error("Failed to update history title"))
+    }
+
+    /// Search history. With `text`, matches URL/title tokens by prefix and ranks
+    /// by frecency; without it, lists the most recently visited URLs.
+    pub fn query(&self, text: Option<&str>, range: Option<TimeRange>, limit: u32) -> Result<Vec<HistoryItem>, BrsrError> {
+        let (start, end) = range.map(|r| r.bounds()).unwrap_or((0, i64::MAX));
+        let fts = text.and_then(fts_query);
+        let now = now_ms() as i64;
+
+        let frecency = format!(
+            "(SELECT COALESCE(SUM({bonus} * CASE \
+                WHEN ?1 - v.visited_at < 4 * {day} THEN 100 \
+                WHEN ?1 - v.visited_at < 14 * {day} THEN 70 \
+                WHEN ?1 - v.visited_at < 31 * {day} THEN 50 \
+                WHEN ?1 - v.visited_at < 90 * {day} THEN 30 \
+                ELSE 10 END), 0) \
+              FROM (SELECT * FROM visits WHERE url_id = u.id ORDER BY visited_at DESC LIMIT 10) v)",
+            bonus = TRANSITION_BONUS_SQL,
+            day = DAY_MS,
+        );
+        let (filter, order) = match fts {
+            Some(_) => ("AND u.id IN (SELECT rowid FROM urls_fts WHERE urls_fts MATCH ?5)", "frecency DESC, u.last_visit_at DESC"),
+            None => ("", "u.last_visit_at DESC"),
+        };
+        let sql = format!(
+            "SELECT u.url, u.title, u.visit_count, u.last_visit_at, {frecency} AS frecency
+             FROM urls u
+             WHERE EXISTS (SELECT 1 FROM visits WHERE url_id = u.id AND visited_at BETWEEN ?2 AND ?3)
+             {filter}
+             ORDER BY {order}
+             LIMIT ?4"
+        );
+
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&sql).map_err(db_error("History query failed"))?;
+        let mut values: Vec<rusqlite::types::Value> = vec![now.into(), start.into(), end.into(), (limit as i64).into()];
+        if let Some(fts) = fts {
+            values.push(fts.into());
+        }
+        let rows = stmt
+            .query_map(params_from_iter(values), |row| {
+                Ok(HistoryItem {
+                    url: row.get(0)?,
+                    title: row.get(1)?,
+                    visit_count: row.get(2)?,
+                    last_visit_at: row.get::<_, i64>(3)? as u64,
+                    frecency: row.get(4)?,
+                })
+            })
+            .map_err(db_error("History query failed"))?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(db_error("History query failed"))
+    }
+
+    /// Delete a URL, or every URL on a host, with all their visits, or every
+    /// visit within a range. Returns the number of visits removed.
+    pub fn delete(&self, target: &HistoryDeleteTarget) -> Result<usize, BrsrError> {
+        let mut conn = self.conn.lock().unwrap();
+        let run = |conn: &mut Connection| -> rusqlite::Result<usize> {
+            let tx = conn.transaction()?;
+            let removed = match target {
+                HistoryDeleteTarget::Url { url } => {
+                    let url_id: Option<i64> = tx
+                        .query_row("SELECT id FROM urls WHERE url = ?1", [url], |r| r.get(0))
+                        .optional()?;
+                    match url_id {
+                        Some(id) => {
+                            let n = tx.execute("DELETE FROM visits WHERE url_id = ?1", [id])?;
+                            tx.execute("DELETE FROM urls WHERE id = ?1", [id])?;
+                            n
+                        }
+                        None => 0,
+                    }
+                }
+                HistoryDeleteTarget::Host { host } => {
+                    let host = host.trim_end_matches('.').to_ascii_lowercase();
+                    let ids: Vec<i64> = {
+                        let mut stmt = tx.prepare("SELECT id, url FROM urls")?;
+                        let rows = stmt.query_map([], |r| Ok((r.get::<_, i64>(0)?, r.get::<_, String>(1)?)))?;
+                        rows.collect::<rusqlite::Result<Vec<_>>>()?
+                            .into_iter()
+                            .filter(|(_, url)| on_host(url, &host))
+                            .map(|(id, _)| id)
+                            .collect()
+                    };
+                    let mut n = 0;
+                    for id in ids {
+                        n += tx.execute("DELETE FROM visits WHERE url_id = ?1", [id])?;
+                        tx.execute("DELETE FROM urls WHERE id = ?1", [id])?;
+                    }
+                    n
+                }
+                HistoryDeleteTarget::Range { range } => {
+                    let (start, end) = range.bounds();
+                    let n = tx.execute("DELETE FROM visits WHERE visited_at BETWEEN ?1 AND ?2", params![start, end])?;
+                    tx.execute("DELETE FROM urls WHERE id NOT IN (SELECT DISTINCT url_id FROM visits)", [])?;
+                    tx.execute(
+                        "UPDATE urls SET
+                            visit_count = (SELECT COUNT(*) FROM visits WHERE url_id = urls.id),
+                            last_visit_at = (SELECT MAX(visited_at) FROM visits WHERE url_id = urls.id)",
+                        [],
+                    )?;
+                    n
+                }
+            };
+            tx.commit()?;
+            Ok(removed)
+        };
+        run(&mut conn).map_err(db_error("Failed to delete history"))
+    }
+
+    /// Origins of the http(s) pages visited within `range`.
+    pub fn visited_origins(&self, range: TimeRange) -> Result<Vec<String>, BrsrError> {
+        let (start, end) = range.bounds();
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare(
+                "SELECT u.url FROM urls u
+                 WHERE EXISTS (SELECT 1 FROM visits WHERE url_id = u.id AND visited_at BETWEEN ?1 AND ?2)",
+            )
+            .map_err(db_error("History query failed"))?;
+        let urls = stmt
+            .query_map(params![start, end], |row| row.get::<_, String>(0))
+            .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+            .map_err(db_error("History query failed"))?;
+        let origins: std::collections::BTreeSet<String> = urls
+            .iter()
+            .filter_map(|u| url::Url::parse(u).ok())
+            .filter(|u| matches!(u.scheme(), "http" | "https"))
+            .map(|u| u.origin().ascii_serialization())
+            .collect();
+        Ok(origins.into_iter().collect())
+    }
+
+    pub fn clear(&self) -> Result<(), BrsrError> {
+        self.conn
+            .lock()
+            .unwrap()
+            .execute_batch("DELETE FROM visits; DELETE FROM urls; INSERT INTO urls_fts(urls_fts) VALUES ('rebuild');")
+            .map_err(db_error("Failed to clear history"))
+    }
+}
+
+/// Wrap a SQLite error with what we were doing; the database has no single
+/// user-visible path worth reporting, so `path` stays empty.
+fn db_error(context: &'static str) -> impl Fn(rusqlite::Error) -> BrsrError {
+    move |e| BrsrError::Io { path: None, message: format!("{}: {}", context, e) }
+}
+
+/// Whether `url` is on `host` (lowercase) or one of its subdomains.
+fn on_host(url: &str, host: &str) -> bool {
+    url::Url::parse(url).ok().and_then(|u| u.host_str().map(str::to_ascii_lowercase)).is_some_and(|h| {
+        h == host || h.strip_suffix(host).is_some_and(|rest| rest.ends_with('.'))
+    })
+}
+
+/// Turn free text into an FTS5 query matching every token as a prefix.
+fn fts_query(text: &str) -> Option<String> {
+    let terms: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(|t| format!("\"{}\"*", t))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+#[tauri::command]
+pub fn history_query(
+    store: tauri::State<'_, HistoryStore>,
+    text: Option<String>,
+    range: Option<TimeRange>,
+    limit: Option<u32>,
+) -> Result<Vec<HistoryItem>, BrsrError> {
+    store.query(text.as_deref(), range, limit.unwrap_or(50))
+}
+
+#[tauri::command]
+pub fn history_delete(store: tauri::State<'_, HistoryStore>, target: HistoryDeleteTarget) -> Result<usize, BrsrError> {
+    store.delete(&target)
+}
+
+#[tauri::command]
+pub fn history_clear(store: tauri::State<'_, HistoryStore>) -> Result<(), BrsrError> {
+    store.clear()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn visit(store: &HistoryStore, url: &str, title: &str, transition: Transition, times: usize) {
+        for _ in 0..times {
+            store.record_visit(url, title, transition, "tab-1").unwrap();
+        }
+    }
+
+    /// Move every visit to `url` back by `days`.
+    fn age(store: &HistoryStore, url: &str, days: i64) {
+        store
+            .conn
+            .lock()
+            .unwrap()
+            .execute_batch(&format!(
+                "UPDATE visits SET visited_at = visited_at - {shift} WHERE url_id = (SELECT id FROM urls WHERE url = '{url}');
+                 UPDATE urls SET last_visit_at = last_visit_at - {shift} WHERE url = '{url}';",
+                shift = days * DAY_MS,
+            ))
+            .unwrap();
+    }
+
+    fn urls(items: &[HistoryItem]) -> Vec<&str> {
+        items.iter().map(|item| item.url.as_str()).collect()
+    }
+
+    fn sorted_urls(items: &[HistoryItem]) -> Vec<&str> {
+        let mut urls = urls(items);
+        urls.sort();
+        urls
+    }
+
+    #[test]
+    fn ranks_matches_by_frecency() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        visit(&store, "https://docs.example.com/", "Docs", Transition::Link, 3);
+        visit(&store, "https://typed.example.com/", "Typed", Transition::Typed, 1);
+        visit(&store, "https://old.example.com/", "Old", Transition::Typed, 5);
+        age(&store, "https://old.example.com/", 100);
+        visit(&store, "https://reload.example.com/", "Reload", Transition::Reload, 1);
+        visit(&store, "https://elsewhere.test/", "Unrelated", Transition::Typed, 5);
+
+        let found = store.query(Some("exam"), None, 10).unwrap();
+        assert_eq!(
+            urls(&found),
+            [
+                "https://docs.example.com/",
+                "https://typed.example.com/",
+                "https://old.example.com/",
+                "https://reload.example.com/",
+            ]
+        );
+        assert_eq!(found[0].frecency, 300.0);
+        assert_eq!(found[2].visit_count, 5);
+
+        let by_title = store.query(Some("typ"), None, 10).unwrap();
+        assert_eq!(urls(&by_title), ["https://typed.example.com/"]);
+        assert_eq!(store.query(None, None, 2).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn fts_query_quotes_every_token() {
+        assert_eq!(fts_query("rust lang").as_deref(), Some("\"rust\"* \"lang\"*"));
+        assert_eq!(
+            fts_query("\"a\" OR b* NEAR(c) -d title:e^").as_deref(),
+            Some("\"a\"* \"OR\"* \"b\"* \"NEAR\"* \"c\"* \"d\"* \"title\"* \"e\"*")
+        );
+        assert_eq!(fts_query(" \"*():^- "), None);
+
+        let store = HistoryStore::open_in_memory().unwrap();
+        visit(&store, "https://example.com/or", "NOT a title", Transition::Link, 1);
+        let found = store.query(Some("\"not\" (or"), None, 10).unwrap();
+        assert_eq!(urls(&found), ["https://example.com/or"]);
+    }
+
+    #[test]
+    fn deletes_by_url_host_and_range() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        visit(&store, "https://example.com/a", "", Transition::Link, 2);
+        visit(&store, "https://www.example.com/b", "", Transition::Link, 1);
+        visit(&store, "https://notexample.com/", "", Transition::Link, 1);
+        visit(&store, "https://old.test/", "", Transition::Link, 1);
+        visit(&store, "https://mixed.test/", "", Transition::Link, 1);
+        age(&store, "https://old.test/", 10);
+        age(&store, "https://mixed.test/", 10);
+        visit(&store, "https://mixed.test/", "", Transition::Link, 1);
+
+        let host = HistoryDeleteTarget::Host { host: "Example.com".into() };
+        assert_eq!(store.delete(&host).unwrap(), 3);
+        let left = store.query(None, None, 10).unwrap();
+        assert_eq!(sorted_urls(&left), ["https://mixed.test/", "https://notexample.com/", "https://old.test/"]);
+
+        let end = now_ms() - 5 * DAY_MS as u64;
+        let range = HistoryDeleteTarget::Range { range: TimeRange { start: None, end: Some(end) } };
+        assert_eq!(store.delete(&range).unwrap(), 2);
+        let left = store.query(None, None, 10).unwrap();
+        assert_eq!(sorted_urls(&left), ["https://mixed.test/", "https://notexample.com/"]);
+        let mixed = left.iter().find(|item| item.url == "https://mixed.test/").unwrap();
+        assert_eq!(mixed.visit_count, 1);
+
+        let url = HistoryDeleteTarget::Url { url: "https://notexample.com/".into() };
+        assert_eq!(store.delete(&url).unwrap(), 1);
+        assert_eq!(store.delete(&url).unwrap(), 0);
+        assert_eq!(urls(&store.query(None, None, 10).unwrap()), ["https://mixed.test/"]);
+    }
+}
@@ -0,0 +1,392 @@
+//! Intake of links opened with brsr from other apps.
+//!
+//! Links arrive on the command line of a launch (Linux and Windows start
+//! `brsr <url>`; another profile's router starts `brsr --profile <id>
+//! --open-url <url>`) or, on macOS, as deep-link events to the running app.
+//! Each web link goes through the URL router once; links routed elsewhere are
+//! sent on from there, and the rest are delivered to the main window as an
+//! `open-urls` event carrying [`RouteDecision`]s with a `tab` or
+//! `privateTab` target. Local files (`file:` URLs, or absolute paths as file
+//! managers pass them) go through the navigation policy as typed navigations,
+//! which opens them in the file viewer unless it is turned off.
+//!
+//! Each profile runs in a single instance. Before the app is built, a launch
+//! takes an OS lock on `instance.lock` in the profile's data directory
+//! ([`claim`]). The launch that gets it runs the profile: it listens on a
+//! loopback port and records it, with a secret token, in `instance.json` next
+//! to the lock. A launch that finds the lock held sends its links there and
+//! exits without building anything, and the running instance brings its
+//! window forward. The OS drops the lock however the process ends, so a
+//! crashed instance never holds up the next launch.
+//!
+//! Nothing is dropped while the frontend isn't listening: links are queued
+//! until the main window calls `subscribe_open_urls` (after registering its
+//! listener), which replays the queue as events. A reload of the main window
+//! starts queueing again until it subscribes anew.
+
+use std::fs::{self, File, TryLockError};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::{mpsc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tauri::{Emitter, Manager};
+
+use crate::error::BrsrError;
+use crate::navigation::policy;
+use crate::profiles::{ProfileManager, OPEN_URL_ARG, PROFILE_ARG};
+use crate::router::{self, RouteDecision, RouteTarget};
+use crate::storage;
+use crate::telemetry::channel::random_token;
+
+pub const OPEN_URLS_EVENT: &str = "open-urls";
+const INSTANCE_FILE: &str = "instance.json";
+/// Locked by the instance running the profile whose data directory holds it.
+pub const LOCK_FILE: &str = "instance.lock";
+/// How long one attempt to reach the running instance may take.
+const FORWARD_TIMEOUT: Duration = Duration::from_secs(2);
+/// How long a launch keeps trying to reach the instance holding the lock,
+/// which may still be starting up, before giving up on its links.
+const CLAIM_TIMEOUT: Duration = Duration::from_secs(10);
+const CLAIM_RETRY: Duration = Duration::from_millis(250);
+/// A forwarded launch is one line; anything longer isn't one.
+const MAX_MESSAGE: u64 = 1 << 20;
+
+/// The links a launch carries.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Launch {
+    /// Opened with brsr: routed on arrival.
+    urls: Vec<String>,
+    /// Already routed here by another instance (`--open-url`).
+    routed: Vec<String>,
+    /// Local files, as `file:` URLs.
+    #[serde(default)]
+    files: Vec<String>,
+}
+
+impl Launch {
+    /// The links among command-line `args` (without the program name).
+    fn from_args(args: impl IntoIterator<Item = String>) -> Self {
+        let mut launch = Launch::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if arg == PROFILE_ARG {
+                args.next();
+            } else if arg == OPEN_URL_ARG {
+                launch.routed.extend(args.next());
+            } else if let Some(url) = arg.strip_prefix(OPEN_URL_ARG).and_then(|rest| rest.strip_prefix('=')) {
+                launch.routed.push(url.to_string());
+            } else {
+                launch.add(arg);
+            }
+        }
+        launch
+    }
+
+    /// Take `arg` in if it is a web link or a local file; anything else is ignored.
+    fn add(&mut self, arg: String) {
+        // Before parsing: `C:\page.html` parses as a URL with scheme `c`.
+        if Path::new(&arg).is_absolute() {
+            self.files.extend(url::Url::from_file_path(&arg).ok().map(String::from));
+            return;
+        }
+        match url::Url::parse(&arg) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") => self.urls.push(arg),
+            Ok(url) if url.scheme() == "file" => self.files.push(url.into()),
+            _ => {}
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct InstanceFile {
+    port: u16,
+    token: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Forward {
+    token: String,
+    launch: Launch,
+}
+
+#[derive(Default)]
+struct Queue {
+    pending: Vec<RouteDecision>,
+    /// The main window listens for `open-urls`.
+    subscribed: bool,
+}
+
+/// Managed state: links waiting for the main window.
+#[derive(Default)]
+pub struct UrlIntake {
+    queue: Mutex<Queue>,
+    /// The profile's instance lock, held until the process ends.
+    lock: Mutex<Option<File>>,
+}
+
+/// What [`claim`] decided for this launch.
+pub enum Claim {
+    /// This launch runs the profile; hand the instance to [`start`] once the app exists.
+    Run(Instance),
+    /// Another instance runs the profile. Exit without building the app.
+    Exit,
+}
+
+/// This launch's hold on its profile: the lock, and the launches handed over
+/// to it since.
+pub struct Instance {
+    lock: Option<File>,
+    launches: Option<mpsc::Receiver<Launch>>,
+}
+
+impl UrlIntake {
+    /// The main window started loading a page; its listener is gone.
+    pub fn unsubscribe(&self) {
+        self.queue.lock().unwrap().subscribed = false;
+    }
+}
+
+/// Send `decisions` to the main window, or queue them until it subscribes.
+fn deliver<R: tauri::Runtime>(app: &tauri::AppHandle<R>, decisions: Vec<RouteDecision>) {
+    if decisions.is_empty() {
+        return;
+    }
+    let intake = app.state::<UrlIntake>();
+    let mut queue = intake.queue.lock().unwrap();
+    if queue.subscribed {
+        let _ = app.emit_to("main", OPEN_URLS_EVENT, decisions);
+    } else {
+        println!("[Rust] Queued {} link(s) until the window is ready", decisions.len());
+        queue.pending.extend(decisions);
+    }
+}
+
+/// Route a launch's links and deliver the ones that open here. Files go to
+/// the navigation policy as if typed into the main window.
+fn receive<R: tauri::Runtime>(app: &tauri::AppHandle<R>, launch: Launch) {
+    for file in &launch.files {
+        if let Ok(url) = url::Url::parse(file) {
+            policy::apply(app, "main", &url, true);
+        }
+    }
+    let mut here: Vec<RouteDecision> = launch
+        .urls
+        .iter()
+        .map(|url| router::route(app, url))
+        .filter(|decision| matches!(decision.target, RouteTarget::Tab | RouteTarget::PrivateTab))
+        .collect();
+    here.extend(launch.routed.into_iter().map(|url| RouteDecision {
+        url,
+        rule_index: None,
+        rule_name: None,
+        target: RouteTarget::Tab,
+    }));
+    deliver(app, here);
+}
+
+/// Links the OS opened with the running app (deep-link events).
+fn open_urls<R: tauri::Runtime>(app: &tauri::AppHandle<R>, urls: Vec<String>) {
+    let mut launch = Launch::default();
+    for url in urls {
+        launch.add(url);
+    }
+    receive(app, launch);
+}
+
+/// Hand this launch to the instance already running our profile. Returns
+/// `false` if there is none, or it didn't answer.
+fn forward(instance: &InstanceFile, launch: &Launch) -> bool {
+    let send = || -> std::io::Result<bool> {
+        let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, instance.port));
+        let mut stream = TcpStream::connect_timeout(&addr, FORWARD_TIMEOUT)?;
+        stream.set_read_timeout(Some(FORWARD_TIMEOUT))?;
+        let message = Forward {
+            token: instance.token.clone(),
+            launch: launch.clone(),
+        };
+        let mut line = serde_json::to_vec(&message)?;
+        line.push(b'\n');
+        stream.write_all(&line)?;
+        let mut reply = String::new();
+        BufReader::new(stream).read_line(&mut reply)?;
+        Ok(reply.trim() == "ok")
+    };
+    send().unwrap_or(false)
+}
+
+/// Read one forwarded launch from `stream`, if it carries our token.
+fn accept(stream: TcpStream, token: &str) -> Option<Launch> {
+    stream.set_read_timeout(Some(FORWARD_TIMEOUT)).ok()?;
+    let mut reader = BufReader::new((&stream).take(MAX_MESSAGE));
+    let mut line = String::new();
+    reader.read_line(&mut line).ok()?;
+    let message: Forward = serde_json::from_str(&line).ok()?;
+    if message.token != token {
+        eprintln!("[Rust] Ignoring a forwarded launch with the wrong token");
+        return None;
+    }
+    let _ = (&stream).write_all(b"ok\n");
+    Some(message.launch)
+}
+
+fn read_instance(dir: &Path) -> Option<InstanceFile> {
+    let bytes = fs::read(dir.join(INSTANCE_FILE)).ok()?;
+    serde_json::from_slice(&bytes).ok()
+}
+
+fn show_main_window<R: tauri::Runtime>(app: &tauri::AppHandle<R>) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+/// Take later launches over a loopback port recorded in `dir/instance.json`.
+/// Only called with the lock held, so nothing else writes that file. Launches
+/// are acknowledged as they arrive and wait in the channel until [`start`].
+fn listen(dir: &Path) -> Result<mpsc::Receiver<Launch>, BrsrError> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+        .map_err(|e| BrsrError::platform(format!("Failed to listen for other launches: {}", e)))?;
+    let port = listener.local_addr().map_err(BrsrError::platform)?.port();
+    let token = random_token();
+    storage::save_json(&dir.join(INSTANCE_FILE), &InstanceFile { port, token: token.clone() })?;
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            if let Some(launch) = accept(stream, &token) {
+                let _ = sender.send(launch);
+            }
+        }
+    });
+    Ok(receiver)
+}
+
+/// Called before the app is built, once the profile is known. Takes the
+/// profile's instance lock, or hands this launch's links to the instance that
+/// holds it. Without a lock to take, this launch runs on its own.
+pub fn claim(profiles: &ProfileManager) -> Claim {
+    let alone = |e: BrsrError| {
+        eprintln!("[Rust] Later launches will start their own instance: {}", e);
+        Claim::Run(Instance { lock: None, launches: None })
+    };
+    let dir = match profiles.data_dir() {
+        Ok(dir) => dir,
+        Err(e) => return alone(e),
+    };
+    let path = dir.join(LOCK_FILE);
+    let lock = match fs::create_dir_all(&dir).and_then(|_| File::options().create(true).append(true).open(&path)) {
+        Ok(lock) => lock,
+        Err(e) => return alone(BrsrError::io(&path, e)),
+    };
+    let launch = Launch::from_args(std::env::args().skip(1));
+    let deadline = Instant::now() + CLAIM_TIMEOUT;
+    loop {
+        match lock.try_lock() {
+            Ok(()) => {
+                let launches = listen(&dir)
+                    .map_err(|e| eprintln!("[Rust] Later launches can't reach this one: {}", e))
+                    .ok();
+                return Claim::Run(Instance { lock: Some(lock), launches });
+            }
+            Err(TryLockError::WouldBlock) => {}
+            Err(TryLockError::Error(e)) => return alone(BrsrError::io(&path, e)),
+        }
+        if read_instance(&dir).is_some_and(|instance| forward(&instance, &launch)) {
+            println!("[Rust] Handed this launch to the running instance");
+            return Claim::Exit;
+        }
+        if Instant::now() >= deadline {
+            eprintln!("[Rust] The instance running this profile isn't answering; not starting another");
+            return Claim::Exit;
+        }
+        std::thread::sleep(CLAIM_RETRY);
+    }
+}
+
+/// Whether an instance runs the profile with data directory `dir`, whoever started it.
+pub fn is_running(dir: &Path) -> bool {
+    File::open(dir.join(LOCK_FILE)).is_ok_and(|file| matches!(file.try_lock(), Err(TryLockError::WouldBlock)))
+}
+
+/// Take in the links this instance was launched with, those the OS sends it
+/// and those later launches hand over, and keep the lock [`claim`] took for
+/// as long as the app runs. Needs the URL router.
+pub fn start<R: tauri::Runtime>(app: &tauri::AppHandle<R>, instance: Instance) {
+    use tauri_plugin_deep_link::DeepLinkExt;
+
+    *app.state::<UrlIntake>().lock.lock().unwrap() = instance.lock;
+    receive(app, Launch::from_args(std::env::args().skip(1)));
+    // On Linux and Windows the plugin only reports the command line, read above.
+    #[cfg(target_os = "macos")]
+    if let Ok(Some(urls)) = app.deep_link().get_current() {
+        open_urls(app, urls.iter().map(|url| url.to_string()).collect());
+    }
+    let handle = app.clone();
+    app.deep_link().on_open_url(move |event| {
+        open_urls(&handle, event.urls().iter().map(|url| url.to_string()).collect());
+    });
+    if let Some(launches) = instance.launches {
+        let app = app.clone();
+        std::thread::spawn(move || {
+            for launch in launches {
+                println!(
+                    "[Rust] Another launch handed over {} link(s)",
+                    launch.urls.len() + launch.routed.len() + launch.files.len()
+                );
+                show_main_window(&app);
+                receive(&app, launch);
+            }
+        });
+    }
+}
+
+/// The main window is listening for `open-urls`: replay what queued up while it wasn't.
+#[tauri::command]
+pub fn subscribe_open_urls(app: tauri::AppHandle, intake: tauri::State<'_, UrlIntake>) {
+    let mut queue = intake.queue.lock().unwrap();
+    queue.subscribed = true;
+    let pending = std::mem::take(&mut queue.pending);
+    if !pending.is_empty() {
+        println!("[Rust] Delivering {} queued link(s)", pending.len());
+        let _ = app.emit_to("main", OPEN_URLS_EVENT, pending);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_running_follows_the_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(!is_running(dir.path()));
+        let lock = File::create(dir.path().join(LOCK_FILE)).unwrap();
+        assert!(!is_running(dir.path()));
+        lock.try_lock().unwrap();
+        assert!(is_running(dir.path()));
+        drop(lock);
+        assert!(!is_running(dir.path()));
+    }
+
+    #[test]
+    fn launches_take_web_links_and_files() {
+        let path = std::env::temp_dir().join("page.html");
+        let args = [
+            "https://example.com/",
+            "file:///tmp/a.html",
+            path.to_str().unwrap(),
+            "page.html",
+            "mailto:someone@example.com",
+            "--open-url",
+            "https://routed.example/",
+        ];
+        let launch = Launch::from_args(args.map(String::from));
+        assert_eq!(launch.urls, ["https://example.com/"]);
+        assert_eq!(launch.routed, ["https://routed.example/"]);
+        assert_eq!(launch.files, ["file:///tmp/a.html", url::Url::from_file_path(&path).unwrap().as_str()]);
+    }
+}
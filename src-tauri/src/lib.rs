@@ -1,7 +1,38 @@
+use tauri::webview::PageLoadEvent;
 use tauri::{Emitter, LogicalPosition, LogicalSize, Manager, WebviewBuilder, WebviewUrl};
 use tauri::menu::{Menu, MenuItem, MenuItemKind, PredefinedMenuItem};
 // WebviewWindowExt not used directly; plugin is initialized below
 
+mod blocker;
+mod bookmarks;
+mod downloads;
+mod error;
+mod history;
+mod intake;
+mod navigation;
+mod omnibox;
+mod page_load;
+mod permissions;
+mod profiles;
+mod router;
+mod scripts;
+mod session;
+mod session_history;
+mod site_data;
+mod storage;
+mod tabs;
+mod telemetry;
+#[cfg(target_os = "linux")]
+mod xdg;
+
+use history::{HistoryStore, Transition};
+use navigation::policy::Decision;
+use error::BrsrError;
+use navigation::NativeAction;
+use session_history::Traversal;
+use tabs::{Bounds, ScrollPosition, TabRegistry, TabState};
+use telemetry::TelemetryEvent;
+
 #[cfg(target_os = "macos")]
 use window_vibrancy::{apply_vibrancy, NSVisualEffectMaterial};
 
@@ -11,208 +42,96 @@ use window_vibrancy::{apply_mica, apply_acrylic};
 #[tauri::command]
 async fn create_browser_webview(
     window: tauri::Window,
+    registry: tauri::State<'_, TabRegistry>,
     label: String,
     url: String,
     x: f64,
     y: f64,
     width: f64,
     height: f64,
-) -> Result<(), String> {
+    private: Option<bool>,
+) -> Result<(), BrsrError> {
     println!("[Rust] Creating webview '{}' at position ({}, {}) with size {}x{}", label, x, y, width, height);
     println!("[Rust] URL: {}", url);
     
-    let url_parsed = url.parse::<url::Url>()
-        .map_err(|e| format!("Failed to parse URL: {}", e))?;
+    let url_parsed = navigation::parse_navigable_url(&url)?;
     
     // Coordinates are already provided in logical (CSS) pixels from the frontend
     let logical_pos = LogicalPosition::new(x, y);
     let logical_size = LogicalSize::new(width, height);
+    let bounds = Bounds { x, y, width, height };
+
+    // A reloaded frontend re-mounts its slots and asks for webviews that still exist
+    // natively. Reuse them instead of failing on the duplicate label.
+    if registry.contains(&label) {
+        if let Some(webview) = window.get_webview(&label) {
+            println!("[Rust] Webview '{}' already exists, reusing it", label);
+            webview.set_position(logical_pos)?;
+            webview.set_size(logical_size)?;
+            registry.set_bounds(&label, bounds);
+            return Ok(());
+        }
+        // Stale entry: the native webview is gone, so rebuild it below.
+        registry.remove(&label);
+    }
+
+    let mut tab = TabState::new(label, url_parsed.to_string(), bounds);
+    tab.private = private.unwrap_or(false);
+    open_tab_webview(&window, tab, url_parsed)?;
+    Ok(())
+}
 
-    // Inject a lightweight telemetry script into every webview.
-    // It streams page info, basic performance, network metadata, console/error signals, and selection snippets
-    // back to the main window via Tauri's event API. This is an initial prototype and intentionally minimal.
-    let navigation_script = format!(r#"
-        (() => {{
-          const LABEL = {label:?};
-          const toMain = async (event, payload) => {{
-            try {{
-              const p = Object.assign({{ label: LABEL, ts: Date.now() }}, payload);
-              if (window.__TAURI__ && window.__TAURI__.event && window.__TAURI__.event.emitTo) {{
-                await window.__TAURI__.event.emitTo('main', event, p);
-              }} else if (window.__TAURI__ && window.__TAURI__.event && window.__TAURI__.event.emit) {{
-                await window.__TAURI__.event.emit(event, p);
-              }}
-            }} catch (_) {{}}
-          }};
-
-          const limit = (arr, n = 25) => arr.slice(-n);
-          let recentLogs = [];
-          let lastSelection = '';
-
-          const snapshot = () => {{
-            try {{
-              const metas = {{}};
-              for (const m of document.querySelectorAll('meta[name][content], meta[property][content]')) {{
-                const key = m.getAttribute('name') || m.getAttribute('property');
-                if (key && !(key in metas)) metas[key] = m.getAttribute('content') || '';
-              }}
-              const links = Array.from(document.querySelectorAll('a[href]')).slice(0, 200).map(a => ({{
-                href: a.href,
-                text: (a.textContent || '').trim().slice(0, 140),
-              }}));
-              const headings = Array.from(document.querySelectorAll('h1,h2,h3')).slice(0, 50).map(h => ({{
-                tag: h.tagName,
-                text: (h.textContent || '').trim().slice(0, 160),
-              }}));
-              const icons = Array.from(document.querySelectorAll('link[rel~="icon"]')).map(l => l.href);
-              const themeColor = (document.querySelector('meta[name="theme-color"]')?.content || null);
-              toMain('webview-telemetry', {{
-                kind: 'page-info',
-                title: document.title,
-                url: location.href,
-                lang: document.documentElement.getAttribute('lang') || null,
-                metas,
-                icons,
-                themeColor,
-                links,
-                headings,
-              }});
-            }} catch (_) {{}}
-          }};
-
-          // initial snapshot and on DOMContentLoaded
-          if (document.readyState !== 'loading') snapshot();
-          document.addEventListener('DOMContentLoaded', snapshot, {{ once: true }});
-
-          // selection changes
-          document.addEventListener('selectionchange', () => {{
-            try {{
-              const sel = window.getSelection();
-              const text = (sel && sel.toString()) ? sel.toString().trim().slice(0, 500) : '';
-              if (text && text !== lastSelection) {{
-                lastSelection = text;
-                toMain('webview-telemetry', {{ kind: 'selection', text }});
-              }}
-            }} catch (_) {{}}
-          }});
-
-          // Performance observers
-          try {{
-            const perfHandler = (list) => {{
-              for (const e of list.getEntries()) {{
-                if (e.entryType === 'resource') {{
-                  toMain('webview-telemetry', {{ kind: 'resource', item: {{
-                    type: e.initiatorType,
-                    name: e.name,
-                    duration: e.duration,
-                    startTime: e.startTime,
-                    transferSize: e.transferSize,
-                  }} }});
-                }} else if (e.entryType === 'paint') {{
-                  toMain('webview-telemetry', {{ kind: 'paint', name: e.name, startTime: e.startTime }});
-                }} else if (e.entryType === 'largest-contentful-paint') {{
-                  toMain('webview-telemetry', {{ kind: 'lcp', startTime: e.startTime, size: e.size, url: e.url || null }});
-                }} else if (e.entryType === 'navigation') {{
-                  toMain('webview-telemetry', {{ kind: 'navigation', domContentLoaded: e.domContentLoadedEventEnd, loadEventEnd: e.loadEventEnd, type: e.type }});
-                }} else if (e.entryType === 'longtask') {{
-                  toMain('webview-telemetry', {{ kind: 'longtask', startTime: e.startTime, duration: e.duration }});
-                }}
-              }}
-            }};
-            const po = new PerformanceObserver(perfHandler);
-            po.observe({{ entryTypes: ['resource', 'paint', 'largest-contentful-paint', 'navigation', 'longtask'] }});
-          }} catch (_) {{}}
-
-          // Wrap fetch
-          try {{
-            const origFetch = window.fetch;
-            window.fetch = async (...args) => {{
-              const started = performance.now();
-              const input = args[0];
-              const info = args[1] || {{}};
-              const url = (typeof input === 'string') ? input : input.url;
-              const method = (info && info.method) || (typeof input !== 'string' && input.method) || 'GET';
-              try {{
-                const res = await origFetch(...args);
-                const ended = performance.now();
-                let preview = null;
-                const ct = res.headers.get('content-type') || '';
-                if (ct.includes('application/json')) {{
-                  try {{ preview = JSON.stringify(await res.clone().json()).slice(0, 2000); }} catch (_) {{}}
-                }} else if (ct.startsWith('text/')) {{
-                  try {{ preview = (await res.clone().text()).slice(0, 2000); }} catch (_) {{}}
-                }}
-                toMain('webview-telemetry', {{ kind: 'fetch', url, method, status: res.status, duration: ended - started, preview }});
-                return res;
-              }} catch (err) {{
-                const ended = performance.now();
-                toMain('webview-telemetry', {{ kind: 'fetch-error', url, method, error: String(err), duration: ended - started }});
-                throw err;
-              }}
-            }};
-          }} catch (_) {{}}
-
-          // Wrap XHR
-          try {{
-            const OrigXHR = window.XMLHttpRequest;
-            function XHR() {{
-              const xhr = new OrigXHR();
-              let url = '';
-              let method = 'GET';
-              let started = 0;
-              const origOpen = xhr.open;
-              const origSend = xhr.send;
-              xhr.open = function(m, u, ...rest) {{ method = m; url = u; return origOpen.call(this, m, u, ...rest); }};
-              xhr.send = function(...rest) {{ started = performance.now(); return origSend.apply(this, rest); }};
-              xhr.addEventListener('loadend', function() {{
-                const ended = performance.now();
-                toMain('webview-telemetry', {{ kind: 'xhr', url, method, status: xhr.status, duration: ended - started }});
-              }});
-              return xhr;
-            }}
-            window.XMLHttpRequest = XHR;
-          }} catch (_) {{}}
-
-          // Console proxy
-          (() => {{
-            const levels = ['log', 'info', 'warn', 'error'];
-            for (const level of levels) {{
-              const orig = console[level];
-              console[level] = function(...args) {{
-                try {{
-                  const msg = args.map(a => {{ try {{ return typeof a === 'string' ? a : JSON.stringify(a); }} catch {{ return String(a); }} }}).join(' ');
-                  recentLogs = limit([...recentLogs, {{ level, msg }}], 50);
-                  toMain('webview-telemetry', {{ kind: 'console', level, message: msg }});
-                }} catch (_) {{}}
-                return orig.apply(this, args);
-              }};
-            }}
-          }})();
-
-          // Errors
-          window.addEventListener('error', (e) => toMain('webview-telemetry', {{ kind: 'error', message: e && e.message || 'Error', source: e && e.filename || null, lineno: e && e.lineno || null, colno: e && e.colno || null }}), {{ capture: true }});
-          window.addEventListener('unhandledrejection', (e) => toMain('webview-telemetry', {{ kind: 'unhandledrejection', reason: String(e && e.reason) }}), {{ capture: true }});
-
-          // Periodic lightweight ping of basic info
-          setInterval(() => {{
-            try {{ toMain('webview-telemetry', {{ kind: 'heartbeat', title: document.title, url: location.href }}); }} catch (_) {{}}
-          }}, 5000);
-
-          // Init log for sanity
-          toMain('webview-telemetry', {{ kind: 'init' }});
-        }})();
-    "#, label = label);
+/// Create the native webview for `tab` as a child of `window` and register it.
+/// Shared by `create_browser_webview`, session restore and `rebuild_tab_webview`.
+///
+/// The webview keeps its cookies and storage in the running profile's data
+/// directory. Private tabs get an incognito webview instead, whose data lives
+/// only as long as the webview does, so rebuilding one starts it clean.
+pub(crate) fn open_tab_webview(
+    window: &tauri::Window,
+    tab: TabState,
+    url_parsed: url::Url,
+) -> Result<tauri::Webview, BrsrError> {
+    let label = tab.label.clone();
+    let private = tab.private;
+    let logical_pos = LogicalPosition::new(tab.bounds.x, tab.bounds.y);
+    let logical_size = LogicalSize::new(tab.bounds.width, tab.bounds.height);
+    let registry = window.state::<TabRegistry>();
+    // Registered before the webview exists so the load hooks always find it.
+    registry.insert(tab);
+
+    // Bridge + telemetry probes for this tab; see scripts/mod.rs.
+    let navigation_script = window.state::<scripts::ScriptSettings>().script_for(window.app_handle(), &label);
+    let frame_script = window.state::<scripts::ScriptSettings>().frame_script_for(window.app_handle());
 
     let label_clone = label.clone();
     let window_clone = window.clone();
     let label_for_page_load = label.clone();
     let window_for_page_load = window.clone();
     
+    let mut builder = WebviewBuilder::new(label.clone(), WebviewUrl::External(url_parsed))
+        .initialization_script(&navigation_script)
+        .initialization_script_for_all_frames(&frame_script)
+        .incognito(private);
+    // Tabs share their profile's cookies and storage; private tabs have their own.
+    if !private {
+        builder = window.state::<profiles::ProfileManager>().with_data_store(builder);
+    }
+
     let result = window.add_child(
-        WebviewBuilder::new(label.clone(), WebviewUrl::External(url_parsed))
-            .initialization_script(&navigation_script)
+        builder
             .on_navigation(move |url| {
                 println!("[Rust] Webview '{}' navigating to: {}", label_clone, url);
+                // Hand-offs and blocks are reported by the policy itself
+                if navigation::policy::apply(window_clone.app_handle(), &label_clone, url, false) != Decision::Allow {
+                    return false;
+                }
+                let registry = window_clone.state::<TabRegistry>();
+                registry.update(&label_clone, |tab| {
+                    tab.url = url.to_string();
+                    tab.history.navigation_started(url.as_str());
+                });
+                page_load::navigation_started(window_clone.app_handle(), &label_clone, url.as_str());
                 
                 // Emit an event when navigation starts
                 let _ = window_clone.emit("webview-navigation-started", serde_json::json!({
@@ -220,18 +139,46 @@ async fn create_browser_webview(
                     "url": url.to_string()
                 }));
                 
-                // Allow all navigation
                 true
             })
-            .on_page_load(move |_window, payload| {
+            .on_download(|webview, event| downloads::intercept(&webview, event))
+            .on_page_load(move |webview, payload| {
                 let url = payload.url();
                 println!("[Rust] Webview '{}' page loaded with URL: {}", label_for_page_load, url);
-                
-                // Emit an event when page finishes loading
-                let _ = window_for_page_load.emit("webview-navigated", serde_json::json!({
-                    "label": label_for_page_load.clone(),
-                    "url": url.to_string()
-                }));
+                let registry = window_for_page_load.state::<TabRegistry>();
+                registry.set_url(&label_for_page_load, url.as_str());
+                if let PageLoadEvent::Started = payload.event() {
+                    page_load::committed(window_for_page_load.app_handle(), &label_for_page_load, Some(url.as_str()));
+                }
+                if let PageLoadEvent::Finished = payload.event() {
+                    let mut transition = None;
+                    registry.update(&label_for_page_load, |tab| {
+                        let committed = tab.history.commit(url.as_str());
+                        let typed = std::mem::take(&mut tab.typed_navigation);
+                        transition = Some(match committed {
+                            Transition::Link if typed => Transition::Typed,
+                            other => other,
+                        });
+                    });
+                    if let Some(transition) = transition {
+                        record_visit(window_for_page_load.app_handle(), &label_for_page_load, url.as_str(), transition);
+                    }
+                    emit_history_changed(window_for_page_load.app_handle(), &label_for_page_load);
+
+                    // Restored tabs return to where the user had scrolled
+                    let mut pending_scroll = None;
+                    registry.update(&label_for_page_load, |tab| pending_scroll = tab.pending_scroll.take());
+                    if let Some(scroll) = pending_scroll {
+                        let _ = webview.eval(&format!("window.scrollTo({}, {})", scroll.x, scroll.y));
+                    }
+
+                    page_load::finished(window_for_page_load.app_handle(), &label_for_page_load, url.as_str());
+                    // Emit an event when page finishes loading
+                    let _ = window_for_page_load.emit("webview-navigated", serde_json::json!({
+                        "label": label_for_page_load.clone(),
+                        "url": url.to_string()
+                    }));
+                }
             })
             .on_document_title_changed({
                 let window_for_title = window.clone();
@@ -241,6 +188,13 @@ async fn create_browser_webview(
                         "[Rust] Webview '{}' title changed: {}",
                         label_for_title, title
                     );
+                    let registry = window_for_title.state::<TabRegistry>();
+                    registry.set_title(&label_for_title, &title);
+                    if let Some(tab) = registry.get(&label_for_title).filter(|tab| !tab.private) {
+                        if let Err(e) = window_for_title.state::<HistoryStore>().set_title(&tab.url, &title) {
+                            eprintln!("[Rust] {}", e);
+                        }
+                    }
                     let _ = window_for_title.emit(
                         "webview-title-changed",
                         serde_json::json!({
@@ -255,151 +209,339 @@ async fn create_browser_webview(
     );
     
     match result {
-        Ok(_webview) => {
+        Ok(webview) => {
             println!("[Rust] Successfully created webview '{}'", label);
-            Ok(())
+            permissions::watch_native(window.app_handle(), &webview);
+            blocker::attach(window.app_handle(), &webview);
+            Ok(webview)
         }
         Err(e) => {
             println!("[Rust] Failed to create webview '{}': {}", label, e);
-            Err(format!("Failed to create webview: {}", e))
+            registry.remove(&label);
+            Err(BrsrError::platform(format!("Failed to create webview: {}", e)))
         }
     }
 }
 
+/// Recreate a tab's webview in place, keeping its registry state (title, session
+/// history, flags) and reloading its current URL. Used when its injected
+/// script has to change. The native back/forward list starts over; steps
+/// beyond it load the entries' URLs.
+pub(crate) fn rebuild_tab_webview(window: &tauri::Window, label: &str) -> Result<(), BrsrError> {
+    let registry = window.state::<TabRegistry>();
+    let mut tab = registry
+        .get(label)
+        .ok_or_else(|| BrsrError::not_found("tab", label))?;
+    let url = navigation::parse_navigable_url(&tab.url)?;
+    println!("[Rust] Rebuilding webview '{}'", label);
+    if let Some(webview) = window.get_webview(label) {
+        webview.close()?;
+    }
+    tab.pending_scroll = Some(tab.scroll);
+    tab.history.webview_replaced();
+    let visible = tab.visible;
+    let webview = open_tab_webview(window, tab, url)?;
+    if !visible {
+        webview.hide()?;
+    }
+    Ok(())
+}
+
 #[tauri::command]
-async fn show_webview(window: tauri::Window, label: String) -> Result<(), String> {
+async fn show_webview(
+    window: tauri::Window,
+    registry: tauri::State<'_, TabRegistry>,
+    label: String,
+) -> Result<(), BrsrError> {
     println!("[Rust] Showing webview '{}'", label);
-    if let Some(webview) = window.get_webview(&label) {
-        webview.show().map_err(|e| e.to_string())?;
-        println!("[Rust] Webview '{}' shown successfully", label);
-    } else {
+    let webview = window.get_webview(&label).ok_or_else(|| {
         println!("[Rust] Webview '{}' not found!", label);
-    }
+        BrsrError::not_found("tab", &label)
+    })?;
+    webview.show()?;
+    registry.set_visible(&label, true);
+    println!("[Rust] Webview '{}' shown successfully", label);
     Ok(())
 }
 
 #[tauri::command]
-async fn hide_webview(window: tauri::Window, label: String) -> Result<(), String> {
+async fn hide_webview(
+    window: tauri::Window,
+    registry: tauri::State<'_, TabRegistry>,
+    label: String,
+) -> Result<(), BrsrError> {
     println!("[Rust] Hiding webview '{}'", label);
-    if let Some(webview) = window.get_webview(&label) {
-        webview.hide().map_err(|e| e.to_string())?;
-        println!("[Rust] Webview '{}' hidden successfully", label);
-    } else {
+    let webview = window.get_webview(&label).ok_or_else(|| {
         println!("[Rust] Webview '{}' not found!", label);
-    }
+        BrsrError::not_found("tab", &label)
+    })?;
+    webview.hide()?;
+    registry.set_visible(&label, false);
+    println!("[Rust] Webview '{}' hidden successfully", label);
     Ok(())
 }
 
 #[tauri::command]
-async fn close_webview(window: tauri::Window, label: String) -> Result<(), String> {
-    if let Some(webview) = window.get_webview(&label) {
-        webview.close().map_err(|e| e.to_string())?;
+async fn close_webview(
+    window: tauri::Window,
+    registry: tauri::State<'_, TabRegistry>,
+    label: String,
+) -> Result<(), BrsrError> {
+    let webview = window.get_webview(&label);
+    if let Some(webview) = &webview {
+        webview.close()?;
+    }
+    window.state::<telemetry::metrics::TabMetricsStore>().remove(&label);
+    window.state::<blocker::ContentBlocker>().remove(&label);
+    window.state::<downloads::DownloadManager>().reset_page(&label);
+    window.state::<telemetry::channel::Channel>().remove(&label);
+    window.state::<permissions::SitePermissionStore>().cancel_for_tab(window.app_handle(), &label);
+    let tab = registry.remove(&label);
+    if let Some(tab) = &tab {
+        if tab.private {
+            if !registry.any_private() {
+                discard_private_data(window.app_handle());
+            }
+        } else {
+            window.state::<session::SessionStore>().push_closed(tab);
+            session::save(window.app_handle());
+        }
+    }
+    // Cleanup above still ran in case only one of the two was left behind.
+    if webview.is_none() && tab.is_none() {
+        println!("[Rust] Webview '{}' not found!", label);
+        return Err(BrsrError::not_found("tab", &label));
     }
     Ok(())
 }
 
+/// The last private tab closed. Their cookies and storage went with their
+/// webviews; drop what Rust kept in memory for them.
+fn discard_private_data(app: &tauri::AppHandle) {
+    println!("[Rust] Last private tab closed, discarding private data");
+    app.state::<downloads::DownloadManager>().discard_private(app);
+}
+
 #[tauri::command]
 async fn update_webview_bounds(
     window: tauri::Window,
+    registry: tauri::State<'_, TabRegistry>,
     label: String,
     x: f64,
     y: f64,
     width: f64,
     height: f64,
-) -> Result<(), String> {
+) -> Result<(), BrsrError> {
     println!("[Rust] Updating webview '{}' position to ({}, {}) with size {}x{}", label, x, y, width, height);
     
-    if let Some(webview) = window.get_webview(&label) {
-        let logical_pos = LogicalPosition::new(x, y);
-        let logical_size = LogicalSize::new(width, height);
-
-        webview.set_position(logical_pos).map_err(|e| e.to_string())?;
-        webview.set_size(logical_size).map_err(|e| e.to_string())?;
-        
-        println!("[Rust] Webview '{}' bounds updated successfully", label);
-    } else {
+    let webview = window.get_webview(&label).ok_or_else(|| {
         println!("[Rust] Webview '{}' not found!", label);
-    }
+        BrsrError::not_found("tab", &label)
+    })?;
+    let logical_pos = LogicalPosition::new(x, y);
+    let logical_size = LogicalSize::new(width, height);
+
+    webview.set_position(logical_pos)?;
+    webview.set_size(logical_size)?;
+    registry.set_bounds(&label, Bounds { x, y, width, height });
+
+    println!("[Rust] Webview '{}' bounds updated successfully", label);
     Ok(())
 }
 
 #[tauri::command]
-async fn navigate_webview(window: tauri::Window, label: String, url: String) -> Result<(), String> {
+async fn navigate_webview(window: tauri::Window, label: String, url: String) -> Result<(), BrsrError> {
     println!("[Rust] Navigating webview '{}' to '{}'", label, url);
-    if let Some(webview) = window.get_webview(&label) {
-        // Navigate to the new URL using JavaScript
-        let script = format!("window.location.href = '{}'", url);
-        webview.eval(&script).map_err(|e| e.to_string())?;
-        println!("[Rust] Webview '{}' navigated to '{}' successfully", label, url);
-    } else {
+    let target = navigation::parse_navigable_url(&url)?;
+    let webview = window.get_webview(&label).ok_or_else(|| {
         println!("[Rust] Webview '{}' not found!", label);
-        return Err(format!("Webview '{}' not found", label));
+        BrsrError::not_found("tab", &label)
+    })?;
+    // Checked up front so a blocked URL fails the command instead of silently not loading
+    match navigation::policy::apply(window.app_handle(), &label, &target, true) {
+        Decision::Allow => {}
+        Decision::Block(reason) => {
+            return Err(BrsrError::Policy {
+                url: Some(target.to_string()),
+                reason: reason.as_str().to_string(),
+            })
+        }
+        // Handed to the OS or the file viewer; the tab stays where it is
+        Decision::OpenExternal | Decision::AskExternal | Decision::OpenFileViewer => return Ok(()),
     }
+    window
+        .state::<TabRegistry>()
+        .update(&label, |tab| tab.typed_navigation = true);
+    webview.navigate(target.clone())?;
+    println!("[Rust] Webview '{}' navigated to '{}' successfully", label, target);
     Ok(())
 }
 
 #[tauri::command]
-async fn refresh_webview(window: tauri::Window, label: String) -> Result<(), String> {
-    println!("[Rust] Refreshing webview '{}'", label);
-    if let Some(webview) = window.get_webview(&label) {
-        webview.eval("location.reload()").map_err(|e| e.to_string())?;
-        println!("[Rust] Webview '{}' refreshed successfully", label);
-    } else {
+async fn refresh_webview(
+    window: tauri::Window,
+    label: String,
+    hard: Option<bool>,
+) -> Result<(), BrsrError> {
+    let hard = hard.unwrap_or(false);
+    println!("[Rust] Refreshing webview '{}' (hard: {})", label, hard);
+    let webview = window.get_webview(&label).ok_or_else(|| {
         println!("[Rust] Webview '{}' not found!", label);
-        return Err(format!("Webview '{}' not found", label));
+        BrsrError::not_found("tab", &label)
+    })?;
+    let action = if hard { NativeAction::HardReload } else { NativeAction::Reload };
+    navigation::perform(&webview, action)?;
+    println!("[Rust] Webview '{}' refreshed successfully", label);
+    Ok(())
+}
+
+/// Step a tab `delta` entries through its session history: natively when its
+/// webview has the entry, else by loading the entry's URL (restored and
+/// rebuilt tabs; see session_history.rs).
+fn traverse(
+    webview: &tauri::Webview,
+    registry: &TabRegistry,
+    delta: isize,
+    action: NativeAction,
+) -> Result<(), BrsrError> {
+    let mut step = None;
+    registry.update(webview.label(), |tab| step = tab.history.begin_traversal(delta));
+    match step {
+        Some(Traversal::Load(url)) => webview.navigate(navigation::parse_navigable_url(&url)?)?,
+        // Our list may have missed a step the webview knows about (swipes).
+        Some(Traversal::Native) | None => navigation::perform(webview, action)?,
     }
     Ok(())
 }
 
 #[tauri::command]
-async fn navigate_back_webview(window: tauri::Window, label: String) -> Result<(), String> {
+async fn navigate_back_webview(
+    window: tauri::Window,
+    registry: tauri::State<'_, TabRegistry>,
+    label: String,
+) -> Result<(), BrsrError> {
     println!("[Rust] Navigating back in webview '{}'", label);
-    if let Some(webview) = window.get_webview(&label) {
-        webview.eval("history.back()").map_err(|e| e.to_string())?;
-        println!("[Rust] Webview '{}' navigated back successfully", label);
-    } else {
+    let webview = window.get_webview(&label).ok_or_else(|| {
         println!("[Rust] Webview '{}' not found!", label);
-        return Err(format!("Webview '{}' not found", label));
-    }
+        BrsrError::not_found("tab", &label)
+    })?;
+    traverse(&webview, &registry, -1, NativeAction::Back)?;
+    println!("[Rust] Webview '{}' navigated back successfully", label);
     Ok(())
 }
 
 #[tauri::command]
-async fn navigate_forward_webview(window: tauri::Window, label: String) -> Result<(), String> {
+async fn navigate_forward_webview(
+    window: tauri::Window,
+    registry: tauri::State<'_, TabRegistry>,
+    label: String,
+) -> Result<(), BrsrError> {
     println!("[Rust] Navigating forward in webview '{}'", label);
-    if let Some(webview) = window.get_webview(&label) {
-        webview.eval("history.forward()").map_err(|e| e.to_string())?;
-        println!("[Rust] Webview '{}' navigated forward successfully", label);
-    } else {
+    let webview = window.get_webview(&label).ok_or_else(|| {
         println!("[Rust] Webview '{}' not found!", label);
-        return Err(format!("Webview '{}' not found", label));
-    }
+        BrsrError::not_found("tab", &label)
+    })?;
+    traverse(&webview, &registry, 1, NativeAction::Forward)?;
+    println!("[Rust] Webview '{}' navigated forward successfully", label);
     Ok(())
 }
 
 
-
 // (removed) poll_webview_url: replaced by event-driven navigation reporting
 
+/// Add a committed navigation to the persistent browsing history.
+fn record_visit(app: &tauri::AppHandle, label: &str, url: &str, transition: Transition) {
+    if app.state::<TabRegistry>().is_private(label) {
+        return;
+    }
+    if let Err(e) = app.state::<HistoryStore>().record_visit(url, "", transition, label) {
+        eprintln!("[Rust] {}", e);
+    }
+}
+
+/// Tell the main window that a tab's back/forward availability may have changed.
+fn emit_history_changed(app: &tauri::AppHandle, label: &str) {
+    if let Some(tab) = app.state::<TabRegistry>().get(label) {
+        let _ = app.emit_to("main", "webview-history-changed", serde_json::json!({
+            "label": label,
+            "url": tab.url,
+            "canGoBack": tab.history.can_go_back(),
+            "canGoForward": tab.history.can_go_forward()
+        }));
+    }
+}
+
+/// Rust-side consumers of validated telemetry from the tab channel: the
+/// per-tab metrics store, same-document history changes, bookmark favicons,
+/// scroll offsets for session restore, blocked-request counts, site
+/// permission requests and page-load steps.
+fn handle_telemetry(app: &tauri::AppHandle, message: &telemetry::TelemetryMessage) {
+    app.state::<telemetry::metrics::TabMetricsStore>().record(message);
+    let registry = app.state::<TabRegistry>();
+    match &message.event {
+        TelemetryEvent::History(signal) => {
+            if let Some(change) = session_history::handle_history_signal(
+                &registry,
+                &message.label,
+                signal.action,
+                &signal.url,
+                signal.delta,
+            ) {
+                if let Some(transition) = change.transition {
+                    record_visit(app, &change.label, &change.url, transition);
+                }
+                emit_history_changed(app, &change.label);
+            }
+        }
+        TelemetryEvent::PageInfo(info) if !registry.is_private(&message.label) => {
+            if let Some(tab) = registry.get(&message.label) {
+                app.state::<bookmarks::Bookmarks>().handle_page_info(&tab.url, info)
+            }
+        }
+        TelemetryEvent::Scroll(scroll) => {
+            registry.update(&message.label, |tab| tab.scroll = ScrollPosition { x: scroll.x, y: scroll.y });
+        }
+        TelemetryEvent::Init => {
+            page_load::committed(app, &message.label, None);
+            app.state::<blocker::ContentBlocker>().reset_page(&message.label);
+            app.state::<downloads::DownloadManager>().reset_page(&message.label);
+            // Requests from the previous document can no longer be answered.
+            app.state::<permissions::SitePermissionStore>().cancel_for_tab(app, &message.label);
+        }
+        TelemetryEvent::Blocked(request) => {
+            if let Some(tab) = registry.get(&message.label) {
+                app.state::<blocker::ContentBlocker>().confirm_blocked(&message.label, &tab.url, request)
+            }
+        }
+        TelemetryEvent::PermissionRequest(request) => permissions::handle_request(app, &message.label, request),
+        TelemetryEvent::DomReady(ready) => {
+            page_load::dom_ready(app, &message.label, &ready.url, ready.dom_content_loaded)
+        }
+        TelemetryEvent::FormPost(post) => {
+            app.state::<downloads::DownloadManager>().form_posted(&message.label, &post.action)
+        }
+        _ => {}
+    }
+}
+
 #[tauri::command]
-async fn check_navigation_state(window: tauri::Window, label: String) -> Result<(bool, bool), String> {
+async fn check_navigation_state(
+    registry: tauri::State<'_, TabRegistry>,
+    label: String,
+) -> Result<(bool, bool), BrsrError> {
     println!("[Rust] Checking navigation state for webview '{}'", label);
-    if let Some(_webview) = window.get_webview(&label) {
-        // Unfortunately, browsers don't expose navigation state reliably due to security
-        // For now, we'll enable back after the first navigation
-        // This will be improved when Tauri adds proper navigation event support
-        
-        // Always enable back button (browser will handle if it can't go back)
-        // Never enable forward button (can't reliably detect)
-        Ok((true, false))
-    } else {
-        println!("[Rust] Webview '{}' not found!", label);
-        return Err(format!("Webview '{}' not found", label));
+    // Answered from the session history we track in Rust; see session_history.rs.
+    match registry.get(&label) {
+        Some(tab) => Ok((tab.history.can_go_back(), tab.history.can_go_forward())),
+        None => {
+            println!("[Rust] Webview '{}' not found!", label);
+            Err(BrsrError::not_found("tab", &label))
+        }
     }
 }
 
 #[tauri::command]
-fn set_default_browser(app: tauri::AppHandle, _bundle_id: Option<String>) -> Result<(), String> {
+fn set_default_browser(app: tauri::AppHandle, _bundle_id: Option<String>) -> Result<(), BrsrError> {
     #[cfg(target_os = "macos")]
     {
         use objc::{class, msg_send, sel, sel_impl};
@@ -543,18 +685,34 @@ fn set_default_browser(app: tauri::AppHandle, _bundle_id: Option<String>) -> Res
             }
 
             println!("[set_default_browser] NSWorkspace calls completed");
-        }).map_err(|e| e.to_string())?;
+        })?;
 
         Ok(())
     }
-    #[cfg(not(target_os = "macos"))]
+    #[cfg(target_os = "linux")]
     {
-        Err("Default browser setting is only supported on macOS".to_string())
+        let _ = app;
+        let dirs = xdg::XdgDirs::from_env()?;
+        let apps = dirs.applications();
+        let id = match xdg::own_entry(&apps) {
+            Some(ours) => ours.id.clone(),
+            // Nothing installed us (an AppImage or unpacked build): register first.
+            None => {
+                let exe = xdg::executable().ok_or_else(|| BrsrError::platform("Could not locate the executable"))?;
+                xdg::register(&dirs, &exe)?.desktop_id
+            }
+        };
+        xdg::set_default_browser(&dirs, &id)?;
+        Ok(())
+    }
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+    {
+        Err(BrsrError::unsupported("Setting the default browser"))
     }
 }
 
 #[tauri::command]
-fn get_default_http_handler() -> Result<String, String> {
+fn get_default_http_handler() -> Result<String, BrsrError> {
     #[cfg(target_os = "macos")]
     unsafe {
         use core_foundation::base::TCFType;
@@ -573,16 +731,24 @@ fn get_default_http_handler() -> Result<String, String> {
         let handler = { CFString::wrap_under_create_rule(handler_ref) };
         Ok(handler.to_string())
     }
-    #[cfg(not(target_os = "macos"))]
+    #[cfg(target_os = "linux")]
+    {
+        let dirs = xdg::XdgDirs::from_env()?;
+        let apps = dirs.applications();
+        Ok(xdg::default_for(&dirs, &apps, "x-scheme-handler/http")
+            .map(|app| app.id.clone())
+            .unwrap_or_else(|| "(none)".into()))
+    }
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
     {
-        Err("macOS only".into())
+        Err(BrsrError::unsupported("Reading the default browser"))
     }
 }
 
 // Devtools command: available in debug builds or when the `devtools` feature is enabled.
 #[cfg(any(debug_assertions, feature = "devtools"))]
 #[tauri::command]
-fn open_main_devtools(app: tauri::AppHandle) -> Result<(), String> {
+fn open_main_devtools(app: tauri::AppHandle) -> Result<(), BrsrError> {
     if let Some(w) = app.get_webview_window("main") {
         w.open_devtools();
     }
@@ -591,12 +757,12 @@ fn open_main_devtools(app: tauri::AppHandle) -> Result<(), String> {
 
 #[cfg(not(any(debug_assertions, feature = "devtools")))]
 #[tauri::command]
-fn open_main_devtools(_app: tauri::AppHandle) -> Result<(), String> {
-    Err("Devtools are disabled in this build".to_string())
+fn open_main_devtools(_app: tauri::AppHandle) -> Result<(), BrsrError> {
+    Err(BrsrError::unsupported("Devtools"))
 }
 
 #[tauri::command]
-fn list_http_candidates() -> Result<Vec<String>, String> {
+fn list_http_candidates() -> Result<Vec<String>, BrsrError> {
     #[cfg(target_os = "macos")]
     unsafe {
         use objc::{class, msg_send, sel, sel_impl};
@@ -605,7 +771,7 @@ fn list_http_candidates() -> Result<Vec<String>, String> {
 
         let ws: *mut Object = msg_send![class!(NSWorkspace), sharedWorkspace];
         let http: *mut Object = msg_send![class!(NSURL), URLWithString: NSString::from_str("http:")];
-        if http.is_null() { return Err("Could not create http URL".into()); }
+        if http.is_null() { return Err(BrsrError::platform("Could not create http URL")); }
         let urls: *mut Object = msg_send![ws, URLsForApplicationsToOpenURL: http];
         if urls.is_null() {
             return Ok(Vec::new());
@@ -647,14 +813,23 @@ fn list_http_candidates() -> Result<Vec<String>, String> {
         }
         Ok(out)
     }
-    #[cfg(not(target_os = "macos"))]
+    #[cfg(target_os = "linux")]
     {
-        Err("macOS only".into())
+        let dirs = xdg::XdgDirs::from_env()?;
+        let apps = dirs.applications();
+        Ok(xdg::browser_candidates(&apps)
+            .into_iter()
+            .map(|app| format!("{} ({})", app.name, app.id))
+            .collect())
+    }
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+    {
+        Err(BrsrError::unsupported("Listing browsers"))
     }
 }
 
 #[tauri::command]
-fn is_default_browser() -> Result<bool, String> {
+fn is_default_browser() -> Result<bool, BrsrError> {
     #[cfg(target_os = "macos")]
     unsafe {
         use core_foundation::base::TCFType;
@@ -686,13 +861,57 @@ fn is_default_browser() -> Result<bool, String> {
 
         Ok(handler == our_bid)
     }
-    #[cfg(not(target_os = "macos"))]
+    #[cfg(target_os = "linux")]
+    {
+        let dirs = xdg::XdgDirs::from_env()?;
+        let apps = dirs.applications();
+        let Some(ours) = xdg::own_entry(&apps) else { return Ok(false) };
+        Ok(xdg::default_for(&dirs, &apps, "x-scheme-handler/http").is_some_and(|app| app.id == ours.id))
+    }
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
     {
         Ok(false)
     }
 }
+
+/// Install a `.desktop` entry and icons for this executable in the user's data
+/// dir, so the desktop offers brsr as a browser.
+#[cfg(target_os = "linux")]
+#[tauri::command]
+fn register_as_browser_candidate() -> Result<xdg::CandidateRegistration, BrsrError> {
+    let dirs = xdg::XdgDirs::from_env()?;
+    let exe = xdg::executable().ok_or_else(|| BrsrError::platform("Could not locate the executable"))?;
+    xdg::register(&dirs, &exe)
+}
+
+#[cfg(not(target_os = "linux"))]
+#[tauri::command]
+fn register_as_browser_candidate() -> Result<(), BrsrError> {
+    Err(BrsrError::unsupported("Registering as a browser"))
+}
+
+#[cfg(target_os = "linux")]
+#[tauri::command]
+fn unregister_browser_candidate() -> Result<xdg::CandidateRegistration, BrsrError> {
+    xdg::unregister(&xdg::XdgDirs::from_env()?)
+}
+
+#[cfg(not(target_os = "linux"))]
+#[tauri::command]
+fn unregister_browser_candidate() -> Result<(), BrsrError> {
+    Err(BrsrError::unsupported("Registering as a browser"))
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    let context = tauri::generate_context!();
+    // Every store reads from the running profile's data directory
+    let profiles = profiles::ProfileManager::load(storage::app_data_dir(&context.config().identifier));
+    // A launch for a profile that is already open only passes its links on
+    let instance = match intake::claim(&profiles) {
+        intake::Claim::Run(instance) => instance,
+        intake::Claim::Exit => return,
+    };
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_window_state::Builder::default().build())
@@ -700,6 +919,22 @@ pub fn run() {
         .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_process::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
+        .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_opener::init())
+        .plugin(telemetry::channel::init())
+        .manage(TabRegistry::default())
+        .manage(telemetry::Telemetry::default())
+        .manage(telemetry::channel::Channel::default())
+        .manage(telemetry::metrics::TabMetricsStore::default())
+        .manage(site_data::SiteData::default())
+        .manage(intake::UrlIntake::default())
+        .manage(profiles)
+        // A reloading main window stops listening for links until it subscribes again
+        .on_page_load(|webview, payload| {
+            if webview.label() == "main" && payload.event() == PageLoadEvent::Started {
+                webview.state::<intake::UrlIntake>().unsubscribe();
+            }
+        })
         .invoke_handler(tauri::generate_handler![
             create_browser_webview,
             show_webview,
@@ -715,11 +950,93 @@ pub fn run() {
             get_default_http_handler,
             list_http_candidates,
             is_default_browser,
-            open_main_devtools
+            register_as_browser_candidate,
+            unregister_browser_candidate,
+            open_main_devtools,
+            tabs::list_webviews,
+            tabs::get_webview_state,
+            tabs::set_tab_pinned,
+            telemetry::get_telemetry_stats,
+            telemetry::get_tab_metrics,
+            telemetry::get_tab_logs,
+            telemetry::export_har,
+            scripts::get_probe_settings,
+            scripts::set_probe_defaults,
+            scripts::set_site_probes,
+            scripts::set_tab_probes,
+            session::restore_last_session,
+            session::list_closed_tabs,
+            session::reopen_closed_tab,
+            session_history::get_session_history,
+            navigation::policy::get_navigation_policy,
+            navigation::policy::set_navigation_policy,
+            blocker::get_content_blocker,
+            blocker::add_filter_list,
+            blocker::reload_filter_list,
+            blocker::remove_filter_list,
+            blocker::set_filter_list_enabled,
+            blocker::set_content_blocking,
+            blocker::set_site_blocking,
+            blocker::get_blocked_counts,
+            permissions::get_site_permissions,
+            permissions::set_site_permission,
+            permissions::resolve_permission_request,
+            downloads::list_downloads,
+            downloads::cancel_download,
+            downloads::pause_download,
+            downloads::resume_download,
+            downloads::retry_download,
+            downloads::open_download,
+            downloads::reveal_download,
+            downloads::remove_download,
+            downloads::get_download_settings,
+            downloads::set_download_settings,
+            profiles::list_profiles,
+            profiles::create_profile,
+            profiles::rename_profile,
+            profiles::delete_profile,
+            profiles::switch_profile,
+            profiles::open_profile_window,
+            router::get_url_router,
+            router::set_url_router,
+            router::test_route,
+            intake::subscribe_open_urls,
+            site_data::list_cookie_domains,
+            site_data::list_cookies,
+            site_data::delete_cookies,
+            site_data::clear_site_data,
+            site_data::clear_browsing_data,
+            omnibox::resolve_omnibox_input,
+            omnibox::get_search_engines,
+            omnibox::set_search_engines,
+            history::history_query,
+            history::history_delete,
+            history::history_clear,
+            bookmarks::bookmarks_tree,
+            bookmarks::bookmark_create,
+            bookmarks::bookmark_create_folder,
+            bookmarks::bookmark_update,
+            bookmarks::bookmark_move,
+            bookmarks::bookmark_delete,
+            bookmarks::bookmarks_search,
+            bookmarks::bookmarks_for_url,
+            bookmarks::bookmarks_import_html,
+            bookmarks::bookmarks_export_html
         ])
-        .setup(|app| {
+        .setup(move |app| {
             let main_window = app.get_webview_window("main").unwrap();
             println!("[Rust] Main window created, label: {}", main_window.label());
+            app.manage(omnibox::SearchEngines::load(app.handle()));
+            app.manage(blocker::ContentBlocker::load(app.handle()));
+            app.manage(permissions::SitePermissionStore::load(app.handle()));
+            app.manage(scripts::ScriptSettings::load(app.handle()));
+            app.manage(navigation::policy::NavigationPolicy::load(app.handle()));
+            app.manage(router::UrlRouter::load(app.handle()));
+            intake::start(app.handle(), instance);
+            app.manage(HistoryStore::load(app.handle()));
+            app.manage(bookmarks::Bookmarks::load(app.handle()));
+            app.manage(session::SessionStore::load(app.handle()));
+            app.manage(downloads::DownloadManager::load(app.handle()));
             // Auto-open devtools on debug builds to aid diagnostics
             #[cfg(debug_assertions)]
             {
@@ -797,6 +1114,11 @@ pub fn run() {
                 });
             }
 
+            // Bring back the previous session's tabs before the frontend asks for them,
+            // then keep the snapshot on disk fresh
+            session::restore_on_startup(app.handle());
+            session::start_autosave(app.handle());
+
             // List all webviews
             let webviews = main_window.webviews();
             println!("[Rust] Initial webviews count: {}", webviews.len());
@@ -806,6 +1128,11 @@ pub fn run() {
             
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(context)
+        .expect("error while building tauri application")
+        .run(|app_handle, event| {
+            if let tauri::RunEvent::ExitRequested { .. } = event {
+                session::save(app_handle);
+            }
+        });
 }
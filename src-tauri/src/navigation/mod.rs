@@ -0,0 +1,135 @@
+//! Validated, native navigation for child webviews.
+//!
+//! User input is parsed into a `url::Url` and checked against the schemes a tab
+//! may load before it reaches the webview's native `navigate`. Nothing here
+//! builds JavaScript from user input. Where a tab may go from there is decided
+//! by the policy in [`policy`].
+
+pub mod policy;
+
+use std::fmt;
+
+use url::Url;
+
+/// Schemes a tab may be navigated to directly.
+const ALLOWED_SCHEMES: &[&str] = &["http", "https", "file", "about"];
+
+#[derive(Debug, Clone)]
+pub enum NavigationError {
+    /// The input could not be parsed as an absolute URL.
+    InvalidUrl { input: String, reason: String },
+    /// The URL parsed but uses a scheme tabs may not load (e.g. `javascript:`).
+    DisallowedScheme { url: String, scheme: String },
+    /// The webview rejected the operation.
+    Webview { label: String, message: String },
+    /// This platform's webview can't do the operation.
+    Unsupported { operation: String },
+}
+
+impl fmt::Display for NavigationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidUrl { input, reason } => write!(f, "Invalid URL '{}': {}", input, reason),
+            Self::DisallowedScheme { scheme, .. } => write!(f, "Navigation to '{}:' URLs is not allowed", scheme),
+            Self::Webview { label, message } => write!(f, "Webview '{}' error: {}", label, message),
+            Self::Unsupported { operation } => write!(f, "{} is not supported here", operation),
+        }
+    }
+}
+
+impl std::error::Error for NavigationError {}
+
+/// Parse user input into a URL a tab may navigate to.
+///
+/// Leading/trailing whitespace is trimmed; the `url` crate normalises the rest
+/// (lowercased scheme and host, percent-encoding, stripped tabs/newlines).
+/// Relative input is rejected rather than guessed at.
+pub fn parse_navigable_url(input: &str) -> Result<Url, NavigationError> {
+    let trimmed = input.trim();
+    if trimmed.is_empty() {
+        return Err(NavigationError::InvalidUrl {
+            input: input.to_string(),
+            reason: "empty input".into(),
+        });
+    }
+    let url = Url::parse(trimmed).map_err(|e| NavigationError::InvalidUrl {
+        input: input.to_string(),
+        reason: e.to_string(),
+    })?;
+    if !ALLOWED_SCHEMES.contains(&url.scheme()) {
+        return Err(NavigationError::DisallowedScheme {
+            url: url.to_string(),
+            scheme: url.scheme().to_string(),
+        });
+    }
+    if url.scheme() == "about" && url.path() != "blank" {
+        return Err(NavigationError::DisallowedScheme {
+            url: url.to_string(),
+            scheme: "about".into(),
+        });
+    }
+    Ok(url)
+}
+
+/// History traversal / reload operations we ask the webview to perform.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NativeAction {
+    Back,
+    Forward,
+    Reload,
+    /// Reload bypassing the HTTP cache; WKWebView and WebKitGTK only.
+    HardReload,
+}
+
+/// Run `action` on `webview`, using the platform webview API where we have one.
+pub fn perform<R: tauri::Runtime>(
+    webview: &tauri::Webview<R>,
+    action: NativeAction,
+) -> Result<(), NavigationError> {
+    let label = webview.label().to_string();
+    let to_err = |e: tauri::Error| NavigationError::Webview {
+        label: label.clone(),
+        message: e.to_string(),
+    };
+
+    #[cfg(target_os = "macos")]
+    {
+        // WKWebView exposes all four directly; reloadFromOrigin revalidates
+        // every resource end-to-end instead of using the cache.
+        webview
+            .with_webview(move |platform| unsafe {
+                use objc::runtime::Object;
+                use objc::{msg_send, sel, sel_impl};
+                let wk = platform.inner() as *mut Object;
+                let _: *mut Object = match action {
+                    NativeAction::Back => msg_send![wk, goBack],
+                    NativeAction::Forward => msg_send![wk, goForward],
+                    NativeAction::Reload => msg_send![wk, reload],
+                    NativeAction::HardReload => msg_send![wk, reloadFromOrigin],
+                };
+            })
+            .map_err(to_err)
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        // No native traversal API is exposed here, so use constant scripts.
+        match action {
+            NativeAction::Back => webview.eval("history.back()").map_err(to_err),
+            NativeAction::Forward => webview.eval("history.forward()").map_err(to_err),
+            NativeAction::Reload => webview.reload().map_err(to_err),
+            // WebKitGTK can reload past the cache; WebView2 has no such call.
+            #[cfg(target_os = "linux")]
+            NativeAction::HardReload => webview
+                .with_webview(|platform| {
+                    use webkit2gtk::WebViewExt;
+                    platform.inner().reload_bypass_cache();
+                })
+                .map_err(to_err),
+            #[cfg(not(target_os = "linux"))]
+            NativeAction::HardReload => Err(NavigationError::Unsupported {
+                operation: "Reloading without the cache".into(),
+            }),
+        }
+    }
+}
@@ -0,0 +1,441 @@
+//! The navigation policy every tab navigation passes through.
+//!
+//! `on_navigation` asks [`NavigationPolicy::decide`] what to do with each
+//! top-level navigation: let the tab load it, hand it to the OS, open it in
+//! the file viewer (`file:`), or block it. Web URLs the policy allows are also
+//! checked against the content blocker's `$document` rules. Anything that
+//! doesn't load in the tab is reported to the main window as
+//! `webview-navigation-blocked` with the reason.
+//!
+//! Only `mailto:` and `tel:` (by default) go to the OS straight away; for any
+//! other app scheme the user is asked first, so a page can't launch apps on
+//! its own. `file:` URLs open in the viewer only when the user typed them; a
+//! page navigating to one is blocked.
+//!
+//! The rules are persisted in `navigation-policy.json` and edited through
+//! `get_navigation_policy` / `set_navigation_policy`.
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::{Emitter, Manager};
+use url::Url;
+
+use crate::blocker::ContentBlocker;
+use crate::error::BrsrError;
+use crate::storage;
+
+const SETTINGS_FILE: &str = "navigation-policy.json";
+/// Reported for navigations a tab didn't load.
+pub const BLOCKED_EVENT: &str = "webview-navigation-blocked";
+const FILE_VIEWER_LABEL: &str = "file-viewer";
+
+/// Schemes tabs load themselves. Everything else is handed off or blocked.
+const WEB_SCHEMES: &[&str] = &["http", "https", "about", "blob"];
+/// Never loaded as a top-level document: they run in, or forge, the opener's origin.
+const SCRIPT_SCHEMES: &[&str] = &["javascript", "data"];
+/// Handed to the OS without asking unless the settings say otherwise.
+const DEFAULT_EXTERNAL_SCHEMES: &[&str] = &["mailto", "tel"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PolicySettings {
+    /// Schemes never loaded, handed off or viewed, checked before anything else.
+    pub blocked_schemes: Vec<String>,
+    /// When non-empty, only hosts listed here (and their subdomains) load.
+    pub allowed_hosts: Vec<String>,
+    /// Hosts (and their subdomains) that never load.
+    pub blocked_hosts: Vec<String>,
+    /// Hand non-web schemes to the OS: those in `external_schemes` directly,
+    /// others once the user agrees.
+    pub open_external: bool,
+    /// Schemes handed to the OS without asking.
+    pub external_schemes: Vec<String>,
+    /// Open typed `file:` URLs in the file viewer instead of blocking them.
+    pub file_viewer: bool,
+}
+
+impl Default for PolicySettings {
+    fn default() -> Self {
+        Self {
+            blocked_schemes: Vec::new(),
+            allowed_hosts: Vec::new(),
+            blocked_hosts: Vec::new(),
+            open_external: true,
+            external_schemes: DEFAULT_EXTERNAL_SCHEMES.iter().map(|s| s.to_string()).collect(),
+            file_viewer: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockReason {
+    /// `javascript:` or `data:` as a top-level document.
+    ScriptUrl,
+    BlockedScheme,
+    BlockedHost,
+    /// An allowlist is set and the host isn't on it.
+    HostNotAllowed,
+    /// A non-web scheme while handing off to the OS is turned off.
+    ExternalDisabled,
+    /// A `file:` URL while the file viewer is turned off.
+    FileViewerDisabled,
+    /// A page, not the user, navigated to a `file:` URL.
+    FileFromPage,
+    /// The user declined handing the URL to another app.
+    ExternalDeclined,
+    /// Handing the URL to the OS failed.
+    HandoffFailed,
+    /// A content-blocker `$document` rule.
+    FilterRule,
+}
+
+impl BlockReason {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BlockReason::ScriptUrl => "script-url",
+            BlockReason::BlockedScheme => "blocked-scheme",
+            BlockReason::BlockedHost => "blocked-host",
+            BlockReason::HostNotAllowed => "host-not-allowed",
+            BlockReason::ExternalDisabled => "external-disabled",
+            BlockReason::FileViewerDisabled => "file-viewer-disabled",
+            BlockReason::FileFromPage => "file-from-page",
+            BlockReason::ExternalDeclined => "external-declined",
+            BlockReason::HandoffFailed => "handoff-failed",
+            BlockReason::FilterRule => "filter-rule",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    Allow,
+    OpenExternal,
+    /// Hand off to the OS if the user agrees.
+    AskExternal,
+    OpenFileViewer,
+    Block(BlockReason),
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BlockedPayload<'a> {
+    label: &'a str,
+    url: &'a str,
+    /// What happened instead: `blocked`, `external`, `ask-external` or `file-viewer`.
+    action: &'static str,
+    reason: &'static str,
+}
+
+pub(crate) fn host_matches(host: &str, rule: &str) -> bool {
+    let rule = rule.trim().trim_end_matches('.').to_ascii_lowercase();
+    !rule.is_empty() && (host == rule || host.ends_with(&format!(".{}", rule)))
+}
+
+/// Whether a scheme from the settings, written with or without its `:`, names `scheme`.
+fn scheme_matches(scheme: &str, rule: &str) -> bool {
+    rule.trim().trim_end_matches(':').eq_ignore_ascii_case(scheme)
+}
+
+impl PolicySettings {
+    /// What to do with a top-level navigation to `url`. `typed` is set for
+    /// navigations the user asked for, as opposed to ones a page started.
+    pub fn decide(&self, url: &Url, typed: bool) -> Decision {
+        let scheme = url.scheme();
+        if SCRIPT_SCHEMES.contains(&scheme) {
+            return Decision::Block(BlockReason::ScriptUrl);
+        }
+        if self.blocked_schemes.iter().any(|s| scheme_matches(scheme, s)) {
+            return Decision::Block(BlockReason::BlockedScheme);
+        }
+        if scheme == "file" {
+            return if !self.file_viewer {
+                Decision::Block(BlockReason::FileViewerDisabled)
+            } else if !typed {
+                Decision::Block(BlockReason::FileFromPage)
+            } else {
+                Decision::OpenFileViewer
+            };
+        }
+        if !WEB_SCHEMES.contains(&scheme) {
+            return if !self.open_external {
+                Decision::Block(BlockReason::ExternalDisabled)
+            } else if self.external_schemes.iter().any(|s| scheme_matches(scheme, s)) {
+                Decision::OpenExternal
+            } else {
+                Decision::AskExternal
+            };
+        }
+        // about:blank and blob: URLs have no host of their own to check.
+        let Some(host) = url.host_str().map(|h| h.to_ascii_lowercase()) else {
+            return Decision::Allow;
+        };
+        if self.blocked_hosts.iter().any(|rule| host_matches(&host, rule)) {
+            return Decision::Block(BlockReason::BlockedHost);
+        }
+        if !self.allowed_hosts.is_empty() && !self.allowed_hosts.iter().any(|rule| host_matches(&host, rule)) {
+            return Decision::Block(BlockReason::HostNotAllowed);
+        }
+        Decision::Allow
+    }
+}
+
+/// Managed state: the navigation policy, persisted under the app data dir.
+pub struct NavigationPolicy {
+    settings: Mutex<PolicySettings>,
+    path: Option<PathBuf>,
+    /// A hand-off prompt is showing; further ones are dropped until it closes.
+    asking: AtomicBool,
+}
+
+impl NavigationPolicy {
+    pub fn load<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Self {
+        let path = storage::data_file(app, SETTINGS_FILE)
+            .map_err(|e| eprintln!("[Rust] Navigation policy will not persist: {}", e))
+            .ok();
+        let settings = path
+            .as_deref()
+            .map(storage::load_json::<PolicySettings>)
+            .unwrap_or_default();
+        Self {
+            settings: Mutex::new(settings),
+            path,
+            asking: AtomicBool::new(false),
+        }
+    }
+
+    pub fn settings(&self) -> PolicySettings {
+        self.settings.lock().unwrap().clone()
+    }
+
+    fn replace(&self, next: PolicySettings) -> Result<(), BrsrError> {
+        if let Some(path) = &self.path {
+            storage::save_json(path, &next)?;
+        }
+        *self.settings.lock().unwrap() = next;
+        Ok(())
+    }
+
+    pub fn decide(&self, url: &Url, typed: bool) -> Decision {
+        self.settings.lock().unwrap().decide(url, typed)
+    }
+}
+
+/// Run the policy for a navigation in tab `label` and carry out anything other
+/// than loading it. The tab should load `url` only if this returns `Allow`.
+/// `typed` as for [`PolicySettings::decide`].
+pub fn apply<R: tauri::Runtime>(app: &tauri::AppHandle<R>, label: &str, url: &Url, typed: bool) -> Decision {
+    let mut decision = app.state::<NavigationPolicy>().decide(url, typed);
+    if decision == Decision::Allow {
+        let blocker = app.state::<ContentBlocker>();
+        if let Some(rule) = blocker.check_document(url) {
+            println!("[Rust] Filter rule '{}' matched {}", rule, url);
+            blocker.record_blocked(label);
+            decision = Decision::Block(BlockReason::FilterRule);
+        }
+    }
+    let (action, reason) = match decision {
+        Decision::Allow => return decision,
+        Decision::OpenExternal => match open_external(app, url) {
+            Ok(()) => ("external", "external-scheme"),
+            Err(reason) => {
+                decision = Decision::Block(reason);
+                ("blocked", reason.as_str())
+            }
+        },
+        Decision::AskExternal => {
+            ask_external(app, label, url.clone());
+            ("ask-external", "external-scheme")
+        }
+        Decision::OpenFileViewer => {
+            open_file_viewer(app, url.clone());
+            ("file-viewer", "file-url")
+        }
+        Decision::Block(reason) => ("blocked", reason.as_str()),
+    };
+    println!("[Rust] Webview '{}' did not load {} ({}: {})", label, url, action, reason);
+    let _ = app.emit_to(
+        "main",
+        BLOCKED_EVENT,
+        BlockedPayload {
+            label,
+            url: url.as_str(),
+            action,
+            reason,
+        },
+    );
+    decision
+}
+
+fn open_external<R: tauri::Runtime>(app: &tauri::AppHandle<R>, url: &Url) -> Result<(), BlockReason> {
+    use tauri_plugin_opener::OpenerExt;
+    hand_off(url, |url| app.opener().open_url(url, None::<&str>).map_err(BrsrError::platform))
+}
+
+/// Give `url` to `open` as it is. The policy (and for `AskExternal`, the user)
+/// has already decided, so no second scope narrows what reaches the OS.
+fn hand_off(url: &Url, open: impl FnOnce(&str) -> Result<(), BrsrError>) -> Result<(), BlockReason> {
+    open(url.as_str()).map_err(|e| {
+        eprintln!("[Rust] Could not open '{}' externally: {}", url, e);
+        BlockReason::HandoffFailed
+    })
+}
+
+/// Ask the user whether to hand `url` to another app, and do so if they agree.
+/// Requests arriving while a prompt is up are dropped, so a page can't queue
+/// a stream of them.
+fn ask_external<R: tauri::Runtime>(app: &tauri::AppHandle<R>, label: &str, url: Url) {
+    use tauri_plugin_dialog::{DialogExt, MessageDialogButtons};
+    let policy = app.state::<NavigationPolicy>();
+    if policy.asking.swap(true, Ordering::SeqCst) {
+        println!("[Rust] Dropping hand-off of {}: already asking", url);
+        return;
+    }
+    let app = app.clone();
+    let label = label.to_string();
+    app.dialog()
+        .message(format!(
+            "This page wants to open a \"{}:\" link in another app:\n\n{}",
+            url.scheme(),
+            url
+        ))
+        .title("Open in another app?")
+        .buttons(MessageDialogButtons::OkCancelCustom("Open".into(), "Cancel".into()))
+        .show({
+            let app = app.clone();
+            move |open| {
+                app.state::<NavigationPolicy>().asking.store(false, Ordering::SeqCst);
+                let reason = if !open {
+                    BlockReason::ExternalDeclined
+                } else if let Err(reason) = open_external(&app, &url) {
+                    reason
+                } else {
+                    return;
+                };
+                println!("[Rust] Webview '{}' did not hand off {} ({})", label, url, reason.as_str());
+                let _ = app.emit_to(
+                    "main",
+                    BLOCKED_EVENT,
+                    BlockedPayload {
+                        label: &label,
+                        url: url.as_str(),
+                        action: "blocked",
+                        reason: reason.as_str(),
+                    },
+                );
+            }
+        });
+}
+
+/// Show `url` in the file viewer window, creating it on first use. The viewer
+/// is a plain window with no injected script and no capabilities, so local
+/// files never share a webview with remote pages.
+fn open_file_viewer<R: tauri::Runtime>(app: &tauri::AppHandle<R>, url: Url) {
+    let app = app.clone();
+    // Windows can't be built from inside a navigation callback on every platform.
+    tauri::async_runtime::spawn(async move {
+        let title = url
+            .to_file_path()
+            .ok()
+            .and_then(|p| p.file_name().map(|n| n.to_string_lossy().into_owned()))
+            .unwrap_or_else(|| url.to_string());
+        let result = match app.get_webview_window(FILE_VIEWER_LABEL) {
+            Some(viewer) => viewer
+                .navigate(url)
+                .and_then(|_| viewer.set_title(&title))
+                .and_then(|_| viewer.set_focus()),
+            None => tauri::WebviewWindowBuilder::new(&app, FILE_VIEWER_LABEL, tauri::WebviewUrl::External(url))
+                .title(title)
+                .inner_size(900.0, 700.0)
+                .build()
+                .map(|_| ()),
+        };
+        if let Err(e) = result {
+            eprintln!("[Rust] Could not open the file viewer: {}", e);
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_navigation_policy(policy: tauri::State<'_, NavigationPolicy>) -> Result<PolicySettings, BrsrError> {
+    Ok(policy.settings())
+}
+
+/// Replace the policy. Applies to the next navigation in every tab.
+#[tauri::command]
+pub fn set_navigation_policy(
+    policy: tauri::State<'_, NavigationPolicy>,
+    settings: PolicySettings,
+) -> Result<PolicySettings, BrsrError> {
+    policy.replace(settings.clone())?;
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decide(settings: &PolicySettings, url: &str, typed: bool) -> Decision {
+        settings.decide(&Url::parse(url).unwrap(), typed)
+    }
+
+    #[test]
+    fn only_allowlisted_schemes_skip_the_prompt() {
+        let mut settings = PolicySettings::default();
+        assert_eq!(decide(&settings, "javascript:alert(1)", false), Decision::Block(BlockReason::ScriptUrl));
+        assert_eq!(decide(&settings, "mailto:a@b.test", false), Decision::OpenExternal);
+        assert_eq!(decide(&settings, "tel:123", false), Decision::OpenExternal);
+        assert_eq!(decide(&settings, "zoommtg://join", false), Decision::AskExternal);
+        settings.external_schemes.push("ZoomMtg:".into());
+        assert_eq!(decide(&settings, "zoommtg://join", false), Decision::OpenExternal);
+        settings.open_external = false;
+        assert_eq!(decide(&settings, "mailto:a@b.test", false), Decision::Block(BlockReason::ExternalDisabled));
+    }
+
+    #[test]
+    fn handed_off_urls_reach_the_opener_unchanged() {
+        let settings = PolicySettings {
+            external_schemes: vec!["mailto".into(), "tel".into(), "zoommtg".into()],
+            ..Default::default()
+        };
+        for url in ["zoommtg://join?confno=123", "tel:+15551234", "mailto:?to=a@b.test"] {
+            let url = Url::parse(url).unwrap();
+            assert_eq!(settings.decide(&url, false), Decision::OpenExternal);
+            let mut opened = None;
+            let result = hand_off(&url, |u| {
+                opened = Some(u.to_string());
+                Ok(())
+            });
+            assert_eq!(result, Ok(()));
+            assert_eq!(opened.as_deref(), Some(url.as_str()));
+        }
+        let url = Url::parse("slack://open").unwrap();
+        assert_eq!(settings.decide(&url, false), Decision::AskExternal);
+        let failed = hand_off(&url, |_| Err(BrsrError::platform("no handler")));
+        assert_eq!(failed, Err(BlockReason::HandoffFailed));
+    }
+
+    #[test]
+    fn file_urls_open_only_when_typed() {
+        let mut settings = PolicySettings::default();
+        assert_eq!(decide(&settings, "file:///etc/hosts", true), Decision::OpenFileViewer);
+        assert_eq!(decide(&settings, "file:///etc/hosts", false), Decision::Block(BlockReason::FileFromPage));
+        settings.file_viewer = false;
+        assert_eq!(decide(&settings, "file:///etc/hosts", true), Decision::Block(BlockReason::FileViewerDisabled));
+    }
+
+    #[test]
+    fn host_rules() {
+        let mut settings = PolicySettings {
+            blocked_hosts: vec!["Ads.example.".into()],
+            ..Default::default()
+        };
+        assert_eq!(decide(&settings, "https://x.ads.example/", false), Decision::Block(BlockReason::BlockedHost));
+        assert_eq!(decide(&settings, "about:blank", false), Decision::Allow);
+        settings.allowed_hosts = vec!["example.org".into()];
+        assert_eq!(decide(&settings, "https://www.example.org/", false), Decision::Allow);
+        assert_eq!(decide(&settings, "https://badexample.org/", false), Decision::Block(BlockReason::HostNotAllowed));
+    }
+}
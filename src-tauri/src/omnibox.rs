@@ -0,0 +1,417 @@
+//! Omnibox input resolution: decide whether what the user typed is a URL, a
+//! bare host, a local address, a file path or a search query, and turn it into
+//! the URL the tab should load.
+
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::error::BrsrError;
+use crate::storage;
+
+/// Placeholder replaced with the percent-encoded query in engine templates.
+pub const QUERY_PLACEHOLDER: &str = "%s";
+
+/// Escaped in a query placed in a template's path or fragment, where a `+`
+/// would be taken literally: everything but RFC 3986's unreserved characters.
+const OUTSIDE_QUERY: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'.').remove(b'_').remove(b'~');
+
+const SETTINGS_FILE: &str = "search-engines.json";
+
+/// Schemes we accept as "the user typed a URL" even without `//`.
+const KNOWN_SCHEMES: &[&str] = &[
+    "http", "https", "file", "about", "data", "javascript", "mailto", "tel", "blob", "view-source",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchEngine {
+    pub name: String,
+    /// Shortcut typed before the query, e.g. `gh` in `gh tauri`.
+    #[serde(default)]
+    pub keyword: Option<String>,
+    /// URL template containing `%s` where the query goes.
+    pub template: String,
+}
+
+impl SearchEngine {
+    fn new(name: &str, keyword: &str, template: &str) -> Self {
+        Self {
+            name: name.into(),
+            keyword: Some(keyword.into()),
+            template: template.into(),
+        }
+    }
+
+    /// The template with `query` in place of `%s`: form-encoded inside the
+    /// query string (`a+b`), percent-encoded anywhere else (`a%20b`).
+    pub fn search_url(&self, query: &str) -> String {
+        let in_query = self.template.find(QUERY_PLACEHOLDER).is_some_and(|at| {
+            let before = &self.template[..at];
+            before.contains('?') && !before.contains('#')
+        });
+        let encoded: String = if in_query {
+            url::form_urlencoded::byte_serialize(query.as_bytes()).collect()
+        } else {
+            utf8_percent_encode(query, OUTSIDE_QUERY).collect()
+        };
+        self.template.replace(QUERY_PLACEHOLDER, &encoded)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchSettings {
+    /// `name` of the engine used for plain queries.
+    pub default_engine: String,
+    pub engines: Vec<SearchEngine>,
+}
+
+impl Default for SearchSettings {
+    fn default() -> Self {
+        Self {
+            default_engine: "Google".into(),
+            engines: vec![
+                SearchEngine::new("Google", "g", "https://www.google.com/search?q=%s"),
+                SearchEngine::new("DuckDuckGo", "ddg", "https://duckduckgo.com/?q=%s"),
+                SearchEngine::new("GitHub", "gh", "https://github.com/search?q=%s"),
+                SearchEngine::new("Wikipedia", "w", "https://en.wikipedia.org/w/index.php?search=%s"),
+                SearchEngine::new("YouTube", "yt", "https://www.youtube.com/results?search_query=%s"),
+            ],
+        }
+    }
+}
+
+impl SearchSettings {
+    fn default_engine(&self) -> Option<&SearchEngine> {
+        self.engines
+            .iter()
+            .find(|e| e.name == self.default_engine)
+            .or_else(|| self.engines.first())
+    }
+
+    fn engine_for_keyword(&self, keyword: &str) -> Option<&SearchEngine> {
+        self.engines
+            .iter()
+            .find(|e| e.keyword.as_deref().is_some_and(|k| k.eq_ignore_ascii_case(keyword)))
+    }
+
+    fn validate(&self) -> Result<(), BrsrError> {
+        if self.engines.is_empty() {
+            return Err(BrsrError::invalid("At least one search engine is required"));
+        }
+        if !self.engines.iter().any(|e| e.name == self.default_engine) {
+            return Err(BrsrError::invalid(format!(
+                "Default engine '{}' is not in the engine list",
+                self.default_engine
+            )));
+        }
+        for engine in &self.engines {
+            if !engine.template.contains(QUERY_PLACEHOLDER) {
+                return Err(BrsrError::invalid(format!(
+                    "Template for '{}' has no {} placeholder",
+                    engine.name, QUERY_PLACEHOLDER
+                )));
+            }
+            Url::parse(&engine.search_url("test"))
+                .map_err(|e| BrsrError::invalid_url(&engine.template, e))?;
+        }
+        Ok(())
+    }
+}
+
+/// How the input was interpreted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum InputKind {
+    /// A full URL with a scheme.
+    Url,
+    /// A bare hostname such as `example.com/path`, loaded over https.
+    Host,
+    /// `localhost` / `*.localhost`, optionally with a port, loaded over http.
+    Localhost,
+    /// An IPv4 or bracketed IPv6 literal, loaded over http.
+    IpAddress,
+    /// An absolute filesystem path.
+    FilePath,
+    /// Free text sent to the default search engine.
+    Search,
+    /// Free text sent to the engine selected by a keyword shortcut.
+    Keyword,
+}
+
+/// Structured result the UI can preview before navigating.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OmniboxResolution {
+    pub kind: InputKind,
+    pub url: String,
+    /// The search terms, for `search`/`keyword` results.
+    pub query: Option<String>,
+    /// Engine name, for `search`/`keyword` results.
+    pub engine: Option<String>,
+}
+
+impl OmniboxResolution {
+    fn navigate(kind: InputKind, url: Url) -> Self {
+        Self {
+            kind,
+            url: url.to_string(),
+            query: None,
+            engine: None,
+        }
+    }
+
+    fn search(kind: InputKind, engine: &SearchEngine, query: &str) -> Self {
+        Self {
+            kind,
+            url: engine.search_url(query),
+            query: Some(query.to_string()),
+            engine: Some(engine.name.clone()),
+        }
+    }
+}
+
+/// Resolve raw omnibox input against the configured search engines.
+pub fn resolve(input: &str, settings: &SearchSettings) -> Result<OmniboxResolution, BrsrError> {
+    let text = input.trim();
+    if text.is_empty() {
+        return Err(BrsrError::invalid("Nothing to resolve"));
+    }
+    let default_engine = settings
+        .default_engine()
+        .ok_or_else(|| BrsrError::invalid("No search engine configured"))?;
+
+    // `?foo` forces a search, like Chrome.
+    if let Some(query) = text.strip_prefix('?') {
+        return Ok(OmniboxResolution::search(InputKind::Search, default_engine, query.trim()));
+    }
+
+    // Keyword shortcut: `gh tauri webview`
+    if let Some((first, rest)) = text.split_once(char::is_whitespace) {
+        if let Some(engine) = settings.engine_for_keyword(first) {
+            let query = rest.trim();
+            if !query.is_empty() {
+                return Ok(OmniboxResolution::search(InputKind::Keyword, engine, query));
+            }
+        }
+    }
+
+    if let Some(url) = file_path_url(text) {
+        return Ok(OmniboxResolution::navigate(InputKind::FilePath, url));
+    }
+
+    if text.contains(char::is_whitespace) {
+        return Ok(OmniboxResolution::search(InputKind::Search, default_engine, text));
+    }
+
+    if let Some(url) = explicit_url(text) {
+        return Ok(OmniboxResolution::navigate(InputKind::Url, url));
+    }
+
+    if let Some((kind, url)) = hostlike_url(text) {
+        return Ok(OmniboxResolution::navigate(kind, url));
+    }
+
+    Ok(OmniboxResolution::search(InputKind::Search, default_engine, text))
+}
+
+/// Input that already carries a scheme we know (`https://…`, `about:blank`, `mailto:…`).
+fn explicit_url(text: &str) -> Option<Url> {
+    let url = Url::parse(text).ok()?;
+    // `localhost:3000` and `example.com:8080` parse with the host as the "scheme".
+    if text.contains("://") || KNOWN_SCHEMES.contains(&url.scheme()) {
+        Some(url)
+    } else {
+        None
+    }
+}
+
+/// Absolute paths (`/tmp/a.html`, `~/a.html`, `C:\a.html`) as `file://` URLs.
+fn file_path_url(text: &str) -> Option<Url> {
+    let path: PathBuf = if let Some(rest) = text.strip_prefix("~/") {
+        Path::new(&std::env::var_os("HOME")?).join(rest)
+    } else if text.starts_with('/') || is_windows_drive_path(text) {
+        PathBuf::from(text)
+    } else {
+        return None;
+    };
+    Url::from_file_path(path).ok()
+}
+
+fn is_windows_drive_path(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    bytes.len() >= 3 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' && (bytes[2] == b'\\' || bytes[2] == b'/')
+}
+
+/// `host[:port][/path]` without a scheme.
+fn hostlike_url(text: &str) -> Option<(InputKind, Url)> {
+    let authority_end = text.find(['/', '?', '#']).unwrap_or(text.len());
+    let authority = &text[..authority_end];
+    if authority.is_empty() || authority.contains('@') {
+        return None;
+    }
+    let host = split_host(authority)?;
+
+    let (kind, scheme) = if host.eq_ignore_ascii_case("localhost") || host.to_ascii_lowercase().ends_with(".localhost") {
+        (InputKind::Localhost, "http")
+    } else if host.parse::<Ipv4Addr>().is_ok() || is_bracketed_ipv6(host) {
+        (InputKind::IpAddress, "http")
+    } else if looks_like_domain(host) {
+        (InputKind::Host, "https")
+    } else {
+        return None;
+    };
+    let url = Url::parse(&format!("{}://{}", scheme, text)).ok()?;
+    Some((kind, url))
+}
+
+/// Strip an optional `:port` (which must be numeric) from an authority.
+fn split_host(authority: &str) -> Option<&str> {
+    if authority.starts_with('[') {
+        let end = authority.find(']')?;
+        let rest = &authority[end + 1..];
+        return (rest.is_empty() || valid_port(rest.strip_prefix(':')?)).then_some(&authority[..=end]);
+    }
+    match authority.rsplit_once(':') {
+        Some((host, port)) if valid_port(port) => Some(host),
+        Some(_) => None,
+        None => Some(authority),
+    }
+}
+
+fn valid_port(port: &str) -> bool {
+    port.parse::<u16>().is_ok()
+}
+
+fn is_bracketed_ipv6(host: &str) -> bool {
+    host.strip_prefix('[')
+        .and_then(|h| h.strip_suffix(']'))
+        .is_some_and(|h| h.parse::<Ipv6Addr>().is_ok())
+}
+
+/// Dotted name with a plausible alphabetic TLD (`example.com`, `sub.example.co.uk`).
+fn looks_like_domain(host: &str) -> bool {
+    let labels: Vec<&str> = host.trim_end_matches('.').split('.').collect();
+    if labels.len() < 2 {
+        return false;
+    }
+    let valid_label = |l: &&str| {
+        !l.is_empty()
+            && !l.starts_with('-')
+            && !l.ends_with('-')
+            && l.chars().all(|c| c.is_alphanumeric() || c == '-')
+    };
+    let tld = labels[labels.len() - 1];
+    labels.iter().all(valid_label) && tld.len() >= 2 && tld.chars().all(|c| c.is_alphabetic() || c == '-')
+}
+
+/// Managed state: the search engine settings, persisted under the app data dir.
+pub struct SearchEngines {
+    settings: Mutex<SearchSettings>,
+    path: Option<PathBuf>,
+}
+
+impl SearchEngines {
+    pub fn load<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Self {
+        let path = storage::data_file(app, SETTINGS_FILE)
+            .map_err(|e| eprintln!("[Rust] Search engine settings will not persist: {}", e))
+            .ok();
+        let settings = path
+            .as_deref()
+            .map(storage::load_json::<SearchSettings>)
+            .filter(|s| s.validate().is_ok())
+            .unwrap_or_default();
+        Self {
+            settings: Mutex::new(settings),
+            path,
+        }
+    }
+
+    pub fn settings(&self) -> SearchSettings {
+        self.settings.lock().unwrap().clone()
+    }
+
+    fn replace(&self, settings: SearchSettings) -> Result<(), BrsrError> {
+        settings.validate()?;
+        if let Some(path) = &self.path {
+            storage::save_json(path, &settings)?;
+        }
+        *self.settings.lock().unwrap() = settings;
+        Ok(())
+    }
+}
+
+#[tauri::command]
+pub fn resolve_omnibox_input(
+    engines: tauri::State<'_, SearchEngines>,
+    input: String,
+) -> Result<OmniboxResolution, BrsrError> {
+    resolve(&input, &engines.settings())
+}
+
+#[tauri::command]
+pub fn get_search_engines(engines: tauri::State<'_, SearchEngines>) -> Result<SearchSettings, BrsrError> {
+    Ok(engines.settings())
+}
+
+#[tauri::command]
+pub fn set_search_engines(
+    engines: tauri::State<'_, SearchEngines>,
+    settings: SearchSettings,
+) -> Result<(), BrsrError> {
+    engines.replace(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolved(input: &str) -> (InputKind, String) {
+        let resolution = resolve(input, &SearchSettings::default()).unwrap();
+        (resolution.kind, resolution.url)
+    }
+
+    #[test]
+    fn resolves_urls_hosts_and_addresses() {
+        assert_eq!(resolved("https://example.com/a"), (InputKind::Url, "https://example.com/a".into()));
+        assert_eq!(resolved("about:blank"), (InputKind::Url, "about:blank".into()));
+        assert_eq!(resolved("example.com/path?q=1"), (InputKind::Host, "https://example.com/path?q=1".into()));
+        assert_eq!(resolved("example.com:8080"), (InputKind::Host, "https://example.com:8080/".into()));
+        assert_eq!(resolved("localhost:3000"), (InputKind::Localhost, "http://localhost:3000/".into()));
+        assert_eq!(resolved("app.localhost"), (InputKind::Localhost, "http://app.localhost/".into()));
+        assert_eq!(resolved("192.168.0.1/admin"), (InputKind::IpAddress, "http://192.168.0.1/admin".into()));
+        assert_eq!(resolved("[::1]:8000"), (InputKind::IpAddress, "http://[::1]:8000/".into()));
+        #[cfg(unix)]
+        assert_eq!(resolved(" /tmp/a b.html "), (InputKind::FilePath, "file:///tmp/a%20b.html".into()));
+    }
+
+    #[test]
+    fn resolves_searches_and_keywords() {
+        let google = "https://www.google.com/search?q=";
+        assert_eq!(resolved("rust lang"), (InputKind::Search, format!("{}rust+lang", google)));
+        assert_eq!(resolved("tauri"), (InputKind::Search, format!("{}tauri", google)));
+        assert_eq!(resolved("example.c0m"), (InputKind::Search, format!("{}example.c0m", google)));
+        assert_eq!(resolved("?example.com"), (InputKind::Search, format!("{}example.com", google)));
+        assert_eq!(
+            resolved("gh tauri webview"),
+            (InputKind::Keyword, "https://github.com/search?q=tauri+webview".into())
+        );
+        // A keyword alone is a word like any other.
+        assert_eq!(resolved("gh"), (InputKind::Search, format!("{}gh", google)));
+        assert!(resolve("   ", &SearchSettings::default()).is_err());
+    }
+
+    #[test]
+    fn encodes_the_query_for_where_it_goes() {
+        let engine = |template: &str| SearchEngine::new("Test", "t", template);
+        assert_eq!(engine("https://s.example/?q=%s").search_url("a b&c"), "https://s.example/?q=a+b%26c");
+        assert_eq!(engine("https://s.example/search/%s").search_url("a b/c+d"), "https://s.example/search/a%20b%2Fc%2Bd");
+        assert_eq!(engine("https://s.example/#q=%s").search_url("a b"), "https://s.example/#q=a%20b");
+        assert_eq!(engine("https://s.example/?q=%s").search_url("ü"), "https://s.example/?q=%C3%BC");
+    }
+}
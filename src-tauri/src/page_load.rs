@@ -0,0 +1,332 @@
+//! Per-tab page-load lifecycle.
+//!
+//! A load moves Idle → Started → Committed → DomContentLoaded → Finished, or
+//! ends in Failed. The steps come from three places:
+//! - the builder's `on_navigation` hook: the tab asked for a new document;
+//! - `on_page_load`: `Started` once the response is being rendered, `Finished`
+//!   after the load event;
+//! - the injected script: `init` as soon as the new document runs and
+//!   `dom-ready` at DOMContentLoaded (see `scripts/load.js`).
+//!
+//! `on_navigation` fires again for redirects before anything commits; those
+//! stay part of the same load, so the URL a load finishes with is the one that
+//! committed. It also fires for fragment changes on the loaded document, which
+//! never load anything and are not loads here. A load that is replaced by a
+//! new one before finishing, or that doesn't commit within
+//! [`COMMIT_TIMEOUT`], fails. The webview doesn't report network errors, so
+//! those show up as one of the two.
+//!
+//! Every step goes to the main window as `webview-load-started`,
+//! `webview-load-progress` or `webview-load-finished`, each with a
+//! [`LoadUpdate`].
+
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::{Emitter, Manager};
+use ts_rs::TS;
+
+use crate::tabs::TabRegistry;
+
+pub const STARTED_EVENT: &str = "webview-load-started";
+pub const PROGRESS_EVENT: &str = "webview-load-progress";
+pub const FINISHED_EVENT: &str = "webview-load-finished";
+
+/// How long a started load may go without committing before it counts as failed.
+const COMMIT_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub enum LoadPhase {
+    /// Nothing loaded yet.
+    #[default]
+    Idle,
+    /// Requested; waiting for a response.
+    Started,
+    /// The new document replaced the old one.
+    Committed,
+    DomContentLoaded,
+    Finished,
+    Failed,
+}
+
+impl LoadPhase {
+    /// Rough share of the load done, for a progress bar.
+    fn progress(self) -> f64 {
+        match self {
+            LoadPhase::Idle => 0.0,
+            LoadPhase::Started => 0.1,
+            LoadPhase::Committed => 0.4,
+            LoadPhase::DomContentLoaded => 0.8,
+            LoadPhase::Finished | LoadPhase::Failed => 1.0,
+        }
+    }
+
+    fn in_flight(self) -> bool {
+        matches!(self, LoadPhase::Started | LoadPhase::Committed | LoadPhase::DomContentLoaded)
+    }
+}
+
+/// Payload of the three load events.
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct LoadUpdate {
+    pub label: String,
+    /// Counts the tab's loads, so events of a replaced load can be told apart.
+    pub load_id: u32,
+    pub phase: LoadPhase,
+    /// 0 to 1, estimated from the phase.
+    pub progress: f64,
+    /// Milliseconds since the load started.
+    pub elapsed_ms: f64,
+    /// The URL being loaded; once committed, the final one after redirects.
+    pub url: String,
+    /// When DOMContentLoaded started, in milliseconds from navigation start,
+    /// as the page measured it.
+    pub dom_content_loaded_ms: Option<f64>,
+    /// Why a `failed` load failed.
+    pub error: Option<String>,
+}
+
+/// Where a tab's current load is. Kept in its `TabState`.
+#[derive(Debug, Clone, Default)]
+pub struct PageLoad {
+    id: u32,
+    phase: LoadPhase,
+    url: String,
+    started_at: Option<Instant>,
+    dom_content_loaded_ms: Option<f64>,
+    error: Option<String>,
+}
+
+type Emit = (&'static str, LoadUpdate);
+
+impl PageLoad {
+    fn update(&self, label: &str) -> LoadUpdate {
+        LoadUpdate {
+            label: label.to_string(),
+            load_id: self.id,
+            phase: self.phase,
+            progress: self.phase.progress(),
+            elapsed_ms: self
+                .started_at
+                .map(|t| t.elapsed().as_secs_f64() * 1000.0)
+                .unwrap_or(0.0),
+            url: self.url.clone(),
+            dom_content_loaded_ms: self.dom_content_loaded_ms,
+            error: self.error.clone(),
+        }
+    }
+
+    fn begin(&mut self, label: &str, url: &str, out: &mut Vec<Emit>) {
+        if self.phase.in_flight() {
+            self.fail(label, "interrupted", out);
+        }
+        *self = PageLoad {
+            id: self.id + 1,
+            phase: LoadPhase::Started,
+            url: url.to_string(),
+            started_at: Some(Instant::now()),
+            ..PageLoad::default()
+        };
+        out.push((STARTED_EVENT, self.update(label)));
+    }
+
+    /// The tab is about to request `url`.
+    fn navigate(&mut self, label: &str, url: &str, out: &mut Vec<Emit>) {
+        let on_document = matches!(
+            self.phase,
+            LoadPhase::Committed | LoadPhase::DomContentLoaded | LoadPhase::Finished
+        );
+        if on_document && fragment_only(&self.url, url) {
+            self.url = url.to_string();
+            return;
+        }
+        if self.phase == LoadPhase::Started {
+            // A redirect, or a new request before the last one got anywhere.
+            self.url = url.to_string();
+            out.push((PROGRESS_EVENT, self.update(label)));
+        } else {
+            self.begin(label, url, out);
+        }
+    }
+
+    /// The new document is in place. Back/forward and restored loads may
+    /// commit without a navigation hook first, so this can start a load too.
+    fn commit(&mut self, label: &str, url: Option<&str>, out: &mut Vec<Emit>) {
+        match self.phase {
+            LoadPhase::Started => {}
+            LoadPhase::Committed | LoadPhase::DomContentLoaded => {
+                // The other of page-load `Started` and the script's `init`.
+                if let Some(url) = url {
+                    self.url = url.to_string();
+                }
+                return;
+            }
+            LoadPhase::Idle | LoadPhase::Finished | LoadPhase::Failed => match url {
+                Some(url) => self.begin(label, url, out),
+                None => return,
+            },
+        }
+        if let Some(url) = url {
+            self.url = url.to_string();
+        }
+        self.phase = LoadPhase::Committed;
+        out.push((PROGRESS_EVENT, self.update(label)));
+    }
+
+    fn dom_ready(&mut self, label: &str, url: &str, dom_content_loaded_ms: f64, out: &mut Vec<Emit>) {
+        if !matches!(self.phase, LoadPhase::Started | LoadPhase::Committed) {
+            return;
+        }
+        self.phase = LoadPhase::DomContentLoaded;
+        self.url = url.to_string();
+        self.dom_content_loaded_ms = Some(dom_content_loaded_ms);
+        out.push((PROGRESS_EVENT, self.update(label)));
+    }
+
+    fn finish(&mut self, label: &str, url: &str, out: &mut Vec<Emit>) {
+        if !self.phase.in_flight() {
+            return;
+        }
+        self.phase = LoadPhase::Finished;
+        self.url = url.to_string();
+        out.push((FINISHED_EVENT, self.update(label)));
+    }
+
+    fn fail(&mut self, label: &str, error: &str, out: &mut Vec<Emit>) {
+        if !self.phase.in_flight() {
+            return;
+        }
+        self.phase = LoadPhase::Failed;
+        self.error = Some(error.to_string());
+        out.push((FINISHED_EVENT, self.update(label)));
+    }
+}
+
+/// Whether going from `from` to `to` only moves to a fragment of the same
+/// document, which the webview does without loading anything.
+fn fragment_only(from: &str, to: &str) -> bool {
+    let (Ok(mut from), Ok(mut to)) = (url::Url::parse(from), url::Url::parse(to)) else {
+        return false;
+    };
+    if to.fragment().is_none() {
+        return false;
+    }
+    from.set_fragment(None);
+    to.set_fragment(None);
+    from == to
+}
+
+/// Run `f` on the tab's load and send what it produced to the main window.
+fn step<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    label: &str,
+    f: impl FnOnce(&mut PageLoad, &mut Vec<Emit>),
+) -> Vec<Emit> {
+    let mut out = Vec::new();
+    app.state::<TabRegistry>().update(label, |tab| f(&mut tab.load, &mut out));
+    for (event, update) in &out {
+        match update.phase {
+            LoadPhase::Failed => println!(
+                "[Rust] Webview '{}' load {} failed after {:.0} ms: {}",
+                label,
+                update.load_id,
+                update.elapsed_ms,
+                update.error.as_deref().unwrap_or_default()
+            ),
+            LoadPhase::Finished => println!(
+                "[Rust] Webview '{}' load {} finished in {:.0} ms: {}",
+                label, update.load_id, update.elapsed_ms, update.url
+            ),
+            _ => {}
+        }
+        let _ = app.emit_to("main", event, update);
+    }
+    out
+}
+
+/// `on_navigation` let the tab go to `url`.
+pub fn navigation_started<R: tauri::Runtime>(app: &tauri::AppHandle<R>, label: &str, url: &str) {
+    let out = step(app, label, |load, out| load.navigate(label, url, out));
+    for (event, update) in out {
+        if event == STARTED_EVENT {
+            watch_commit(app.clone(), label.to_string(), update.load_id);
+        }
+    }
+}
+
+/// `on_page_load` reported `Started`, or the script reported `init` (no URL).
+pub fn committed<R: tauri::Runtime>(app: &tauri::AppHandle<R>, label: &str, url: Option<&str>) {
+    step(app, label, |load, out| load.commit(label, url, out));
+}
+
+/// The script reported DOMContentLoaded.
+pub fn dom_ready<R: tauri::Runtime>(app: &tauri::AppHandle<R>, label: &str, url: &str, dom_content_loaded_ms: f64) {
+    step(app, label, |load, out| load.dom_ready(label, url, dom_content_loaded_ms, out));
+}
+
+/// `on_page_load` reported `Finished`.
+pub fn finished<R: tauri::Runtime>(app: &tauri::AppHandle<R>, label: &str, url: &str) {
+    step(app, label, |load, out| load.finish(label, url, out));
+}
+
+/// Fail load `load_id` if it is still waiting for a response after [`COMMIT_TIMEOUT`].
+fn watch_commit<R: tauri::Runtime>(app: tauri::AppHandle<R>, label: String, load_id: u32) {
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(COMMIT_TIMEOUT).await;
+        step(&app, &label, |load, out| {
+            if load.id == load_id && load.phase == LoadPhase::Started {
+                load.fail(&label, "timedOut", out);
+            }
+        });
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn loaded(url: &str) -> PageLoad {
+        let mut load = PageLoad::default();
+        let mut out = Vec::new();
+        load.navigate("t", url, &mut out);
+        load.commit("t", Some(url), &mut out);
+        load.finish("t", url, &mut out);
+        load
+    }
+
+    #[test]
+    fn fragment_changes_are_not_loads() {
+        let mut load = loaded("https://example.com/doc");
+        let mut out = Vec::new();
+        load.navigate("t", "https://example.com/doc#part", &mut out);
+        load.navigate("t", "https://example.com/doc#other", &mut out);
+        assert!(out.is_empty());
+        assert_eq!(load.phase, LoadPhase::Finished);
+        assert_eq!(load.url, "https://example.com/doc#other");
+    }
+
+    #[test]
+    fn other_documents_and_reloads_are_loads() {
+        for next in ["https://example.com/doc", "https://example.com/doc?q=1#part", "https://example.com/other#part"] {
+            let mut load = loaded("https://example.com/doc");
+            let mut out = Vec::new();
+            load.navigate("t", next, &mut out);
+            assert_eq!(load.phase, LoadPhase::Started, "{}", next);
+            assert_eq!(out.len(), 1);
+        }
+    }
+
+    #[test]
+    fn fragments_during_a_load_stay_part_of_it() {
+        let mut load = PageLoad::default();
+        let mut out = Vec::new();
+        load.navigate("t", "https://example.com/doc", &mut out);
+        load.navigate("t", "https://example.com/doc#part", &mut out);
+        assert_eq!(load.phase, LoadPhase::Started);
+        assert_eq!(load.id, 1);
+    }
+}
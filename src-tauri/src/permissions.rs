@@ -0,0 +1,669 @@
+//! Per-site permissions: camera, microphone, geolocation, notifications,
+//! clipboard read and popups, each allowed, denied or asked for, keyed by
+//! origin and persisted in `site-permissions.json`.
+//!
+//! The injected bridge (`scripts/permissions.js`) gets the stored decisions
+//! when the webview is created and wraps the page APIs behind each
+//! permission, in the top frame and, through a separate all-frames script, in
+//! every subframe. Denied APIs fail the way the platform reports a refusal;
+//! for "ask" the top frame sends a `permission-request` telemetry event, the
+//! main window is asked through `site-permission-requested`, and the answer
+//! goes back to the page through a non-enumerable hook with a per-run random
+//! name.
+//!
+//! The wrappers keep well-behaved pages honest and give them a prompt, but
+//! they run in the page's own world, so a page that wants to can get past
+//! them (or find the hook with `Object.getOwnPropertyNames`). What actually
+//! stops it depends on the engine:
+//! - WebKitGTK (Linux): [`watch_native`] answers the engine's own
+//!   `permission-request` for camera, microphone, geolocation and
+//!   notifications from the same decisions. "Ask" is only granted once the
+//!   user allowed that request through the bridge.
+//! - WKWebView (macOS 12+): [`watch_native`] does the same for camera and
+//!   microphone, through the UI delegate's media capture request. wry's
+//!   delegate grants those outright, so its class gets our handler, and views
+//!   that aren't tabs are passed on to wry's. Geolocation and notifications
+//!   have no such hook there and rely on the engine's prompt.
+//! - WebView2 (Windows): wry owns the permission event, so the engine's own
+//!   prompt is the only native check.
+//!
+//! Clipboard reads and popups are page-side only everywhere.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::{Emitter, Manager};
+use ts_rs::TS;
+
+use crate::error::BrsrError;
+use crate::storage;
+use crate::tabs::TabRegistry;
+use crate::telemetry::channel::random_token;
+use crate::telemetry::event::PermissionRequest;
+
+const SETTINGS_FILE: &str = "site-permissions.json";
+/// Sent to the main window when a site asks for a permission set to "ask".
+pub const REQUESTED_EVENT: &str = "site-permission-requested";
+/// Sent when a pending request was answered or its page went away.
+pub const RESOLVED_EVENT: &str = "site-permission-resolved";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, TS)]
+#[serde(rename_all = "kebab-case")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub enum Permission {
+    Camera,
+    Microphone,
+    Geolocation,
+    Notifications,
+    ClipboardRead,
+    Popups,
+}
+
+impl Permission {
+    pub const ALL: [Permission; 6] = [
+        Permission::Camera,
+        Permission::Microphone,
+        Permission::Geolocation,
+        Permission::Notifications,
+        Permission::ClipboardRead,
+        Permission::Popups,
+    ];
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "lowercase")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub enum PermissionState {
+    Allow,
+    Deny,
+    #[default]
+    Ask,
+}
+
+/// Stored decisions for one origin; permissions without one are "ask".
+pub type PermissionMap = BTreeMap<Permission, PermissionState>;
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct SitePermissions {
+    pub origin: String,
+    /// Every permission, including the ones still at "ask".
+    pub permissions: PermissionMap,
+}
+
+/// Payload of `site-permission-requested`.
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct PermissionPrompt {
+    /// Pass to `resolve_permission_request`.
+    pub request_id: u32,
+    pub label: String,
+    pub origin: String,
+    pub permission: Permission,
+}
+
+/// What the bridge reads from `PARAMS.permissions`. Subframes get no hook.
+#[derive(Debug, Clone, Serialize)]
+pub struct PagePermissions {
+    hook: Option<String>,
+    sites: BTreeMap<String, PermissionMap>,
+}
+
+struct Pending {
+    prompt: PermissionPrompt,
+    /// The page's own id for the request.
+    page_id: u32,
+}
+
+/// The origin of `input`, which may be a full URL.
+pub fn origin_of(input: &str) -> Result<String, BrsrError> {
+    let url = url::Url::parse(input.trim()).map_err(|e| BrsrError::invalid_url(input, e))?;
+    match url.origin() {
+        origin @ url::Origin::Tuple(..) => Ok(origin.ascii_serialization()),
+        url::Origin::Opaque(_) => Err(BrsrError::invalid_url(input, "it has no origin permissions can be kept for")),
+    }
+}
+
+/// Managed state: stored decisions and requests waiting on the user.
+pub struct SitePermissionStore {
+    sites: Mutex<BTreeMap<String, PermissionMap>>,
+    path: Option<PathBuf>,
+    /// Name of the page hook; random per run so pages can't guess it up front.
+    hook: String,
+    pending: Mutex<HashMap<u32, Pending>>,
+    next_id: Mutex<u32>,
+    /// "Allow this time" answers not yet used by the engine's own request:
+    /// (tab label, origin, permission).
+    granted_once: Mutex<HashSet<(String, String, Permission)>>,
+}
+
+impl SitePermissionStore {
+    pub fn load<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Self {
+        let path = storage::data_file(app, SETTINGS_FILE)
+            .map_err(|e| eprintln!("[Rust] Site permissions will not persist: {}", e))
+            .ok();
+        let sites = path
+            .as_deref()
+            .map(storage::load_json::<BTreeMap<String, PermissionMap>>)
+            .unwrap_or_default();
+        Self::new(sites, path)
+    }
+
+    fn new(sites: BTreeMap<String, PermissionMap>, path: Option<PathBuf>) -> Self {
+        Self {
+            sites: Mutex::new(sites),
+            path,
+            hook: format!("__brsr_permissions_{}", random_token()),
+            pending: Mutex::new(HashMap::new()),
+            next_id: Mutex::new(1),
+            granted_once: Mutex::new(HashSet::new()),
+        }
+    }
+
+    pub fn state(&self, origin: &str, permission: Permission) -> PermissionState {
+        self.sites
+            .lock()
+            .unwrap()
+            .get(origin)
+            .and_then(|site| site.get(&permission).copied())
+            .unwrap_or_default()
+    }
+
+    pub fn site(&self, origin: &str) -> SitePermissions {
+        let stored = self.sites.lock().unwrap().get(origin).cloned().unwrap_or_default();
+        SitePermissions {
+            origin: origin.to_string(),
+            permissions: Permission::ALL
+                .into_iter()
+                .map(|p| (p, stored.get(&p).copied().unwrap_or_default()))
+                .collect(),
+        }
+    }
+
+    fn set(&self, origin: &str, permission: Permission, state: PermissionState) -> Result<SitePermissions, BrsrError> {
+        let mut sites = self.sites.lock().unwrap();
+        let mut next = sites.clone();
+        let site = next.entry(origin.to_string()).or_default();
+        // "ask" is the default, so it isn't stored.
+        match state {
+            PermissionState::Ask => {
+                site.remove(&permission);
+            }
+            _ => {
+                site.insert(permission, state);
+            }
+        }
+        if site.is_empty() {
+            next.remove(origin);
+        }
+        if let Some(path) = &self.path {
+            storage::save_json(path, &next)?;
+        }
+        *sites = next;
+        drop(sites);
+        Ok(self.site(origin))
+    }
+
+    /// Drop every decision for `origin`; open tabs on it go back to asking.
+    pub fn forget<R: tauri::Runtime>(&self, app: &tauri::AppHandle<R>, origin: &str) -> Result<(), BrsrError> {
+        if self.remove(origin)? {
+            push_update(app, self, &self.site(origin));
+        }
+        Ok(())
+    }
+
+    /// Drop `origin`'s decisions. Returns whether it had any.
+    fn remove(&self, origin: &str) -> Result<bool, BrsrError> {
+        let mut sites = self.sites.lock().unwrap();
+        if !sites.contains_key(origin) {
+            return Ok(false);
+        }
+        let mut next = sites.clone();
+        next.remove(origin);
+        if let Some(path) = &self.path {
+            storage::save_json(path, &next)?;
+        }
+        *sites = next;
+        Ok(true)
+    }
+
+    pub fn page_params(&self) -> PagePermissions {
+        PagePermissions {
+            hook: Some(self.hook.clone()),
+            sites: self.sites.lock().unwrap().clone(),
+        }
+    }
+
+    /// Like [`Self::page_params`], for the script every subframe runs.
+    pub fn frame_params(&self) -> PagePermissions {
+        PagePermissions {
+            hook: None,
+            sites: self.sites.lock().unwrap().clone(),
+        }
+    }
+
+    /// Whether the engine may go ahead with `permission` for `origin` in tab
+    /// `label`. An "ask" is allowed only by a one-time answer from the user,
+    /// which this uses up.
+    pub fn engine_may_use(&self, label: &str, origin: &str, permission: Permission) -> bool {
+        match self.state(origin, permission) {
+            PermissionState::Allow => true,
+            PermissionState::Deny => false,
+            PermissionState::Ask => self.granted_once.lock().unwrap().remove(&(
+                label.to_string(),
+                origin.to_string(),
+                permission,
+            )),
+        }
+    }
+
+    /// Call the bridge hook in tab `label` with `message`.
+    fn notify_page<R: tauri::Runtime>(&self, app: &tauri::AppHandle<R>, label: &str, message: serde_json::Value) {
+        if let Some(webview) = app.get_webview(label) {
+            let hook = serde_json::to_string(&self.hook).expect("hook serializes");
+            let _ = webview.eval(&format!("window[{hook}] && window[{hook}]({message});"));
+        }
+    }
+
+    fn reply<R: tauri::Runtime>(&self, app: &tauri::AppHandle<R>, label: &str, page_id: u32, granted: bool) {
+        self.notify_page(app, label, serde_json::json!({ "type": "reply", "id": page_id, "granted": granted }));
+    }
+
+    /// Drop `label`'s pending requests and one-time answers, e.g. when it loads
+    /// a new document or closes.
+    pub fn cancel_for_tab<R: tauri::Runtime>(&self, app: &tauri::AppHandle<R>, label: &str) {
+        self.granted_once.lock().unwrap().retain(|(tab, _, _)| tab != label);
+        let mut cancelled = Vec::new();
+        self.pending.lock().unwrap().retain(|id, pending| {
+            let keep = pending.prompt.label != label;
+            if !keep {
+                cancelled.push(*id);
+            }
+            keep
+        });
+        for id in cancelled {
+            let _ = app.emit_to("main", RESOLVED_EVENT, serde_json::json!({ "requestId": id }));
+        }
+    }
+}
+
+/// Handle a `permission-request` from the bridge in tab `label`.
+pub fn handle_request<R: tauri::Runtime>(app: &tauri::AppHandle<R>, label: &str, request: &PermissionRequest) {
+    let store = app.state::<SitePermissionStore>();
+    // Trust the tab's committed URL over what the page says about itself.
+    let origin = app
+        .state::<TabRegistry>()
+        .get(label)
+        .and_then(|tab| origin_of(&tab.url).ok());
+    let Some(origin) = origin.filter(|o| *o == request.origin) else {
+        eprintln!("[Rust] Permission request from '{}' for an origin it isn't on: {}", label, request.origin);
+        store.reply(app, label, request.id, false);
+        return;
+    };
+    match store.state(&origin, request.permission) {
+        PermissionState::Allow => store.reply(app, label, request.id, true),
+        PermissionState::Deny => store.reply(app, label, request.id, false),
+        PermissionState::Ask => {
+            let request_id = {
+                let mut next = store.next_id.lock().unwrap();
+                let id = *next;
+                *next += 1;
+                id
+            };
+            let prompt = PermissionPrompt {
+                request_id,
+                label: label.to_string(),
+                origin,
+                permission: request.permission,
+            };
+            println!("[Rust] {} asks for {:?} in '{}'", prompt.origin, prompt.permission, label);
+            let _ = app.emit_to("main", REQUESTED_EVENT, &prompt);
+            store.pending.lock().unwrap().insert(
+                request_id,
+                Pending {
+                    prompt,
+                    page_id: request.id,
+                },
+            );
+        }
+    }
+}
+
+/// Push `origin`'s decisions to the tabs currently on it.
+fn push_update<R: tauri::Runtime>(app: &tauri::AppHandle<R>, store: &SitePermissionStore, site: &SitePermissions) {
+    let message = serde_json::json!({ "type": "update", "origin": site.origin, "permissions": site.permissions });
+    for tab in app.state::<TabRegistry>().list() {
+        if origin_of(&tab.url).is_ok_and(|o| o == site.origin) {
+            store.notify_page(app, &tab.label, message.clone());
+        }
+    }
+}
+
+/// Decisions for the origin of `url` (a URL or an origin).
+#[tauri::command]
+pub fn get_site_permissions(
+    store: tauri::State<'_, SitePermissionStore>,
+    url: String,
+) -> Result<SitePermissions, BrsrError> {
+    Ok(store.site(&origin_of(&url)?))
+}
+
+/// Set one permission for the origin of `url`. Open tabs on it pick the change up immediately.
+#[tauri::command]
+pub fn set_site_permission(
+    app: tauri::AppHandle,
+    store: tauri::State<'_, SitePermissionStore>,
+    url: String,
+    permission: Permission,
+    state: PermissionState,
+) -> Result<SitePermissions, BrsrError> {
+    let origin = origin_of(&url)?;
+    let site = store.set(&origin, permission, state)?;
+    push_update(&app, &store, &site);
+    Ok(site)
+}
+
+/// Answer a `site-permission-requested` prompt. With `remember` the answer is
+/// stored for the origin; otherwise it only applies to this request. Answers
+/// from private tabs are never stored.
+#[tauri::command]
+pub fn resolve_permission_request(
+    app: tauri::AppHandle,
+    store: tauri::State<'_, SitePermissionStore>,
+    request_id: u32,
+    allow: bool,
+    remember: bool,
+) -> Result<(), BrsrError> {
+    let pending = store
+        .pending
+        .lock()
+        .unwrap()
+        .remove(&request_id)
+        .ok_or_else(|| BrsrError::not_found("permission request", request_id))?;
+    let prompt = pending.prompt;
+    if remember && !app.state::<TabRegistry>().is_private(&prompt.label) {
+        let state = if allow { PermissionState::Allow } else { PermissionState::Deny };
+        let site = store.set(&prompt.origin, prompt.permission, state)?;
+        push_update(&app, &store, &site);
+    } else if allow {
+        store
+            .granted_once
+            .lock()
+            .unwrap()
+            .insert((prompt.label.clone(), prompt.origin.clone(), prompt.permission));
+    }
+    store.reply(&app, &prompt.label, pending.page_id, allow);
+    let _ = app.emit_to("main", RESOLVED_EVENT, serde_json::json!({ "requestId": request_id }));
+    Ok(())
+}
+
+/// Answer the engine's own permission requests in `webview` (tab `label`)
+/// from the stored decisions; see the module docs for where this is possible.
+#[cfg(target_os = "linux")]
+pub fn watch_native<R: tauri::Runtime>(app: &tauri::AppHandle<R>, webview: &tauri::Webview<R>) {
+    use webkit2gtk::glib::prelude::Cast;
+    use webkit2gtk::{
+        GeolocationPermissionRequest, NotificationPermissionRequest, PermissionRequestExt, UserMediaPermissionRequest,
+        UserMediaPermissionRequestExt, WebViewExt,
+    };
+
+    let app = app.clone();
+    let label = webview.label().to_string();
+    let result = webview.with_webview(move |platform| {
+        platform.inner().connect_permission_request(move |view, request| {
+            let wanted = if let Some(media) = request.downcast_ref::<UserMediaPermissionRequest>() {
+                let mut wanted = Vec::new();
+                if media.is_for_video_device() {
+                    wanted.push(Permission::Camera);
+                }
+                if media.is_for_audio_device() {
+                    wanted.push(Permission::Microphone);
+                }
+                wanted
+            } else if request.is::<GeolocationPermissionRequest>() {
+                vec![Permission::Geolocation]
+            } else if request.is::<NotificationPermissionRequest>() {
+                vec![Permission::Notifications]
+            } else {
+                // Not one of ours: WebKit's default answer stands.
+                return false;
+            };
+            let granted = engine_decides(&app, &label, view.uri().as_deref(), &wanted);
+            if granted {
+                request.allow();
+            } else {
+                request.deny();
+            }
+            true
+        });
+    });
+    if let Err(e) = result {
+        eprintln!("[Rust] Permission requests in '{}' go unchecked: {}", webview.label(), e);
+    }
+}
+
+/// Answer WKWebView's camera and microphone requests in `webview` (tab
+/// `label`) from the stored decisions; see the module docs.
+#[cfg(target_os = "macos")]
+pub fn watch_native<R: tauri::Runtime>(app: &tauri::AppHandle<R>, webview: &tauri::Webview<R>) {
+    let app = app.clone();
+    let label = webview.label().to_string();
+    let result = webview.with_webview(move |platform| {
+        let view = platform.inner() as *mut objc::runtime::Object;
+        let decide = move |url: Option<&str>, wanted: &[Permission]| engine_decides(&app, &label, url, wanted);
+        if !unsafe { media::watch(view, Box::new(decide)) } {
+            eprintln!("[Rust] Camera and microphone requests go unchecked: the webview has no UI delegate");
+        }
+    });
+    if let Err(e) = result {
+        eprintln!("[Rust] Permission requests in '{}' go unchecked: {}", webview.label(), e);
+    }
+}
+
+/// See the Linux version; on Windows the engine's own prompt is the native check.
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn watch_native<R: tauri::Runtime>(_app: &tauri::AppHandle<R>, _webview: &tauri::Webview<R>) {}
+
+/// Whether the engine may go ahead with all of `wanted` for the page at `url`
+/// in tab `label`.
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn engine_decides<R: tauri::Runtime>(app: &tauri::AppHandle<R>, label: &str, url: Option<&str>, wanted: &[Permission]) -> bool {
+    let store = app.state::<SitePermissionStore>();
+    let origin = url.and_then(|url| origin_of(url).ok());
+    let granted = origin.as_deref().is_some_and(|origin| {
+        // Check every permission, so each one-time answer is used up.
+        let allowed: Vec<bool> = wanted.iter().map(|p| store.engine_may_use(label, origin, *p)).collect();
+        allowed.into_iter().all(|ok| ok)
+    });
+    println!(
+        "[Rust] Engine asked for {:?} in '{}' ({})",
+        wanted,
+        label,
+        if granted { "allowed" } else { "denied" }
+    );
+    granted
+}
+
+/// WKUIDelegate's media capture request,
+/// `webView:requestMediaCapturePermissionForOrigin:initiatedByFrame:type:decisionHandler:`.
+#[cfg(target_os = "macos")]
+mod media {
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::ffi::CStr;
+    use std::os::raw::c_char;
+
+    use block::Block;
+    use objc::runtime::{self, Class, Imp, Object, Sel};
+    use objc::{msg_send, sel, sel_impl};
+
+    use super::Permission;
+
+    /// `WKPermissionDecision` values.
+    const PROMPT: isize = 0;
+    const GRANT: isize = 1;
+    const DENY: isize = 2;
+
+    type Decide = Box<dyn Fn(Option<&str>, &[Permission]) -> bool>;
+    type Handler = unsafe extern "C" fn(*mut Object, Sel, *mut Object, *mut Object, *mut Object, isize, *mut Object);
+
+    thread_local! {
+        /// How to answer for each watched WKWebView, by address. A view that
+        /// goes away leaves its entry until the address is watched again.
+        static VIEWS: RefCell<HashMap<usize, Decide>> = RefCell::new(HashMap::new());
+        /// Delegate classes given [`request_media`], with the handler each had before.
+        static CLASSES: RefCell<HashMap<usize, Option<Imp>>> = RefCell::new(HashMap::new());
+    }
+
+    fn selector() -> Sel {
+        sel!(webView:requestMediaCapturePermissionForOrigin:initiatedByFrame:type:decisionHandler:)
+    }
+
+    /// Answer `view`'s media capture requests with `decide`. Returns `false`
+    /// if the view has no UI delegate to take them.
+    pub unsafe fn watch(view: *mut Object, decide: Decide) -> bool {
+        let delegate: *mut Object = msg_send![view, UIDelegate];
+        if delegate.is_null() {
+            return false;
+        }
+        let class = runtime::object_getClass(delegate) as *mut Class;
+        CLASSES.with(|classes| {
+            classes.borrow_mut().entry(class as usize).or_insert_with(|| {
+                let imp: Imp = std::mem::transmute(request_media as Handler);
+                let method = runtime::class_getInstanceMethod(class, selector());
+                if method.is_null() {
+                    runtime::class_addMethod(class, selector(), imp, b"v@:@@@q@?\0".as_ptr() as *const c_char);
+                    None
+                } else {
+                    Some(runtime::method_setImplementation(method as *mut _, imp))
+                }
+            });
+        });
+        VIEWS.with(|views| views.borrow_mut().insert(view as usize, decide));
+        true
+    }
+
+    unsafe fn page_url(view: *mut Object) -> Option<String> {
+        let url: *mut Object = msg_send![view, URL];
+        if url.is_null() {
+            return None;
+        }
+        let string: *mut Object = msg_send![url, absoluteString];
+        if string.is_null() {
+            return None;
+        }
+        let bytes: *const c_char = msg_send![string, UTF8String];
+        (!bytes.is_null()).then(|| CStr::from_ptr(bytes).to_string_lossy().into_owned())
+    }
+
+    unsafe extern "C" fn request_media(
+        this: *mut Object,
+        cmd: Sel,
+        view: *mut Object,
+        origin: *mut Object,
+        frame: *mut Object,
+        kind: isize,
+        handler: *mut Object,
+    ) {
+        // `WKMediaCaptureType`: camera, microphone, or both.
+        let wanted: &[Permission] = match kind {
+            0 => &[Permission::Camera],
+            1 => &[Permission::Microphone],
+            2 => &[Permission::Camera, Permission::Microphone],
+            _ => &[],
+        };
+        let granted = if wanted.is_empty() {
+            None
+        } else {
+            let url = page_url(view);
+            VIEWS.with(|views| views.borrow().get(&(view as usize)).map(|decide| decide(url.as_deref(), wanted)))
+        };
+        let decision = match granted {
+            Some(true) => GRANT,
+            Some(false) => DENY,
+            None => {
+                // Not a tab: whatever the delegate did before.
+                let class = runtime::object_getClass(this) as usize;
+                if let Some(original) = CLASSES.with(|classes| classes.borrow().get(&class).copied().flatten()) {
+                    let original: Handler = std::mem::transmute(original);
+                    return original(this, cmd, view, origin, frame, kind, handler);
+                }
+                PROMPT
+            }
+        };
+        let handler = &*(handler as *const Block<(isize,), ()>);
+        handler.call((decision,));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SITE: &str = "https://example.com";
+
+    #[test]
+    fn stores_only_allow_and_deny() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SETTINGS_FILE);
+        let store = SitePermissionStore::new(BTreeMap::new(), Some(path.clone()));
+
+        let site = store.set(SITE, Permission::Camera, PermissionState::Allow).unwrap();
+        assert_eq!(site.permissions.len(), Permission::ALL.len());
+        assert_eq!(site.permissions[&Permission::Camera], PermissionState::Allow);
+        assert_eq!(site.permissions[&Permission::Popups], PermissionState::Ask);
+        store.set(SITE, Permission::Geolocation, PermissionState::Deny).unwrap();
+        store.set(SITE, Permission::Camera, PermissionState::Ask).unwrap();
+
+        let saved: BTreeMap<String, PermissionMap> = storage::load_json(&path);
+        assert_eq!(saved[SITE], PermissionMap::from([(Permission::Geolocation, PermissionState::Deny)]));
+
+        // A site with nothing but "ask" left isn't kept at all.
+        store.set(SITE, Permission::Geolocation, PermissionState::Ask).unwrap();
+        assert!(store.page_params().sites.is_empty());
+        assert!(storage::load_json::<BTreeMap<String, PermissionMap>>(&path).is_empty());
+    }
+
+    #[test]
+    fn forgetting_a_site_goes_back_to_asking() {
+        let store = SitePermissionStore::new(BTreeMap::new(), None);
+        store.set(SITE, Permission::Microphone, PermissionState::Deny).unwrap();
+        store.set("https://other.example", Permission::Microphone, PermissionState::Allow).unwrap();
+        assert!(store.remove(SITE).unwrap());
+        assert!(!store.remove(SITE).unwrap());
+        assert_eq!(store.state(SITE, Permission::Microphone), PermissionState::Ask);
+        assert_eq!(store.state("https://other.example", Permission::Microphone), PermissionState::Allow);
+    }
+
+    #[test]
+    fn the_engine_gets_an_ask_only_once_per_answer() {
+        let store = SitePermissionStore::new(BTreeMap::new(), None);
+        store.set(SITE, Permission::Camera, PermissionState::Allow).unwrap();
+        store.set(SITE, Permission::Notifications, PermissionState::Deny).unwrap();
+        assert!(store.engine_may_use("tab-1", SITE, Permission::Camera));
+        assert!(store.engine_may_use("tab-1", SITE, Permission::Camera));
+        assert!(!store.engine_may_use("tab-1", SITE, Permission::Notifications));
+
+        assert!(!store.engine_may_use("tab-1", SITE, Permission::Microphone));
+        store
+            .granted_once
+            .lock()
+            .unwrap()
+            .insert(("tab-1".into(), SITE.into(), Permission::Microphone));
+        assert!(!store.engine_may_use("tab-2", SITE, Permission::Microphone));
+        assert!(!store.engine_may_use("tab-1", "https://evil.example", Permission::Microphone));
+        assert!(store.engine_may_use("tab-1", SITE, Permission::Microphone));
+        assert!(!store.engine_may_use("tab-1", SITE, Permission::Microphone));
+    }
+
+    #[test]
+    fn origins_come_from_urls() {
+        assert_eq!(origin_of(" https://Example.com:443/a?b ").unwrap(), SITE);
+        assert_eq!(origin_of("http://localhost:3000/").unwrap(), "http://localhost:3000");
+        assert!(origin_of("data:text/html,hi").is_err());
+        assert!(origin_of("not a url").is_err());
+    }
+}
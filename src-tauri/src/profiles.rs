@@ -0,0 +1,437 @@
+//! Named browser profiles.
+//!
+//! Each profile has its own data directory. Every store keeps its files there
+//! (history, bookmarks, site permissions, settings, the session, downloads),
+//! and the tabs keep their cookies, storage and cache in its `webview/`
+//! subdirectory. The built-in `default` profile uses the app data directory
+//! itself, and the platform's default webview store, so data from before
+//! profiles existed stays where it was.
+//!
+//! A running instance is bound to one profile for its lifetime, chosen with
+//! `--profile <id>` on the command line or else the one last switched to. Its
+//! main window and every tab in it belong to that profile. Another profile is
+//! opened side by side by starting a second instance for it
+//! (`open_profile_window`); `switch_profile` replaces the current instance
+//! with one for the other profile. A profile runs in one instance at a time:
+//! launching it again hands the launch's links to the open one. Whether a
+//! profile is open comes from its instance lock (see `intake`), so instances
+//! started from anywhere count.
+//!
+//! The list of profiles lives in `profiles.json` in the app data directory.
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::{Emitter, Manager, WebviewBuilder};
+use ts_rs::TS;
+
+use crate::error::BrsrError;
+use crate::storage;
+use crate::tabs::now_ms;
+use crate::telemetry::channel::random_token;
+
+const PROFILES_FILE: &str = "profiles.json";
+/// Non-default profiles live in `<app data dir>/profiles/<id>/`.
+const PROFILES_DIR: &str = "profiles";
+const WEBVIEW_DIR: &str = "webview";
+pub const DEFAULT_PROFILE: &str = "default";
+/// Command-line flag selecting the profile an instance runs.
+pub const PROFILE_ARG: &str = "--profile";
+/// Command-line flag, repeatable, with a URL for a launched instance to open.
+pub const OPEN_URL_ARG: &str = "--open-url";
+
+pub const CHANGED_EVENT: &str = "profiles-changed";
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct Profile {
+    pub id: String,
+    pub name: String,
+    pub created_at: f64,
+}
+
+impl Profile {
+    fn builtin() -> Self {
+        Self {
+            id: DEFAULT_PROFILE.to_string(),
+            name: "Default".to_string(),
+            created_at: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct ProfileList {
+    pub profiles: Vec<Profile>,
+    /// The profile this window runs.
+    pub current: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProfilesFile {
+    profiles: Vec<Profile>,
+    /// Opened on a launch without `--profile`.
+    #[serde(default)]
+    last_used: Option<String>,
+}
+
+impl Default for ProfilesFile {
+    fn default() -> Self {
+        Self {
+            profiles: vec![Profile::builtin()],
+            last_used: None,
+        }
+    }
+}
+
+/// Ids end up in paths, so anything else in a hand-edited file is dropped.
+fn valid_id(id: &str) -> bool {
+    !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+/// The id passed with `--profile <id>` or `--profile=<id>`, if any.
+fn profile_arg() -> Option<String> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == PROFILE_ARG {
+            return args.next();
+        }
+        if let Some(id) = arg.strip_prefix(PROFILE_ARG).and_then(|rest| rest.strip_prefix('=')) {
+            return Some(id.to_string());
+        }
+    }
+    None
+}
+
+/// Managed state: the profile list and the profile this instance runs.
+pub struct ProfileManager {
+    file: Mutex<ProfilesFile>,
+    path: Option<PathBuf>,
+    root: Option<PathBuf>,
+    current: String,
+}
+
+impl ProfileManager {
+    /// Load the list from `root`, the app data directory, and pick the profile
+    /// this instance runs. Runs before the app is built, so the single-instance
+    /// check knows the profile; must be managed before any store that calls
+    /// [`storage::data_file`].
+    pub fn load(root: Option<PathBuf>) -> Self {
+        let root = root.filter(|root| match fs::create_dir_all(root) {
+            Ok(()) => true,
+            Err(e) => {
+                eprintln!("[Rust] Profiles will not persist: {}", BrsrError::io(root, e));
+                false
+            }
+        });
+        let path = root.as_ref().map(|root| root.join(PROFILES_FILE));
+        let mut file = path
+            .as_deref()
+            .map(storage::load_json::<ProfilesFile>)
+            .unwrap_or_default();
+        file.profiles.retain(|p| {
+            let ok = valid_id(&p.id);
+            if !ok {
+                eprintln!("[Rust] Ignoring profile with invalid id '{}'", p.id);
+            }
+            ok
+        });
+        if !file.profiles.iter().any(|p| p.id == DEFAULT_PROFILE) {
+            file.profiles.insert(0, Profile::builtin());
+        }
+
+        let known = |id: &String| file.profiles.iter().any(|p| &p.id == id);
+        let requested = profile_arg();
+        if let Some(id) = requested.as_ref().filter(|id| !known(id)) {
+            eprintln!("[Rust] Unknown profile '{}', using the last used one", id);
+        }
+        let current = requested
+            .filter(known)
+            .or_else(|| file.last_used.clone().filter(known))
+            .unwrap_or_else(|| DEFAULT_PROFILE.to_string());
+        println!("[Rust] Running profile '{}'", current);
+
+        Self {
+            file: Mutex::new(file),
+            path,
+            root,
+            current,
+        }
+    }
+
+    pub fn current(&self) -> &str {
+        &self.current
+    }
+
+    fn dir(&self, id: &str) -> Result<PathBuf, BrsrError> {
+        let root = self
+            .root
+            .clone()
+            .ok_or_else(|| BrsrError::platform("App data dir is unavailable"))?;
+        Ok(if id == DEFAULT_PROFILE {
+            root
+        } else {
+            root.join(PROFILES_DIR).join(id)
+        })
+    }
+
+    /// Where the stores of the running profile keep their files.
+    pub fn data_dir(&self) -> Result<PathBuf, BrsrError> {
+        self.dir(&self.current)
+    }
+
+    /// Webview data directory for tabs of the running profile; `None` for the
+    /// default profile, which uses the platform's default store.
+    fn webview_data_dir(&self) -> Option<PathBuf> {
+        if self.shares_ui_store() {
+            return None;
+        }
+        self.data_dir().ok().map(|dir| dir.join(WEBVIEW_DIR))
+    }
+
+    /// Whether tabs share the webview store of the app's own UI, as the
+    /// default profile's do.
+    pub fn shares_ui_store(&self) -> bool {
+        self.current == DEFAULT_PROFILE
+    }
+
+    /// WKWebView data store for the running profile: a name-based (v5) UUID
+    /// of its id, so every profile gets its own and keeps it across launches.
+    #[cfg(target_os = "macos")]
+    fn data_store_identifier(&self) -> Option<[u8; 16]> {
+        /// Namespace of brsr's profile ids.
+        const NAMESPACE: uuid::Uuid = uuid::Uuid::from_u128(0x5f0c2b9e_8d41_4a6c_b3e7_91d20a4f6c18);
+        if self.shares_ui_store() {
+            return None;
+        }
+        Some(uuid::Uuid::new_v5(&NAMESPACE, self.current.as_bytes()).into_bytes())
+    }
+
+    /// Point `builder` at the running profile's webview data store.
+    pub fn with_data_store<R: tauri::Runtime>(&self, mut builder: WebviewBuilder<R>) -> WebviewBuilder<R> {
+        if let Some(dir) = self.webview_data_dir() {
+            builder = builder.data_directory(dir);
+        }
+        #[cfg(target_os = "macos")]
+        if let Some(identifier) = self.data_store_identifier() {
+            builder = builder.data_store_identifier(identifier);
+        }
+        builder
+    }
+
+    pub fn list(&self) -> ProfileList {
+        ProfileList {
+            profiles: self.file.lock().unwrap().profiles.clone(),
+            current: self.current.clone(),
+        }
+    }
+
+    fn get(&self, id: &str) -> Result<Profile, BrsrError> {
+        self.file
+            .lock()
+            .unwrap()
+            .profiles
+            .iter()
+            .find(|p| p.id == id)
+            .cloned()
+            .ok_or_else(|| BrsrError::not_found("profile", id))
+    }
+
+    /// Apply `change` to a copy of the file and keep it only if it saves.
+    fn update<T>(&self, change: impl FnOnce(&mut ProfilesFile) -> Result<T, BrsrError>) -> Result<T, BrsrError> {
+        let mut file = self.file.lock().unwrap();
+        let mut next = file.clone();
+        let result = change(&mut next)?;
+        if let Some(path) = &self.path {
+            storage::save_json(path, &next)?;
+        }
+        *file = next;
+        Ok(result)
+    }
+
+    fn is_running(&self, id: &str) -> bool {
+        self.dir(id).is_ok_and(|dir| crate::intake::is_running(&dir))
+    }
+
+    /// Start another instance of the app running profile `id`, opening `urls`.
+    fn launch(&self, id: &str, urls: &[String]) -> Result<(), BrsrError> {
+        let exe = std::env::current_exe()
+            .map_err(|e| BrsrError::platform(format!("Failed to find the app executable: {}", e)))?;
+        let mut command = Command::new(exe);
+        command.arg(PROFILE_ARG).arg(id);
+        for url in urls {
+            command.arg(OPEN_URL_ARG).arg(url);
+        }
+        let mut child = command
+            .spawn()
+            .map_err(|e| BrsrError::platform(format!("Failed to start profile '{}': {}", id, e)))?;
+        // Reap it when it exits; it may well outlive us, too.
+        std::thread::spawn(move || child.wait());
+        Ok(())
+    }
+
+    /// Open `urls` in a window of profile `id`, which must not be the one this
+    /// instance runs. If that profile is already open, the instance launched
+    /// here hands them to it and exits.
+    pub fn open_urls(&self, id: &str, urls: &[String]) -> Result<(), BrsrError> {
+        let profile = self.get(id)?;
+        self.launch(id, urls)?;
+        println!("[Rust] Opened {} link(s) in profile '{}'", urls.len(), profile.name);
+        Ok(())
+    }
+}
+
+fn check_name(profiles: &[Profile], name: &str, except: Option<&str>) -> Result<String, BrsrError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(BrsrError::invalid("Profile name can't be empty"));
+    }
+    if profiles
+        .iter()
+        .any(|p| Some(p.id.as_str()) != except && p.name.eq_ignore_ascii_case(name))
+    {
+        return Err(BrsrError::conflict(format!("A profile named '{}' already exists", name)));
+    }
+    Ok(name.to_string())
+}
+
+fn emit_changed(app: &tauri::AppHandle, manager: &ProfileManager) {
+    let _ = app.emit_to("main", CHANGED_EVENT, manager.list());
+}
+
+#[tauri::command]
+pub fn list_profiles(manager: tauri::State<'_, ProfileManager>) -> ProfileList {
+    manager.list()
+}
+
+#[tauri::command]
+pub fn create_profile(
+    app: tauri::AppHandle,
+    manager: tauri::State<'_, ProfileManager>,
+    name: String,
+) -> Result<Profile, BrsrError> {
+    let profile = manager.update(|file| {
+        let profile = Profile {
+            id: random_token(),
+            name: check_name(&file.profiles, &name, None)?,
+            created_at: now_ms() as f64,
+        };
+        file.profiles.push(profile.clone());
+        Ok(profile)
+    })?;
+    let dir = manager.dir(&profile.id)?;
+    fs::create_dir_all(&dir).map_err(|e| BrsrError::io(&dir, e))?;
+    println!("[Rust] Created profile '{}' ({})", profile.name, profile.id);
+    emit_changed(&app, &manager);
+    Ok(profile)
+}
+
+#[tauri::command]
+pub fn rename_profile(
+    app: tauri::AppHandle,
+    manager: tauri::State<'_, ProfileManager>,
+    id: String,
+    name: String,
+) -> Result<Profile, BrsrError> {
+    let profile = manager.update(|file| {
+        let name = check_name(&file.profiles, &name, Some(&id))?;
+        let profile = file
+            .profiles
+            .iter_mut()
+            .find(|p| p.id == id)
+            .ok_or_else(|| BrsrError::not_found("profile", &id))?;
+        profile.name = name;
+        Ok(profile.clone())
+    })?;
+    emit_changed(&app, &manager);
+    Ok(profile)
+}
+
+/// Delete a profile and everything in its data directory. The default
+/// profile and profiles that are open can't be deleted.
+#[tauri::command]
+pub fn delete_profile(
+    app: tauri::AppHandle,
+    manager: tauri::State<'_, ProfileManager>,
+    id: String,
+) -> Result<(), BrsrError> {
+    if id == DEFAULT_PROFILE {
+        return Err(BrsrError::conflict("The default profile can't be deleted"));
+    }
+    let profile = manager.get(&id)?;
+    if id == manager.current() || manager.is_running(&id) {
+        return Err(BrsrError::conflict(format!("Close the '{}' profile before deleting it", profile.name)));
+    }
+    let dir = manager.dir(&id)?;
+    manager.update(|file| {
+        file.profiles.retain(|p| p.id != id);
+        if file.last_used.as_deref() == Some(id.as_str()) {
+            file.last_used = None;
+        }
+        Ok(())
+    })?;
+    if dir.exists() {
+        fs::remove_dir_all(&dir).map_err(|e| BrsrError::io(&dir, e))?;
+    }
+    println!("[Rust] Deleted profile '{}' ({})", profile.name, id);
+    emit_changed(&app, &manager);
+    Ok(())
+}
+
+/// Make `id` the profile brsr opens with and relaunch into it. The session of
+/// the current profile is saved first, so switching back restores it.
+#[tauri::command]
+pub fn switch_profile(
+    app: tauri::AppHandle,
+    manager: tauri::State<'_, ProfileManager>,
+    id: String,
+) -> Result<(), BrsrError> {
+    let profile = manager.get(&id)?;
+    manager.update(|file| {
+        file.last_used = Some(id.clone());
+        Ok(())
+    })?;
+    if id == manager.current() {
+        return Ok(());
+    }
+    if manager.is_running(&id) {
+        return Err(BrsrError::conflict(format!(
+            "The '{}' profile is already open in another window",
+            profile.name
+        )));
+    }
+    crate::session::save(&app);
+    manager.launch(&id, &[])?;
+    println!("[Rust] Switching to profile '{}'", profile.name);
+    app.exit(0);
+    Ok(())
+}
+
+/// Open a window for profile `id` next to this one, in its own instance.
+/// For the profile this window runs, it just brings the window forward.
+#[tauri::command]
+pub fn open_profile_window(
+    app: tauri::AppHandle,
+    manager: tauri::State<'_, ProfileManager>,
+    id: String,
+) -> Result<(), BrsrError> {
+    let profile = manager.get(&id)?;
+    if id == manager.current() {
+        if let Some(window) = app.get_webview_window("main") {
+            window.set_focus()?;
+        }
+        return Ok(());
+    }
+    // If the profile is already open, the new instance brings that window
+    // forward and exits.
+    manager.launch(&id, &[])?;
+    println!("[Rust] Opened a window for profile '{}'", profile.name);
+    Ok(())
+}
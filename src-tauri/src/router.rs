@@ -0,0 +1,506 @@
+//! Routing of links that other apps open with brsr.
+//!
+//! Rules are tried in order and the first whose conditions all hold picks
+//! where the link goes: a tab here, a private tab, another profile's window,
+//! or another installed browser (by the id `list_http_candidates` shows in
+//! parentheses). A rule's conditions are
+//! - `hosts`: the URL's host is one of these or a subdomain of one;
+//! - `path`: a glob over the URL path, where `*` stays within a segment,
+//!   `**` crosses segments (`/**/x` also matches `/x`) and `?` is one
+//!   character;
+//! - `regex`: a regular expression found in the full URL.
+//!
+//! Rules can't name `sourceApps`, the app that opened the link, yet: no
+//! platform tells brsr where a link came from, so such rules are refused
+//! rather than kept around never matching. Nor can a rule name brsr itself as
+//! the other browser: the link would come straight back, match the same rule
+//! and go round for ever.
+//!
+//! Conditions left empty match anything. A link no rule matches opens in a
+//! tab here, as it would without the router.
+//!
+//! The rules are kept in `url-router.json` in the app data directory, shared
+//! by all profiles, so it can be edited by hand; `set_url_router` replaces
+//! them and `test_route` shows which rule a URL would hit.
+
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+use ts_rs::TS;
+use url::Url;
+
+use crate::error::BrsrError;
+use crate::navigation::policy::host_matches;
+use crate::profiles::ProfileManager;
+use crate::storage;
+
+const SETTINGS_FILE: &str = "url-router.json";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub enum RouteTarget {
+    /// A new tab in this window.
+    Tab,
+    /// A new private tab in this window.
+    PrivateTab,
+    /// A window of another profile.
+    #[serde(rename_all = "camelCase")]
+    Profile { profile_id: String },
+    /// Another installed browser: a bundle id on macOS, a desktop file id on Linux.
+    #[serde(rename_all = "camelCase")]
+    Browser { browser_id: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct RouteRule {
+    pub name: String,
+    #[serde(default = "enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub hosts: Vec<String>,
+    #[serde(default)]
+    pub path: Option<String>,
+    #[serde(default)]
+    pub regex: Option<String>,
+    /// Apps that opened the link. Not supported yet, so it must be empty.
+    #[serde(default)]
+    pub source_apps: Vec<String>,
+    pub target: RouteTarget,
+}
+
+fn enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase", default)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct RouterSettings {
+    /// When off, every link opens in a tab here.
+    pub enabled: bool,
+    pub rules: Vec<RouteRule>,
+}
+
+impl Default for RouterSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            rules: Vec::new(),
+        }
+    }
+}
+
+/// Where a link goes, and which rule sent it there.
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct RouteDecision {
+    pub url: String,
+    /// Index of the matching rule; `None` when no rule matched.
+    pub rule_index: Option<u32>,
+    pub rule_name: Option<String>,
+    pub target: RouteTarget,
+}
+
+/// A path glob as an anchored regular expression.
+fn glob_regex(glob: &str) -> Result<Regex, regex::Error> {
+    let mut pattern = String::from("^");
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                // `**/` is any number of whole segments, none included.
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    pattern.push_str("(?:.*/)?");
+                } else {
+                    pattern.push_str(".*");
+                }
+            }
+            '*' => pattern.push_str("[^/]*"),
+            '?' => pattern.push_str("[^/]"),
+            c => pattern.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    pattern.push('$');
+    Regex::new(&pattern)
+}
+
+/// A rule with its patterns compiled.
+struct Compiled {
+    path: Option<Regex>,
+    regex: Option<Regex>,
+}
+
+impl Compiled {
+    /// `own_ids` are the ids brsr itself goes by, which no rule may send links to.
+    fn new(rule: &RouteRule, own_ids: &[String]) -> Result<Self, BrsrError> {
+        if !rule.source_apps.is_empty() {
+            return Err(BrsrError::unsupported(format!(
+                "Matching the app that opened a link (rule '{}')",
+                rule.name
+            )));
+        }
+        if let RouteTarget::Browser { browser_id } = &rule.target {
+            if own_ids.iter().any(|id| id.eq_ignore_ascii_case(browser_id)) {
+                return Err(BrsrError::invalid(format!(
+                    "Rule '{}' sends links to brsr itself; use a tab instead",
+                    rule.name
+                )));
+            }
+        }
+        let invalid = |what: &str, e: regex::Error| {
+            BrsrError::invalid(format!("Rule '{}' has an invalid {}: {}", rule.name, what, e))
+        };
+        Ok(Self {
+            path: rule
+                .path
+                .as_deref()
+                .filter(|p| !p.is_empty())
+                .map(glob_regex)
+                .transpose()
+                .map_err(|e| invalid("path glob", e))?,
+            regex: rule
+                .regex
+                .as_deref()
+                .filter(|r| !r.is_empty())
+                .map(Regex::new)
+                .transpose()
+                .map_err(|e| invalid("regex", e))?,
+        })
+    }
+
+    fn matches(&self, rule: &RouteRule, url: &Url) -> bool {
+        let host = url.host_str().unwrap_or_default().to_ascii_lowercase();
+        (rule.hosts.is_empty() || rule.hosts.iter().any(|h| host_matches(&host, h)))
+            && self.path.as_ref().is_none_or(|p| p.is_match(url.path()))
+            && self.regex.as_ref().is_none_or(|r| r.is_match(url.as_str()))
+    }
+}
+
+/// Managed state: the routing rules, persisted under the app data dir.
+pub struct UrlRouter {
+    rules: Mutex<(RouterSettings, Vec<Compiled>)>,
+    path: Option<PathBuf>,
+    /// See [`own_ids`].
+    own_ids: Vec<String>,
+}
+
+fn compile(settings: &RouterSettings, own_ids: &[String]) -> Result<Vec<Compiled>, BrsrError> {
+    settings.rules.iter().map(|rule| Compiled::new(rule, own_ids)).collect()
+}
+
+/// The ids other browsers' lists know brsr by: its bundle id, and on Linux
+/// its desktop file ids.
+fn own_ids<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Vec<String> {
+    #[allow(unused_mut)]
+    let mut ids = vec![app.config().identifier.clone()];
+    #[cfg(target_os = "linux")]
+    {
+        ids.push(crate::xdg::DESKTOP_ID.to_string());
+        if let Ok(dirs) = crate::xdg::XdgDirs::from_env() {
+            let apps = dirs.applications();
+            ids.extend(crate::xdg::own_entry(&apps).map(|app| app.id.clone()));
+        }
+    }
+    ids
+}
+
+impl UrlRouter {
+    pub fn load<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Self {
+        let path = storage::app_file(app, SETTINGS_FILE)
+            .map_err(|e| eprintln!("[Rust] URL router rules will not persist: {}", e))
+            .ok();
+        let mut settings = path
+            .as_deref()
+            .map(storage::load_json::<RouterSettings>)
+            .unwrap_or_default();
+        let own_ids = own_ids(app);
+        // A hand-edited file may hold rules that don't compile; drop just those.
+        settings.rules.retain(|rule| match Compiled::new(rule, &own_ids) {
+            Ok(_) => true,
+            Err(e) => {
+                eprintln!("[Rust] Ignoring URL route: {}", e);
+                false
+            }
+        });
+        let compiled = compile(&settings, &own_ids).unwrap_or_default();
+        Self {
+            rules: Mutex::new((settings, compiled)),
+            path,
+            own_ids,
+        }
+    }
+
+    pub fn settings(&self) -> RouterSettings {
+        self.rules.lock().unwrap().0.clone()
+    }
+
+    fn replace(&self, next: RouterSettings) -> Result<(), BrsrError> {
+        let compiled = compile(&next, &self.own_ids)?;
+        if let Some(path) = &self.path {
+            storage::save_json(path, &next)?;
+        }
+        *self.rules.lock().unwrap() = (next, compiled);
+        Ok(())
+    }
+
+    /// Where `url` goes.
+    pub fn decide(&self, url: &str) -> Result<RouteDecision, BrsrError> {
+        let parsed = Url::parse(url.trim()).map_err(|e| BrsrError::invalid_url(url, e))?;
+        let rules = self.rules.lock().unwrap();
+        let (settings, compiled) = &*rules;
+        let hit = settings.enabled.then(|| {
+            settings
+                .rules
+                .iter()
+                .zip(compiled)
+                .enumerate()
+                .find(|(_, (rule, c))| rule.enabled && c.matches(rule, &parsed))
+        });
+        Ok(match hit.flatten() {
+            Some((index, (rule, _))) => RouteDecision {
+                url: parsed.to_string(),
+                rule_index: Some(index as u32),
+                rule_name: Some(rule.name.clone()),
+                target: rule.target.clone(),
+            },
+            None => RouteDecision {
+                url: parsed.to_string(),
+                rule_index: None,
+                rule_name: None,
+                target: RouteTarget::Tab,
+            },
+        })
+    }
+}
+
+/// Open `url` in the browser with id `browser_id`.
+fn open_in_browser(browser_id: &str, url: &str) -> Result<(), BrsrError> {
+    #[cfg(target_os = "macos")]
+    {
+        std::process::Command::new("open")
+            .args(["-b", browser_id, url])
+            .spawn()
+            .map(|_| ())
+            .map_err(|e| BrsrError::platform(format!("Failed to open {} in {}: {}", url, browser_id, e)))
+    }
+    #[cfg(target_os = "linux")]
+    {
+        let apps = crate::xdg::XdgDirs::from_env()?.applications();
+        let command = apps
+            .iter()
+            .find(|app| app.id == browser_id)
+            .and_then(|app| app.command_for(url))
+            .ok_or_else(|| BrsrError::not_found("browser", browser_id))?;
+        std::process::Command::new(&command[0])
+            .args(&command[1..])
+            .spawn()
+            .map(|_| ())
+            .map_err(|e| BrsrError::platform(format!("Failed to open {} in {}: {}", url, browser_id, e)))
+    }
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+    {
+        let _ = (browser_id, url);
+        Err(BrsrError::unsupported("Opening links in another browser"))
+    }
+}
+
+/// Carry out `decision` if it sends the link away from this window. Returns
+/// the decision's target, or a tab here if the link could not be handed off.
+fn dispatch<R: tauri::Runtime>(app: &tauri::AppHandle<R>, decision: &RouteDecision) -> RouteTarget {
+    let result = match &decision.target {
+        RouteTarget::Tab | RouteTarget::PrivateTab => return decision.target.clone(),
+        RouteTarget::Profile { profile_id } => {
+            let manager = app.state::<ProfileManager>();
+            if profile_id == manager.current() {
+                return RouteTarget::Tab;
+            }
+            manager.open_urls(profile_id, std::slice::from_ref(&decision.url))
+        }
+        RouteTarget::Browser { browser_id } => open_in_browser(browser_id, &decision.url),
+    };
+    match result {
+        Ok(()) => decision.target.clone(),
+        Err(e) => {
+            eprintln!("[Rust] Could not route {}: {}; opening it here", decision.url, e);
+            RouteTarget::Tab
+        }
+    }
+}
+
+/// Where `url`, opened with brsr from elsewhere, goes. Links for another
+/// profile or browser are sent there; the decision says whether that worked.
+/// If it didn't, or the URL doesn't parse, it comes back as a tab here so the
+/// link isn't lost.
+pub fn route<R: tauri::Runtime>(app: &tauri::AppHandle<R>, url: &str) -> RouteDecision {
+    let mut decision = match app.state::<UrlRouter>().decide(url) {
+        Ok(decision) => decision,
+        Err(e) => {
+            // Let the tab report it, as it would without the router.
+            eprintln!("[Rust] Not routing '{}': {}", url, e);
+            return RouteDecision {
+                url: url.to_string(),
+                rule_index: None,
+                rule_name: None,
+                target: RouteTarget::Tab,
+            };
+        }
+    };
+    if let Some(name) = &decision.rule_name {
+        println!("[Rust] Route '{}' matched {}", name, decision.url);
+    }
+    decision.target = dispatch(app, &decision);
+    decision
+}
+
+#[tauri::command]
+pub fn get_url_router(router: tauri::State<'_, UrlRouter>) -> Result<RouterSettings, BrsrError> {
+    Ok(router.settings())
+}
+
+/// Replace the rules. Fails without changing anything if a pattern doesn't
+/// compile, a rule names `sourceApps` or a rule sends links to brsr itself.
+#[tauri::command]
+pub fn set_url_router(
+    router: tauri::State<'_, UrlRouter>,
+    settings: RouterSettings,
+) -> Result<RouterSettings, BrsrError> {
+    router.replace(settings.clone())?;
+    Ok(settings)
+}
+
+/// Which rule `url` would hit, without opening it.
+#[tauri::command]
+pub fn test_route(
+    router: tauri::State<'_, UrlRouter>,
+    url: String,
+) -> Result<RouteDecision, BrsrError> {
+    router.decide(&url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn router(rules: Vec<RouteRule>) -> UrlRouter {
+        let own_ids = vec!["com.brsr.browser".to_string(), "brsr.desktop".to_string()];
+        let settings = RouterSettings { enabled: true, rules };
+        let compiled = compile(&settings, &own_ids).unwrap();
+        UrlRouter {
+            rules: Mutex::new((settings, compiled)),
+            path: None,
+            own_ids,
+        }
+    }
+
+    fn rule(name: &str, target: RouteTarget) -> RouteRule {
+        RouteRule {
+            name: name.to_string(),
+            enabled: true,
+            hosts: Vec::new(),
+            path: None,
+            regex: None,
+            source_apps: Vec::new(),
+            target,
+        }
+    }
+
+    fn glob(glob: &str, path: &str) -> bool {
+        glob_regex(glob).unwrap().is_match(path)
+    }
+
+    #[test]
+    fn double_star_slash_matches_zero_or_more_segments() {
+        for path in ["/x", "/a/x", "/a/b/x"] {
+            assert!(glob("/**/x", path), "{}", path);
+        }
+        assert!(!glob("/**/x", "/ax"));
+        assert!(!glob("/**/x", "/a/x/y"));
+        assert!(glob("/docs/**", "/docs/"));
+        assert!(glob("/docs/**", "/docs/a/b"));
+        assert!(!glob("/docs/**", "/doc"));
+    }
+
+    #[test]
+    fn single_star_and_question_mark_stay_in_a_segment() {
+        assert!(glob("/docs/*", "/docs/a"));
+        assert!(!glob("/docs/*", "/docs/a/b"));
+        assert!(glob("/v?/api", "/v2/api"));
+        assert!(!glob("/v?/api", "/v/api"));
+        assert!(!glob("/v?/api", "/v//api"));
+        assert!(glob("/a.b+(c)", "/a.b+(c)"));
+        assert!(!glob("/a.b", "/aXb"));
+    }
+
+    #[test]
+    fn first_enabled_matching_rule_wins() {
+        let mut off = rule("off", RouteTarget::PrivateTab);
+        off.enabled = false;
+        let mut work = rule("work", RouteTarget::Profile { profile_id: "work".into() });
+        work.hosts = vec!["example.com".into()];
+        let mut docs = rule("docs", RouteTarget::Browser { browser_id: "other".into() });
+        docs.hosts = vec!["docs.example.com".into()];
+        let catch_all = rule("rest", RouteTarget::PrivateTab);
+        let router = router(vec![off, work, docs, catch_all]);
+
+        let hit = router.decide("https://docs.example.com/a").unwrap();
+        assert_eq!((hit.rule_index, hit.rule_name.as_deref()), (Some(1), Some("work")));
+        assert_eq!(hit.target, RouteTarget::Profile { profile_id: "work".into() });
+        let hit = router.decide("https://other.test/").unwrap();
+        assert_eq!((hit.rule_index, hit.target), (Some(3), RouteTarget::PrivateTab));
+    }
+
+    #[test]
+    fn every_condition_has_to_hold() {
+        let mut both = rule("both", RouteTarget::PrivateTab);
+        both.hosts = vec!["example.com".into()];
+        both.path = Some("/private/**".into());
+        both.regex = Some("[?&]secret=".into());
+        let router = router(vec![both]);
+        assert!(router.decide("https://www.example.com/private/x?secret=1").unwrap().rule_index.is_some());
+        for url in [
+            "https://example.org/private/x?secret=1",
+            "https://example.com/public/x?secret=1",
+            "https://example.com/private/x?open=1",
+        ] {
+            let decision = router.decide(url).unwrap();
+            assert_eq!((decision.rule_index, decision.target), (None, RouteTarget::Tab), "{}", url);
+        }
+    }
+
+    #[test]
+    fn disabled_router_opens_everything_here() {
+        let router = router(vec![rule("all", RouteTarget::PrivateTab)]);
+        router.rules.lock().unwrap().0.enabled = false;
+        assert_eq!(router.decide("https://a.test/").unwrap().target, RouteTarget::Tab);
+    }
+
+    #[test]
+    fn source_apps_are_refused() {
+        let mut from_mail = rule("mail", RouteTarget::PrivateTab);
+        from_mail.source_apps = vec!["com.apple.mail".into()];
+        let error = router(Vec::new()).replace(RouterSettings { enabled: true, rules: vec![from_mail] });
+        assert!(matches!(error, Err(BrsrError::Unsupported { .. })));
+    }
+
+    #[test]
+    fn rules_cannot_send_links_back_to_brsr() {
+        let router = router(Vec::new());
+        for id in ["brsr.desktop", "com.BRSR.browser"] {
+            let own = rule("loop", RouteTarget::Browser { browser_id: id.into() });
+            let error = router.replace(RouterSettings { enabled: true, rules: vec![own] });
+            assert!(matches!(error, Err(BrsrError::InvalidInput { .. })), "{}", id);
+        }
+        assert!(router.settings().rules.is_empty());
+        let other = rule("other", RouteTarget::Browser { browser_id: "firefox.desktop".into() });
+        assert!(router.replace(RouterSettings { enabled: true, rules: vec![other] }).is_ok());
+    }
+}
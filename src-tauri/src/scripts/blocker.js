@@ -0,0 +1,38 @@
+// Content blocker: WebKit applies the rules itself (see
+// src-tauri/src/blocker/) but doesn't say what it blocked. Report loads that
+// failed; Rust counts the ones its rules block. Not installed on WebView2,
+// which counts blocked requests as they happen.
+
+const report = (raw, resourceType) => {
+  let url;
+  try { url = new URL(raw, location.href); } catch (_) { return; }
+  if (/^(https?|wss?):$/.test(url.protocol)) send({ kind: 'blocked', url: url.href, resourceType });
+};
+
+// Elements: their load errors don't bubble, so listen while capturing.
+const ELEMENT_TYPES = { SCRIPT: 'script', IMG: 'image', LINK: 'stylesheet', VIDEO: 'media', AUDIO: 'media', SOURCE: 'media' };
+window.addEventListener('error', (event) => {
+  const el = event.target;
+  const type = el && ELEMENT_TYPES[el.tagName];
+  if (!type) return;
+  if (type === 'stylesheet' && !/\bstylesheet\b/i.test(el.rel)) return;
+  report(el.currentSrc || el.src || el.href, type);
+}, true);
+
+// Requests made from script
+const origFetch = window.fetch;
+window.fetch = function (input, init) {
+  const url = (input instanceof Request) ? input.url : String(input);
+  return origFetch.call(this, input, init).catch((error) => {
+    report(url, 'xmlhttprequest');
+    throw error;
+  });
+};
+
+const xhrUrls = new WeakMap();
+const xhrOpen = XMLHttpRequest.prototype.open;
+XMLHttpRequest.prototype.open = function (method, url, ...rest) {
+  if (!xhrUrls.has(this)) this.addEventListener('error', () => report(xhrUrls.get(this), 'xmlhttprequest'));
+  xhrUrls.set(this, String(url));
+  return xhrOpen.call(this, method, url, ...rest);
+};
@@ -0,0 +1,47 @@
+// Prelude shared by every injected script. The assembler in mod.rs declares
+// `PARAMS` (a JSON literal) and `sign` (sign.js) right before this file.
+
+// The only way a tab talks to Rust: one command, checked against the sending
+// tab and its origin, and signed with `sign` (see
+// src-tauri/src/telemetry/channel.rs). `invoke` and what `send` uses are
+// captured now, before page scripts get a chance to replace them.
+const internals = window.__TAURI_INTERNALS__;
+const invoke = internals && internals.invoke && internals.invoke.bind(internals);
+const { command } = PARAMS.channel;
+const assign = Object.assign;
+const stringify = JSON.stringify;
+const objectPrototype = Object.prototype;
+const arrayPrototype = Array.prototype;
+
+// Messages are numbered per document; Rust drops repeats, and messages from a
+// document once a newer one of the tab has been heard from.
+const documentId = Array.from(crypto.getRandomValues(new Uint8Array(16)), (b) => b.toString(16).padStart(2, '0')).join('');
+let sequence = 0;
+
+// Every telemetry event carries the schema version Rust validates against.
+// See src-tauri/src/telemetry/event.rs for the shapes.
+const send = (payload) => {
+  if (!invoke) return;
+  // A toJSON the page put on a prototype would decide the text we sign.
+  if ('toJSON' in objectPrototype || 'toJSON' in arrayPrototype) return;
+  const message = assign({ v: PARAMS.schemaVersion, label: PARAMS.label, ts: Date.now() }, payload);
+  const text = stringify(message);
+  const seq = sequence++;
+  const mac = sign(`${documentId}.${seq}.${text}`);
+  invoke(command, { origin: location.origin, document: documentId, seq, payload: text, mac }).catch(() => {});
+};
+
+// Whether a probe runs on this document: the tab's override first, then the
+// most specific site rule for this host or a parent domain, then the default.
+const probeEnabled = (name) => {
+  const { tab, sites, defaults } = PARAMS.probes;
+  if (Object.hasOwn(tab, name)) return tab[name];
+  let host = location.hostname.toLowerCase();
+  while (host) {
+    const site = sites[host];
+    if (site && Object.hasOwn(site, name)) return site[name];
+    const dot = host.indexOf('.');
+    host = dot === -1 ? '' : host.slice(dot + 1);
+  }
+  return defaults[name] !== false;
+};
@@ -0,0 +1,20 @@
+// Forms submitted by POST. A download that answers one can't be fetched a
+// second time, so Rust leaves it to the webview (see
+// src-tauri/src/downloads/mod.rs). Heard in the capture phase, before the
+// page gets a chance to stop the event.
+const reportPost = (form, submitter) => {
+  const override = submitter && submitter.hasAttribute('formmethod');
+  const method = override ? submitter.getAttribute('formmethod') : form.getAttribute('method');
+  if (String(method).toLowerCase() !== 'post') return;
+  const action = submitter && submitter.hasAttribute('formaction') ? submitter.formAction : form.action;
+  send({ kind: 'form-post', action });
+};
+window.addEventListener('submit', (e) => {
+  if (e.target instanceof HTMLFormElement) reportPost(e.target, e.submitter);
+}, true);
+// `form.submit()` skips the submit event.
+const origSubmit = HTMLFormElement.prototype.submit;
+HTMLFormElement.prototype.submit = function () {
+  reportPost(this, null);
+  return origSubmit.call(this);
+};
@@ -0,0 +1,19 @@
+// History API signals so Rust can track same-document navigations.
+for (const method of ['pushState', 'replaceState']) {
+  const orig = history[method];
+  history[method] = function(...args) {
+    const ret = orig.apply(this, args);
+    send({ kind: 'history', action: method === 'pushState' ? 'push' : 'replace', url: location.href });
+    return ret;
+  };
+}
+window.addEventListener('popstate', () => send({ kind: 'history', action: 'pop', url: location.href }));
+// Traversals the page starts itself, so Rust doesn't mistake the next load for a new one.
+for (const [method, step] of [['back', -1], ['forward', 1], ['go', null]]) {
+  const orig = history[method];
+  history[method] = function(...args) {
+    const delta = step ?? Math.trunc(Number(args[0]) || 0);
+    if (delta !== 0) send({ kind: 'history', action: 'traverse', url: location.href, delta });
+    return orig.apply(this, args);
+  };
+}
@@ -0,0 +1,14 @@
+// DOMContentLoaded, one step of the tab's load lifecycle (see src-tauri/src/page_load.rs).
+const reportReady = () => {
+  const [timing] = performance.getEntriesByType('navigation');
+  send({
+    kind: 'dom-ready',
+    url: location.href,
+    domContentLoaded: timing ? timing.domContentLoadedEventStart : performance.now(),
+  });
+};
+if (document.readyState === 'loading') {
+  document.addEventListener('DOMContentLoaded', reportReady, { once: true });
+} else {
+  reportReady();
+}
@@ -0,0 +1,340 @@
+//! Assembly of the script injected into every tab.
+//!
+//! The JavaScript lives in the `.js` files next to this module and is bundled
+//! with `include_str!`. A tab's script is the bridge prelude and its message
+//! signing (`sign.js`, keyed per webview; see `telemetry::channel`), the core
+//! pieces Rust depends on (history, scroll, DOMContentLoaded and form POST
+//! signals), and whichever telemetry probes can run for that tab. Parameters
+//! reach the script as a single JSON literal, never by splicing strings into
+//! code.
+//!
+//! Site permissions (see `crate::permissions`) always ride along in `PARAMS`
+//! for the piece that enforces them. The same piece also goes, on its own, in
+//! a second script that runs in every subframe ([`assemble_frames`]).
+//!
+//! On WebKit the script also reports failed loads for the content blocker to
+//! count, since the engine applies its rules without saying what it blocked
+//! (see `crate::blocker`).
+//!
+//! Probes are toggled by default, per site (a host and its subdomains) and per
+//! tab. Site rules are resolved in the page against `location.hostname`, so a
+//! tab moving between sites picks up the right set on each document. Changing a
+//! tab's or a site's probes rebuilds the affected webviews, since an
+//! initialization script can't be swapped on a live webview.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+
+use crate::error::BrsrError;
+use crate::permissions::{PagePermissions, SitePermissionStore};
+use crate::storage;
+use crate::tabs::TabRegistry;
+use crate::telemetry;
+
+const SETTINGS_FILE: &str = "probe-settings.json";
+
+const SIGN: &str = include_str!("sign.js");
+const BRIDGE: &str = include_str!("bridge.js");
+const BLOCKER: &str = include_str!("blocker.js");
+/// Always installed: session history, session restore, site permissions,
+/// load progress and downloads depend on them.
+const CORE: &[(&str, &str)] = &[
+    ("history", include_str!("history.js")),
+    ("scroll", include_str!("scroll.js")),
+    ("permissions", include_str!("permissions.js")),
+    ("load", include_str!("load.js")),
+    ("forms", include_str!("forms.js")),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Probe {
+    PageInfo,
+    Perf,
+    Fetch,
+    Xhr,
+    Console,
+    Errors,
+    Selection,
+    Heartbeat,
+}
+
+impl Probe {
+    pub const ALL: [Probe; 8] = [
+        Probe::PageInfo,
+        Probe::Perf,
+        Probe::Fetch,
+        Probe::Xhr,
+        Probe::Console,
+        Probe::Errors,
+        Probe::Selection,
+        Probe::Heartbeat,
+    ];
+
+    /// Name used in settings and by `probeEnabled` in the bridge.
+    pub fn name(self) -> &'static str {
+        match self {
+            Probe::PageInfo => "page-info",
+            Probe::Perf => "perf",
+            Probe::Fetch => "fetch",
+            Probe::Xhr => "xhr",
+            Probe::Console => "console",
+            Probe::Errors => "errors",
+            Probe::Selection => "selection",
+            Probe::Heartbeat => "heartbeat",
+        }
+    }
+
+    fn source(self) -> &'static str {
+        match self {
+            Probe::PageInfo => include_str!("probes/page-info.js"),
+            Probe::Perf => include_str!("probes/perf.js"),
+            Probe::Fetch => include_str!("probes/fetch.js"),
+            Probe::Xhr => include_str!("probes/xhr.js"),
+            Probe::Console => include_str!("probes/console.js"),
+            Probe::Errors => include_str!("probes/errors.js"),
+            Probe::Selection => include_str!("probes/selection.js"),
+            Probe::Heartbeat => include_str!("probes/heartbeat.js"),
+        }
+    }
+}
+
+/// Probe → on/off. Probes without an entry fall through to the next level.
+pub type ProbeToggles = BTreeMap<Probe, bool>;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProbeSettings {
+    /// Probes missing here are on.
+    #[serde(default)]
+    pub defaults: ProbeToggles,
+    /// Keyed by host; a rule also covers that host's subdomains.
+    #[serde(default)]
+    pub sites: BTreeMap<String, ProbeToggles>,
+}
+
+impl ProbeSettings {
+    /// Whether `probe` could run on some document of a tab with `tab` overrides.
+    /// Probes that can't are left out of the script entirely.
+    fn may_run(&self, probe: Probe, tab: &ProbeToggles) -> bool {
+        match tab.get(&probe) {
+            Some(enabled) => *enabled,
+            None => {
+                self.defaults.get(&probe) != Some(&false)
+                    || self.sites.values().any(|site| site.get(&probe) == Some(&true))
+            }
+        }
+    }
+}
+
+/// Lowercased host without a trailing dot; rejects URLs and paths.
+pub(crate) fn normalize_host(host: &str) -> Result<String, BrsrError> {
+    let host = host.trim().trim_end_matches('.').to_ascii_lowercase();
+    if host.is_empty() || host.contains(['/', ':', ' ', '*']) {
+        return Err(BrsrError::invalid(format!("'{}' is not a host name", host)));
+    }
+    Ok(host)
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ScriptParams<'a> {
+    label: &'a str,
+    channel: ChannelParams<'a>,
+    schema_version: u32,
+    probes: ProbeParams<'a>,
+    permissions: &'a PagePermissions,
+}
+
+/// How the bridge reaches Rust; see `telemetry::channel`.
+#[derive(Serialize)]
+struct ChannelParams<'a> {
+    command: &'a str,
+    key: &'a str,
+}
+
+#[derive(Serialize)]
+struct ProbeParams<'a> {
+    defaults: &'a ProbeToggles,
+    sites: &'a BTreeMap<String, ProbeToggles>,
+    tab: &'a ProbeToggles,
+}
+
+/// Build the initialization script for the tab `label`. `key` is the
+/// webview's channel key.
+pub fn assemble(
+    label: &str,
+    key: &str,
+    settings: &ProbeSettings,
+    tab: &ProbeToggles,
+    permissions: &PagePermissions,
+) -> String {
+    let params = ScriptParams {
+        label,
+        channel: ChannelParams {
+            command: telemetry::channel::COMMAND,
+            key,
+        },
+        schema_version: telemetry::SCHEMA_VERSION,
+        probes: ProbeParams {
+            defaults: &settings.defaults,
+            sites: &settings.sites,
+            tab,
+        },
+        permissions,
+    };
+    // serde_json output is a valid JavaScript expression.
+    let params = serde_json::to_string(&params).expect("script params serialize");
+
+    let mut script = String::from("(() => {\n");
+    script.push_str(&format!("const PARAMS = {};\n", params));
+    script.push_str(SIGN);
+    script.push_str(BRIDGE);
+    // Each piece runs in its own scope so one failing probe can't take out the rest.
+    for (name, source) in CORE {
+        script.push_str(&format!("\n// core: {}\ntry {{ (() => {{\n{}\n}})(); }} catch (_) {{}}\n", name, source));
+    }
+    // WebView2 counts blocked requests itself.
+    if !cfg!(windows) {
+        script.push_str(&format!("\n// content blocker\ntry {{ (() => {{\n{}\n}})(); }} catch (_) {{}}\n", BLOCKER));
+    }
+    for probe in Probe::ALL {
+        if !settings.may_run(probe, tab) {
+            continue;
+        }
+        script.push_str(&format!(
+            "\n// probe: {name}\nif (probeEnabled({name:?})) {{ try {{ (() => {{\n{}\n}})(); }} catch (_) {{}} }}\n",
+            probe.source(),
+            name = probe.name(),
+        ));
+    }
+    script.push_str("\nsend({ kind: 'init' });\n})();\n");
+    script
+}
+
+/// Build the script every subframe of a tab runs: the permission wrappers,
+/// without the bridge.
+pub fn assemble_frames(permissions: &PagePermissions) -> String {
+    let params = serde_json::json!({ "permissions": permissions });
+    let (_, source) = CORE.iter().find(|(name, _)| *name == "permissions").expect("permissions piece");
+    format!("(() => {{\nconst PARAMS = {};\ntry {{ (() => {{\n{}\n}})(); }} catch (_) {{}}\n}})();\n", params, source)
+}
+
+/// Managed state: probe settings, persisted under the app data dir.
+pub struct ScriptSettings {
+    settings: Mutex<ProbeSettings>,
+    path: Option<PathBuf>,
+}
+
+impl ScriptSettings {
+    pub fn load<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Self {
+        let path = storage::data_file(app, SETTINGS_FILE)
+            .map_err(|e| eprintln!("[Rust] Probe settings will not persist: {}", e))
+            .ok();
+        let settings = path
+            .as_deref()
+            .map(storage::load_json::<ProbeSettings>)
+            .unwrap_or_default();
+        Self {
+            settings: Mutex::new(settings),
+            path,
+        }
+    }
+
+    pub fn settings(&self) -> ProbeSettings {
+        self.settings.lock().unwrap().clone()
+    }
+
+    fn update(&self, f: impl FnOnce(&mut ProbeSettings)) -> Result<ProbeSettings, BrsrError> {
+        let mut settings = self.settings.lock().unwrap();
+        let mut next = settings.clone();
+        f(&mut next);
+        if let Some(path) = &self.path {
+            storage::save_json(path, &next)?;
+        }
+        *settings = next.clone();
+        Ok(next)
+    }
+
+    /// The script for the tab `label`, using its per-tab overrides. Issues a
+    /// new channel key, so call it once per webview.
+    pub fn script_for<R: tauri::Runtime>(&self, app: &tauri::AppHandle<R>, label: &str) -> String {
+        let tab = app
+            .state::<TabRegistry>()
+            .get(label)
+            .map(|tab| tab.probes)
+            .unwrap_or_default();
+        let key = app.state::<telemetry::channel::Channel>().issue(label);
+        let permissions = app.state::<SitePermissionStore>().page_params();
+        assemble(label, &key, &self.settings.lock().unwrap(), &tab, &permissions)
+    }
+
+    /// The subframe script for a new webview; see [`assemble_frames`].
+    pub fn frame_script_for<R: tauri::Runtime>(&self, app: &tauri::AppHandle<R>) -> String {
+        assemble_frames(&app.state::<SitePermissionStore>().frame_params())
+    }
+}
+
+/// Does `url` belong to `host` or one of its subdomains?
+fn on_site(url: &str, host: &str) -> bool {
+    url::Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(|h| h.to_ascii_lowercase()))
+        .is_some_and(|h| h == host || h.ends_with(&format!(".{}", host)))
+}
+
+#[tauri::command]
+pub fn get_probe_settings(settings: tauri::State<'_, ScriptSettings>) -> Result<ProbeSettings, BrsrError> {
+    Ok(settings.settings())
+}
+
+/// Replace the default toggles. Applies to tabs opened afterwards.
+#[tauri::command]
+pub fn set_probe_defaults(
+    settings: tauri::State<'_, ScriptSettings>,
+    probes: ProbeToggles,
+) -> Result<ProbeSettings, BrsrError> {
+    settings.update(|s| s.defaults = probes)
+}
+
+/// Set (or with `None`, clear) the toggles for a site and rebuild its open tabs.
+#[tauri::command]
+pub async fn set_site_probes(
+    window: tauri::Window,
+    settings: tauri::State<'_, ScriptSettings>,
+    registry: tauri::State<'_, TabRegistry>,
+    host: String,
+    probes: Option<ProbeToggles>,
+) -> Result<ProbeSettings, BrsrError> {
+    let host = normalize_host(&host)?;
+    let updated = settings.update(|s| match probes {
+        Some(probes) if !probes.is_empty() => {
+            s.sites.insert(host.clone(), probes);
+        }
+        _ => {
+            s.sites.remove(&host);
+        }
+    })?;
+    for tab in registry.list().iter().filter(|tab| on_site(&tab.url, &host)) {
+        crate::rebuild_tab_webview(&window, &tab.label)?;
+    }
+    Ok(updated)
+}
+
+/// Override probes for one tab and rebuild its webview so the change applies now.
+#[tauri::command]
+pub async fn set_tab_probes(
+    window: tauri::Window,
+    registry: tauri::State<'_, TabRegistry>,
+    label: String,
+    probes: ProbeToggles,
+) -> Result<(), BrsrError> {
+    if !registry.update(&label, |tab| tab.probes = probes) {
+        return Err(BrsrError::not_found("tab", &label));
+    }
+    crate::rebuild_tab_webview(&window, &label)
+}
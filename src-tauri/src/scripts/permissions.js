@@ -0,0 +1,203 @@
+// Site permissions: camera, microphone, geolocation, notifications, clipboard
+// read and popups. `PARAMS.permissions` holds the stored decisions by origin
+// and the name of the hook Rust answers through; see
+// src-tauri/src/permissions.rs.
+//
+// The tab's script runs this in the top frame, with a hook. The frame script
+// runs it in every other frame without one: frames answer to the tab's
+// origin and never ask, so only "allow" lets an API through there. Same-origin
+// frames a page reaches through `contentWindow` before their own script runs
+// are patched on access. None of this stops a page that is determined to get
+// around it; where the engine lets us, Rust checks the same decisions again.
+
+const { hook, sites } = PARAMS.permissions;
+const isTop = window === window.top;
+if (isTop !== Boolean(hook)) return;
+const ancestors = location.ancestorOrigins;
+const origin = isTop ? location.origin : (ancestors && ancestors[ancestors.length - 1]) || 'null';
+const stateOf = (permission) => (sites[origin] && sites[origin][permission]) || 'ask';
+
+const pending = new Map();
+let nextRequest = 1;
+if (isTop) {
+  Object.defineProperty(window, hook, {
+    enumerable: false,
+    configurable: false,
+    writable: false,
+    value: (msg) => {
+      if (msg.type === 'reply') {
+        const resolve = pending.get(msg.id);
+        pending.delete(msg.id);
+        if (resolve) resolve(msg.granted);
+      } else if (msg.type === 'update' && msg.origin === origin) {
+        sites[origin] = msg.permissions;
+      }
+    },
+  });
+}
+
+// Whether `permission` may be used now, asking through Rust when it's "ask".
+const request = (permission) => {
+  const state = stateOf(permission);
+  if (state !== 'ask' || !isTop) return Promise.resolve(state === 'allow');
+  const id = nextRequest++;
+  return new Promise((resolve) => {
+    pending.set(id, resolve);
+    send({ kind: 'permission-request', id, permission, origin });
+  });
+};
+const refused = (what) => new DOMException(`Permission to use ${what} was denied`, 'NotAllowedError');
+
+const guarded = new WeakSet();
+// Put every permission behind `request` in `win`, this window or a same-origin frame.
+const guard = (win) => {
+  if (guarded.has(win)) return;
+  guarded.add(win);
+  const nav = win.navigator;
+
+  // Camera and microphone
+  const media = nav.mediaDevices;
+  if (media && media.getUserMedia) {
+    const origGetUserMedia = media.getUserMedia.bind(media);
+    media.getUserMedia = async (constraints) => {
+      const wanted = [];
+      if (constraints && constraints.video) wanted.push('camera');
+      if (constraints && constraints.audio) wanted.push('microphone');
+      for (const permission of wanted) {
+        if (!(await request(permission))) throw refused(permission);
+      }
+      return origGetUserMedia(constraints);
+    };
+  }
+
+  // Geolocation
+  const geo = nav.geolocation;
+  if (geo) {
+    const origGetCurrent = geo.getCurrentPosition.bind(geo);
+    const origWatch = geo.watchPosition.bind(geo);
+    const origClear = geo.clearWatch.bind(geo);
+    const geoDenied = (error) => {
+      if (typeof error === 'function') {
+        error({
+          code: 1, message: 'User denied Geolocation', PERMISSION_DENIED: 1, POSITION_UNAVAILABLE: 2, TIMEOUT: 3,
+        });
+      }
+    };
+    geo.getCurrentPosition = (success, error, options) => {
+      request('geolocation').then((ok) => (ok ? origGetCurrent(success, error, options) : geoDenied(error)));
+    };
+    // watchPosition returns its id synchronously, before the answer is known.
+    const watches = new Map();
+    let nextWatch = 1;
+    geo.watchPosition = (success, error, options) => {
+      const id = nextWatch++;
+      watches.set(id, null);
+      request('geolocation').then((ok) => {
+        if (!watches.has(id)) return;
+        if (ok) watches.set(id, origWatch(success, error, options));
+        else geoDenied(error);
+      });
+      return id;
+    };
+    geo.clearWatch = (id) => {
+      const real = watches.get(id);
+      watches.delete(id);
+      if (real != null) origClear(real);
+    };
+  }
+
+  // Notifications
+  if (win.Notification) {
+    const OrigNotification = win.Notification;
+    const origRequest = OrigNotification.requestPermission.bind(OrigNotification);
+    const permissionDesc = Object.getOwnPropertyDescriptor(OrigNotification, 'permission');
+    const Wrapped = new Proxy(OrigNotification, {
+      construct(target, args, newTarget) {
+        if (stateOf('notifications') === 'deny') {
+          // What the platform does without permission: no notification, an error event.
+          const dummy = new EventTarget();
+          setTimeout(() => dummy.dispatchEvent(new Event('error')), 0);
+          return dummy;
+        }
+        return Reflect.construct(target, args, newTarget);
+      },
+    });
+    Object.defineProperty(Wrapped, 'permission', {
+      configurable: true,
+      get() {
+        const state = stateOf('notifications');
+        if (state === 'deny') return 'denied';
+        if (state === 'ask') return 'default';
+        return permissionDesc && permissionDesc.get ? permissionDesc.get.call(OrigNotification) : 'granted';
+      },
+    });
+    Wrapped.requestPermission = (callback) =>
+      request('notifications')
+        .then((ok) => (ok ? origRequest() : 'denied'))
+        .then((result) => {
+          if (typeof callback === 'function') callback(result);
+          return result;
+        });
+    win.Notification = Wrapped;
+  }
+
+  // Clipboard read
+  const clipboard = nav.clipboard;
+  if (clipboard) {
+    for (const name of ['read', 'readText']) {
+      if (typeof clipboard[name] !== 'function') continue;
+      const orig = clipboard[name].bind(clipboard);
+      clipboard[name] = async (...args) => {
+        if (!(await request('clipboard-read'))) throw refused('the clipboard');
+        return orig(...args);
+      };
+    }
+  }
+
+  // Popups: window.open has to answer synchronously, so "ask" blocks this call
+  // and an answer of "allow" applies to later ones on this document.
+  const origOpen = win.open;
+  let popupsGranted = false;
+  win.open = function (...args) {
+    const state = stateOf('popups');
+    if (state === 'allow' || (state === 'ask' && popupsGranted)) return origOpen.apply(this, args);
+    if (state === 'ask' && isTop) request('popups').then((ok) => { popupsGranted = ok; });
+    return null;
+  };
+
+  // Permissions API: report denials the page would otherwise not see.
+  const QUERY_NAMES = new Set(['camera', 'microphone', 'geolocation', 'notifications', 'clipboard-read']);
+  if (nav.permissions && nav.permissions.query) {
+    const origQuery = nav.permissions.query.bind(nav.permissions);
+    nav.permissions.query = (descriptor) => {
+      if (descriptor && QUERY_NAMES.has(descriptor.name)) {
+        const state = stateOf(descriptor.name);
+        if (state === 'deny') return Promise.resolve({ name: descriptor.name, state: 'denied', onchange: null });
+        if (state === 'ask') return Promise.resolve({ name: descriptor.name, state: 'prompt', onchange: null });
+      }
+      return origQuery(descriptor);
+    };
+  }
+
+  // Same-origin frames: patch them the first time the page reaches into one.
+  for (const Ctor of [win.HTMLIFrameElement, win.HTMLFrameElement, win.HTMLObjectElement]) {
+    if (!Ctor) continue;
+    for (const prop of ['contentWindow', 'contentDocument']) {
+      const desc = Object.getOwnPropertyDescriptor(Ctor.prototype, prop);
+      if (!desc || !desc.get) continue;
+      Object.defineProperty(Ctor.prototype, prop, {
+        ...desc,
+        get() {
+          const value = desc.get.call(this);
+          const child = prop === 'contentWindow' ? value : value && value.defaultView;
+          try {
+            // Throws for cross-origin frames, which run the frame script themselves.
+            if (child && child.document) guard(child);
+          } catch (_) {}
+          return value;
+        },
+      });
+    }
+  }
+};
+guard(window);
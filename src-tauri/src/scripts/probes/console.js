@@ -0,0 +1,11 @@
+// Mirror console output while still calling through to the real console.
+for (const level of ['log', 'info', 'warn', 'error']) {
+  const orig = console[level];
+  console[level] = function(...args) {
+    try {
+      const message = args.map(a => { try { return typeof a === 'string' ? a : JSON.stringify(a); } catch { return String(a); } }).join(' ');
+      send({ kind: 'console', level, message });
+    } catch (_) {}
+    return orig.apply(this, args);
+  };
+}
@@ -0,0 +1,3 @@
+// Uncaught errors and unhandled promise rejections.
+window.addEventListener('error', (e) => send({ kind: 'error', message: e && e.message || 'Error', source: e && e.filename || null, lineno: e && e.lineno || null, colno: e && e.colno || null }), { capture: true });
+window.addEventListener('unhandledrejection', (e) => send({ kind: 'unhandledrejection', reason: String(e && e.reason) }), { capture: true });
@@ -0,0 +1,26 @@
+// Wrap fetch to report URL, status, timing and a short body preview.
+const origFetch = window.fetch;
+window.fetch = async (...args) => {
+  const started = performance.now();
+  const input = args[0];
+  const info = args[1] || {};
+  const url = String((input instanceof Request) ? input.url : input);
+  const method = (info && info.method) || (input instanceof Request && input.method) || 'GET';
+  try {
+    const res = await origFetch(...args);
+    const ended = performance.now();
+    let preview = null;
+    const ct = res.headers.get('content-type') || '';
+    if (ct.includes('application/json')) {
+      try { preview = JSON.stringify(await res.clone().json()).slice(0, 2000); } catch (_) {}
+    } else if (ct.startsWith('text/')) {
+      try { preview = (await res.clone().text()).slice(0, 2000); } catch (_) {}
+    }
+    send({ kind: 'fetch', url, method, status: res.status, duration: ended - started, preview, contentType: ct || null });
+    return res;
+  } catch (err) {
+    const ended = performance.now();
+    send({ kind: 'fetch-error', url, method, error: String(err), duration: ended - started });
+    throw err;
+  }
+};
@@ -0,0 +1,4 @@
+// Periodic lightweight ping with the current title and URL.
+setInterval(() => {
+  try { send({ kind: 'heartbeat', title: document.title, url: location.href }); } catch (_) {}
+}, 5000);
@@ -0,0 +1,32 @@
+// Title, metadata, icons, links and headings, once the DOM is ready.
+const snapshot = () => {
+  const metas = {};
+  for (const m of document.querySelectorAll('meta[name][content], meta[property][content]')) {
+    const key = m.getAttribute('name') || m.getAttribute('property');
+    if (key && !(key in metas)) metas[key] = m.getAttribute('content') || '';
+  }
+  const links = Array.from(document.querySelectorAll('a[href]')).slice(0, 200).map(a => ({
+    href: a.href,
+    text: (a.textContent || '').trim().slice(0, 140),
+  }));
+  const headings = Array.from(document.querySelectorAll('h1,h2,h3')).slice(0, 50).map(h => ({
+    tag: h.tagName,
+    text: (h.textContent || '').trim().slice(0, 160),
+  }));
+  const icons = Array.from(document.querySelectorAll('link[rel~="icon"]')).map(l => l.href);
+  const themeColor = (document.querySelector('meta[name="theme-color"]')?.content || null);
+  send({
+    kind: 'page-info',
+    title: document.title,
+    url: location.href,
+    lang: document.documentElement.getAttribute('lang') || null,
+    metas,
+    icons,
+    themeColor,
+    links,
+    headings,
+  });
+};
+
+if (document.readyState !== 'loading') snapshot();
+document.addEventListener('DOMContentLoaded', () => { try { snapshot(); } catch (_) {} }, { once: true });
@@ -0,0 +1,24 @@
+// Resource timing, paints, LCP, navigation timing and long tasks.
+const perfHandler = (list) => {
+  for (const e of list.getEntries()) {
+    if (e.entryType === 'resource') {
+      send({ kind: 'resource', item: {
+        type: e.initiatorType,
+        name: e.name,
+        duration: e.duration,
+        startTime: e.startTime,
+        transferSize: e.transferSize,
+      } });
+    } else if (e.entryType === 'paint') {
+      send({ kind: 'paint', name: e.name, startTime: e.startTime });
+    } else if (e.entryType === 'largest-contentful-paint') {
+      send({ kind: 'lcp', startTime: e.startTime, size: e.size, url: e.url || null });
+    } else if (e.entryType === 'navigation') {
+      send({ kind: 'navigation', domContentLoaded: e.domContentLoadedEventEnd, loadEventEnd: e.loadEventEnd, type: e.type });
+    } else if (e.entryType === 'longtask') {
+      send({ kind: 'longtask', startTime: e.startTime, duration: e.duration });
+    }
+  }
+};
+const po = new PerformanceObserver(perfHandler);
+po.observe({ entryTypes: ['resource', 'paint', 'largest-contentful-paint', 'navigation', 'longtask'] });
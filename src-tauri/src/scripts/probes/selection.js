@@ -0,0 +1,12 @@
+// Text the user selects, trimmed and deduplicated.
+let lastSelection = '';
+document.addEventListener('selectionchange', () => {
+  try {
+    const sel = window.getSelection();
+    const text = (sel && sel.toString()) ? sel.toString().trim().slice(0, 500) : '';
+    if (text && text !== lastSelection) {
+      lastSelection = text;
+      send({ kind: 'selection', text });
+    }
+  } catch (_) {}
+});
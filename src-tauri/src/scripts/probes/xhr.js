@@ -0,0 +1,20 @@
+// Wrap XMLHttpRequest to report URL, status and timing.
+const OrigXHR = window.XMLHttpRequest;
+function XHR() {
+  const xhr = new OrigXHR();
+  let url = '';
+  let method = 'GET';
+  let started = 0;
+  const origOpen = xhr.open;
+  const origSend = xhr.send;
+  xhr.open = function(m, u, ...rest) { method = String(m); url = String(u); return origOpen.call(this, m, u, ...rest); };
+  xhr.send = function(...rest) { started = performance.now(); return origSend.apply(this, rest); };
+  xhr.addEventListener('loadend', function() {
+    const ended = performance.now();
+    let contentType = null;
+    try { contentType = xhr.getResponseHeader('content-type'); } catch (_) {}
+    send({ kind: 'xhr', url, method, status: xhr.status, duration: ended - started, contentType });
+  });
+  return xhr;
+}
+window.XMLHttpRequest = XHR;
@@ -0,0 +1,7 @@
+// Scroll offset, so a restored session can return to it.
+let scrollTimer = 0;
+const reportScroll = () => send({ kind: 'scroll', x: window.scrollX, y: window.scrollY });
+window.addEventListener('scroll', () => {
+  clearTimeout(scrollTimer);
+  scrollTimer = setTimeout(reportScroll, 500);
+}, { passive: true });
@@ -0,0 +1,99 @@
+// HMAC-SHA-256 under the key Rust issued for this webview, so Rust can tell
+// the injected script's messages from anything else the page sends (see
+// src-tauri/src/telemetry/channel.rs). Runs before any page script: the key
+// is turned into hash states here and dropped, and everything signing uses
+// later is captured now. It works on typed arrays and operators only, since
+// page scripts can replace any method or getter it would call later.
+// Defines `sign(text)`, returning the MAC as hex.
+const sign = (() => {
+  const U8 = Uint8Array;
+  const U32 = Uint32Array;
+  const encode = TextEncoder.prototype.encode.bind(new TextEncoder());
+  const lengthGetter = Object.getOwnPropertyDescriptor(Object.getPrototypeOf(U8.prototype), 'length').get;
+  const lengthOf = Function.prototype.call.bind(lengthGetter);
+  const HEX = Array.from({ length: 256 }, (_, i) => (i < 16 ? '0' : '') + i.toString(16));
+
+  const IV = new U32([
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+  ]);
+  const K = new U32([
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+  ]);
+
+  // One 64-byte block of `bytes` at `offset` into the hash state `h`.
+  const compress = (h, w, bytes, offset) => {
+    for (let i = 0; i < 16; i++) {
+      const j = offset + i * 4;
+      w[i] = (bytes[j] << 24) | (bytes[j + 1] << 16) | (bytes[j + 2] << 8) | bytes[j + 3];
+    }
+    for (let i = 16; i < 64; i++) {
+      const x = w[i - 15];
+      const y = w[i - 2];
+      const s0 = ((x >>> 7) | (x << 25)) ^ ((x >>> 18) | (x << 14)) ^ (x >>> 3);
+      const s1 = ((y >>> 17) | (y << 15)) ^ ((y >>> 19) | (y << 13)) ^ (y >>> 10);
+      w[i] = w[i - 16] + s0 + w[i - 7] + s1;
+    }
+    let a = h[0], b = h[1], c = h[2], d = h[3], e = h[4], f = h[5], g = h[6], k = h[7];
+    for (let i = 0; i < 64; i++) {
+      const s1 = ((e >>> 6) | (e << 26)) ^ ((e >>> 11) | (e << 21)) ^ ((e >>> 25) | (e << 7));
+      const t1 = (k + s1 + ((e & f) ^ (~e & g)) + K[i] + w[i]) | 0;
+      const s0 = ((a >>> 2) | (a << 30)) ^ ((a >>> 13) | (a << 19)) ^ ((a >>> 22) | (a << 10));
+      const t2 = (s0 + ((a & b) ^ (a & c) ^ (b & c))) | 0;
+      k = g; g = f; f = e; e = (d + t1) | 0; d = c; c = b; b = a; a = (t1 + t2) | 0;
+    }
+    h[0] += a; h[1] += b; h[2] += c; h[3] += d; h[4] += e; h[5] += f; h[6] += g; h[7] += k;
+  };
+
+  // Hash `length` bytes following one block already absorbed into `start`.
+  const finish = (start, bytes, length) => {
+    const h = new U32(8);
+    for (let i = 0; i < 8; i++) h[i] = start[i];
+    const size = ((length + 9 + 63) >>> 6) << 6;
+    const padded = new U8(size);
+    for (let i = 0; i < length; i++) padded[i] = bytes[i];
+    padded[length] = 0x80;
+    const bits = (length + 64) * 8;
+    const high = (bits / 0x100000000) >>> 0;
+    const low = bits >>> 0;
+    for (let i = 0; i < 4; i++) {
+      padded[size - 8 + i] = high >>> (24 - 8 * i);
+      padded[size - 4 + i] = low >>> (24 - 8 * i);
+    }
+    const w = new U32(64);
+    for (let offset = 0; offset < size; offset += 64) compress(h, w, padded, offset);
+    return h;
+  };
+
+  // The state after the key block padded with `pad`.
+  const keyed = (key, pad) => {
+    const block = new U8(64);
+    for (let i = 0; i < 64; i++) block[i] = (i < key.length ? key[i] : 0) ^ pad;
+    const h = new U32(8);
+    for (let i = 0; i < 8; i++) h[i] = IV[i];
+    compress(h, new U32(64), block, 0);
+    return h;
+  };
+
+  const key = encode(PARAMS.channel.key);
+  delete PARAMS.channel.key;
+  const inner = keyed(key, 0x36);
+  const outer = keyed(key, 0x5c);
+
+  return (text) => {
+    const bytes = encode(text);
+    const digest = finish(inner, bytes, lengthOf(bytes));
+    const middle = new U8(32);
+    for (let i = 0; i < 32; i++) middle[i] = digest[i >>> 2] >>> (24 - 8 * (i & 3));
+    const mac = finish(outer, middle, 32);
+    let hex = '';
+    for (let i = 0; i < 32; i++) hex += HEX[(mac[i >>> 2] >>> (24 - 8 * (i & 3))) & 0xff];
+    return hex;
+  };
+})();
@@ -0,0 +1,454 @@
+//! Session save and restore.
+//!
+//! The open tabs (URL, title, back/forward list, scroll offset, pinned/active
+//! flags) and the main window layout are snapshotted to `session.json`
+//! periodically, whenever a tab closes, and on exit. On startup the saved tabs
+//! are recreated natively before the frontend loads, and the frontend picks
+//! them up through `list_webviews` like it does after a reload.
+//!
+//! Writes rotate the previous file to `session.json.bak` before the new one is
+//! renamed into place, so a crash at any point leaves at least one complete
+//! snapshot on disk.
+
+use std::collections::VecDeque;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+
+use crate::error::BrsrError;
+use crate::navigation;
+use crate::session_history::{SessionHistory, SessionHistoryEntry};
+use crate::storage;
+use crate::tabs::{now_ms, Bounds, ScrollPosition, TabRegistry, TabState};
+
+const SESSION_FILE: &str = "session.json";
+const FORMAT_VERSION: u32 = 1;
+/// How many recently closed tabs `reopen_closed_tab` can bring back.
+const MAX_CLOSED_TABS: usize = 25;
+pub const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// A tab as written to disk.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedTab {
+    pub label: String,
+    pub url: String,
+    pub title: String,
+    pub history: Vec<SessionHistoryEntry>,
+    pub history_index: usize,
+    #[serde(default)]
+    pub scroll: ScrollPosition,
+    #[serde(default)]
+    pub pinned: bool,
+    #[serde(default)]
+    pub active: bool,
+    pub bounds: Bounds,
+    pub created_at: u64,
+    /// Set for entries in the recently-closed list.
+    #[serde(default)]
+    pub closed_at: Option<u64>,
+}
+
+impl SavedTab {
+    fn from_tab(tab: &TabState, active: bool) -> Self {
+        let history = tab.history.snapshot();
+        Self {
+            label: tab.label.clone(),
+            url: tab.url.clone(),
+            title: tab.title.clone(),
+            history: history.entries,
+            history_index: history.current_index,
+            scroll: tab.scroll,
+            pinned: tab.pinned,
+            active,
+            bounds: tab.bounds,
+            created_at: tab.created_at,
+            closed_at: None,
+        }
+    }
+
+    /// Registry state for this tab once its webview is recreated.
+    fn to_tab(&self) -> TabState {
+        let mut tab = TabState::new(self.label.clone(), self.url.clone(), self.bounds);
+        tab.title = self.title.clone();
+        tab.created_at = self.created_at;
+        tab.pinned = self.pinned;
+        tab.scroll = self.scroll;
+        tab.visible = self.active;
+        if self.scroll != ScrollPosition::default() {
+            tab.pending_scroll = Some(self.scroll);
+        }
+        if let Some(history) = SessionHistory::restore(self.history.clone(), self.history_index) {
+            tab.history = history;
+        }
+        tab
+    }
+}
+
+/// Outer frame of the main window, in logical pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowLayout {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub maximized: bool,
+    pub fullscreen: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionFile {
+    pub version: u32,
+    pub saved_at: u64,
+    pub window: Option<WindowLayout>,
+    pub tabs: Vec<SavedTab>,
+    #[serde(default)]
+    pub closed_tabs: Vec<SavedTab>,
+}
+
+impl Default for SessionFile {
+    fn default() -> Self {
+        Self {
+            version: FORMAT_VERSION,
+            saved_at: 0,
+            window: None,
+            tabs: Vec::new(),
+            closed_tabs: Vec::new(),
+        }
+    }
+}
+
+/// Managed state: the session file on disk plus what we know about the last run.
+pub struct SessionStore {
+    path: Option<PathBuf>,
+    /// The session found on disk at startup.
+    previous: SessionFile,
+    closed: Mutex<VecDeque<SavedTab>>,
+    /// Bytes of the last successful write, to skip rewriting an unchanged session.
+    last_written: Mutex<Vec<u8>>,
+}
+
+impl SessionStore {
+    pub fn load<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Self {
+        let path = storage::data_file(app, SESSION_FILE)
+            .map_err(|e| eprintln!("[Rust] Session will not persist: {}", e))
+            .ok();
+        let previous = path.as_ref().map(|p| read_session(p)).unwrap_or_default();
+        Self {
+            closed: Mutex::new(previous.closed_tabs.iter().cloned().collect()),
+            path,
+            previous,
+            last_written: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn previous(&self) -> &SessionFile {
+        &self.previous
+    }
+
+    /// Remember a tab that is about to be closed so it can be reopened.
+    pub fn push_closed(&self, tab: &TabState) {
+        let mut saved = SavedTab::from_tab(tab, false);
+        saved.closed_at = Some(now_ms());
+        let mut closed = self.closed.lock().unwrap();
+        closed.retain(|t| t.label != saved.label);
+        closed.push_front(saved);
+        closed.truncate(MAX_CLOSED_TABS);
+    }
+
+    /// Most recently closed first.
+    pub fn closed_tabs(&self) -> Vec<SavedTab> {
+        self.closed.lock().unwrap().iter().cloned().collect()
+    }
+
+    fn take_closed(&self, label: Option<&str>) -> Option<SavedTab> {
+        let mut closed = self.closed.lock().unwrap();
+        match label {
+            Some(label) => {
+                let index = closed.iter().position(|t| t.label == label)?;
+                closed.remove(index)
+            }
+            None => closed.pop_front(),
+        }
+    }
+
+    /// Write `session` unless it is identical to the last write.
+    fn write(&self, session: &SessionFile) -> Result<(), BrsrError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        // `saved_at` changes every time; compare everything else.
+        let comparable = serde_json::to_vec(&SessionFile {
+            saved_at: 0,
+            ..session.clone()
+        })
+        .map_err(|e| BrsrError::io(path, e))?;
+        let mut last = self.last_written.lock().unwrap();
+        if *last == comparable {
+            return Ok(());
+        }
+        let bytes = serde_json::to_vec_pretty(session).map_err(|e| BrsrError::io(path, e))?;
+        if path.exists() {
+            let _ = fs::rename(path, backup_path(path));
+        }
+        storage::write_atomic(path, &bytes)?;
+        *last = comparable;
+        Ok(())
+    }
+}
+
+fn backup_path(path: &std::path::Path) -> PathBuf {
+    path.with_extension("json.bak")
+}
+
+/// Read the session file, falling back to the backup if the main file is
+/// missing or unreadable (e.g. we died between the rotate and the rename).
+fn read_session(path: &std::path::Path) -> SessionFile {
+    for candidate in [path.to_path_buf(), backup_path(path)] {
+        let Ok(bytes) = fs::read(&candidate) else {
+            continue;
+        };
+        match serde_json::from_slice::<SessionFile>(&bytes) {
+            Ok(session) if session.version <= FORMAT_VERSION => return session,
+            Ok(session) => eprintln!(
+                "[Rust] Ignoring {}: unsupported session version {}",
+                candidate.display(),
+                session.version
+            ),
+            Err(e) => eprintln!("[Rust] Ignoring malformed {}: {}", candidate.display(), e),
+        }
+    }
+    SessionFile::default()
+}
+
+fn window_layout<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Option<WindowLayout> {
+    let window = app.get_window("main")?;
+    let scale = window.scale_factor().ok()?;
+    let position = window.outer_position().ok()?.to_logical::<f64>(scale);
+    let size = window.inner_size().ok()?.to_logical::<f64>(scale);
+    Some(WindowLayout {
+        x: position.x,
+        y: position.y,
+        width: size.width,
+        height: size.height,
+        maximized: window.is_maximized().unwrap_or(false),
+        fullscreen: window.is_fullscreen().unwrap_or(false),
+    })
+}
+
+/// Build the current session from the tab registry and the main window.
+pub fn snapshot<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> SessionFile {
+    let registry = app.state::<TabRegistry>();
+    let active = registry.active_label();
+    let tabs = registry
+        .list()
+        .iter()
+        .filter(|tab| !tab.private)
+        .map(|tab| SavedTab::from_tab(tab, Some(&tab.label) == active.as_ref()))
+        .collect();
+    SessionFile {
+        version: FORMAT_VERSION,
+        saved_at: now_ms(),
+        window: window_layout(app),
+        tabs,
+        closed_tabs: app.state::<SessionStore>().closed_tabs(),
+    }
+}
+
+/// Snapshot the session and write it if anything changed.
+pub fn save<R: tauri::Runtime>(app: &tauri::AppHandle<R>) {
+    let session = snapshot(app);
+    if let Err(e) = app.state::<SessionStore>().write(&session) {
+        eprintln!("[Rust] Failed to save session: {}", e);
+    }
+}
+
+/// Save the session every `SNAPSHOT_INTERVAL` for as long as the app runs.
+pub fn start_autosave<R: tauri::Runtime>(app: &tauri::AppHandle<R>) {
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(SNAPSHOT_INTERVAL);
+        save(&app);
+    });
+}
+
+/// Recreate a saved tab's webview in the main window.
+fn reopen(app: &tauri::AppHandle, saved: &SavedTab) -> Result<(), BrsrError> {
+    let window = app
+        .get_window("main")
+        .ok_or_else(|| BrsrError::not_found("window", "main"))?;
+    let url = navigation::parse_navigable_url(&saved.url)?;
+    let webview = crate::open_tab_webview(&window, saved.to_tab(), url)?;
+    if !saved.active {
+        webview.hide()?;
+    }
+    Ok(())
+}
+
+/// Reopen the tabs of `session` that aren't open already.
+/// Returns the labels of the tabs that were recreated.
+fn restore_tabs(app: &tauri::AppHandle, session: &SessionFile) -> Vec<String> {
+    let registry = app.state::<TabRegistry>();
+    let mut restored = Vec::new();
+    for saved in &session.tabs {
+        if registry.contains(&saved.label) {
+            continue;
+        }
+        match reopen(app, saved) {
+            Ok(()) => restored.push(saved.label.clone()),
+            Err(e) => eprintln!("[Rust] Failed to restore tab '{}': {}", saved.label, e),
+        }
+    }
+    restored
+}
+
+/// Put the main window back where it was. The window-state plugin normally
+/// agrees with this; the session copy wins if they differ.
+fn restore_window(app: &tauri::AppHandle, layout: &WindowLayout) {
+    let Some(window) = app.get_window("main") else {
+        return;
+    };
+    if layout.fullscreen {
+        let _ = window.set_fullscreen(true);
+    } else if layout.maximized {
+        let _ = window.maximize();
+    } else if layout.width > 0.0 && layout.height > 0.0 {
+        let _ = window.set_position(tauri::LogicalPosition::new(layout.x, layout.y));
+        let _ = window.set_size(tauri::LogicalSize::new(layout.width, layout.height));
+    }
+}
+
+/// Called from `setup`: bring back the window layout and tabs of the last run.
+pub fn restore_on_startup(app: &tauri::AppHandle) {
+    let session = app.state::<SessionStore>().previous().clone();
+    if let Some(layout) = &session.window {
+        restore_window(app, layout);
+    }
+    let restored = restore_tabs(app, &session);
+    println!("[Rust] Restored {} tab(s) from the last session", restored.len());
+}
+
+#[tauri::command]
+pub async fn restore_last_session(app: tauri::AppHandle) -> Result<Vec<String>, BrsrError> {
+    let session = app.state::<SessionStore>().previous().clone();
+    let restored = restore_tabs(&app, &session);
+    save(&app);
+    Ok(restored)
+}
+
+#[tauri::command]
+pub async fn list_closed_tabs(store: tauri::State<'_, SessionStore>) -> Result<Vec<SavedTab>, BrsrError> {
+    Ok(store.closed_tabs())
+}
+
+/// Reopen a closed tab by label, or the most recently closed one.
+/// Returns the label of the reopened tab.
+#[tauri::command]
+pub async fn reopen_closed_tab(app: tauri::AppHandle, label: Option<String>) -> Result<String, BrsrError> {
+    let store = app.state::<SessionStore>();
+    let mut saved = store.take_closed(label.as_deref()).ok_or_else(|| match &label {
+        Some(label) => BrsrError::not_found("closed tab", label),
+        None => BrsrError::conflict("No closed tab to reopen"),
+    })?;
+    if app.state::<TabRegistry>().contains(&saved.label) {
+        saved.label = format!("webview-{}", now_ms());
+    }
+    saved.active = true;
+    saved.closed_at = None;
+    saved.created_at = now_ms();
+    if let Err(e) = reopen(&app, &saved) {
+        store.closed.lock().unwrap().push_front(saved);
+        return Err(e);
+    }
+    save(&app);
+    Ok(saved.label)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session_history::Traversal;
+
+    fn store(path: PathBuf) -> SessionStore {
+        SessionStore {
+            path: Some(path),
+            previous: SessionFile::default(),
+            closed: Mutex::new(VecDeque::new()),
+            last_written: Mutex::new(Vec::new()),
+        }
+    }
+
+    fn tab(label: &str, url: &str) -> TabState {
+        let bounds = Bounds { x: 0.0, y: 0.0, width: 800.0, height: 600.0 };
+        TabState::new(label.into(), url.into(), bounds)
+    }
+
+    fn session(tabs: &[&TabState], saved_at: u64) -> SessionFile {
+        SessionFile {
+            saved_at,
+            tabs: tabs.iter().map(|t| SavedTab::from_tab(t, false)).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn writes_rotate_the_previous_snapshot() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SESSION_FILE);
+        let store = store(path.clone());
+        let (a, b) = (tab("a", "https://a.test/"), tab("b", "https://b.test/"));
+
+        store.write(&session(&[&a], 1)).unwrap();
+        assert!(!backup_path(&path).exists());
+        // Only `saved_at` differs: nothing is written.
+        store.write(&session(&[&a], 2)).unwrap();
+        assert!(!backup_path(&path).exists());
+        assert_eq!(read_session(&path).saved_at, 1);
+
+        store.write(&session(&[&a, &b], 3)).unwrap();
+        assert_eq!(read_session(&path).tabs.len(), 2);
+        let backup: SessionFile = serde_json::from_slice(&fs::read(backup_path(&path)).unwrap()).unwrap();
+        assert_eq!(backup.tabs.len(), 1);
+    }
+
+    #[test]
+    fn reads_fall_back_to_the_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SESSION_FILE);
+        let a = tab("a", "https://a.test/");
+        fs::write(backup_path(&path), serde_json::to_vec(&session(&[&a], 1)).unwrap()).unwrap();
+        fs::write(&path, b"{ torn").unwrap();
+        assert_eq!(read_session(&path).tabs.len(), 1);
+
+        let newer = SessionFile { version: FORMAT_VERSION + 1, ..session(&[&a], 2) };
+        fs::write(&path, serde_json::to_vec(&newer).unwrap()).unwrap();
+        assert_eq!(read_session(&path).saved_at, 1);
+
+        fs::remove_file(backup_path(&path)).unwrap();
+        assert!(read_session(&path).tabs.is_empty());
+    }
+
+    #[test]
+    fn restored_tabs_keep_their_list_but_load_old_entries() {
+        let mut open = tab("a", "https://a.test/");
+        open.history.commit("https://b.test/");
+        open.url = "https://b.test/".into();
+        open.scroll = ScrollPosition { x: 0.0, y: 120.0 };
+        let saved = SavedTab::from_tab(&open, true);
+
+        let mut restored = saved.to_tab();
+        assert_eq!((restored.label.as_str(), restored.url.as_str()), ("a", "https://b.test/"));
+        assert!(restored.visible);
+        assert_eq!(restored.pending_scroll, Some(ScrollPosition { x: 0.0, y: 120.0 }));
+        assert_eq!(restored.history.snapshot().entries.len(), 2);
+        // The new webview only has b.test, so going back loads a.test.
+        let back = restored.history.begin_traversal(-1);
+        assert_eq!(back, Some(Traversal::Load("https://a.test/".into())));
+    }
+}
@@ -0,0 +1,465 @@
+//! Per-webview session history (the back/forward list).
+//!
+//! Native webviews don't expose their back/forward list, so we rebuild it from
+//! what we can observe: the builder's `on_navigation`/`on_page_load` hooks for
+//! cross-document loads, and `history` signals from the injected script for
+//! same-document `pushState`/`replaceState`/`popstate` changes. Back/forward
+//! requests issued through our own commands, or by the page through
+//! `history.back()`/`forward()`/`go()`, are recorded as a pending traversal so
+//! the next committed load moves the cursor instead of pushing a new entry.
+//! One is only recorded when there is an entry to move to, and is dropped when
+//! the next navigation isn't headed for that entry's URL, so a traversal the
+//! webview never made can't turn a later load into one. Any other load
+//! pushes, even when its URL matches a neighbouring entry: a link from B back
+//! to A after A→B leaves A, B, A in the webview's list.
+//! Traversals we get no signal for (swipe gestures) are recorded as loads.
+//!
+//! A webview's own list can be shorter than ours: a tab restored from a saved
+//! session, or rebuilt, starts its webview with just the current entry. We
+//! keep track of which entries the webview has, and a step to one it doesn't
+//! loads that entry's URL instead of asking the webview to go back or forward.
+
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::error::BrsrError;
+use crate::history::Transition;
+use crate::tabs::{now_ms, TabRegistry};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionHistoryEntry {
+    pub url: String,
+    pub title: String,
+    pub visited_at: u64,
+}
+
+impl SessionHistoryEntry {
+    fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            title: String::new(),
+            visited_at: now_ms(),
+        }
+    }
+}
+
+/// What `get_session_history` returns to the frontend.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionHistorySnapshot {
+    pub entries: Vec<SessionHistoryEntry>,
+    pub current_index: usize,
+    pub can_go_back: bool,
+    pub can_go_forward: bool,
+}
+
+/// History change reported by the injected script.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "lowercase")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub enum HistoryAction {
+    Push,
+    Replace,
+    Pop,
+    /// The page called `history.back()`, `forward()` or `go()`.
+    Traverse,
+}
+
+/// How to carry out a step back or forward.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Traversal {
+    /// The webview's own back/forward list has the entry.
+    Native,
+    /// Only we have the entry: load its URL.
+    Load(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PendingTraversal {
+    /// -1 back, +1 forward, or further for `history.go()`.
+    delta: isize,
+    /// Carried out as a load, so the webview's list starts over there.
+    load: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SessionHistory {
+    entries: Vec<SessionHistoryEntry>,
+    index: usize,
+    /// Entries the webview's own list has too. Always holds `index`.
+    native: Range<usize>,
+    /// Traversal we asked the webview for and haven't seen land yet.
+    pending_traversal: Option<PendingTraversal>,
+}
+
+impl SessionHistory {
+    pub fn new(initial_url: &str) -> Self {
+        Self {
+            entries: vec![SessionHistoryEntry::new(initial_url)],
+            index: 0,
+            native: 0..1,
+            pending_traversal: None,
+        }
+    }
+
+    /// Rebuild a back/forward list saved by a previous session.
+    /// Returns `None` if there is nothing usable to restore.
+    pub fn restore(entries: Vec<SessionHistoryEntry>, index: usize) -> Option<Self> {
+        if entries.is_empty() {
+            return None;
+        }
+        let index = index.min(entries.len() - 1);
+        Some(Self {
+            entries,
+            index,
+            native: index..index + 1,
+            pending_traversal: None,
+        })
+    }
+
+    /// The tab got a new webview, which has only the current entry.
+    pub fn webview_replaced(&mut self) {
+        self.native = self.index..self.index + 1;
+        self.pending_traversal = None;
+    }
+
+    pub fn can_go_back(&self) -> bool {
+        self.index > 0
+    }
+
+    pub fn can_go_forward(&self) -> bool {
+        self.index + 1 < self.entries.len()
+    }
+
+    pub fn current(&self) -> Option<&SessionHistoryEntry> {
+        self.entries.get(self.index)
+    }
+
+    pub fn snapshot(&self) -> SessionHistorySnapshot {
+        SessionHistorySnapshot {
+            entries: self.entries.clone(),
+            current_index: self.index,
+            can_go_back: self.can_go_back(),
+            can_go_forward: self.can_go_forward(),
+        }
+    }
+
+    /// Go back (`-1`) or forward (`+1`): remember the step, and say how the
+    /// webview should take it. `None` if there is no entry that way.
+    pub fn begin_traversal(&mut self, delta: isize) -> Option<Traversal> {
+        let target = self.traversal_target(delta)?;
+        let load = !self.native.contains(&target);
+        self.pending_traversal = Some(PendingTraversal { delta, load });
+        Some(if load {
+            Traversal::Load(self.entries[target].url.clone())
+        } else {
+            Traversal::Native
+        })
+    }
+
+    /// A cross-document navigation to `url` is starting. A pending traversal
+    /// headed somewhere else didn't happen, and this navigation isn't it.
+    pub fn navigation_started(&mut self, url: &str) {
+        let target = self.pending_traversal.and_then(|p| self.traversal_target(p.delta));
+        if target.is_none_or(|target| self.entries[target].url != url) {
+            self.pending_traversal = None;
+        }
+    }
+
+    /// The entry `delta` steps from the cursor, if there is one.
+    fn traversal_target(&self, delta: isize) -> Option<usize> {
+        let target = self.index as isize + delta;
+        (delta != 0 && target >= 0 && (target as usize) < self.entries.len()).then_some(target as usize)
+    }
+
+    /// A cross-document load committed at `url` (after redirects).
+    /// Returns how the load most likely came about.
+    pub fn commit(&mut self, url: &str) -> Transition {
+        let pending = self.pending_traversal.take();
+        if let Some((target, load)) = pending.and_then(|p| Some((self.traversal_target(p.delta)?, p.load))) {
+            self.index = target;
+            if load {
+                self.native = target..target + 1;
+            } else {
+                self.extend_native(target);
+            }
+            self.entries[self.index].url = url.to_string();
+            self.entries[self.index].visited_at = now_ms();
+            return Transition::BackForward;
+        }
+        if self.current().is_some_and(|e| e.url == url) {
+            return Transition::Reload;
+        }
+        self.push(url);
+        Transition::Link
+    }
+
+    /// A history change signalled by the injected script. `delta` comes with
+    /// `Traverse`. Returns the transition if it produced a visit worth recording.
+    pub fn apply(&mut self, action: HistoryAction, url: &str, delta: Option<isize>) -> Option<Transition> {
+        match action {
+            HistoryAction::Push => {
+                if self.current().is_none_or(|e| e.url != url) {
+                    self.push(url);
+                    return Some(Transition::SameDocument);
+                }
+                None
+            }
+            HistoryAction::Replace => {
+                match self.entries.get_mut(self.index) {
+                    Some(entry) => entry.url = url.to_string(),
+                    None => self.push(url),
+                }
+                None
+            }
+            HistoryAction::Traverse => {
+                // Lands as a commit, or as a popstate if it stays in the document.
+                // The webview can only go where its own list reaches.
+                let reachable = |d: &isize| self.traversal_target(*d).is_some_and(|t| self.native.contains(&t));
+                if let Some(delta) = delta.filter(reachable) {
+                    self.pending_traversal = Some(PendingTraversal { delta, load: false });
+                }
+                None
+            }
+            HistoryAction::Pop => {
+                let pending = self.pending_traversal.take().and_then(|p| self.traversal_target(p.delta));
+                match pending.or_else(|| self.neighbour_index(url)) {
+                    Some(target) => {
+                        self.index = target;
+                        // popstate means the webview moved there itself.
+                        self.extend_native(target);
+                        self.entries[self.index].url = url.to_string();
+                        Some(Transition::BackForward)
+                    }
+                    // Fragment navigations also fire popstate.
+                    None if self.current().is_none_or(|e| e.url != url) => {
+                        self.push(url);
+                        Some(Transition::SameDocument)
+                    }
+                    None => None,
+                }
+            }
+        }
+    }
+
+    pub fn set_title(&mut self, title: &str) {
+        if let Some(entry) = self.entries.get_mut(self.index) {
+            entry.title = title.to_string();
+        }
+    }
+
+    /// The webview's own list reaches `index` too.
+    fn extend_native(&mut self, index: usize) {
+        self.native = self.native.start.min(index)..self.native.end.max(index + 1);
+    }
+
+    fn push(&mut self, url: &str) {
+        self.entries.truncate(self.index + 1);
+        self.entries.push(SessionHistoryEntry::new(url));
+        self.index = self.entries.len() - 1;
+        self.native = self.native.start.min(self.index)..self.entries.len();
+    }
+
+    /// The entry right behind or ahead of the cursor, if it is at `url`. Only
+    /// for popstate, which is always a traversal.
+    fn neighbour_index(&self, url: &str) -> Option<usize> {
+        if self.can_go_back() && self.entries[self.index - 1].url == url {
+            Some(self.index - 1)
+        } else if self.can_go_forward() && self.entries[self.index + 1].url == url {
+            Some(self.index + 1)
+        } else {
+            None
+        }
+    }
+}
+
+/// A same-document history change applied to a tab.
+pub struct HistoryChange {
+    pub label: String,
+    pub url: String,
+    /// Set when the change should be recorded as a visit.
+    pub transition: Option<Transition>,
+}
+
+/// Apply a `history` telemetry event from the tab with `label`.
+pub fn handle_history_signal(
+    registry: &TabRegistry,
+    label: &str,
+    action: HistoryAction,
+    url: &str,
+    delta: Option<i32>,
+) -> Option<HistoryChange> {
+    let mut transition = None;
+    registry
+        .update(label, |tab| {
+            transition = tab.history.apply(action, url, delta.map(|d| d as isize));
+            if action != HistoryAction::Traverse {
+                tab.url = url.to_string();
+            }
+        })
+        .then(|| HistoryChange {
+            label: label.to_string(),
+            url: url.to_string(),
+            transition,
+        })
+}
+
+#[tauri::command]
+pub fn get_session_history(
+    registry: tauri::State<'_, TabRegistry>,
+    label: String,
+) -> Result<SessionHistorySnapshot, BrsrError> {
+    registry
+        .get(&label)
+        .map(|tab| tab.history.snapshot())
+        .ok_or_else(|| BrsrError::not_found("tab", &label))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn urls(history: &SessionHistory) -> Vec<&str> {
+        history.entries.iter().map(|e| e.url.as_str()).collect()
+    }
+
+    #[test]
+    fn link_back_to_previous_page_pushes() {
+        let mut history = SessionHistory::new("https://a.test/");
+        assert_eq!(history.commit("https://b.test/"), Transition::Link);
+        assert_eq!(history.commit("https://a.test/"), Transition::Link);
+        assert_eq!(urls(&history), ["https://a.test/", "https://b.test/", "https://a.test/"]);
+        assert_eq!(history.index, 2);
+    }
+
+    #[test]
+    fn same_url_is_a_reload() {
+        let mut history = SessionHistory::new("https://a.test/");
+        assert_eq!(history.commit("https://a.test/"), Transition::Reload);
+        assert_eq!(urls(&history), ["https://a.test/"]);
+    }
+
+    #[test]
+    fn requested_traversal_moves_the_cursor() {
+        let mut history = SessionHistory::new("https://a.test/");
+        history.commit("https://b.test/");
+        history.begin_traversal(-1);
+        assert_eq!(history.commit("https://a.test/"), Transition::BackForward);
+        assert_eq!(history.index, 0);
+        assert!(history.can_go_forward());
+
+        history.begin_traversal(1);
+        assert_eq!(history.commit("https://b.test/"), Transition::BackForward);
+        assert_eq!(history.index, 1);
+        assert_eq!(urls(&history).len(), 2);
+    }
+
+    #[test]
+    fn traversal_out_of_range_is_a_load() {
+        let mut history = SessionHistory::new("https://a.test/");
+        history.begin_traversal(-1);
+        assert_eq!(history.commit("https://b.test/"), Transition::Link);
+        assert_eq!(history.pending_traversal, None);
+    }
+
+    #[test]
+    fn traversal_the_webview_never_made_is_dropped() {
+        let mut history = SessionHistory::new("https://a.test/");
+        history.begin_traversal(1);
+        assert_eq!(history.pending_traversal, None);
+
+        history.commit("https://b.test/");
+        history.begin_traversal(-1);
+        history.navigation_started("https://a.test/");
+        assert_eq!(history.pending_traversal.map(|p| p.delta), Some(-1));
+        // The webview didn't move; a later link must push, not rewrite a.test.
+        history.navigation_started("https://c.test/");
+        assert_eq!(history.pending_traversal, None);
+        assert_eq!(history.commit("https://c.test/"), Transition::Link);
+        assert_eq!(urls(&history), ["https://a.test/", "https://b.test/", "https://c.test/"]);
+    }
+
+    fn restored(index: usize) -> SessionHistory {
+        let entries = ["https://a.test/", "https://b.test/", "https://c.test/"].map(SessionHistoryEntry::new);
+        SessionHistory::restore(entries.to_vec(), index).unwrap()
+    }
+
+    #[test]
+    fn restored_entries_load_by_url() {
+        let mut history = restored(2);
+        assert!(history.can_go_back());
+        assert_eq!(history.begin_traversal(-1), Some(Traversal::Load("https://b.test/".into())));
+        history.navigation_started("https://b.test/");
+        assert_eq!(history.commit("https://b.test/"), Transition::BackForward);
+        assert_eq!(history.index, 1);
+        // The webview has only b.test now; c.test is ours alone as well.
+        assert_eq!(history.begin_traversal(1), Some(Traversal::Load("https://c.test/".into())));
+        assert_eq!(history.begin_traversal(-1), Some(Traversal::Load("https://a.test/".into())));
+        assert_eq!(urls(&history).len(), 3);
+    }
+
+    #[test]
+    fn loads_after_a_restore_are_native() {
+        let mut history = restored(1);
+        assert_eq!(history.commit("https://d.test/"), Transition::Link);
+        assert_eq!(urls(&history), ["https://a.test/", "https://b.test/", "https://d.test/"]);
+        assert_eq!(history.begin_traversal(-1), Some(Traversal::Native));
+        assert_eq!(history.commit("https://b.test/"), Transition::BackForward);
+        assert_eq!(history.begin_traversal(-1), Some(Traversal::Load("https://a.test/".into())));
+
+        // The page can't take the webview past the start of its own list.
+        history.pending_traversal = None;
+        history.apply(HistoryAction::Traverse, "https://b.test/", Some(-1));
+        assert_eq!(history.pending_traversal, None);
+        assert_eq!(history.commit("https://e.test/"), Transition::Link);
+        assert_eq!(urls(&history), ["https://a.test/", "https://b.test/", "https://e.test/"]);
+    }
+
+    #[test]
+    fn a_new_webview_starts_its_list_over() {
+        let mut history = SessionHistory::new("https://a.test/");
+        history.commit("https://b.test/");
+        history.webview_replaced();
+        assert_eq!(history.begin_traversal(-1), Some(Traversal::Load("https://a.test/".into())));
+    }
+
+    #[test]
+    fn page_traversal_signal_lands_on_commit() {
+        let mut history = SessionHistory::new("https://a.test/");
+        history.commit("https://b.test/");
+        history.commit("https://c.test/");
+        assert_eq!(history.apply(HistoryAction::Traverse, "https://c.test/", Some(-2)), None);
+        assert_eq!(history.commit("https://a.test/"), Transition::BackForward);
+        assert_eq!(history.index, 0);
+
+        // Out of range: ignored, the next load pushes.
+        history.apply(HistoryAction::Traverse, "https://a.test/", Some(-1));
+        assert_eq!(history.commit("https://d.test/"), Transition::Link);
+        assert_eq!(urls(&history), ["https://a.test/", "https://d.test/"]);
+    }
+
+    #[test]
+    fn push_replace_and_pop() {
+        let mut history = SessionHistory::new("https://a.test/");
+        assert_eq!(history.apply(HistoryAction::Push, "https://a.test/1", None), Some(Transition::SameDocument));
+        assert_eq!(history.apply(HistoryAction::Push, "https://a.test/1", None), None);
+        assert_eq!(history.apply(HistoryAction::Replace, "https://a.test/2", None), None);
+        assert_eq!(urls(&history), ["https://a.test/", "https://a.test/2"]);
+
+        assert_eq!(history.apply(HistoryAction::Pop, "https://a.test/", None), Some(Transition::BackForward));
+        assert_eq!(history.index, 0);
+
+        // A pending traversal decides where popstate lands, whatever the URL.
+        history.apply(HistoryAction::Traverse, "https://a.test/", Some(1));
+        assert_eq!(history.apply(HistoryAction::Pop, "https://a.test/#x", None), Some(Transition::BackForward));
+        assert_eq!(urls(&history), ["https://a.test/", "https://a.test/#x"]);
+        assert_eq!(history.index, 1);
+
+        // A fragment change that isn't a neighbour pushes.
+        assert_eq!(history.apply(HistoryAction::Pop, "https://a.test/#y", None), Some(Transition::SameDocument));
+        assert_eq!(history.index, 2);
+    }
+}
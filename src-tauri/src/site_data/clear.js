@@ -0,0 +1,35 @@
+// Runs in the site-data helper on a page of the origin being cleared; see
+// site_data/mod.rs. PARAMS: { storage, cache, done }. Setting the title to
+// PARAMS.done tells Rust it finished.
+const settle = (request) =>
+  new Promise((resolve) => {
+    request.onsuccess = request.onerror = request.onblocked = resolve;
+  });
+
+const clearStorage = async () => {
+  try { localStorage.clear(); } catch (_) {}
+  try { sessionStorage.clear(); } catch (_) {}
+  const tasks = [];
+  if (window.indexedDB && indexedDB.databases) {
+    const databases = await indexedDB.databases();
+    tasks.push(...databases.map((db) => settle(indexedDB.deleteDatabase(db.name))));
+  }
+  if (navigator.serviceWorker) {
+    const registrations = await navigator.serviceWorker.getRegistrations();
+    tasks.push(...registrations.map((registration) => registration.unregister()));
+  }
+  await Promise.allSettled(tasks);
+};
+
+const clearCaches = async () => {
+  if (!window.caches) return;
+  const keys = await caches.keys();
+  await Promise.allSettled(keys.map((key) => caches.delete(key)));
+};
+
+Promise.allSettled([
+  PARAMS.storage ? clearStorage() : null,
+  PARAMS.cache ? clearCaches() : null,
+]).then(() => {
+  document.title = PARAMS.done;
+});
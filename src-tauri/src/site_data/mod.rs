@@ -0,0 +1,437 @@
+//! Cookies and other site data: list and delete cookies by domain, forget an
+//! origin, and clear browsing data by time range and category.
+//!
+//! Cookies, storage and caches live in the running profile's webview data
+//! store, which is only reachable through a webview. Everything here goes
+//! through a hidden helper webview on the same store as non-private tabs, so
+//! it works with no tabs open. Private tabs have stores of their own and are
+//! never touched.
+//!
+//! Storage and caches are removed through the engine's data store API (see
+//! `native.rs`), site by site. Where that isn't available, or fails, the
+//! helper loads each origin's `/robots.txt` and clears localStorage,
+//! sessionStorage, IndexedDB, service workers and Cache Storage from inside
+//! the page (see `clear.js`), signalling completion by changing its title.
+//! That takes a request to the site and fails if it can't be reached, so it
+//! is done for at most [`MAX_FALLBACK_ORIGINS`] origins at a time.
+//!
+//! Profiles with a store of their own clear a time range from the whole
+//! store at once, where the engine can. The default profile's tabs share the
+//! store of the app's own UI, whose settings live in localStorage, so there
+//! every site but the UI's loses its data for all time, and the sites
+//! visited in the range, according to history, for a shorter one.
+
+mod native;
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::webview::{Cookie, PageLoadEvent};
+use tauri::{LogicalPosition, LogicalSize, Manager, WebviewBuilder, WebviewUrl};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use ts_rs::TS;
+use url::Url;
+
+use crate::error::BrsrError;
+use crate::history::{HistoryDeleteTarget, HistoryStore, TimeRange};
+use crate::permissions::SitePermissionStore;
+use crate::profiles::ProfileManager;
+use crate::telemetry::channel::random_token;
+use native::{Kinds, Sites};
+
+const HELPER_LABEL: &str = "site-data";
+const CLEAR_SCRIPT: &str = include_str!("clear.js");
+/// How long one origin may take to load and clear.
+const ORIGIN_TIMEOUT: Duration = Duration::from_secs(15);
+/// Most origins cleared from a page in one go; the rest are reported as failed.
+const MAX_FALLBACK_ORIGINS: usize = 20;
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct CookieInfo {
+    pub name: String,
+    pub value: String,
+    /// Without the leading dot.
+    pub domain: String,
+    pub path: String,
+    /// Milliseconds since the epoch; `None` for session cookies.
+    pub expires: Option<f64>,
+    pub secure: bool,
+    pub http_only: bool,
+    pub same_site: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct CookieDomain {
+    pub domain: String,
+    pub cookies: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "lowercase")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub enum DataCategory {
+    /// Cache Storage and the HTTP cache.
+    Cache,
+    Cookies,
+    /// localStorage, sessionStorage, IndexedDB and service workers.
+    Storage,
+    History,
+}
+
+/// What a clear removed.
+#[derive(Debug, Clone, Default, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct ClearedData {
+    pub cookies: u32,
+    /// Sites whose storage or caches were cleared.
+    pub origins: u32,
+    /// Whether storage or caches were cleared from the whole store at once,
+    /// which doesn't count sites.
+    pub whole_store: bool,
+    pub history_visits: u32,
+    /// Origins that couldn't be cleared, with the reason.
+    pub failed: Vec<String>,
+}
+
+#[derive(Debug)]
+enum Signal {
+    Loaded(String),
+    Title(String),
+}
+
+/// Managed state: the helper webview's signals. Holding the receiver is what
+/// gives an operation the helper to itself.
+pub struct SiteData {
+    sender: UnboundedSender<Signal>,
+    receiver: tokio::sync::Mutex<UnboundedReceiver<Signal>>,
+}
+
+impl Default for SiteData {
+    fn default() -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        Self {
+            sender,
+            receiver: tokio::sync::Mutex::new(receiver),
+        }
+    }
+}
+
+/// The hidden helper webview, created on first use.
+fn helper(app: &tauri::AppHandle) -> Result<tauri::Webview, BrsrError> {
+    if let Some(webview) = app.get_webview(HELPER_LABEL) {
+        return Ok(webview);
+    }
+    let window = app
+        .get_window("main")
+        .ok_or_else(|| BrsrError::not_found("window", "main"))?;
+    let loaded = app.state::<SiteData>().sender.clone();
+    let titled = loaded.clone();
+    let blank = Url::parse("about:blank").expect("about:blank parses");
+    let builder = WebviewBuilder::new(HELPER_LABEL, WebviewUrl::External(blank))
+        .on_page_load(move |_, payload| {
+            if let PageLoadEvent::Finished = payload.event() {
+                let _ = loaded.send(Signal::Loaded(payload.url().to_string()));
+            }
+        })
+        .on_document_title_changed(move |_, title| {
+            let _ = titled.send(Signal::Title(title));
+        });
+    let builder = app.state::<ProfileManager>().with_data_store(builder);
+    let webview = window
+        .add_child(builder, LogicalPosition::new(0.0, 0.0), LogicalSize::new(1.0, 1.0))
+        .map_err(|e| BrsrError::platform(format!("Failed to create the site data webview: {}", e)))?;
+    webview.hide()?;
+    Ok(webview)
+}
+
+/// Host of the app's own UI, whose cookies are never cleared.
+fn ui_host(app: &tauri::AppHandle) -> Option<String> {
+    let url = app.get_webview("main")?.url().ok()?;
+    url.host_str().map(str::to_ascii_lowercase)
+}
+
+/// Cookie domains are stored with or without a leading dot.
+fn bare_domain(domain: &str) -> String {
+    domain.trim_start_matches('.').to_ascii_lowercase()
+}
+
+/// Whether `host` is `domain` or one of its subdomains.
+fn within(host: &str, domain: &str) -> bool {
+    host == domain || host.strip_suffix(domain).is_some_and(|rest| rest.ends_with('.'))
+}
+
+fn cookie_domain(cookie: &Cookie<'_>) -> String {
+    cookie.domain().map(bare_domain).unwrap_or_default()
+}
+
+fn cookie_info(cookie: &Cookie<'_>) -> CookieInfo {
+    CookieInfo {
+        name: cookie.name().to_string(),
+        value: cookie.value().to_string(),
+        domain: cookie_domain(cookie),
+        path: cookie.path().unwrap_or("/").to_string(),
+        expires: cookie.expires_datetime().map(|t| t.unix_timestamp() as f64 * 1000.0),
+        secure: cookie.secure().unwrap_or(false),
+        http_only: cookie.http_only().unwrap_or(false),
+        same_site: cookie.same_site().map(|s| s.to_string()),
+    }
+}
+
+/// Delete the cookies `matches` picks. Returns how many went.
+fn delete_cookies_where(webview: &tauri::Webview, matches: impl Fn(&str) -> bool) -> Result<u32, BrsrError> {
+    let cookies = webview.cookies().map_err(cookie_error("Failed to read cookies"))?;
+    let mut deleted = 0;
+    for cookie in cookies.into_iter().filter(|c| matches(&cookie_domain(c))) {
+        webview
+            .delete_cookie(cookie)
+            .map_err(cookie_error("Failed to delete a cookie"))?;
+        deleted += 1;
+    }
+    Ok(deleted)
+}
+
+fn cookie_error(context: &'static str) -> impl Fn(tauri::Error) -> BrsrError {
+    move |e| BrsrError::platform(format!("{}: {}", context, e))
+}
+
+/// Cookies that go with `host`: the ones it is sent, which includes those set
+/// for a parent domain, and the ones of its subdomains.
+fn visible_to(host: &str) -> impl Fn(&str) -> bool + '_ {
+    move |domain: &str| within(host, domain) || within(domain, host)
+}
+
+/// The origin of `input` as a URL, if it has one worth clearing.
+fn web_origin(input: &str) -> Result<Url, BrsrError> {
+    let url = Url::parse(input.trim()).map_err(|e| BrsrError::invalid_url(input, e))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(BrsrError::invalid_url(input, "it has no site data to clear"));
+    }
+    Url::parse(&url.origin().ascii_serialization()).map_err(|e| BrsrError::invalid_url(input, e))
+}
+
+/// Wait for the helper to report `matches`, dropping other signals. `None`
+/// when it doesn't within [`ORIGIN_TIMEOUT`].
+async fn wait_for(receiver: &mut UnboundedReceiver<Signal>, matches: impl Fn(&Signal) -> bool) -> Option<Signal> {
+    let wait = async {
+        while let Some(signal) = receiver.recv().await {
+            if matches(&signal) {
+                return Some(signal);
+            }
+        }
+        None
+    };
+    tokio::time::timeout(ORIGIN_TIMEOUT, wait).await.ok().flatten()
+}
+
+/// Clear storage and/or Cache Storage of `origin` from inside a page on it.
+async fn clear_origin(
+    webview: &tauri::Webview,
+    receiver: &mut UnboundedReceiver<Signal>,
+    origin: &Url,
+    storage: bool,
+    cache: bool,
+) -> Result<(), BrsrError> {
+    while receiver.try_recv().is_ok() {}
+    let target = origin.join("/robots.txt").map_err(|e| BrsrError::invalid_url(origin.as_str(), e))?;
+    webview.navigate(target)?;
+    let loaded = match wait_for(receiver, |s| matches!(s, Signal::Loaded(_))).await {
+        Some(Signal::Loaded(url)) => url,
+        Some(_) => unreachable!("wait_for only returns matching signals"),
+        None => return Err(BrsrError::network(origin, "timed out")),
+    };
+    let on_origin = Url::parse(&loaded).is_ok_and(|url| url.origin() == origin.origin());
+    if !on_origin {
+        return Err(BrsrError::network(origin, "couldn't reach the site"));
+    }
+
+    let done = format!("brsr-cleared-{}", random_token());
+    let params = serde_json::json!({ "storage": storage, "cache": cache, "done": done });
+    webview.eval(&format!("(() => {{\nconst PARAMS = {};\n{}}})();", params, CLEAR_SCRIPT))?;
+    wait_for(receiver, |s| matches!(s, Signal::Title(title) if *title == done))
+        .await
+        .map(|_| ())
+        .ok_or_else(|| BrsrError::network(origin, "timed out"))
+}
+
+/// Clear storage and/or caches for each of `origins` from a page on it, one
+/// after another.
+async fn clear_origins(app: &tauri::AppHandle, origins: &[Url], storage: bool, cache: bool, cleared: &mut ClearedData) -> Result<(), BrsrError> {
+    if origins.is_empty() {
+        return Ok(());
+    }
+    let site_data = app.state::<SiteData>();
+    let mut receiver = site_data.receiver.lock().await;
+    let webview = helper(app)?;
+    for origin in origins {
+        let name = origin.origin().ascii_serialization();
+        match clear_origin(&webview, &mut receiver, origin, storage, cache).await {
+            Ok(()) => cleared.origins += 1,
+            Err(e) => {
+                eprintln!("[Rust] Failed to clear site data for {}: {}", name, e);
+                cleared.failed.push(e.to_string());
+            }
+        }
+    }
+    let _ = webview.navigate(Url::parse("about:blank").expect("about:blank parses"));
+    Ok(())
+}
+
+/// Clear `kinds` of data of `sites`: through the data store where the engine
+/// can, otherwise from a page on each of their origins, up to
+/// [`MAX_FALLBACK_ORIGINS`].
+async fn clear_sites(app: &tauri::AppHandle, kinds: Kinds, sites: Sites, cleared: &mut ClearedData) -> Result<(), BrsrError> {
+    if !(kinds.storage || kinds.cache) {
+        return Ok(());
+    }
+    let origins = sites.origins().to_vec();
+    match native::remove_sites(&helper(app)?, kinds, sites).await {
+        Ok(Some(sites)) => {
+            cleared.origins += sites;
+            return Ok(());
+        }
+        Ok(None) => {}
+        Err(e) => eprintln!("[Rust] Failed to clear site data natively, clearing from pages: {}", e),
+    }
+    let (now, skipped) = origins.split_at(origins.len().min(MAX_FALLBACK_ORIGINS));
+    cleared.failed.extend(skipped.iter().map(|origin| {
+        format!(
+            "{}: more than {} sites to clear from their pages",
+            origin.origin().ascii_serialization(),
+            MAX_FALLBACK_ORIGINS
+        )
+    }));
+    clear_origins(app, now, kinds.storage, kinds.cache, cleared).await
+}
+
+/// Domains with cookies, with how many each has.
+#[tauri::command]
+pub async fn list_cookie_domains(app: tauri::AppHandle) -> Result<Vec<CookieDomain>, BrsrError> {
+    let cookies = helper(&app)?.cookies().map_err(cookie_error("Failed to read cookies"))?;
+    let mut domains: BTreeMap<String, u32> = BTreeMap::new();
+    for cookie in &cookies {
+        *domains.entry(cookie_domain(cookie)).or_default() += 1;
+    }
+    Ok(domains
+        .into_iter()
+        .map(|(domain, cookies)| CookieDomain { domain, cookies })
+        .collect())
+}
+
+/// Cookies of `domain` and its subdomains.
+#[tauri::command]
+pub async fn list_cookies(app: tauri::AppHandle, domain: String) -> Result<Vec<CookieInfo>, BrsrError> {
+    let domain = bare_domain(&domain);
+    let cookies = helper(&app)?.cookies().map_err(cookie_error("Failed to read cookies"))?;
+    Ok(cookies
+        .iter()
+        .filter(|c| within(&cookie_domain(c), &domain))
+        .map(cookie_info)
+        .collect())
+}
+
+/// Delete the cookies of `domain` and its subdomains. Returns how many went.
+#[tauri::command]
+pub async fn delete_cookies(app: tauri::AppHandle, domain: String) -> Result<u32, BrsrError> {
+    let domain = bare_domain(&domain);
+    let deleted = delete_cookies_where(&helper(&app)?, |d| within(d, &domain))?;
+    println!("[Rust] Deleted {} cookie(s) for {}", deleted, domain);
+    Ok(deleted)
+}
+
+/// Forget the origin of `url`: the cookies it gets, its storage and caches,
+/// and its site permissions.
+#[tauri::command]
+pub async fn clear_site_data(app: tauri::AppHandle, url: String) -> Result<ClearedData, BrsrError> {
+    let origin = web_origin(&url)?;
+    let host = origin.host_str().unwrap_or_default().to_ascii_lowercase();
+    let mut cleared = ClearedData {
+        cookies: delete_cookies_where(&helper(&app)?, visible_to(&host))?,
+        ..Default::default()
+    };
+    let kinds = Kinds { storage: true, cache: true };
+    clear_sites(&app, kinds, Sites::Only(vec![origin.clone()]), &mut cleared).await?;
+    app.state::<SitePermissionStore>()
+        .forget(&app, &origin.origin().ascii_serialization())?;
+    println!("[Rust] Cleared site data for {}", origin);
+    Ok(cleared)
+}
+
+/// Clear the chosen `categories` of browsing data for `range` (all time when
+/// it is open on both ends).
+#[tauri::command]
+pub async fn clear_browsing_data(
+    app: tauri::AppHandle,
+    range: TimeRange,
+    categories: Vec<DataCategory>,
+) -> Result<ClearedData, BrsrError> {
+    let has = |category| categories.contains(&category);
+    let all_time = range.start.is_none() && range.end.is_none();
+    let mut cleared = ClearedData::default();
+
+    if has(DataCategory::Cookies) || has(DataCategory::Storage) || has(DataCategory::Cache) {
+        let webview = helper(&app)?;
+        let kinds = Kinds {
+            storage: has(DataCategory::Storage),
+            cache: has(DataCategory::Cache),
+        };
+        let shared = app.state::<ProfileManager>().shares_ui_store();
+        let everything = has(DataCategory::Cookies) && kinds.storage && kinds.cache;
+        if all_time && everything && !shared {
+            cleared.cookies = webview.cookies().map(|c| c.len() as u32).unwrap_or(0);
+            webview
+                .clear_all_browsing_data()
+                .map_err(|e| BrsrError::platform(format!("Failed to clear browsing data: {}", e)))?;
+            cleared.whole_store = true;
+        } else {
+            // Read before history is cleared below.
+            let origins: Vec<Url> = app
+                .state::<HistoryStore>()
+                .visited_origins(range)?
+                .iter()
+                .filter_map(|o| Url::parse(o).ok())
+                .collect();
+            let ui = ui_host(&app);
+            if has(DataCategory::Cookies) {
+                cleared.cookies = if all_time {
+                    delete_cookies_where(&webview, |d| ui.as_deref() != Some(d))?
+                } else {
+                    let hosts: Vec<String> = origins.iter().filter_map(|o| o.host_str().map(str::to_string)).collect();
+                    delete_cookies_where(&webview, |d| hosts.iter().any(|h| visible_to(h)(d)))?
+                };
+            }
+            if kinds.storage || kinds.cache {
+                let store_cleared = !shared
+                    && native::clear_store(&webview, kinds, range).await.unwrap_or_else(|e| {
+                        eprintln!("[Rust] Failed to clear the data store, clearing site by site: {}", e);
+                        false
+                    });
+                if store_cleared {
+                    cleared.whole_store = true;
+                } else {
+                    let sites = if all_time {
+                        Sites::AllBut { ui, visited: origins }
+                    } else {
+                        Sites::Only(origins)
+                    };
+                    clear_sites(&app, kinds, sites, &mut cleared).await?;
+                }
+            }
+        }
+    }
+
+    if has(DataCategory::History) {
+        let history = app.state::<HistoryStore>();
+        cleared.history_visits = history.delete(&HistoryDeleteTarget::Range { range })? as u32;
+    }
+    println!(
+        "[Rust] Cleared browsing data: {} cookie(s), {} origin(s), {} visit(s)",
+        cleared.cookies, cleared.origins, cleared.history_visits
+    );
+    Ok(cleared)
+}
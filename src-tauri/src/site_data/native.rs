@@ -0,0 +1,477 @@
+//! Removing storage and caches through the engine's own data store API:
+//! `WKWebsiteDataStore` on macOS, `WebKitWebsiteDataManager` on Linux and the
+//! WebView2 profile on Windows. All of them run on the main thread through
+//! `with_webview` and report back through a oneshot channel.
+//!
+//! WebKit lists its data by site (registrable domain), so a record covers an
+//! origin's subdomains as well. WebView2 can't list its data; there sites are
+//! cleared origin by origin through the DevTools protocol.
+
+use std::time::Duration;
+
+use tokio::sync::oneshot;
+use url::Url;
+
+use super::within;
+use crate::error::BrsrError;
+use crate::history::TimeRange;
+
+/// How long the engine may take to answer one request.
+const NATIVE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Which kinds of data to remove. Cookies go through the cookie API instead.
+#[derive(Debug, Clone, Copy)]
+pub struct Kinds {
+    /// localStorage, sessionStorage, IndexedDB, WebSQL and service workers.
+    pub storage: bool,
+    /// Cache Storage and the HTTP caches.
+    pub cache: bool,
+}
+
+/// Which sites lose their data.
+#[derive(Debug, Clone)]
+pub enum Sites {
+    /// The sites of these origins.
+    Only(Vec<Url>),
+    /// Every site but the one of the app's UI (`ui`, a host). `visited` are
+    /// the origins to go through where the store can't be listed.
+    AllBut { ui: Option<String>, visited: Vec<Url> },
+}
+
+impl Sites {
+    /// Origins to clear one by one.
+    pub fn origins(&self) -> &[Url] {
+        match self {
+            Sites::Only(origins) => origins,
+            Sites::AllBut { visited, .. } => visited,
+        }
+    }
+
+    /// Whether the store's record for site `name` goes.
+    #[cfg_attr(not(any(target_os = "macos", target_os = "linux")), allow(dead_code))]
+    fn picks(&self, name: &str) -> bool {
+        let name = name.to_ascii_lowercase();
+        match self {
+            Sites::Only(origins) => origins
+                .iter()
+                .filter_map(|o| o.host_str())
+                .any(|host| within(&host.to_ascii_lowercase(), &name)),
+            Sites::AllBut { ui, .. } => !ui.as_deref().is_some_and(|ui| within(ui, &name) || within(&name, ui)),
+        }
+    }
+}
+
+/// Start of `range` in milliseconds (0 for all time), when it runs up to now.
+/// The stores can only drop what changed since a point in time.
+#[cfg_attr(not(any(target_os = "macos", target_os = "linux")), allow(dead_code))]
+fn since(range: TimeRange) -> Option<u64> {
+    match range.end {
+        Some(_) => None,
+        None => Some(range.start.unwrap_or(0)),
+    }
+}
+
+/// Wait for the engine's answer on `result`.
+async fn answer<T>(result: oneshot::Receiver<Result<T, BrsrError>>) -> Result<T, BrsrError> {
+    match tokio::time::timeout(NATIVE_TIMEOUT, result).await {
+        Ok(Ok(answer)) => answer,
+        Ok(Err(_)) => Err(BrsrError::platform("The webview dropped a site data request")),
+        Err(_) => Err(BrsrError::platform("The webview didn't answer a site data request in time")),
+    }
+}
+
+/// Remove `kinds` of data of `sites` from the store of `webview`. Returns how
+/// many sites (or origins) lost data, or `None` where this platform can't.
+pub async fn remove_sites(webview: &tauri::Webview, kinds: Kinds, sites: Sites) -> Result<Option<u32>, BrsrError> {
+    platform::remove_sites(webview, kinds, sites).await
+}
+
+/// Remove `kinds` of data changed in `range` from the whole store of
+/// `webview`. `false` where this platform can't for that range.
+pub async fn clear_store(webview: &tauri::Webview, kinds: Kinds, range: TimeRange) -> Result<bool, BrsrError> {
+    platform::clear_store(webview, kinds, range).await
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::cell::Cell;
+    use std::ffi::CStr;
+    use std::os::raw::c_char;
+
+    use block::ConcreteBlock;
+    use objc::runtime::Object;
+    use objc::{class, msg_send, sel, sel_impl};
+    use objc_foundation::{INSString, NSString};
+    use tokio::sync::oneshot;
+
+    use super::{answer, since, Kinds, Sites};
+    use crate::error::BrsrError;
+    use crate::history::TimeRange;
+
+    /// The set of `WKWebsiteDataType…` for `kinds`; the constants' values are
+    /// their names.
+    unsafe fn data_types(kinds: Kinds) -> *mut Object {
+        let mut names = Vec::new();
+        if kinds.storage {
+            names.extend([
+                "WKWebsiteDataTypeLocalStorage",
+                "WKWebsiteDataTypeSessionStorage",
+                "WKWebsiteDataTypeIndexedDBDatabases",
+                "WKWebsiteDataTypeWebSQLDatabases",
+                "WKWebsiteDataTypeServiceWorkerRegistrations",
+            ]);
+        }
+        if kinds.cache {
+            names.extend([
+                "WKWebsiteDataTypeFetchCache",
+                "WKWebsiteDataTypeDiskCache",
+                "WKWebsiteDataTypeMemoryCache",
+                "WKWebsiteDataTypeOfflineWebApplicationCache",
+            ]);
+        }
+        let strings: Vec<_> = names.into_iter().map(NSString::from_str).collect();
+        let objects: Vec<*const NSString> = strings.iter().map(|s| &**s as *const NSString).collect();
+        msg_send![class!(NSSet), setWithObjects: objects.as_ptr() count: objects.len()]
+    }
+
+    unsafe fn data_store(view: *mut std::ffi::c_void) -> *mut Object {
+        let configuration: *mut Object = msg_send![view as *mut Object, configuration];
+        msg_send![configuration, websiteDataStore]
+    }
+
+    unsafe fn to_string(string: *mut Object) -> Option<String> {
+        if string.is_null() {
+            return None;
+        }
+        let bytes: *const c_char = msg_send![string, UTF8String];
+        (!bytes.is_null()).then(|| CStr::from_ptr(bytes).to_string_lossy().into_owned())
+    }
+
+    pub async fn remove_sites(webview: &tauri::Webview, kinds: Kinds, sites: Sites) -> Result<Option<u32>, BrsrError> {
+        let (done, result) = oneshot::channel();
+        webview.with_webview(move |platform| unsafe {
+            let store = data_store(platform.inner());
+            let done = Cell::new(Some(done));
+            let fetched = ConcreteBlock::new(move |records: *mut Object| {
+                let picked: *mut Object = msg_send![class!(NSMutableArray), array];
+                let count: usize = msg_send![records, count];
+                for i in 0..count {
+                    let record: *mut Object = msg_send![records, objectAtIndex: i];
+                    let name: *mut Object = msg_send![record, displayName];
+                    if to_string(name).is_some_and(|name| sites.picks(&name)) {
+                        let _: () = msg_send![picked, addObject: record];
+                    }
+                }
+                let removed: usize = msg_send![picked, count];
+                let done = Cell::new(done.take());
+                let finished = ConcreteBlock::new(move || {
+                    if let Some(done) = done.take() {
+                        let _ = done.send(Ok(removed as u32));
+                    }
+                })
+                .copy();
+                let _: () = msg_send![
+                    store,
+                    removeDataOfTypes: data_types(kinds)
+                    forDataRecords: picked
+                    completionHandler: &*finished
+                ];
+            })
+            .copy();
+            let _: () = msg_send![store, fetchDataRecordsOfTypes: data_types(kinds) completionHandler: &*fetched];
+        })?;
+        answer(result).await.map(Some)
+    }
+
+    pub async fn clear_store(webview: &tauri::Webview, kinds: Kinds, range: TimeRange) -> Result<bool, BrsrError> {
+        let Some(start) = since(range) else {
+            return Ok(false);
+        };
+        let (done, result) = oneshot::channel();
+        webview.with_webview(move |platform| unsafe {
+            let store = data_store(platform.inner());
+            let date: *mut Object = msg_send![class!(NSDate), dateWithTimeIntervalSince1970: start as f64 / 1000.0];
+            let done = Cell::new(Some(done));
+            let finished = ConcreteBlock::new(move || {
+                if let Some(done) = done.take() {
+                    let _ = done.send(Ok(()));
+                }
+            })
+            .copy();
+            let _: () = msg_send![
+                store,
+                removeDataOfTypes: data_types(kinds)
+                modifiedSince: date
+                completionHandler: &*finished
+            ];
+        })?;
+        answer(result).await.map(|()| true)
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use tokio::sync::oneshot;
+    use webkit2gtk::gio::Cancellable;
+    use webkit2gtk::glib::TimeSpan;
+    use webkit2gtk::{WebViewExt, WebsiteData, WebsiteDataManagerExt, WebsiteDataTypes};
+
+    use super::{answer, since, Kinds, Sites};
+    use crate::error::BrsrError;
+    use crate::history::TimeRange;
+    use crate::tabs::now_ms;
+
+    fn data_types(kinds: Kinds) -> WebsiteDataTypes {
+        let mut types = WebsiteDataTypes::empty();
+        if kinds.storage {
+            types |= WebsiteDataTypes::LOCAL_STORAGE
+                | WebsiteDataTypes::SESSION_STORAGE
+                | WebsiteDataTypes::INDEXEDDB_DATABASES
+                | WebsiteDataTypes::WEBSQL_DATABASES
+                | WebsiteDataTypes::SERVICE_WORKER_REGISTRATIONS;
+        }
+        if kinds.cache {
+            types |= WebsiteDataTypes::DOM_CACHE
+                | WebsiteDataTypes::DISK_CACHE
+                | WebsiteDataTypes::MEMORY_CACHE
+                | WebsiteDataTypes::OFFLINE_APPLICATION_CACHE;
+        }
+        types
+    }
+
+    fn engine_error(error: impl std::fmt::Display) -> BrsrError {
+        BrsrError::platform(format!("Failed to clear site data: {}", error))
+    }
+
+    pub async fn remove_sites(webview: &tauri::Webview, kinds: Kinds, sites: Sites) -> Result<Option<u32>, BrsrError> {
+        let (done, result) = oneshot::channel();
+        webview.with_webview(move |platform| {
+            let Some(manager) = platform.inner().website_data_manager() else {
+                let _ = done.send(Err(BrsrError::platform("The webview has no website data manager")));
+                return;
+            };
+            let types = data_types(kinds);
+            let remover = manager.clone();
+            manager.fetch(types, None::<&Cancellable>, move |records| {
+                let records = match records {
+                    Ok(records) => records,
+                    Err(e) => {
+                        let _ = done.send(Err(engine_error(e)));
+                        return;
+                    }
+                };
+                let picked: Vec<WebsiteData> = records
+                    .into_iter()
+                    .filter(|record| record.name().is_some_and(|name| sites.picks(&name)))
+                    .collect();
+                if picked.is_empty() {
+                    let _ = done.send(Ok(0));
+                    return;
+                }
+                let removed = picked.len() as u32;
+                remover.remove(types, &picked, None::<&Cancellable>, move |outcome| {
+                    let _ = done.send(outcome.map(|()| removed).map_err(engine_error));
+                });
+            });
+        })?;
+        answer(result).await.map(Some)
+    }
+
+    pub async fn clear_store(webview: &tauri::Webview, kinds: Kinds, range: TimeRange) -> Result<bool, BrsrError> {
+        let Some(start) = since(range) else {
+            return Ok(false);
+        };
+        // Microseconds back from now; 0 means everything.
+        let span = match start {
+            0 => 0,
+            start => now_ms().saturating_sub(start).max(1) as i64 * 1000,
+        };
+        let (done, result) = oneshot::channel();
+        webview.with_webview(move |platform| {
+            let Some(manager) = platform.inner().website_data_manager() else {
+                let _ = done.send(Err(BrsrError::platform("The webview has no website data manager")));
+                return;
+            };
+            manager.clear(data_types(kinds), TimeSpan(span), None::<&Cancellable>, move |outcome| {
+                let _ = done.send(outcome.map_err(engine_error));
+            });
+        })?;
+        answer(result).await.map(|()| true)
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use tokio::sync::oneshot;
+    use webview2_com::Microsoft::Web::WebView2::Win32::{
+        ICoreWebView2Profile2, ICoreWebView2_13, COREWEBVIEW2_BROWSING_DATA_KINDS,
+        COREWEBVIEW2_BROWSING_DATA_KINDS_CACHE_STORAGE, COREWEBVIEW2_BROWSING_DATA_KINDS_DISK_CACHE,
+        COREWEBVIEW2_BROWSING_DATA_KINDS_FILE_SYSTEMS, COREWEBVIEW2_BROWSING_DATA_KINDS_INDEXED_DB,
+        COREWEBVIEW2_BROWSING_DATA_KINDS_LOCAL_STORAGE, COREWEBVIEW2_BROWSING_DATA_KINDS_SERVICE_WORKERS,
+        COREWEBVIEW2_BROWSING_DATA_KINDS_WEB_SQL,
+    };
+    use webview2_com::{CallDevToolsProtocolMethodCompletedHandler, ClearBrowsingDataCompletedHandler};
+    use windows::core::{Interface, HSTRING};
+
+    use super::{answer, Kinds, Sites};
+    use crate::error::BrsrError;
+    use crate::history::TimeRange;
+    use crate::tabs::now_ms;
+
+    fn data_kinds(kinds: Kinds) -> COREWEBVIEW2_BROWSING_DATA_KINDS {
+        let mut bits = 0;
+        if kinds.storage {
+            bits |= COREWEBVIEW2_BROWSING_DATA_KINDS_FILE_SYSTEMS.0
+                | COREWEBVIEW2_BROWSING_DATA_KINDS_INDEXED_DB.0
+                | COREWEBVIEW2_BROWSING_DATA_KINDS_LOCAL_STORAGE.0
+                | COREWEBVIEW2_BROWSING_DATA_KINDS_WEB_SQL.0
+                | COREWEBVIEW2_BROWSING_DATA_KINDS_SERVICE_WORKERS.0;
+        }
+        if kinds.cache {
+            bits |= COREWEBVIEW2_BROWSING_DATA_KINDS_CACHE_STORAGE.0 | COREWEBVIEW2_BROWSING_DATA_KINDS_DISK_CACHE.0;
+        }
+        COREWEBVIEW2_BROWSING_DATA_KINDS(bits)
+    }
+
+    /// `storageTypes` of the DevTools `Storage.clearDataForOrigin` command.
+    fn storage_types(kinds: Kinds) -> String {
+        let mut types = Vec::new();
+        if kinds.storage {
+            types.extend(["local_storage", "indexeddb", "websql", "file_systems", "service_workers"]);
+        }
+        if kinds.cache {
+            types.push("cache_storage");
+        }
+        types.join(",")
+    }
+
+    fn engine_error(error: windows::core::Error) -> BrsrError {
+        BrsrError::platform(format!("Failed to clear site data: {}", error))
+    }
+
+    /// Origin by origin; the HTTP cache isn't kept per origin and stays.
+    pub async fn remove_sites(webview: &tauri::Webview, kinds: Kinds, sites: Sites) -> Result<Option<u32>, BrsrError> {
+        let types = storage_types(kinds);
+        let mut requests = Vec::new();
+        let mut results = Vec::new();
+        for origin in sites.origins() {
+            let (done, result) = oneshot::channel();
+            let params = serde_json::json!({ "origin": origin.origin().ascii_serialization(), "storageTypes": types });
+            requests.push((params.to_string(), done));
+            results.push(result);
+        }
+        webview.with_webview(move |platform| {
+            let core = match unsafe { platform.controller().CoreWebView2() } {
+                Ok(core) => core,
+                Err(e) => {
+                    for (_, done) in requests {
+                        let _ = done.send(Err(engine_error(e.clone())));
+                    }
+                    return;
+                }
+            };
+            for (params, done) in requests {
+                let done = std::sync::Mutex::new(Some(done));
+                let handler = CallDevToolsProtocolMethodCompletedHandler::create(Box::new(move |outcome, _| {
+                    if let Some(done) = done.lock().unwrap().take() {
+                        let _ = done.send(outcome.map_err(engine_error));
+                    }
+                    Ok(())
+                }));
+                let method = HSTRING::from("Storage.clearDataForOrigin");
+                if let Err(e) = unsafe { core.CallDevToolsProtocolMethod(&method, &HSTRING::from(params), &handler) } {
+                    eprintln!("[Rust] DevTools call to clear site data failed: {}", e);
+                }
+            }
+        })?;
+        let mut removed = 0;
+        for result in results {
+            answer(result).await?;
+            removed += 1;
+        }
+        Ok(Some(removed))
+    }
+
+    pub async fn clear_store(webview: &tauri::Webview, kinds: Kinds, range: TimeRange) -> Result<bool, BrsrError> {
+        let start = range.start.unwrap_or(0) as f64 / 1000.0;
+        let end = range.end.unwrap_or_else(now_ms) as f64 / 1000.0;
+        let (done, result) = oneshot::channel();
+        webview.with_webview(move |platform| {
+            let profile = unsafe { platform.controller().CoreWebView2() }
+                .and_then(|core| core.cast::<ICoreWebView2_13>())
+                .and_then(|core| unsafe { core.Profile() })
+                .and_then(|profile| profile.cast::<ICoreWebView2Profile2>());
+            let profile = match profile {
+                Ok(profile) => profile,
+                Err(e) => {
+                    let _ = done.send(Err(engine_error(e)));
+                    return;
+                }
+            };
+            let done = std::sync::Mutex::new(Some(done));
+            let handler = ClearBrowsingDataCompletedHandler::create(Box::new(move |outcome| {
+                if let Some(done) = done.lock().unwrap().take() {
+                    let _ = done.send(outcome.map_err(engine_error));
+                }
+                Ok(())
+            }));
+            if let Err(e) = unsafe { profile.ClearBrowsingDataInTimeRange(data_kinds(kinds), start, end, &handler) } {
+                eprintln!("[Rust] Clearing browsing data failed: {}", e);
+            }
+        })?;
+        answer(result).await.map(|()| true)
+    }
+}
+
+/// No engine API for the store here: callers fall back to clearing from a page.
+#[cfg(not(any(target_os = "macos", target_os = "linux", windows)))]
+mod platform {
+    use super::{Kinds, Sites};
+    use crate::error::BrsrError;
+    use crate::history::TimeRange;
+
+    pub async fn remove_sites(_webview: &tauri::Webview, _kinds: Kinds, _sites: Sites) -> Result<Option<u32>, BrsrError> {
+        Ok(None)
+    }
+
+    pub async fn clear_store(_webview: &tauri::Webview, _kinds: Kinds, _range: TimeRange) -> Result<bool, BrsrError> {
+        Ok(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn origins(urls: &[&str]) -> Vec<Url> {
+        urls.iter().map(|u| Url::parse(u).unwrap()).collect()
+    }
+
+    #[test]
+    fn site_records_cover_the_origins_subdomains() {
+        let sites = Sites::Only(origins(&["https://www.example.com", "http://news.site.org"]));
+        assert!(sites.picks("example.com"));
+        assert!(sites.picks("Site.org"));
+        assert!(!sites.picks("ample.com"));
+        assert!(!sites.picks("other.net"));
+    }
+
+    #[test]
+    fn clearing_everything_keeps_the_ui_site() {
+        let sites = Sites::AllBut {
+            ui: Some("localhost".into()),
+            visited: origins(&["https://example.com"]),
+        };
+        assert!(!sites.picks("localhost"));
+        assert!(sites.picks("example.com"));
+        assert!(sites.picks("never-visited.org"));
+        assert_eq!(sites.origins().len(), 1);
+    }
+
+    #[test]
+    fn stores_only_clear_ranges_that_run_up_to_now() {
+        assert_eq!(since(TimeRange { start: None, end: None }), Some(0));
+        assert_eq!(since(TimeRange { start: Some(5), end: None }), Some(5));
+        assert_eq!(since(TimeRange { start: Some(5), end: Some(10) }), None);
+    }
+}
@@ -0,0 +1,106 @@
+//! Small helpers for the JSON files we keep under the app data directory and
+//! the running profile's data directory.
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tauri::Manager;
+
+use crate::error::BrsrError;
+use crate::profiles::ProfileManager;
+
+/// `<profile data dir>/<name>` for the profile this instance runs, creating
+/// the directory if needed.
+pub fn data_file<R: tauri::Runtime>(app: &tauri::AppHandle<R>, name: &str) -> Result<PathBuf, BrsrError> {
+    let dir = app.state::<ProfileManager>().data_dir()?;
+    fs::create_dir_all(&dir).map_err(|e| BrsrError::io(&dir, e))?;
+    Ok(dir.join(name))
+}
+
+/// `<app data dir>/<name>`, shared by all profiles, creating the directory if needed.
+pub fn app_file<R: tauri::Runtime>(app: &tauri::AppHandle<R>, name: &str) -> Result<PathBuf, BrsrError> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| BrsrError::platform(format!("Failed to resolve app data dir: {}", e)))?;
+    fs::create_dir_all(&dir).map_err(|e| BrsrError::io(&dir, e))?;
+    Ok(dir.join(name))
+}
+
+/// The app data directory as Tauri resolves it for `identifier`, for what
+/// needs it before the app is built.
+pub fn app_data_dir(identifier: &str) -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join(identifier))
+}
+
+/// Read a JSON file, falling back to `T::default()` when it is missing or
+/// unreadable. A malformed file is moved aside to `<name>.corrupt-<unix time>`
+/// first, so the next save doesn't overwrite what might still be recovered.
+pub fn load_json<T: DeserializeOwned + Default>(path: &Path) -> T {
+    match fs::read(path) {
+        Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+            eprintln!("[Rust] Ignoring malformed {}: {}", path.display(), e);
+            set_aside(path);
+            T::default()
+        }),
+        Err(_) => T::default(),
+    }
+}
+
+fn set_aside(path: &Path) {
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".corrupt-{}", secs));
+    let aside = path.with_file_name(name);
+    match fs::rename(path, &aside) {
+        Ok(()) => eprintln!("[Rust] Moved it to {}", aside.display()),
+        Err(e) => eprintln!("[Rust] Could not move it aside: {}", e),
+    }
+}
+
+/// Write `value` as JSON without ever leaving a half-written file behind:
+/// the data goes to a sibling temp file, is fsynced, then renamed over `path`.
+pub fn save_json<T: Serialize>(path: &Path, value: &T) -> Result<(), BrsrError> {
+    let bytes = serde_json::to_vec_pretty(value).map_err(|e| BrsrError::io(path, e))?;
+    write_atomic(path, &bytes)
+}
+
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> Result<(), BrsrError> {
+    let tmp = path.with_extension("tmp");
+    let write = || -> std::io::Result<()> {
+        let mut file = fs::File::create(&tmp)?;
+        file.write_all(bytes)?;
+        file.sync_all()?;
+        fs::rename(&tmp, path)
+    };
+    write().map_err(|e| {
+        let _ = fs::remove_file(&tmp);
+        BrsrError::io(path, format!("write failed: {}", e))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn malformed_files_are_moved_aside() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+        fs::write(&path, b"{ not json").unwrap();
+        let value: Vec<String> = load_json(&path);
+        assert!(value.is_empty());
+        assert!(!path.exists());
+        let names: Vec<String> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names.len(), 1);
+        assert!(names[0].starts_with("settings.json.corrupt-"), "{:?}", names);
+        assert_eq!(fs::read(dir.path().join(&names[0])).unwrap(), b"{ not json");
+    }
+}
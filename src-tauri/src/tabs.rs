@@ -0,0 +1,213 @@
+//! Rust-side registry of the child webviews (tabs) created through
+//! `create_browser_webview`.
+//!
+//! The React side used to be the only place that knew which native webviews
+//! existed. The registry is now the authoritative record: it is updated from
+//! the webview builder hooks and from the show/hide/bounds commands, and it can
+//! be queried so a reloaded frontend can rebuild its tab strip.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::BrsrError;
+use crate::page_load::PageLoad;
+use crate::scripts::ProbeToggles;
+use crate::session_history::SessionHistory;
+
+/// Milliseconds since the Unix epoch, matching `Date.now()` on the JS side.
+pub fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Logical (CSS pixel) position and size of a child webview.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Bounds {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// Document scroll offset, reported by the injected script.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ScrollPosition {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// Snapshot of a single child webview as tracked by the registry.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TabState {
+    pub label: String,
+    pub url: String,
+    pub title: String,
+    pub visible: bool,
+    pub bounds: Bounds,
+    pub created_at: u64,
+    pub last_active_at: u64,
+    pub pinned: bool,
+    /// Private tab: its own non-persistent data store, and nothing it does is
+    /// written to history, the session or other files.
+    pub private: bool,
+    pub scroll: ScrollPosition,
+    /// Back/forward list; served separately through `get_session_history`.
+    #[serde(skip)]
+    pub history: SessionHistory,
+    /// The next committed load was requested from the omnibox (`navigate_webview`).
+    #[serde(skip)]
+    pub typed_navigation: bool,
+    /// Scroll offset to apply once the current load finishes (restored tabs).
+    #[serde(skip)]
+    pub pending_scroll: Option<ScrollPosition>,
+    /// Per-tab telemetry probe overrides baked into its injected script.
+    #[serde(skip)]
+    pub probes: ProbeToggles,
+    /// Where the current page load is; see `page_load`.
+    #[serde(skip)]
+    pub load: PageLoad,
+}
+
+impl TabState {
+    pub fn new(label: String, url: String, bounds: Bounds) -> Self {
+        let now = now_ms();
+        let history = SessionHistory::new(&url);
+        Self {
+            label,
+            url,
+            title: String::new(),
+            visible: true,
+            bounds,
+            created_at: now,
+            last_active_at: now,
+            pinned: false,
+            private: false,
+            scroll: ScrollPosition::default(),
+            history,
+            typed_navigation: false,
+            pending_scroll: None,
+            probes: ProbeToggles::new(),
+            load: PageLoad::default(),
+        }
+    }
+}
+
+/// Managed Tauri state holding every child webview we created, keyed by label.
+#[derive(Default)]
+pub struct TabRegistry {
+    tabs: Mutex<HashMap<String, TabState>>,
+}
+
+impl TabRegistry {
+    pub fn insert(&self, tab: TabState) {
+        self.tabs.lock().unwrap().insert(tab.label.clone(), tab);
+    }
+
+    pub fn remove(&self, label: &str) -> Option<TabState> {
+        self.tabs.lock().unwrap().remove(label)
+    }
+
+    pub fn contains(&self, label: &str) -> bool {
+        self.tabs.lock().unwrap().contains_key(label)
+    }
+
+    pub fn get(&self, label: &str) -> Option<TabState> {
+        self.tabs.lock().unwrap().get(label).cloned()
+    }
+
+    /// Apply `f` to the tab with `label`. Returns `false` if it isn't registered.
+    pub fn update<F: FnOnce(&mut TabState)>(&self, label: &str, f: F) -> bool {
+        match self.tabs.lock().unwrap().get_mut(label) {
+            Some(tab) => {
+                f(tab);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// All registered tabs in creation order.
+    pub fn list(&self) -> Vec<TabState> {
+        let mut tabs: Vec<TabState> = self.tabs.lock().unwrap().values().cloned().collect();
+        tabs.sort_by(|a, b| a.created_at.cmp(&b.created_at).then_with(|| a.label.cmp(&b.label)));
+        tabs
+    }
+
+    /// Whether `label` is a private tab. Unknown labels count as private, so
+    /// late events from a closed tab are never persisted.
+    pub fn is_private(&self, label: &str) -> bool {
+        self.tabs.lock().unwrap().get(label).is_none_or(|tab| tab.private)
+    }
+
+    pub fn any_private(&self) -> bool {
+        self.tabs.lock().unwrap().values().any(|tab| tab.private)
+    }
+
+    pub fn set_url(&self, label: &str, url: &str) {
+        self.update(label, |tab| tab.url = url.to_string());
+    }
+
+    pub fn set_title(&self, label: &str, title: &str) {
+        self.update(label, |tab| {
+            tab.title = title.to_string();
+            tab.history.set_title(title);
+        });
+    }
+
+    pub fn set_visible(&self, label: &str, visible: bool) {
+        self.update(label, |tab| {
+            tab.visible = visible;
+            if visible {
+                tab.last_active_at = now_ms();
+            }
+        });
+    }
+
+    pub fn set_bounds(&self, label: &str, bounds: Bounds) {
+        self.update(label, |tab| tab.bounds = bounds);
+    }
+
+    /// The tab the user is looking at: the most recently shown one.
+    pub fn active_label(&self) -> Option<String> {
+        self.tabs
+            .lock()
+            .unwrap()
+            .values()
+            .max_by_key(|tab| (tab.visible, tab.last_active_at))
+            .map(|tab| tab.label.clone())
+    }
+}
+
+#[tauri::command]
+pub fn list_webviews(registry: tauri::State<'_, TabRegistry>) -> Result<Vec<TabState>, BrsrError> {
+    Ok(registry.list())
+}
+
+#[tauri::command]
+pub fn set_tab_pinned(
+    registry: tauri::State<'_, TabRegistry>,
+    label: String,
+    pinned: bool,
+) -> Result<(), BrsrError> {
+    registry
+        .update(&label, |tab| tab.pinned = pinned)
+        .then_some(())
+        .ok_or_else(|| BrsrError::not_found("tab", &label))
+}
+
+#[tauri::command]
+pub fn get_webview_state(
+    registry: tauri::State<'_, TabRegistry>,
+    label: String,
+) -> Result<TabState, BrsrError> {
+    registry
+        .get(&label)
+        .ok_or_else(|| BrsrError::not_found("tab", &label))
+}
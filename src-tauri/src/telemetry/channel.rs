@@ -0,0 +1,334 @@
+//! The only IPC channel remote pages in tabs can use.
+//!
+//! Tabs are granted a single permission, `bridge:allow-ingest`, for the
+//! `ingest` command of the inline `bridge` plugin; they can't emit events or
+//! reach any other plugin. Each call is checked before its payload is parsed:
+//!
+//! - the sender is a registered tab, identified by the webview Tauri reports
+//!   rather than anything in the payload;
+//! - the webview is on an http(s) page and the origin the script reports
+//!   matches it;
+//! - the message is signed with the key baked into that webview's injected
+//!   script;
+//! - it isn't a repeat, nor from a document the tab has moved past;
+//! - the tab is within its rate limit.
+//!
+//! Accepted payloads then go through the usual [`super::validate`] checks,
+//! which also reject a message claiming another tab's label.
+//!
+//! The injected script shares a JavaScript realm with the page, and the IPC
+//! request goes out through the page's `fetch`, so the page sees every
+//! message. The key itself never leaves the script: `sign.js` turns it into
+//! HMAC-SHA-256 states before page scripts run, and signs with nothing a page
+//! can replace afterwards. A page can still hold back or delay the script's
+//! messages, and steer what the script reports about it, but it can't send
+//! one of its own.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use tauri::{Emitter, Manager};
+
+use super::{DropReason, Telemetry};
+use crate::error::BrsrError;
+use crate::tabs::TabRegistry;
+
+/// Name of the inline plugin; the command is `plugin:bridge|ingest`.
+pub const PLUGIN: &str = "bridge";
+/// What the injected script invokes.
+pub const COMMAND: &str = "plugin:bridge|ingest";
+
+/// Messages a tab may send in a burst, e.g. resource timings on a heavy page load.
+const BURST: f64 = 400.0;
+/// Sustained messages per second per tab.
+const PER_SECOND: f64 = 100.0;
+/// Recent documents of a tab, whose messages are no longer accepted once a
+/// newer one has been heard from.
+const DOCUMENTS: usize = 16;
+/// How far behind the newest message of a document one may arrive.
+const WINDOW: u64 = 64;
+
+/// 128 bits from the OS random number generator, as hex.
+pub(crate) fn random_token() -> String {
+    let mut bytes = [0u8; 16];
+    getrandom::fill(&mut bytes).expect("OS random number generator");
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl Bucket {
+    fn take(&mut self) -> bool {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * PER_SECOND).min(BURST);
+        self.refilled_at = now;
+        if self.tokens < 1.0 {
+            return false;
+        }
+        self.tokens -= 1.0;
+        true
+    }
+}
+
+/// What the channel knows about the script in one webview.
+struct Sender {
+    key: String,
+    /// Document ids, newest last.
+    documents: VecDeque<String>,
+    /// Highest sequence number heard from the newest document, with one bit
+    /// per number up to [`WINDOW`] below it for those heard too.
+    newest: Option<u64>,
+    heard: u64,
+}
+
+impl Sender {
+    fn new(key: String) -> Self {
+        Self {
+            key,
+            documents: VecDeque::new(),
+            newest: None,
+            heard: 0,
+        }
+    }
+
+    /// Whether `mac` is the script's signature over `text`.
+    fn signed(&self, text: &str, mac: &str) -> bool {
+        let Some(mac) = decode_hex(mac) else {
+            return false;
+        };
+        let mut hmac = Hmac::<Sha256>::new_from_slice(self.key.as_bytes()).expect("HMAC takes any key length");
+        hmac.update(text.as_bytes());
+        hmac.verify_slice(&mac).is_ok()
+    }
+
+    /// Whether message `seq` of `document` is new, noting it if so.
+    fn fresh(&mut self, document: &str, seq: u64) -> bool {
+        if self.documents.back().map(String::as_str) != Some(document) {
+            if self.documents.iter().any(|d| d == document) {
+                return false;
+            }
+            if self.documents.len() == DOCUMENTS {
+                self.documents.pop_front();
+            }
+            self.documents.push_back(document.to_string());
+            self.newest = None;
+        }
+        match self.newest {
+            Some(newest) if seq <= newest => {
+                let behind = newest - seq;
+                if behind >= WINDOW || self.heard & (1 << behind) != 0 {
+                    return false;
+                }
+                self.heard |= 1 << behind;
+            }
+            Some(newest) => {
+                let ahead = seq - newest;
+                self.heard = if ahead >= WINDOW { 0 } else { self.heard << ahead };
+                self.heard |= 1;
+                self.newest = Some(seq);
+            }
+            None => {
+                self.heard = 1;
+                self.newest = Some(seq);
+            }
+        }
+        true
+    }
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// Managed state: per-webview script keys and per-tab rate limits.
+#[derive(Default)]
+pub struct Channel {
+    senders: Mutex<HashMap<String, Sender>>,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl Channel {
+    /// A fresh key for the script about to be injected into `label`,
+    /// replacing the one of any earlier webview with that label.
+    pub fn issue(&self, label: &str) -> String {
+        let key = random_token();
+        self.senders.lock().unwrap().insert(label.to_string(), Sender::new(key.clone()));
+        key
+    }
+
+    pub fn remove(&self, label: &str) {
+        self.senders.lock().unwrap().remove(label);
+        self.buckets.lock().unwrap().remove(label);
+    }
+
+    /// Whether `call` is a new message signed by the script in `label`.
+    fn authentic(&self, label: &str, call: &Call) -> bool {
+        let mut senders = self.senders.lock().unwrap();
+        let Some(sender) = senders.get_mut(label) else {
+            return false;
+        };
+        let text = format!("{}.{}.{}", call.document, call.seq, call.payload);
+        sender.signed(&text, &call.mac) && sender.fresh(&call.document, call.seq)
+    }
+
+    fn take(&self, label: &str) -> bool {
+        self.buckets
+            .lock()
+            .unwrap()
+            .entry(label.to_string())
+            .or_insert_with(|| Bucket {
+                tokens: BURST,
+                refilled_at: Instant::now(),
+            })
+            .take()
+    }
+}
+
+/// One `ingest` call as the script makes it.
+struct Call {
+    origin: String,
+    document: String,
+    seq: u64,
+    payload: String,
+    mac: String,
+}
+
+/// The origin of the page `webview` is on, if it is a web page.
+fn web_origin<R: tauri::Runtime>(webview: &tauri::Webview<R>) -> Option<String> {
+    let url = webview.url().ok()?;
+    matches!(url.scheme(), "http" | "https").then(|| url.origin().ascii_serialization())
+}
+
+/// Checks that `webview` may send on the channel. Payload checks come after.
+fn admit<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    webview: &tauri::Webview<R>,
+    call: &Call,
+) -> Result<(), DropReason> {
+    let label = webview.label();
+    if !app.state::<TabRegistry>().contains(label) {
+        return Err(DropReason::UnknownTab);
+    }
+    let channel = app.state::<Channel>();
+    if web_origin(webview).as_deref() != Some(call.origin.as_str()) || !channel.authentic(label, call) {
+        return Err(DropReason::Rejected);
+    }
+    if !channel.take(label) {
+        return Err(DropReason::RateLimited);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+async fn ingest(
+    app: tauri::AppHandle,
+    webview: tauri::Webview,
+    origin: String,
+    document: String,
+    seq: u64,
+    payload: String,
+    mac: String,
+) -> Result<(), BrsrError> {
+    let telemetry = app.state::<Telemetry>();
+    let registry = app.state::<TabRegistry>();
+    let call = Call {
+        origin,
+        document,
+        seq,
+        payload,
+        mac,
+    };
+    if let Err(reason) = admit(&app, &webview, &call) {
+        telemetry.count_dropped(reason);
+        return Err(BrsrError::Policy {
+            url: None,
+            reason: format!("telemetry from '{}' rejected ({:?})", webview.label(), reason),
+        });
+    }
+    if let Some(message) = telemetry.ingest(&registry, webview.label(), &call.payload) {
+        crate::handle_telemetry(&app, &message);
+        let _ = app.emit_to("main", super::EVENT, &message);
+    }
+    Ok(())
+}
+
+pub fn init() -> tauri::plugin::TauriPlugin<tauri::Wry> {
+    tauri::plugin::Builder::new(PLUGIN)
+        .invoke_handler(tauri::generate_handler![ingest])
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signatures_match_the_scripts() {
+        // Computed by sign.js for this key and text.
+        let sender = Sender::new("00112233445566778899aabbccddeeff".into());
+        let mac = "485cbdb2a96e452c9da042fecbb6cb7fea564f6796a19478916881a906770bef";
+        assert!(sender.signed(r#"0123abcd.7.{"v":1}"#, mac));
+        assert!(!sender.signed(r#"0123abcd.8.{"v":1}"#, mac));
+        assert!(!sender.signed(r#"0123abcd.7.{"v":1}"#, &mac[..62]));
+        assert!(!sender.signed(r#"0123abcd.7.{"v":1}"#, "zz"));
+        assert!(!Sender::new(random_token()).signed(r#"0123abcd.7.{"v":1}"#, mac));
+    }
+
+    #[test]
+    fn repeats_and_older_documents_are_refused() {
+        let mut sender = Sender::new(random_token());
+        assert!(sender.fresh("a", 0));
+        assert!(sender.fresh("a", 2));
+        assert!(!sender.fresh("a", 2));
+        // Late, but within the window.
+        assert!(sender.fresh("a", 1));
+        assert!(!sender.fresh("a", 0));
+
+        assert!(sender.fresh("a", 100));
+        assert!(!sender.fresh("a", 100 - WINDOW));
+
+        assert!(sender.fresh("b", 0));
+        assert!(!sender.fresh("a", 101));
+        assert!(sender.fresh("b", 1));
+    }
+
+    #[test]
+    fn only_the_current_webviews_key_is_accepted() {
+        let channel = Channel::default();
+        let old = channel.issue("tab-1");
+        let key = channel.issue("tab-1");
+        assert_ne!(old, key);
+        let call = |key: &str, seq: u64| {
+            let payload = r#"{"v":1}"#.to_string();
+            let mut hmac = Hmac::<Sha256>::new_from_slice(key.as_bytes()).unwrap();
+            hmac.update(format!("doc.{}.{}", seq, payload).as_bytes());
+            let mac = hmac.finalize().into_bytes().iter().map(|b| format!("{:02x}", b)).collect();
+            Call {
+                origin: "https://example.com".into(),
+                document: "doc".into(),
+                seq,
+                payload,
+                mac,
+            }
+        };
+        assert!(!channel.authentic("tab-1", &call(&old, 0)));
+        assert!(channel.authentic("tab-1", &call(&key, 0)));
+        assert!(!channel.authentic("tab-1", &call(&key, 0)));
+        assert!(!channel.authentic("tab-2", &call(&key, 1)));
+        channel.remove("tab-1");
+        assert!(!channel.authentic("tab-1", &call(&key, 1)));
+    }
+}
@@ -0,0 +1,287 @@
+//! The `webview-telemetry` event schema shared by the injected script, the
+//! Rust handlers and the frontend.
+//!
+//! TypeScript definitions under `src/bindings/` are generated from these types
+//! by ts-rs (`npm run bindings`); edit the Rust side and regenerate rather than
+//! touching the `.ts` files.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::permissions::Permission;
+use crate::session_history::HistoryAction;
+
+/// Bumped whenever a variant or field changes incompatibly.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// One event as sent by the injected script: the envelope plus the event body.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct TelemetryMessage {
+    /// Schema version the script was built against.
+    pub v: u32,
+    pub label: String,
+    /// `Date.now()` in the page when the event was produced.
+    pub ts: f64,
+    #[serde(flatten)]
+    pub event: TelemetryEvent,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub enum TelemetryEvent {
+    /// The script finished installing itself.
+    Init,
+    /// Periodic ping with the current title and URL.
+    Heartbeat(Heartbeat),
+    PageInfo(PageInfo),
+    Selection(Selection),
+    Resource(Resource),
+    Paint(Paint),
+    Lcp(LargestContentfulPaint),
+    Navigation(NavigationTiming),
+    Longtask(LongTask),
+    Fetch(FetchRequest),
+    FetchError(FetchError),
+    Xhr(XhrRequest),
+    Console(ConsoleMessage),
+    Error(ScriptError),
+    #[serde(rename = "unhandledrejection")]
+    UnhandledRejection(UnhandledRejection),
+    /// Same-document `pushState`/`replaceState`/`popstate` change.
+    History(HistoryChange),
+    /// Debounced document scroll offset.
+    Scroll(Scroll),
+    /// A load failed that the content blocker may have stopped; Rust checks
+    /// it against the rules before counting it.
+    Blocked(BlockedRequest),
+    /// The page wants a permission that is set to "ask" for its origin.
+    PermissionRequest(PermissionRequest),
+    /// The document fired DOMContentLoaded.
+    DomReady(DomReady),
+    /// A form is being submitted by POST.
+    FormPost(FormPost),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct Heartbeat {
+    pub title: String,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct PageInfo {
+    pub title: String,
+    pub url: String,
+    pub lang: Option<String>,
+    /// `<meta name|property>` → `content`, first occurrence wins.
+    pub metas: HashMap<String, String>,
+    pub icons: Vec<String>,
+    pub theme_color: Option<String>,
+    pub links: Vec<PageLink>,
+    pub headings: Vec<PageHeading>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct PageLink {
+    pub href: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct PageHeading {
+    pub tag: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct Selection {
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct Resource {
+    pub item: ResourceTiming,
+}
+
+/// A `PerformanceResourceTiming` entry.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct ResourceTiming {
+    /// `initiatorType`: `img`, `script`, `fetch`, …
+    #[serde(rename = "type")]
+    pub initiator_type: String,
+    pub name: String,
+    pub duration: f64,
+    pub start_time: f64,
+    /// Missing on engines that don't expose it; reported as 0.
+    #[serde(default)]
+    pub transfer_size: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct Paint {
+    /// `first-paint` or `first-contentful-paint`.
+    pub name: String,
+    pub start_time: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct LargestContentfulPaint {
+    pub start_time: f64,
+    pub size: f64,
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct NavigationTiming {
+    pub dom_content_loaded: f64,
+    pub load_event_end: f64,
+    /// `navigate`, `reload`, `back_forward` or `prerender`.
+    #[serde(rename = "type")]
+    pub navigation_type: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct LongTask {
+    pub start_time: f64,
+    pub duration: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct FetchRequest {
+    pub url: String,
+    pub method: String,
+    pub status: u16,
+    pub duration: f64,
+    /// First 2000 characters of a JSON or text response body.
+    pub preview: Option<String>,
+    #[serde(default)]
+    pub content_type: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct FetchError {
+    pub url: String,
+    pub method: String,
+    pub error: String,
+    pub duration: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct XhrRequest {
+    pub url: String,
+    pub method: String,
+    /// 0 when the request failed or was aborted.
+    pub status: u16,
+    pub duration: f64,
+    #[serde(default)]
+    pub content_type: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "lowercase")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub enum ConsoleLevel {
+    Log,
+    Info,
+    Warn,
+    Error,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct ConsoleMessage {
+    pub level: ConsoleLevel,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct ScriptError {
+    pub message: String,
+    pub source: Option<String>,
+    pub lineno: Option<u32>,
+    pub colno: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct UnhandledRejection {
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct HistoryChange {
+    pub action: HistoryAction,
+    pub url: String,
+    /// Steps requested by a `traverse`.
+    pub delta: Option<i32>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct Scroll {
+    pub x: f64,
+    pub y: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct BlockedRequest {
+    pub url: String,
+    /// Filter-list resource type: `script`, `image`, `xmlhttprequest`, …
+    pub resource_type: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct PermissionRequest {
+    /// The page's id for the request, echoed back with the answer.
+    pub id: u32,
+    pub permission: Permission,
+    /// `location.origin`; checked against the tab's committed URL.
+    pub origin: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct DomReady {
+    pub url: String,
+    /// When DOMContentLoaded started, in milliseconds from navigation start.
+    pub dom_content_loaded: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct FormPost {
+    /// Where the form goes. A download answering it can't be fetched again.
+    pub action: String,
+}
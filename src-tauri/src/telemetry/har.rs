@@ -0,0 +1,460 @@
+//! HAR 1.2 export of a tab's network log.
+//!
+//! Built from the buffers in [`super::metrics`]: fetch and XHR requests as the
+//! wrappers saw them, plus resource timings for everything else the page
+//! loaded. The page can't see request or response headers, so those are left
+//! empty. Times are approximate: the page reports when a request finished and
+//! how long it took, and the start is derived from those.
+
+use std::path::Path;
+
+use serde::Serialize;
+
+use super::metrics::{NetworkEntry, NetworkLog, RequestKind, ResourceEntry};
+use crate::error::BrsrError;
+use crate::storage;
+
+const HAR_VERSION: &str = "1.2";
+const PAGE_ID: &str = "page_1";
+/// Length the fetch probe cuts response previews to.
+const PREVIEW_CHARS: usize = 2000;
+
+#[derive(Serialize)]
+struct Har {
+    log: Log,
+}
+
+#[derive(Serialize)]
+struct Log {
+    version: &'static str,
+    creator: Creator,
+    pages: Vec<Page>,
+    entries: Vec<Entry>,
+}
+
+#[derive(Serialize)]
+struct Creator {
+    name: &'static str,
+    version: &'static str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Page {
+    started_date_time: String,
+    id: &'static str,
+    title: String,
+    page_timings: PageTimings,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PageTimings {
+    on_content_load: f64,
+    on_load: f64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Entry {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pageref: Option<&'static str>,
+    started_date_time: String,
+    time: f64,
+    request: Request,
+    response: Response,
+    cache: Cache,
+    timings: Timings,
+    #[serde(rename = "_initiator")]
+    initiator: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Request {
+    method: String,
+    url: String,
+    http_version: &'static str,
+    cookies: Vec<NameValue>,
+    headers: Vec<NameValue>,
+    query_string: Vec<NameValue>,
+    headers_size: i64,
+    body_size: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Response {
+    status: u16,
+    status_text: String,
+    http_version: &'static str,
+    cookies: Vec<NameValue>,
+    headers: Vec<NameValue>,
+    content: Content,
+    #[serde(rename = "redirectURL")]
+    redirect_url: String,
+    headers_size: i64,
+    body_size: i64,
+    #[serde(rename = "_transferSize")]
+    transfer_size: i64,
+    #[serde(rename = "_error", skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Content {
+    size: i64,
+    mime_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<&'static str>,
+}
+
+#[derive(Serialize)]
+struct NameValue {
+    name: String,
+    value: String,
+}
+
+#[derive(Serialize)]
+struct Cache {}
+
+#[derive(Serialize)]
+struct Timings {
+    send: f64,
+    wait: f64,
+    receive: f64,
+}
+
+/// `ms` since the Unix epoch as ISO 8601 in UTC, e.g. `2024-05-01T12:00:00.000Z`.
+fn iso8601(ms: f64) -> String {
+    let ms = ms.max(0.0) as i64;
+    let (days, rem) = (ms.div_euclid(86_400_000), ms.rem_euclid(86_400_000));
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm).
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        rem / 3_600_000,
+        rem / 60_000 % 60,
+        rem / 1000 % 60,
+        rem % 1000
+    )
+}
+
+fn query_string(url: &str) -> Vec<NameValue> {
+    url::Url::parse(url)
+        .map(|u| {
+            u.query_pairs()
+                .map(|(name, value)| NameValue {
+                    name: name.into_owned(),
+                    value: value.into_owned(),
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn request(method: &str, url: String) -> Request {
+    Request {
+        method: method.to_string(),
+        query_string: query_string(&url),
+        url,
+        http_version: "",
+        cookies: Vec::new(),
+        headers: Vec::new(),
+        headers_size: -1,
+        body_size: -1,
+    }
+}
+
+fn timings(duration: f64) -> Timings {
+    // Only the total is known; attribute it all to waiting.
+    Timings {
+        send: 0.0,
+        wait: duration.max(0.0),
+        receive: 0.0,
+    }
+}
+
+struct Builder<'a> {
+    log: &'a NetworkLog,
+    base: Option<url::Url>,
+}
+
+impl Builder<'_> {
+    /// Requests made with a relative URL, resolved against the page.
+    fn absolute(&self, url: &str) -> String {
+        match (&self.base, url::Url::parse(url)) {
+            (_, Ok(parsed)) => parsed.to_string(),
+            (Some(base), Err(_)) => base.join(url).map(|u| u.to_string()).unwrap_or_else(|_| url.to_string()),
+            (None, Err(_)) => url.to_string(),
+        }
+    }
+
+    fn pageref(&self, started: f64) -> Option<&'static str> {
+        self.log
+            .metrics
+            .document_started_at
+            .filter(|doc| started >= *doc)
+            .map(|_| PAGE_ID)
+    }
+
+    fn request_entry(&self, req: &NetworkEntry, timing: Option<&ResourceEntry>) -> Entry {
+        let started = req.ts - req.duration;
+        let transfer_size = timing.map_or(-1, |t| t.timing.transfer_size as i64);
+        let preview_len = req.preview.as_ref().map(|p| p.len() as i64);
+        Entry {
+            pageref: self.pageref(started),
+            started_date_time: iso8601(started),
+            time: req.duration.max(0.0),
+            request: request(&req.method, self.absolute(&req.url)),
+            response: Response {
+                status: req.status.unwrap_or(0),
+                status_text: String::new(),
+                http_version: "",
+                cookies: Vec::new(),
+                headers: Vec::new(),
+                content: Content {
+                    size: preview_len.unwrap_or(0),
+                    mime_type: req.content_type.clone().unwrap_or_default(),
+                    text: req.preview.clone(),
+                    comment: req
+                        .preview
+                        .as_ref()
+                        .filter(|p| p.chars().count() >= PREVIEW_CHARS)
+                        .map(|_| "Truncated to the first 2000 characters"),
+                },
+                redirect_url: String::new(),
+                headers_size: -1,
+                body_size: -1,
+                transfer_size,
+                error: req.error.clone(),
+            },
+            cache: Cache {},
+            timings: timings(req.duration),
+            initiator: match req.kind {
+                RequestKind::Fetch => "fetch",
+                RequestKind::Xhr => "xmlhttprequest",
+            }
+            .to_string(),
+        }
+    }
+
+    fn resource_entry(&self, res: &ResourceEntry) -> Entry {
+        let started = res.ts - res.timing.duration;
+        Entry {
+            pageref: self.pageref(started),
+            started_date_time: iso8601(started),
+            time: res.timing.duration.max(0.0),
+            request: request("GET", res.timing.name.clone()),
+            response: Response {
+                // Resource timing doesn't expose the status.
+                status: 0,
+                status_text: String::new(),
+                http_version: "",
+                cookies: Vec::new(),
+                headers: Vec::new(),
+                content: Content {
+                    size: 0,
+                    mime_type: String::new(),
+                    text: None,
+                    comment: None,
+                },
+                redirect_url: String::new(),
+                headers_size: -1,
+                body_size: -1,
+                transfer_size: res.timing.transfer_size as i64,
+                error: None,
+            },
+            cache: Cache {},
+            timings: timings(res.timing.duration),
+            initiator: res.timing.initiator_type.clone(),
+        }
+    }
+
+    fn build(&self) -> Har {
+        let mut unmatched: Vec<Option<&ResourceEntry>> = self.log.resources.iter().map(Some).collect();
+        let mut entries: Vec<Entry> = Vec::new();
+
+        for req in &self.log.requests {
+            // Pair each request with its resource timing for the transfer size.
+            let url = self.absolute(&req.url);
+            let timing = unmatched
+                .iter_mut()
+                .find(|slot| {
+                    slot.is_some_and(|r| {
+                        matches!(r.timing.initiator_type.as_str(), "fetch" | "xmlhttprequest")
+                            && r.timing.name == url
+                    })
+                })
+                .and_then(Option::take);
+            entries.push(self.request_entry(req, timing));
+        }
+        entries.extend(unmatched.into_iter().flatten().map(|res| self.resource_entry(res)));
+        entries.sort_by(|a, b| a.started_date_time.cmp(&b.started_date_time));
+
+        let metrics = &self.log.metrics;
+        let pages = metrics
+            .document_started_at
+            .map(|started| Page {
+                started_date_time: iso8601(started),
+                id: PAGE_ID,
+                title: metrics.title.clone().or_else(|| metrics.url.clone()).unwrap_or_default(),
+                page_timings: PageTimings {
+                    on_content_load: metrics.dom_content_loaded.unwrap_or(-1.0),
+                    on_load: metrics.load_event_end.unwrap_or(-1.0),
+                },
+            })
+            .into_iter()
+            .collect();
+
+        Har {
+            log: Log {
+                version: HAR_VERSION,
+                creator: Creator {
+                    name: "brsr",
+                    version: env!("CARGO_PKG_VERSION"),
+                },
+                pages,
+                entries,
+            },
+        }
+    }
+}
+
+/// Write `log` to `path` as a HAR file. Returns the number of entries.
+pub fn export(log: &NetworkLog, path: &Path) -> Result<usize, BrsrError> {
+    let builder = Builder {
+        log,
+        base: log.metrics.url.as_deref().and_then(|u| url::Url::parse(u).ok()),
+    };
+    let har = builder.build();
+    let count = har.log.entries.len();
+    let json = serde_json::to_vec_pretty(&har).map_err(|e| BrsrError::io(path, e))?;
+    storage::write_atomic(path, &json)?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::telemetry::event::ResourceTiming;
+    use crate::telemetry::metrics::TabMetrics;
+
+    fn fetch(url: &str, ts: f64, duration: f64) -> NetworkEntry {
+        NetworkEntry {
+            ts,
+            kind: RequestKind::Fetch,
+            url: url.into(),
+            method: "GET".into(),
+            status: Some(200),
+            duration,
+            error: None,
+            content_type: None,
+            preview: None,
+        }
+    }
+
+    fn resource(initiator_type: &str, name: &str, ts: f64, transfer_size: f64) -> ResourceEntry {
+        ResourceEntry {
+            ts,
+            timing: ResourceTiming {
+                initiator_type: initiator_type.into(),
+                name: name.into(),
+                duration: 10.0,
+                start_time: 0.0,
+                transfer_size,
+            },
+        }
+    }
+
+    fn build(log: &NetworkLog) -> Har {
+        Builder {
+            log,
+            base: log.metrics.url.as_deref().and_then(|u| url::Url::parse(u).ok()),
+        }
+        .build()
+    }
+
+    #[test]
+    fn formats_timestamps_as_utc() {
+        assert_eq!(iso8601(0.0), "1970-01-01T00:00:00.000Z");
+        assert_eq!(iso8601(-5.0), "1970-01-01T00:00:00.000Z");
+        assert_eq!(iso8601(951_825_600_000.0), "2000-02-29T12:00:00.000Z");
+        assert_eq!(iso8601(951_868_800_000.0), "2000-03-01T00:00:00.000Z");
+        assert_eq!(iso8601(1_709_251_199_999.0), "2024-02-29T23:59:59.999Z");
+        assert_eq!(iso8601(1_709_251_200_000.0), "2024-03-01T00:00:00.000Z");
+    }
+
+    #[test]
+    fn pairs_requests_with_their_own_resource_timing() {
+        let log = NetworkLog {
+            metrics: TabMetrics {
+                url: Some("https://example.com/app/".into()),
+                ..TabMetrics::default()
+            },
+            requests: vec![
+                fetch("/api?page=2", 2000.0, 100.0),
+                fetch("https://example.com/api?page=2", 3000.0, 100.0),
+                fetch("data.json", 4000.0, 100.0),
+            ],
+            resources: vec![
+                resource("img", "https://example.com/api?page=2", 1500.0, 7.0),
+                resource("fetch", "https://example.com/api?page=2", 2000.0, 300.0),
+                resource("script", "https://example.com/app.js", 500.0, 50.0),
+            ],
+        };
+        let har = build(&log);
+        let summary: Vec<(&str, &str, i64)> = har
+            .log
+            .entries
+            .iter()
+            .map(|e| (e.request.url.as_str(), e.initiator.as_str(), e.response.transfer_size))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("https://example.com/app.js", "script", 50),
+                ("https://example.com/api?page=2", "img", 7),
+                ("https://example.com/api?page=2", "fetch", 300),
+                ("https://example.com/api?page=2", "fetch", -1),
+                ("https://example.com/app/data.json", "fetch", -1),
+            ]
+        );
+        let query = &har.log.entries[2].request.query_string;
+        assert_eq!((query[0].name.as_str(), query[0].value.as_str()), ("page", "2"));
+    }
+
+    #[test]
+    fn entries_from_the_current_document_reference_its_page() {
+        let mut log = NetworkLog {
+            metrics: TabMetrics::default(),
+            requests: vec![fetch("https://example.com/early", 1100.0, 200.0), fetch("https://example.com/late", 1500.0, 100.0)],
+            resources: Vec::new(),
+        };
+        let har = build(&log);
+        assert!(har.log.pages.is_empty());
+        assert!(har.log.entries.iter().all(|e| e.pageref.is_none()));
+
+        log.metrics.document_started_at = Some(1000.0);
+        log.metrics.title = Some("Example".into());
+        let har = build(&log);
+        assert_eq!(har.log.pages.len(), 1);
+        assert_eq!(har.log.pages[0].title, "Example");
+        let pagerefs: Vec<_> = har.log.entries.iter().map(|e| (e.request.url.as_str(), e.pageref)).collect();
+        assert_eq!(pagerefs, [("https://example.com/early", None), ("https://example.com/late", Some(PAGE_ID))]);
+    }
+}
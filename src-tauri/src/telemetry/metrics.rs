@@ -0,0 +1,522 @@
+//! Per-tab aggregation of validated telemetry.
+//!
+//! Console output, errors, network requests and resource timings are kept in
+//! bounded ring buffers, and page-level numbers (paints, LCP,
+//! DOMContentLoaded, long tasks, transfer bytes, failed requests) are folded
+//! into a summary that starts over with each document. The frontend reads them
+//! through `get_tab_metrics` and `get_tab_logs`, so a panel that mounts late
+//! still sees what happened before.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use super::event::{ConsoleLevel, ResourceTiming, TelemetryEvent, TelemetryMessage};
+
+const MAX_CONSOLE: usize = 500;
+const MAX_ERRORS: usize = 200;
+const MAX_NETWORK: usize = 500;
+const MAX_RESOURCES: usize = 1000;
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct ConsoleEntry {
+    pub ts: f64,
+    pub level: ConsoleLevel,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct ErrorEntry {
+    pub ts: f64,
+    pub message: String,
+    /// `file:line:col` when the page reported one.
+    pub source: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "lowercase")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub enum RequestKind {
+    Fetch,
+    Xhr,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct NetworkEntry {
+    pub ts: f64,
+    pub kind: RequestKind,
+    pub url: String,
+    pub method: String,
+    /// `None` when the request never got a response.
+    pub status: Option<u16>,
+    pub duration: f64,
+    pub error: Option<String>,
+    pub content_type: Option<String>,
+    /// Response body preview, fetch only.
+    pub preview: Option<String>,
+}
+
+impl NetworkEntry {
+    pub fn failed(&self) -> bool {
+        self.error.is_some() || matches!(self.status, None | Some(0)) || self.status.is_some_and(|s| s >= 400)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct ResourceEntry {
+    pub ts: f64,
+    #[serde(flatten)]
+    pub timing: ResourceTiming,
+}
+
+/// Page-level numbers for the tab's current document.
+#[derive(Debug, Clone, Default, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct TabMetrics {
+    pub label: String,
+    pub url: Option<String>,
+    pub title: Option<String>,
+    /// When the current document's script started (`init`), page clock.
+    pub document_started_at: Option<f64>,
+    /// All times below are milliseconds since navigation start.
+    pub first_paint: Option<f64>,
+    pub first_contentful_paint: Option<f64>,
+    pub largest_contentful_paint: Option<f64>,
+    pub dom_content_loaded: Option<f64>,
+    pub load_event_end: Option<f64>,
+    pub long_task_count: u32,
+    pub long_task_total_ms: f64,
+    pub resource_count: u32,
+    pub transfer_bytes: f64,
+    pub request_count: u32,
+    pub failed_requests: u32,
+    pub console_errors: u32,
+    pub console_warnings: u32,
+    pub script_errors: u32,
+}
+
+/// Which buffers `get_tab_logs` returns and how they are narrowed.
+#[derive(Debug, Clone, Default, Deserialize, TS)]
+#[serde(rename_all = "camelCase", default)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct LogFilter {
+    /// Buffers to include; all of them when empty.
+    pub kinds: Vec<LogKind>,
+    /// Console levels to include; all when empty.
+    pub levels: Vec<ConsoleLevel>,
+    /// Case-insensitive substring of the message or URL.
+    pub text: Option<String>,
+    /// Only entries with `ts` at or after this (page clock, ms since epoch).
+    pub since: Option<f64>,
+    /// Most recent N entries per buffer.
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, TS)]
+#[serde(rename_all = "lowercase")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub enum LogKind {
+    Console,
+    Errors,
+    Network,
+    Resources,
+}
+
+#[derive(Debug, Clone, Default, Serialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct TabLogs {
+    pub console: Vec<ConsoleEntry>,
+    pub errors: Vec<ErrorEntry>,
+    pub network: Vec<NetworkEntry>,
+    pub resources: Vec<ResourceEntry>,
+}
+
+impl LogFilter {
+    fn wants(&self, kind: LogKind) -> bool {
+        self.kinds.is_empty() || self.kinds.contains(&kind)
+    }
+
+    fn matches(&self, ts: f64, haystacks: &[&str]) -> bool {
+        if self.since.is_some_and(|since| ts < since) {
+            return false;
+        }
+        match &self.text {
+            Some(text) if !text.is_empty() => {
+                let needle = text.to_lowercase();
+                haystacks.iter().any(|h| h.to_lowercase().contains(&needle))
+            }
+            _ => true,
+        }
+    }
+
+    fn collect<T: Clone>(&self, kind: LogKind, buffer: &VecDeque<T>, keep: impl Fn(&T) -> bool) -> Vec<T> {
+        if !self.wants(kind) {
+            return Vec::new();
+        }
+        let mut out: Vec<T> = buffer.iter().filter(|e| keep(e)).cloned().collect();
+        if let Some(limit) = self.limit {
+            let limit = limit as usize;
+            if out.len() > limit {
+                out.drain(..out.len() - limit);
+            }
+        }
+        out
+    }
+}
+
+fn push_bounded<T>(buffer: &mut VecDeque<T>, item: T, cap: usize) {
+    if buffer.len() == cap {
+        buffer.pop_front();
+    }
+    buffer.push_back(item);
+}
+
+#[derive(Debug, Default)]
+struct TabTelemetry {
+    metrics: TabMetrics,
+    console: VecDeque<ConsoleEntry>,
+    errors: VecDeque<ErrorEntry>,
+    network: VecDeque<NetworkEntry>,
+    resources: VecDeque<ResourceEntry>,
+}
+
+impl TabTelemetry {
+    fn record(&mut self, ts: f64, event: &TelemetryEvent) {
+        let m = &mut self.metrics;
+        match event {
+            TelemetryEvent::Init => {
+                // A new document: page-level numbers start over, the logs carry on.
+                self.metrics = TabMetrics {
+                    label: std::mem::take(&mut m.label),
+                    url: m.url.take(),
+                    title: m.title.take(),
+                    document_started_at: Some(ts),
+                    ..TabMetrics::default()
+                };
+            }
+            TelemetryEvent::Heartbeat(beat) => {
+                m.url = Some(beat.url.clone());
+                m.title = Some(beat.title.clone());
+            }
+            TelemetryEvent::PageInfo(info) => {
+                m.url = Some(info.url.clone());
+                m.title = Some(info.title.clone());
+            }
+            TelemetryEvent::History(change) => m.url = Some(change.url.clone()),
+            TelemetryEvent::Paint(paint) => match paint.name.as_str() {
+                "first-paint" => m.first_paint = Some(paint.start_time),
+                "first-contentful-paint" => m.first_contentful_paint = Some(paint.start_time),
+                _ => {}
+            },
+            // LCP candidates keep coming until input; the last one is the answer.
+            TelemetryEvent::Lcp(lcp) => m.largest_contentful_paint = Some(lcp.start_time),
+            TelemetryEvent::Navigation(nav) => {
+                m.dom_content_loaded = Some(nav.dom_content_loaded);
+                if nav.load_event_end > 0.0 {
+                    m.load_event_end = Some(nav.load_event_end);
+                }
+            }
+            TelemetryEvent::Longtask(task) => {
+                m.long_task_count += 1;
+                m.long_task_total_ms += task.duration;
+            }
+            TelemetryEvent::Resource(resource) => {
+                m.resource_count += 1;
+                m.transfer_bytes += resource.item.transfer_size;
+                push_bounded(
+                    &mut self.resources,
+                    ResourceEntry {
+                        ts,
+                        timing: resource.item.clone(),
+                    },
+                    MAX_RESOURCES,
+                );
+            }
+            TelemetryEvent::Fetch(req) => self.record_request(NetworkEntry {
+                ts,
+                kind: RequestKind::Fetch,
+                url: req.url.clone(),
+                method: req.method.clone(),
+                status: Some(req.status),
+                duration: req.duration,
+                error: None,
+                content_type: req.content_type.clone(),
+                preview: req.preview.clone(),
+            }),
+            TelemetryEvent::FetchError(req) => self.record_request(NetworkEntry {
+                ts,
+                kind: RequestKind::Fetch,
+                url: req.url.clone(),
+                method: req.method.clone(),
+                status: None,
+                duration: req.duration,
+                error: Some(req.error.clone()),
+                content_type: None,
+                preview: None,
+            }),
+            TelemetryEvent::Xhr(req) => self.record_request(NetworkEntry {
+                ts,
+                kind: RequestKind::Xhr,
+                url: req.url.clone(),
+                method: req.method.clone(),
+                status: Some(req.status),
+                duration: req.duration,
+                error: None,
+                content_type: req.content_type.clone(),
+                preview: None,
+            }),
+            TelemetryEvent::Console(msg) => {
+                match msg.level {
+                    ConsoleLevel::Error => m.console_errors += 1,
+                    ConsoleLevel::Warn => m.console_warnings += 1,
+                    _ => {}
+                }
+                push_bounded(
+                    &mut self.console,
+                    ConsoleEntry {
+                        ts,
+                        level: msg.level,
+                        message: msg.message.clone(),
+                    },
+                    MAX_CONSOLE,
+                );
+            }
+            TelemetryEvent::Error(err) => {
+                m.script_errors += 1;
+                let source = err.source.as_ref().map(|src| match (err.lineno, err.colno) {
+                    (Some(line), Some(col)) => format!("{}:{}:{}", src, line, col),
+                    (Some(line), None) => format!("{}:{}", src, line),
+                    _ => src.clone(),
+                });
+                push_bounded(
+                    &mut self.errors,
+                    ErrorEntry {
+                        ts,
+                        message: err.message.clone(),
+                        source,
+                    },
+                    MAX_ERRORS,
+                );
+            }
+            TelemetryEvent::UnhandledRejection(rejection) => {
+                m.script_errors += 1;
+                push_bounded(
+                    &mut self.errors,
+                    ErrorEntry {
+                        ts,
+                        message: rejection.reason.clone(),
+                        source: None,
+                    },
+                    MAX_ERRORS,
+                );
+            }
+            // Blocked requests are counted by the content blocker, load phases
+            // tracked by `page_load`.
+            TelemetryEvent::Selection(_)
+            | TelemetryEvent::Scroll(_)
+            | TelemetryEvent::Blocked(_)
+            | TelemetryEvent::PermissionRequest(_)
+            | TelemetryEvent::DomReady(_)
+            | TelemetryEvent::FormPost(_) => {}
+        }
+    }
+
+    fn record_request(&mut self, entry: NetworkEntry) {
+        self.metrics.request_count += 1;
+        if entry.failed() {
+            self.metrics.failed_requests += 1;
+        }
+        push_bounded(&mut self.network, entry, MAX_NETWORK);
+    }
+
+    fn logs(&self, filter: &LogFilter) -> TabLogs {
+        TabLogs {
+            console: filter.collect(LogKind::Console, &self.console, |e| {
+                (filter.levels.is_empty() || filter.levels.contains(&e.level)) && filter.matches(e.ts, &[&e.message])
+            }),
+            errors: filter.collect(LogKind::Errors, &self.errors, |e| {
+                filter.matches(e.ts, &[&e.message, e.source.as_deref().unwrap_or("")])
+            }),
+            network: filter.collect(LogKind::Network, &self.network, |e| filter.matches(e.ts, &[&e.url])),
+            resources: filter.collect(LogKind::Resources, &self.resources, |e| {
+                filter.matches(e.ts, &[&e.timing.name])
+            }),
+        }
+    }
+}
+
+/// A copy of a tab's network buffers.
+#[derive(Default)]
+pub struct NetworkLog {
+    pub metrics: TabMetrics,
+    pub requests: Vec<NetworkEntry>,
+    pub resources: Vec<ResourceEntry>,
+}
+
+/// Managed state: aggregated telemetry for every open tab, keyed by label.
+#[derive(Default)]
+pub struct TabMetricsStore {
+    tabs: Mutex<HashMap<String, TabTelemetry>>,
+}
+
+impl TabMetricsStore {
+    pub fn record(&self, message: &TelemetryMessage) {
+        let mut tabs = self.tabs.lock().unwrap();
+        let tab = tabs.entry(message.label.clone()).or_insert_with(|| TabTelemetry {
+            metrics: TabMetrics {
+                label: message.label.clone(),
+                ..TabMetrics::default()
+            },
+            ..TabTelemetry::default()
+        });
+        tab.record(message.ts, &message.event);
+    }
+
+    pub fn metrics(&self, label: &str) -> Option<TabMetrics> {
+        self.tabs.lock().unwrap().get(label).map(|tab| tab.metrics.clone())
+    }
+
+    pub fn logs(&self, label: &str, filter: &LogFilter) -> Option<TabLogs> {
+        self.tabs.lock().unwrap().get(label).map(|tab| tab.logs(filter))
+    }
+
+    /// Everything the HAR exporter needs for one tab.
+    pub fn network_log(&self, label: &str) -> Option<NetworkLog> {
+        self.tabs.lock().unwrap().get(label).map(|tab| NetworkLog {
+            metrics: tab.metrics.clone(),
+            requests: tab.network.iter().cloned().collect(),
+            resources: tab.resources.iter().cloned().collect(),
+        })
+    }
+
+    pub fn remove(&self, label: &str) {
+        self.tabs.lock().unwrap().remove(label);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::telemetry::event::{ConsoleMessage, FetchError, FetchRequest, Heartbeat, LongTask, XhrRequest};
+
+    fn fetch(url: &str, status: u16) -> TelemetryEvent {
+        TelemetryEvent::Fetch(FetchRequest {
+            url: url.into(),
+            method: "GET".into(),
+            status,
+            duration: 5.0,
+            preview: None,
+            content_type: None,
+        })
+    }
+
+    fn console(level: ConsoleLevel, message: &str) -> TelemetryEvent {
+        TelemetryEvent::Console(ConsoleMessage {
+            level,
+            message: message.into(),
+        })
+    }
+
+    #[test]
+    fn counts_failed_requests() {
+        let mut tab = TabTelemetry::default();
+        tab.record(1.0, &fetch("https://example.com/ok", 200));
+        tab.record(2.0, &fetch("https://example.com/missing", 404));
+        tab.record(3.0, &fetch("https://example.com/redirected", 304));
+        tab.record(
+            4.0,
+            &TelemetryEvent::FetchError(FetchError {
+                url: "https://example.com/offline".into(),
+                method: "POST".into(),
+                error: "TypeError: Failed to fetch".into(),
+                duration: 1.0,
+            }),
+        );
+        tab.record(
+            5.0,
+            &TelemetryEvent::Xhr(XhrRequest {
+                url: "https://example.com/aborted".into(),
+                method: "GET".into(),
+                status: 0,
+                duration: 1.0,
+                content_type: None,
+            }),
+        );
+        assert_eq!(tab.metrics.request_count, 5);
+        assert_eq!(tab.metrics.failed_requests, 3);
+        let failed: Vec<bool> = tab.network.iter().map(NetworkEntry::failed).collect();
+        assert_eq!(failed, [false, true, false, true, true]);
+    }
+
+    #[test]
+    fn init_starts_the_page_over_but_keeps_the_logs() {
+        let mut tab = TabTelemetry {
+            metrics: TabMetrics {
+                label: "tab-1".into(),
+                ..TabMetrics::default()
+            },
+            ..TabTelemetry::default()
+        };
+        let beat = Heartbeat {
+            title: "Example".into(),
+            url: "https://example.com/".into(),
+        };
+        tab.record(1.0, &TelemetryEvent::Heartbeat(beat));
+        tab.record(2.0, &fetch("https://example.com/missing", 404));
+        tab.record(3.0, &console(ConsoleLevel::Error, "boom"));
+        tab.record(4.0, &TelemetryEvent::Longtask(LongTask { duration: 80.0, start_time: 0.0 }));
+
+        tab.record(10.0, &TelemetryEvent::Init);
+        let m = &tab.metrics;
+        assert_eq!(m.label, "tab-1");
+        assert_eq!(m.url.as_deref(), Some("https://example.com/"));
+        assert_eq!(m.title.as_deref(), Some("Example"));
+        assert_eq!(m.document_started_at, Some(10.0));
+        assert_eq!((m.request_count, m.failed_requests, m.console_errors, m.long_task_count), (0, 0, 0, 0));
+        assert_eq!((tab.network.len(), tab.console.len()), (1, 1));
+    }
+
+    #[test]
+    fn filters_logs_by_time_text_and_count() {
+        let mut tab = TabTelemetry::default();
+        tab.record(1.0, &console(ConsoleLevel::Log, "Loading widgets"));
+        tab.record(2.0, &console(ConsoleLevel::Warn, "Slow WIDGET"));
+        tab.record(3.0, &console(ConsoleLevel::Error, "widget failed"));
+        tab.record(4.0, &console(ConsoleLevel::Log, "done"));
+        tab.record(5.0, &fetch("https://example.com/widgets.json", 200));
+
+        let messages = |filter: LogFilter| -> Vec<String> {
+            tab.logs(&filter).console.into_iter().map(|e| e.message).collect()
+        };
+        assert_eq!(messages(LogFilter { since: Some(3.0), ..LogFilter::default() }), ["widget failed", "done"]);
+        assert_eq!(
+            messages(LogFilter { text: Some("widget".into()), ..LogFilter::default() }),
+            ["Loading widgets", "Slow WIDGET", "widget failed"]
+        );
+        assert_eq!(
+            messages(LogFilter { text: Some("Widget".into()), limit: Some(2), ..LogFilter::default() }),
+            ["Slow WIDGET", "widget failed"]
+        );
+        assert_eq!(
+            messages(LogFilter { levels: vec![ConsoleLevel::Warn, ConsoleLevel::Error], ..LogFilter::default() }),
+            ["Slow WIDGET", "widget failed"]
+        );
+
+        let filter = LogFilter {
+            kinds: vec![LogKind::Network],
+            text: Some("WIDGETS".into()),
+            ..LogFilter::default()
+        };
+        let logs = tab.logs(&filter);
+        assert!(logs.console.is_empty());
+        assert_eq!(logs.network.len(), 1);
+    }
+}
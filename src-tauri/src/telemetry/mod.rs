@@ -0,0 +1,192 @@
+//! Intake for the telemetry the injected script streams out of each tab.
+//!
+//! The script sends raw JSON through the [`channel`] command, which checks
+//! who is sending. Every message is then parsed against the typed schema in
+//! [`event`], checked for a supported schema version and for the sending
+//! tab's label, and only then handed to the Rust handlers and re-emitted to
+//! the main window as `webview-telemetry`. Anything else is dropped and
+//! counted.
+
+pub mod channel;
+pub mod event;
+pub mod har;
+pub mod metrics;
+
+use std::sync::Mutex;
+
+use serde::Serialize;
+
+use crate::error::BrsrError;
+use crate::tabs::TabRegistry;
+pub use event::{TelemetryEvent, TelemetryMessage, SCHEMA_VERSION};
+use metrics::{LogFilter, TabLogs, TabMetrics, TabMetricsStore};
+
+/// Validated events, forwarded to the main window.
+pub const EVENT: &str = "webview-telemetry";
+
+/// Upper bound on a single message; page-info with many links is the largest.
+const MAX_PAYLOAD_BYTES: usize = 256 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DropReason {
+    TooLarge,
+    Malformed,
+    UnsupportedVersion,
+    UnknownTab,
+    /// Unsigned, repeated or stale, or the reported origin isn't the page the
+    /// tab is on: not sent by the tab's injected script.
+    Rejected,
+    RateLimited,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DroppedCounts {
+    pub too_large: u64,
+    pub malformed: u64,
+    pub unsupported_version: u64,
+    pub unknown_tab: u64,
+    pub rejected: u64,
+    pub rate_limited: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TelemetryStats {
+    pub schema_version: u32,
+    pub accepted: u64,
+    pub dropped: DroppedCounts,
+}
+
+/// Parse and validate one raw payload sent by the tab `sender`.
+pub fn validate(registry: &TabRegistry, sender: &str, payload: &str) -> Result<TelemetryMessage, DropReason> {
+    if payload.len() > MAX_PAYLOAD_BYTES {
+        return Err(DropReason::TooLarge);
+    }
+    // Check the version before the body so a newer script is reported as such
+    // rather than as malformed.
+    #[derive(serde::Deserialize)]
+    struct Version {
+        v: u32,
+    }
+    let version: Version = serde_json::from_str(payload).map_err(|_| DropReason::Malformed)?;
+    if version.v != SCHEMA_VERSION {
+        return Err(DropReason::UnsupportedVersion);
+    }
+    let message: TelemetryMessage = serde_json::from_str(payload).map_err(|_| DropReason::Malformed)?;
+    if !registry.contains(&message.label) {
+        return Err(DropReason::UnknownTab);
+    }
+    if message.label != sender {
+        return Err(DropReason::Rejected);
+    }
+    Ok(message)
+}
+
+/// Managed state: counters for accepted and dropped telemetry.
+#[derive(Default)]
+pub struct Telemetry {
+    stats: Mutex<TelemetryStats>,
+}
+
+impl Telemetry {
+    /// Validate `payload` from the tab `sender`, counting the outcome. Returns
+    /// the message if it is usable.
+    pub fn ingest(&self, registry: &TabRegistry, sender: &str, payload: &str) -> Option<TelemetryMessage> {
+        match validate(registry, sender, payload) {
+            Ok(message) => {
+                self.stats.lock().unwrap().accepted += 1;
+                Some(message)
+            }
+            Err(reason) => {
+                self.count_dropped(reason);
+                None
+            }
+        }
+    }
+
+    pub fn count_dropped(&self, reason: DropReason) {
+        let mut stats = self.stats.lock().unwrap();
+        let counter = match reason {
+            DropReason::TooLarge => &mut stats.dropped.too_large,
+            DropReason::Malformed => &mut stats.dropped.malformed,
+            DropReason::UnsupportedVersion => &mut stats.dropped.unsupported_version,
+            DropReason::UnknownTab => &mut stats.dropped.unknown_tab,
+            DropReason::Rejected => &mut stats.dropped.rejected,
+            DropReason::RateLimited => &mut stats.dropped.rate_limited,
+        };
+        *counter += 1;
+        // Log the first few of each kind; a broken page can produce thousands.
+        if *counter <= 5 {
+            eprintln!("[Rust] Dropped telemetry event ({:?})", reason);
+        }
+    }
+
+    pub fn stats(&self) -> TelemetryStats {
+        TelemetryStats {
+            schema_version: SCHEMA_VERSION,
+            ..self.stats.lock().unwrap().clone()
+        }
+    }
+}
+
+#[tauri::command]
+pub fn get_telemetry_stats(telemetry: tauri::State<'_, Telemetry>) -> Result<TelemetryStats, BrsrError> {
+    Ok(telemetry.stats())
+}
+
+#[tauri::command]
+pub fn get_tab_metrics(
+    registry: tauri::State<'_, TabRegistry>,
+    store: tauri::State<'_, TabMetricsStore>,
+    label: String,
+) -> Result<TabMetrics, BrsrError> {
+    match store.metrics(&label) {
+        Some(metrics) => Ok(metrics),
+        // Open, but nothing reported yet.
+        None if registry.contains(&label) => Ok(TabMetrics {
+            label,
+            ..TabMetrics::default()
+        }),
+        None => Err(BrsrError::not_found("tab", &label)),
+    }
+}
+
+#[tauri::command]
+pub fn get_tab_logs(
+    registry: tauri::State<'_, TabRegistry>,
+    store: tauri::State<'_, TabMetricsStore>,
+    label: String,
+    filter: Option<LogFilter>,
+) -> Result<TabLogs, BrsrError> {
+    match store.logs(&label, &filter.unwrap_or_default()) {
+        Some(logs) => Ok(logs),
+        None if registry.contains(&label) => Ok(TabLogs::default()),
+        None => Err(BrsrError::not_found("tab", &label)),
+    }
+}
+
+/// Write the tab's network log to `path` as a HAR 1.2 file. Returns the number
+/// of entries written.
+#[tauri::command]
+pub fn export_har(
+    registry: tauri::State<'_, TabRegistry>,
+    store: tauri::State<'_, TabMetricsStore>,
+    label: String,
+    path: String,
+) -> Result<usize, BrsrError> {
+    let log = match store.network_log(&label) {
+        Some(log) => log,
+        None if registry.contains(&label) => metrics::NetworkLog {
+            metrics: TabMetrics {
+                label: label.clone(),
+                ..TabMetrics::default()
+            },
+            ..Default::default()
+        },
+        None => return Err(BrsrError::not_found("tab", &label)),
+    };
+    let count = har::export(&log, std::path::Path::new(&path))?;
+    println!("[Rust] Exported {} HAR entries for '{}' to {}", count, label, path);
+    Ok(count)
+}
@@ -0,0 +1,182 @@
+//! Installed applications, as described by `.desktop` files under the XDG
+//! data directories.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::keyfile::KeyFile;
+
+const GROUP: &str = "Desktop Entry";
+
+#[derive(Debug, Clone)]
+pub struct DesktopEntry {
+    /// Desktop file ID: the path below `applications/` with `/` turned into `-`,
+    /// e.g. `org.mozilla.firefox.desktop`.
+    pub id: String,
+    pub path: PathBuf,
+    pub name: String,
+    /// The `Exec` line, field codes (`%u`, `%U`, ...) included.
+    pub exec: Option<String>,
+    pub mime_types: Vec<String>,
+}
+
+impl DesktopEntry {
+    /// Parse an application entry. Hidden entries and other types yield `None`.
+    pub fn parse(id: String, path: PathBuf, text: &str) -> Option<Self> {
+        let file = KeyFile::parse(text);
+        if file.string(GROUP, "Type").as_deref() != Some("Application") || file.boolean(GROUP, "Hidden") {
+            return None;
+        }
+        let name = file.string(GROUP, "Name").unwrap_or_else(|| id.trim_end_matches(".desktop").to_string());
+        Some(Self {
+            exec: file.string(GROUP, "Exec"),
+            mime_types: file.list(GROUP, "MimeType"),
+            id,
+            path,
+            name,
+        })
+    }
+
+    pub fn handles(&self, mime: &str) -> bool {
+        self.mime_types.iter().any(|m| m == mime)
+    }
+
+    /// The program `Exec` runs, with quoting removed.
+    pub fn program(&self) -> Option<String> {
+        exec_args(self.exec.as_deref()?).into_iter().next().map(|p| p.replace("%%", "%"))
+    }
+
+    /// The command line that opens `url`, field codes expanded. The URL goes
+    /// last when `Exec` has no place for it.
+    pub fn command_for(&self, url: &str) -> Option<Vec<String>> {
+        let mut args = Vec::new();
+        let mut placed = false;
+        for arg in exec_args(self.exec.as_deref()?) {
+            match arg.as_str() {
+                "%u" | "%U" | "%f" | "%F" => {
+                    args.push(url.to_string());
+                    placed = true;
+                }
+                "%c" => args.push(self.name.clone()),
+                "%k" => args.push(self.path.display().to_string()),
+                // No icon to pass, and the rest are deprecated.
+                "%i" | "%d" | "%D" | "%n" | "%N" | "%v" | "%m" => {}
+                _ => args.push(arg.replace("%%", "%")),
+            }
+        }
+        if !placed {
+            args.push(url.to_string());
+        }
+        Some(args)
+    }
+}
+
+/// Split an `Exec` value into arguments, honouring its double-quote rules.
+/// Field codes are left in place.
+pub fn exec_args(exec: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut quoted = false;
+    let mut chars = exec.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                in_arg = true;
+            }
+            '\\' if quoted => {
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+            }
+            c if c.is_whitespace() && !quoted => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            c => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if in_arg {
+        args.push(current);
+    }
+    args
+}
+
+/// Every application under `<dir>/applications` for each data dir, most
+/// important dir first. An ID found in several dirs resolves to the first.
+pub fn scan(data_dirs: &[PathBuf]) -> Vec<DesktopEntry> {
+    let mut seen = std::collections::HashSet::new();
+    let mut entries = Vec::new();
+    for dir in data_dirs {
+        let root = dir.join("applications");
+        let mut files = Vec::new();
+        collect(&root, &mut files);
+        files.sort();
+        for path in files {
+            let Ok(rel) = path.strip_prefix(&root) else { continue };
+            let id = rel.to_string_lossy().replace('/', "-");
+            // Shadowed IDs stay shadowed even when the winning file is hidden.
+            if !seen.insert(id.clone()) {
+                continue;
+            }
+            let Ok(text) = fs::read_to_string(&path) else { continue };
+            if let Some(entry) = DesktopEntry::parse(id, path, &text) {
+                entries.push(entry);
+            }
+        }
+    }
+    entries
+}
+
+fn collect(dir: &Path, out: &mut Vec<PathBuf>) {
+    let Ok(read) = fs::read_dir(dir) else { return };
+    for item in read.flatten() {
+        let path = item.path();
+        if path.is_dir() {
+            collect(&path, out);
+        } else if path.extension().is_some_and(|ext| ext == "desktop") {
+            out.push(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exec_args_follow_the_quoting_rules() {
+        assert_eq!(exec_args("firefox  %u"), ["firefox", "%u"]);
+        assert_eq!(exec_args(r#""/opt/my app/run" --name "a \"b\" \$c \\d" %U"#), [
+            "/opt/my app/run",
+            "--name",
+            r#"a "b" $c \d"#,
+            "%U",
+        ]);
+        assert_eq!(exec_args(r#"run """#), ["run", ""]);
+    }
+
+    #[test]
+    fn command_for_expands_field_codes() {
+        let text = "[Desktop Entry]\nType=Application\nName=Web\nExec=web --class %c %i 100%% %u\n";
+        let entry = DesktopEntry::parse("web.desktop".into(), "/apps/web.desktop".into(), text).unwrap();
+        assert_eq!(entry.command_for("https://a.test/").unwrap(), [
+            "web",
+            "--class",
+            "Web",
+            "100%",
+            "https://a.test/"
+        ]);
+        assert_eq!(entry.program().as_deref(), Some("web"));
+
+        let text = "[Desktop Entry]\nType=Application\nExec=b\n";
+        let bare = DesktopEntry::parse("b.desktop".into(), "/b.desktop".into(), text);
+        assert_eq!(bare.unwrap().command_for("x:").unwrap(), ["b", "x:"]);
+    }
+}
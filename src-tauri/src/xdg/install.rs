@@ -0,0 +1,187 @@
+//! Installing brsr's own `.desktop` entry and icons into the user's data dir,
+//! so desktops list it as a browser even when it runs from an AppImage or an
+//! unpacked build that no package put in place.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde::Serialize;
+use ts_rs::TS;
+
+use super::{XdgDirs, BROWSER_TYPES, DESKTOP_ID};
+use crate::error::BrsrError;
+use crate::storage;
+
+const ICON_NAME: &str = "brsr";
+
+/// Bundled icons by pixel size, installed into the `hicolor` theme.
+const ICONS: [(u32, &[u8]); 4] = [
+    (32, include_bytes!("../../icons/32x32.png")),
+    (128, include_bytes!("../../icons/128x128.png")),
+    (256, include_bytes!("../../icons/128x128@2x.png")),
+    (512, include_bytes!("../../icons/icon.png")),
+];
+
+/// What `register_as_browser_candidate` or `unregister_browser_candidate` did.
+#[derive(Debug, Clone, Default, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct CandidateRegistration {
+    pub desktop_id: String,
+    /// Files written, or removed when unregistering. Files that were already
+    /// up to date aren't listed.
+    pub changed: Vec<String>,
+    /// Whether `update-desktop-database` ran. Where it isn't installed,
+    /// desktops pick the change up on their own next scan.
+    pub database_updated: bool,
+}
+
+/// Quote one `Exec` argument as the spec requires, then escape it for the key file.
+fn exec_arg(arg: &str) -> String {
+    const RESERVED: &[char] = &[
+        ' ', '\t', '\n', '"', '\'', '\\', '>', '<', '~', '|', '&', ';', '$', '*', '?', '#', '(', ')', '`',
+    ];
+    let quoted = if arg.contains(RESERVED) {
+        let mut out = String::from("\"");
+        for c in arg.chars() {
+            if matches!(c, '"' | '`' | '$' | '\\') {
+                out.push('\\');
+            }
+            out.push(c);
+        }
+        out.push('"');
+        out
+    } else {
+        arg.to_string()
+    };
+    quoted.replace('\\', "\\\\").replace('%', "%%")
+}
+
+fn desktop_entry(exe: &Path) -> String {
+    format!(
+        "[Desktop Entry]\n\
+         Type=Application\n\
+         Version=1.0\n\
+         Name=brsr\n\
+         GenericName=Web Browser\n\
+         Comment=Browse the web\n\
+         Exec={} %U\n\
+         Icon={}\n\
+         Terminal=false\n\
+         StartupNotify=true\n\
+         StartupWMClass=brsr\n\
+         Categories=Network;WebBrowser;\n\
+         MimeType={};\n",
+        exec_arg(&exe.to_string_lossy()),
+        ICON_NAME,
+        BROWSER_TYPES.join(";"),
+    )
+}
+
+fn icon_path(dirs: &XdgDirs, size: u32) -> PathBuf {
+    dirs.data_home.join(format!("icons/hicolor/{size}x{size}/apps/{ICON_NAME}.png"))
+}
+
+/// Write `bytes` to `path` unless it already holds them; record it in `changed` if written.
+fn install_file(path: &Path, bytes: &[u8], changed: &mut Vec<String>) -> Result<(), BrsrError> {
+    if fs::read(path).is_ok_and(|current| current == bytes) {
+        return Ok(());
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| BrsrError::io(parent, e))?;
+    }
+    storage::write_atomic(path, bytes)?;
+    changed.push(path.display().to_string());
+    Ok(())
+}
+
+/// Rebuild `mimeinfo.cache` for the user's applications dir, if the tool exists.
+fn update_database(dirs: &XdgDirs) -> bool {
+    match Command::new("update-desktop-database").arg(dirs.applications_home()).status() {
+        Ok(status) if status.success() => true,
+        Ok(status) => {
+            eprintln!("[Rust] update-desktop-database exited with {}", status);
+            false
+        }
+        Err(e) => {
+            println!("[Rust] Not refreshing the MIME cache: update-desktop-database unavailable ({})", e);
+            false
+        }
+    }
+}
+
+/// Install a `.desktop` entry launching `exe` for web links, and our icons.
+pub fn register(dirs: &XdgDirs, exe: &Path) -> Result<CandidateRegistration, BrsrError> {
+    let mut changed = Vec::new();
+    for (size, bytes) in ICONS {
+        install_file(&icon_path(dirs, size), bytes, &mut changed)?;
+    }
+    let entry = dirs.applications_home().join(DESKTOP_ID);
+    install_file(&entry, desktop_entry(exe).as_bytes(), &mut changed)?;
+    let database_updated = !changed.is_empty() && update_database(dirs);
+    println!("[Rust] Registered {} as a browser candidate ({} files changed)", exe.display(), changed.len());
+    Ok(CandidateRegistration {
+        desktop_id: DESKTOP_ID.to_string(),
+        changed,
+        database_updated,
+    })
+}
+
+/// Remove what [`register`] installed. Defaults that still name the entry are
+/// left alone; they are skipped once the entry is gone.
+pub fn unregister(dirs: &XdgDirs) -> Result<CandidateRegistration, BrsrError> {
+    let mut changed = Vec::new();
+    let files = std::iter::once(dirs.applications_home().join(DESKTOP_ID))
+        .chain(ICONS.iter().map(|(size, _)| icon_path(dirs, *size)));
+    for path in files {
+        match fs::remove_file(&path) {
+            Ok(()) => changed.push(path.display().to_string()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(BrsrError::io(&path, e)),
+        }
+    }
+    let database_updated = !changed.is_empty() && update_database(dirs);
+    println!("[Rust] Unregistered browser candidate ({} files removed)", changed.len());
+    Ok(CandidateRegistration {
+        desktop_id: DESKTOP_ID.to_string(),
+        changed,
+        database_updated,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xdg::exec_args;
+    use crate::xdg::keyfile::KeyFile;
+
+    #[test]
+    fn exec_line_parses_back_to_the_executable() {
+        for exe in ["/usr/bin/brsr", "/opt/my apps/brsr$1 \"x\" 100%.AppImage", r"C:\odd\path"] {
+            let file = KeyFile::parse(&desktop_entry(Path::new(exe)));
+            let exec = file.string("Desktop Entry", "Exec").unwrap();
+            let args: Vec<String> = exec_args(&exec).into_iter().map(|a| a.replace("%%", "%")).collect();
+            assert_eq!(args, [exe, "%U"], "{}", exec);
+        }
+    }
+
+    #[test]
+    fn register_is_idempotent_and_unregister_undoes_it() {
+        let root = tempfile::tempdir().unwrap();
+        let dirs = XdgDirs {
+            config_home: root.path().join("config"),
+            config_dirs: vec![],
+            data_home: root.path().join("data"),
+            data_dirs: vec![],
+            desktops: vec![],
+        };
+        assert_eq!(register(&dirs, Path::new("/opt/brsr")).unwrap().changed.len(), ICONS.len() + 1);
+        assert!(register(&dirs, Path::new("/opt/brsr")).unwrap().changed.is_empty());
+        let apps = dirs.applications();
+        assert_eq!(apps.len(), 1);
+        assert_eq!(apps[0].program().as_deref(), Some("/opt/brsr"));
+        assert_eq!(unregister(&dirs).unwrap().changed.len(), ICONS.len() + 1);
+        assert!(dirs.applications().is_empty());
+    }
+}
@@ -0,0 +1,155 @@
+//! The `[Group]` / `key=value` format shared by `.desktop` files and
+//! `mimeapps.list`.
+//!
+//! Files are kept line by line, so writing one back only touches the keys we
+//! set and leaves comments, ordering and unknown groups alone.
+
+use std::fmt;
+
+#[derive(Debug, Clone, Default)]
+pub struct KeyFile {
+    lines: Vec<String>,
+}
+
+fn group_name(line: &str) -> Option<&str> {
+    let line = line.trim();
+    line.strip_prefix('[')?.strip_suffix(']')
+}
+
+fn entry(line: &str) -> Option<(&str, &str)> {
+    let line = line.trim_start();
+    if line.starts_with('#') {
+        return None;
+    }
+    let (key, value) = line.split_once('=')?;
+    Some((key.trim(), value.trim()))
+}
+
+/// Undo the escapes the spec allows in string values.
+fn unescape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('s') => out.push(' '),
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('r') => out.push('\r'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+impl KeyFile {
+    pub fn parse(text: &str) -> Self {
+        Self {
+            lines: text.lines().map(str::to_string).collect(),
+        }
+    }
+
+    /// Line range of `group`'s entries: after its header, up to the next one.
+    fn group_range(&self, group: &str) -> Option<(usize, usize)> {
+        let start = self.lines.iter().position(|l| group_name(l) == Some(group))? + 1;
+        let end = self.lines[start..]
+            .iter()
+            .position(|l| group_name(l).is_some())
+            .map_or(self.lines.len(), |i| start + i);
+        Some((start, end))
+    }
+
+    /// The raw value of `key` in `group`. Localized variants (`Name[de]`) are
+    /// separate keys.
+    pub fn raw(&self, group: &str, key: &str) -> Option<&str> {
+        let (start, end) = self.group_range(group)?;
+        self.lines[start..end]
+            .iter()
+            .filter_map(|l| entry(l))
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| v)
+    }
+
+    pub fn string(&self, group: &str, key: &str) -> Option<String> {
+        self.raw(group, key).map(unescape)
+    }
+
+    /// A `;`-separated list value, without empty items.
+    pub fn list(&self, group: &str, key: &str) -> Vec<String> {
+        self.raw(group, key)
+            .map(|v| v.split(';').map(str::trim).filter(|s| !s.is_empty()).map(unescape).collect())
+            .unwrap_or_default()
+    }
+
+    pub fn boolean(&self, group: &str, key: &str) -> bool {
+        self.raw(group, key) == Some("true")
+    }
+
+    /// Set a list value, adding the group and key as needed.
+    pub fn set_list(&mut self, group: &str, key: &str, values: &[&str]) {
+        let line = format!("{}={};", key, values.join(";"));
+        let Some((start, end)) = self.group_range(group) else {
+            if self.lines.last().is_some_and(|l| !l.trim().is_empty()) {
+                self.lines.push(String::new());
+            }
+            self.lines.push(format!("[{}]", group));
+            self.lines.push(line);
+            return;
+        };
+        match (start..end).find(|&i| entry(&self.lines[i]).is_some_and(|(k, _)| k == key)) {
+            Some(i) => self.lines[i] = line,
+            None => {
+                // After the group's last entry, before any blank lines that separate it from the next.
+                let at = (start..end)
+                    .rev()
+                    .find(|&i| !self.lines[i].trim().is_empty())
+                    .map_or(start, |i| i + 1);
+                self.lines.insert(at, line);
+            }
+        }
+    }
+}
+
+impl fmt::Display for KeyFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in &self.lines {
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_list_replaces_in_place() {
+        let mut file = KeyFile::parse("# top\n[A]\nk=old;\n# note\nother=1\n");
+        file.set_list("A", "k", &["x", "y"]);
+        assert_eq!(file.to_string(), "# top\n[A]\nk=x;y;\n# note\nother=1\n");
+    }
+
+    #[test]
+    fn set_list_adds_keys_after_the_group_and_groups_at_the_end() {
+        let mut file = KeyFile::parse("[A]\na=1\n\n[B]\nb=2\n");
+        file.set_list("A", "k", &["x"]);
+        file.set_list("C", "k", &["y"]);
+        assert_eq!(file.to_string(), "[A]\na=1\nk=x;\n\n[B]\nb=2\n\n[C]\nk=y;\n");
+        assert_eq!(file.list("A", "k"), ["x"]);
+        assert_eq!(file.list("C", "k"), ["y"]);
+        assert!(file.list("B", "k").is_empty());
+    }
+
+    #[test]
+    fn reads_escapes_and_skips_comments() {
+        let file = KeyFile::parse("[A]\n#k=commented\nk = a\\sb;c;;d\nflag=true\n");
+        assert_eq!(file.list("A", "k"), ["a b", "c", "d"]);
+        assert_eq!(file.string("A", "k").as_deref(), Some("a b;c;;d"));
+        assert!(file.boolean("A", "flag"));
+    }
+}
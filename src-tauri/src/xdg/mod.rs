@@ -0,0 +1,369 @@
+//! Default-browser integration for Linux desktops, following the XDG specs.
+//!
+//! The default handler for a MIME type (URL schemes are `x-scheme-handler/*`)
+//! comes from the `mimeapps.list` files, read in this order:
+//! - `$XDG_CONFIG_HOME/<desktop>-mimeapps.list`, then `$XDG_CONFIG_HOME/mimeapps.list`;
+//! - the same two in each of `$XDG_CONFIG_DIRS`;
+//! - the same two in `$XDG_DATA_HOME/applications` and each of
+//!   `$XDG_DATA_DIRS/applications` (deprecated locations, still read).
+//!
+//! `<desktop>` is each name in `$XDG_CURRENT_DESKTOP`, lowercased. The first
+//! installed application named under `[Default Applications]` wins; without
+//! one, the first from `[Added Associations]`, then any application whose
+//! `.desktop` file declares the type. An application a file lists under
+//! `[Removed Associations]` is skipped for that type, there and in every file
+//! read after it.
+//!
+//! For brsr to show up among those applications without a package installing
+//! it, `install` can put a `.desktop` entry of its own in the user's data dir.
+//!
+//! Everything takes an [`XdgDirs`], so it works the same against a temporary
+//! directory tree as against the real environment.
+
+mod desktop;
+mod install;
+mod keyfile;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub use desktop::{exec_args, DesktopEntry};
+pub use install::{register, unregister, CandidateRegistration};
+use keyfile::KeyFile;
+
+use crate::error::BrsrError;
+use crate::storage;
+
+/// The desktop file ID we install under (see `register_as_browser_candidate`).
+pub const DESKTOP_ID: &str = "brsr.desktop";
+
+/// The types a browser has to handle to be the default one.
+pub const BROWSER_TYPES: [&str; 3] = ["x-scheme-handler/http", "x-scheme-handler/https", "text/html"];
+
+const DEFAULTS: &str = "Default Applications";
+const ADDED: &str = "Added Associations";
+const REMOVED: &str = "Removed Associations";
+
+#[derive(Debug, Clone)]
+pub struct XdgDirs {
+    pub config_home: PathBuf,
+    pub config_dirs: Vec<PathBuf>,
+    pub data_home: PathBuf,
+    pub data_dirs: Vec<PathBuf>,
+    /// `$XDG_CURRENT_DESKTOP`, lowercased, most specific first.
+    pub desktops: Vec<String>,
+}
+
+/// A colon-separated list of directories. Relative entries are invalid per
+/// the spec and skipped.
+fn path_list(value: Option<String>, default: &str) -> Vec<PathBuf> {
+    let value = value.filter(|v| !v.is_empty()).unwrap_or_else(|| default.to_string());
+    value.split(':').map(PathBuf::from).filter(|p| p.is_absolute()).collect()
+}
+
+impl XdgDirs {
+    /// The directories from the environment, with the spec's defaults.
+    pub fn from_env() -> Result<Self, BrsrError> {
+        let var = |name: &str| std::env::var(name).ok();
+        let home = var("HOME")
+            .filter(|h| !h.is_empty())
+            .map(PathBuf::from)
+            .ok_or_else(|| BrsrError::platform("HOME is not set"))?;
+        let dir = |name: &str, fallback: PathBuf| {
+            var(name).map(PathBuf::from).filter(|p| p.is_absolute()).unwrap_or(fallback)
+        };
+        Ok(Self {
+            config_home: dir("XDG_CONFIG_HOME", home.join(".config")),
+            config_dirs: path_list(var("XDG_CONFIG_DIRS"), "/etc/xdg"),
+            data_home: dir("XDG_DATA_HOME", home.join(".local/share")),
+            data_dirs: path_list(var("XDG_DATA_DIRS"), "/usr/local/share:/usr/share"),
+            desktops: var("XDG_CURRENT_DESKTOP")
+                .unwrap_or_default()
+                .split(':')
+                .filter(|d| !d.is_empty())
+                .map(str::to_lowercase)
+                .collect(),
+        })
+    }
+
+    /// Data dirs, most important first.
+    fn all_data_dirs(&self) -> Vec<PathBuf> {
+        std::iter::once(self.data_home.clone()).chain(self.data_dirs.iter().cloned()).collect()
+    }
+
+    /// Every `mimeapps.list` location in precedence order, whether it exists or not.
+    pub fn mimeapps_paths(&self) -> Vec<PathBuf> {
+        let dirs = std::iter::once(self.config_home.clone())
+            .chain(self.config_dirs.iter().cloned())
+            .chain(self.all_data_dirs().into_iter().map(|d| d.join("applications")));
+        dirs.flat_map(|dir| {
+            self.desktops
+                .iter()
+                .map(|d| dir.join(format!("{}-mimeapps.list", d)))
+                .chain(std::iter::once(dir.join("mimeapps.list")))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+    }
+
+    /// Installed applications.
+    pub fn applications(&self) -> Vec<DesktopEntry> {
+        desktop::scan(&self.all_data_dirs())
+    }
+
+    /// Where we install our own `.desktop` file.
+    pub fn applications_home(&self) -> PathBuf {
+        self.data_home.join("applications")
+    }
+}
+
+fn read_keyfile(path: &Path) -> Option<KeyFile> {
+    fs::read_to_string(path).ok().map(|text| KeyFile::parse(&text))
+}
+
+/// The application that opens `mime`, if any.
+pub fn default_for<'a>(dirs: &XdgDirs, apps: &'a [DesktopEntry], mime: &str) -> Option<&'a DesktopEntry> {
+    let installed = |id: &String| apps.iter().find(|a| a.id == *id);
+    let mut removed: Vec<String> = Vec::new();
+    let mut added: Vec<&DesktopEntry> = Vec::new();
+    for file in dirs.mimeapps_paths().iter().filter_map(|p| read_keyfile(p)) {
+        removed.extend(file.list(REMOVED, mime));
+        let kept = |id: &&String| !removed.contains(id);
+        if let Some(app) = file.list(DEFAULTS, mime).iter().filter(kept).find_map(installed) {
+            return Some(app);
+        }
+        added.extend(file.list(ADDED, mime).iter().filter(kept).filter_map(installed));
+    }
+    added
+        .first()
+        .copied()
+        .or_else(|| apps.iter().find(|a| a.handles(mime) && !removed.contains(&a.id)))
+}
+
+/// Applications that can be the default browser.
+pub fn browser_candidates(apps: &[DesktopEntry]) -> Vec<&DesktopEntry> {
+    apps.iter()
+        .filter(|a| a.handles("x-scheme-handler/http") || a.handles("x-scheme-handler/https"))
+        .collect()
+}
+
+/// What a desktop entry should launch to start us: the AppImage when running
+/// from one (the executable is inside its temporary mount), else this executable.
+pub fn executable() -> Option<PathBuf> {
+    std::env::var_os("APPIMAGE").map(PathBuf::from).or_else(|| std::env::current_exe().ok())
+}
+
+/// Our own entry: the one we installed, or one launching [`executable`].
+pub fn own_entry(apps: &[DesktopEntry]) -> Option<&DesktopEntry> {
+    if let Some(app) = apps.iter().find(|a| a.id == DESKTOP_ID) {
+        return Some(app);
+    }
+    let exe = executable()?;
+    apps.iter().find(|a| {
+        a.program().is_some_and(|program| {
+            let program = Path::new(&program);
+            if program.is_absolute() {
+                program == exe
+            } else {
+                program.file_name() == exe.file_name()
+            }
+        })
+    })
+}
+
+/// Make `id` the default for [`BROWSER_TYPES`] in the user's
+/// `mimeapps.list`, and in any desktop-specific user file that overrides
+/// one of them. Returns the files that were written.
+pub fn set_default_browser(dirs: &XdgDirs, id: &str) -> Result<Vec<PathBuf>, BrsrError> {
+    let mut changed = Vec::new();
+    let user_file = dirs.config_home.join("mimeapps.list");
+    let overrides = dirs
+        .desktops
+        .iter()
+        .map(|d| dirs.config_home.join(format!("{}-mimeapps.list", d)))
+        .filter(|path| {
+            read_keyfile(path).is_some_and(|f| BROWSER_TYPES.iter().any(|t| f.raw(DEFAULTS, t).is_some()))
+        });
+    for path in std::iter::once(user_file.clone()).chain(overrides) {
+        let before = fs::read_to_string(&path).unwrap_or_default();
+        let mut file = KeyFile::parse(&before);
+        for mime in BROWSER_TYPES {
+            file.set_list(DEFAULTS, mime, &[id]);
+            if path == user_file {
+                let mut added = file.list(ADDED, mime);
+                added.retain(|a| a != id);
+                let mut ids = vec![id];
+                ids.extend(added.iter().map(String::as_str));
+                file.set_list(ADDED, mime, &ids);
+                let removed = file.list(REMOVED, mime);
+                if removed.iter().any(|r| r == id) {
+                    let kept: Vec<&str> = removed.iter().map(String::as_str).filter(|r| *r != id).collect();
+                    file.set_list(REMOVED, mime, &kept);
+                }
+            }
+        }
+        let after = file.to_string();
+        if after == before {
+            continue;
+        }
+        fs::create_dir_all(&dirs.config_home).map_err(|e| BrsrError::io(&dirs.config_home, e))?;
+        storage::write_atomic(&path, after.as_bytes())?;
+        println!("[Rust] Set {} as default browser in {}", id, path.display());
+        changed.push(path);
+    }
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An XDG tree under a temporary directory, with `gnome` as the desktop.
+    struct Tree {
+        root: tempfile::TempDir,
+        dirs: XdgDirs,
+    }
+
+    impl Tree {
+        fn new() -> Self {
+            let root = tempfile::tempdir().unwrap();
+            let dirs = XdgDirs {
+                config_home: root.path().join("home/.config"),
+                config_dirs: vec![root.path().join("etc/xdg")],
+                data_home: root.path().join("home/.local/share"),
+                data_dirs: vec![root.path().join("usr/share")],
+                desktops: vec!["gnome".to_string()],
+            };
+            Self { root, dirs }
+        }
+
+        fn write(&self, rel: &str, text: &str) {
+            let path = self.root.path().join(rel);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, text).unwrap();
+        }
+
+        fn read(&self, rel: &str) -> String {
+            fs::read_to_string(self.root.path().join(rel)).unwrap()
+        }
+
+        /// A system-wide application handling the browser types.
+        fn app(&self, id: &str) {
+            self.write(
+                &format!("usr/share/applications/{}", id),
+                &format!(
+                    "[Desktop Entry]\nType=Application\nName={}\nExec={} %u\nMimeType={};\n",
+                    id,
+                    id.trim_end_matches(".desktop"),
+                    BROWSER_TYPES.join(";")
+                ),
+            );
+        }
+
+        fn default_http(&self) -> Option<String> {
+            let apps = self.dirs.applications();
+            default_for(&self.dirs, &apps, "x-scheme-handler/http").map(|a| a.id.clone())
+        }
+    }
+
+    #[test]
+    fn mimeapps_paths_go_from_user_config_to_system_data() {
+        let tree = Tree::new();
+        let rel: Vec<String> = tree
+            .dirs
+            .mimeapps_paths()
+            .iter()
+            .map(|p| p.strip_prefix(tree.root.path()).unwrap().display().to_string())
+            .collect();
+        assert_eq!(
+            rel,
+            [
+                "home/.config/gnome-mimeapps.list",
+                "home/.config/mimeapps.list",
+                "etc/xdg/gnome-mimeapps.list",
+                "etc/xdg/mimeapps.list",
+                "home/.local/share/applications/gnome-mimeapps.list",
+                "home/.local/share/applications/mimeapps.list",
+                "usr/share/applications/gnome-mimeapps.list",
+                "usr/share/applications/mimeapps.list",
+            ]
+        );
+    }
+
+    #[test]
+    fn defaults_beat_added_associations_in_any_file() {
+        let tree = Tree::new();
+        for id in ["a.desktop", "b.desktop", "c.desktop"] {
+            tree.app(id);
+        }
+        assert_eq!(tree.default_http().as_deref(), Some("a.desktop"), "first declaring app");
+        tree.write("home/.config/mimeapps.list", "[Added Associations]\nx-scheme-handler/http=b.desktop;\n");
+        assert_eq!(tree.default_http().as_deref(), Some("b.desktop"));
+        tree.write("usr/share/applications/mimeapps.list", "[Default Applications]\nx-scheme-handler/http=c.desktop\n");
+        assert_eq!(tree.default_http().as_deref(), Some("c.desktop"));
+    }
+
+    #[test]
+    fn desktop_specific_files_and_missing_apps() {
+        let tree = Tree::new();
+        tree.app("a.desktop");
+        tree.app("b.desktop");
+        tree.write("home/.config/mimeapps.list", "[Default Applications]\nx-scheme-handler/http=a.desktop\n");
+        tree.write(
+            "home/.config/gnome-mimeapps.list",
+            "[Default Applications]\nx-scheme-handler/http=gone.desktop;b.desktop;\n",
+        );
+        assert_eq!(tree.default_http().as_deref(), Some("b.desktop"));
+    }
+
+    #[test]
+    fn removed_associations_hide_apps_in_less_important_files() {
+        let tree = Tree::new();
+        tree.app("a.desktop");
+        tree.app("b.desktop");
+        tree.write(
+            "home/.config/mimeapps.list",
+            "[Removed Associations]\nx-scheme-handler/http=a.desktop;\n",
+        );
+        tree.write("etc/xdg/mimeapps.list", "[Default Applications]\nx-scheme-handler/http=a.desktop\n");
+        assert_eq!(tree.default_http().as_deref(), Some("b.desktop"));
+
+        // A more important file still may name it.
+        tree.write(
+            "home/.config/gnome-mimeapps.list",
+            "[Default Applications]\nx-scheme-handler/http=a.desktop\n",
+        );
+        assert_eq!(tree.default_http().as_deref(), Some("a.desktop"));
+    }
+
+    #[test]
+    fn set_default_browser_round_trips_and_keeps_the_rest() {
+        let tree = Tree::new();
+        tree.app("other.desktop");
+        tree.app(DESKTOP_ID);
+        let user = "# managed by hand\n[Default Applications]\nx-scheme-handler/http=other.desktop\n\
+                    image/png=viewer.desktop\n\n[Added Associations]\ntext/html=other.desktop;\n\n\
+                    [Removed Associations]\nx-scheme-handler/https=brsr.desktop;other.desktop;\n";
+        tree.write("home/.config/mimeapps.list", user);
+        let other_https = "[Default Applications]\nx-scheme-handler/https=other.desktop\n";
+        tree.write("home/.config/gnome-mimeapps.list", other_https);
+        tree.write("home/.config/kde-mimeapps.list", other_https);
+
+        let changed = set_default_browser(&tree.dirs, DESKTOP_ID).unwrap();
+        assert_eq!(changed.len(), 2, "the user file and the gnome override; kde isn't running");
+        assert_eq!(tree.default_http().as_deref(), Some(DESKTOP_ID));
+        let apps = tree.dirs.applications();
+        for mime in BROWSER_TYPES {
+            assert_eq!(default_for(&tree.dirs, &apps, mime).unwrap().id, DESKTOP_ID, "{}", mime);
+        }
+
+        let written = tree.read("home/.config/mimeapps.list");
+        assert!(written.starts_with("# managed by hand\n"));
+        assert!(written.contains("image/png=viewer.desktop\n"));
+        assert!(written.contains("text/html=brsr.desktop;other.desktop;\n"));
+        assert!(written.contains("x-scheme-handler/https=other.desktop;\n"));
+        assert!(tree.read("home/.config/kde-mimeapps.list").contains("=other.desktop"));
+
+        assert!(set_default_browser(&tree.dirs, DESKTOP_ID).unwrap().is_empty());
+    }
+}
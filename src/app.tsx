@@ -1,7 +1,8 @@
 import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
-import { getCurrent, onOpenUrl } from "@tauri-apps/plugin-deep-link";
 import { useCallback, useEffect, useRef, useState } from "react";
+import type { LoadUpdate } from "./bindings/LoadUpdate";
+import type { RouteDecision } from "./bindings/RouteDecision";
 import { AppSidebar } from "./components/app-sidebar";
 import { AssistantPanel } from "./components/assistant-panel";
 import { SettingsDialog } from "./components/settings-dialog";
@@ -15,7 +16,21 @@ import {
   waitForDefaultChange,
 } from "./hooks/use-is-default-browser";
 import { initTheme } from "./lib/theme";
-import type { BrowserState, Tab } from "./types";
+import type { BrowserState, NativeTabState, Tab } from "./types";
+
+const WEBVIEW_LABEL_PREFIX = "webview-";
+
+function tabFromNative(t: NativeTabState): Tab {
+  return {
+    id: `tab-${t.label.replace(WEBVIEW_LABEL_PREFIX, "")}`,
+    title: t.title || "New Tab",
+    url: t.url,
+    active: false,
+    webviewLabel: t.label,
+    pinned: t.pinned,
+    private: t.private,
+  };
+}
 
 function App() {
   const [state, setState] = useState<BrowserState>({
@@ -28,9 +43,34 @@ function App() {
   });
   const { isDefaultBrowser, refresh } = useIsDefaultBrowser();
 
-  // Using JS-side webview plugin now; no manual registry required
+  // Rebuild the tab strip from the Rust tab registry after a frontend reload or
+  // a restored session, so native webviews created without the UI are adopted.
+  useEffect(() => {
+    invoke<NativeTabState[]>("list_webviews")
+      .then((native) => {
+        if (native.length === 0) {
+          return;
+        }
+        console.log("[Frontend] Restoring tabs from registry:", native);
+        const tabs = native.map(tabFromNative);
+        const active = native.reduce((a, b) =>
+          b.lastActiveAt > a.lastActiveAt ? b : a
+        );
+        setState((prev) =>
+          prev.tabs.length > 0
+            ? prev
+            : {
+                tabs,
+                activeTabId: tabs[native.indexOf(active)].id,
+              }
+        );
+      })
+      .catch((error) => {
+        console.error("[Frontend] Failed to list webviews:", error);
+      });
+  }, []);
 
-  const createNewTab = useCallback((initialUrl?: string) => {
+  const createNewTab = useCallback((initialUrl?: string, isPrivate = false) => {
     const timestamp = Date.now();
     const startUrl = initialUrl ?? "https://www.google.com";
 
@@ -40,8 +80,7 @@ function App() {
       url: startUrl,
       active: true,
       webviewLabel: `webview-${timestamp}`,
-      history: [startUrl],
-      historyIndex: 0,
+      private: isPrivate,
     };
 
     console.log("[Frontend] Creating new tab:", newTab);
@@ -78,6 +117,24 @@ function App() {
     });
   }, []);
 
+  // Bring back the most recently closed tab; Rust recreates the webview and
+  // the new slot adopts it.
+  const reopenClosedTab = useCallback(async () => {
+    try {
+      const label = await invoke<string>("reopen_closed_tab");
+      const native = await invoke<NativeTabState>("get_webview_state", {
+        label,
+      });
+      const tab = tabFromNative(native);
+      setState((prev) => ({
+        tabs: [...prev.tabs.filter((t) => t.id !== tab.id), tab],
+        activeTabId: tab.id,
+      }));
+    } catch (error) {
+      console.error("[Frontend] Failed to reopen closed tab:", error);
+    }
+  }, []);
+
   const selectTab = useCallback((tabId: string) => {
     console.log("[Frontend] Selecting tab:", tabId);
     setState((prev) => ({
@@ -109,18 +166,8 @@ function App() {
           url,
         });
 
-        // Update the tab's URL and history
-        const history = activeTab.history || [activeTab.url];
-        const historyIndex = activeTab.historyIndex ?? 0;
-
-        // When navigating to a new URL, clear forward history and add new entry
-        const newHistory = [...history.slice(0, historyIndex + 1), url];
-
-        updateTab(activeTab.id, {
-          url,
-          history: newHistory,
-          historyIndex: newHistory.length - 1,
-        });
+        // Back/forward state follows from Rust's session history
+        updateTab(activeTab.id, { url });
       } catch (error) {
         console.error("Navigation failed:", error);
       }
@@ -134,8 +181,7 @@ function App() {
       return;
     }
 
-    const historyIndex = activeTab.historyIndex ?? 0;
-    if (historyIndex > 0) {
+    if (navState.canGoBack) {
       try {
         await invoke("navigate_back_webview", {
           label: activeTab.webviewLabel,
@@ -144,17 +190,12 @@ function App() {
           "[Frontend] Navigated back in webview:",
           activeTab.webviewLabel
         );
-
-        // Update history index
-        updateTab(activeTab.id, {
-          historyIndex: historyIndex - 1,
-          url: activeTab.history?.[historyIndex - 1] || activeTab.url,
-        });
+        // The tab URL follows from the webview-navigated event once the traversal lands
       } catch (error) {
         console.error("Back navigation failed:", error);
       }
     }
-  }, [state.tabs, state.activeTabId, updateTab]);
+  }, [state.tabs, state.activeTabId, navState.canGoBack]);
 
   const handleForward = useCallback(async () => {
     const activeTab = state.tabs.find((t) => t.id === state.activeTabId);
@@ -162,10 +203,7 @@ function App() {
       return;
     }
 
-    const history = activeTab.history || [];
-    const historyIndex = activeTab.historyIndex ?? 0;
-
-    if (historyIndex < history.length - 1) {
+    if (navState.canGoForward) {
       try {
         await invoke("navigate_forward_webview", {
           label: activeTab.webviewLabel,
@@ -174,17 +212,11 @@ function App() {
           "[Frontend] Navigated forward in webview:",
           activeTab.webviewLabel
         );
-
-        // Update history index
-        updateTab(activeTab.id, {
-          historyIndex: historyIndex + 1,
-          url: history[historyIndex + 1],
-        });
       } catch (error) {
         console.error("Forward navigation failed:", error);
       }
     }
-  }, [state.tabs, state.activeTabId, updateTab]);
+  }, [state.tabs, state.activeTabId, navState.canGoForward]);
 
   const handleReload = useCallback(async () => {
     const activeTab = state.tabs.find((t) => t.id === state.activeTabId);
@@ -222,33 +254,7 @@ function App() {
                 `[Frontend] Updating tab ${tab.id} URL from ${tab.url} to ${url}`
               );
 
-              const history = tab.history || [tab.url];
-              const historyIndex = tab.historyIndex ?? 0;
-
-              // Check if this is a back/forward navigation or a new navigation
-              // If the URL exists in history near our current position, it's likely back/forward
-              const existingIndex = history.indexOf(url);
-
-              if (
-                existingIndex !== -1 &&
-                Math.abs(existingIndex - historyIndex) <= 1
-              ) {
-                // This is likely a back/forward navigation
-                return {
-                  ...tab,
-                  url,
-                  historyIndex: existingIndex,
-                };
-              }
-              // This is a new navigation (e.g., clicking a link)
-              // Clear forward history and add new entry
-              const newHistory = [...history.slice(0, historyIndex + 1), url];
-              return {
-                ...tab,
-                url,
-                history: newHistory,
-                historyIndex: newHistory.length - 1,
-              };
+              return { ...tab, url };
             }
             return tab;
           }),
@@ -261,6 +267,41 @@ function App() {
     };
   }, []);
 
+  // Page-load lifecycle from Rust (page_load.rs): drives the spinner and progress bar
+  useEffect(() => {
+    const apply = (update: LoadUpdate, loading: boolean) =>
+      setState((prev) => ({
+        ...prev,
+        tabs: prev.tabs.map((tab) =>
+          tab.webviewLabel === update.label
+            ? { ...tab, loading, loadProgress: update.progress }
+            : tab
+        ),
+      }));
+    const unlisteners = [
+      listen<LoadUpdate>("webview-load-started", (event) =>
+        apply(event.payload, true)
+      ),
+      listen<LoadUpdate>("webview-load-progress", (event) =>
+        apply(event.payload, true)
+      ),
+      listen<LoadUpdate>("webview-load-finished", (event) => {
+        const { label, phase, url, elapsedMs, error } = event.payload;
+        console.log(
+          `[Frontend] Webview ${label} load ${phase} in ${Math.round(elapsedMs)} ms: ${url}`,
+          error ?? ""
+        );
+        apply(event.payload, false);
+      }),
+    ];
+
+    return () => {
+      for (const unlisten of unlisteners) {
+        unlisten.then((fn) => fn());
+      }
+    };
+  }, []);
+
   // Listen for webview title changes and update associated tab
   useEffect(() => {
     const unlistenTitle = listen<{ label: string; title: string }>(
@@ -292,6 +333,7 @@ function App() {
   const stateRef = useRef(state);
   const navigateActiveTabRef = useRef(navigateActiveTab);
   const createNewTabRef = useRef(createNewTab);
+  const reopenClosedTabRef = useRef(reopenClosedTab);
 
   // Update refs when values change
   useEffect(() => {
@@ -306,7 +348,12 @@ function App() {
     createNewTabRef.current = createNewTab;
   }, [createNewTab]);
 
-  // Initialize theme and register Cmd+, shortcut to open Settings
+  useEffect(() => {
+    reopenClosedTabRef.current = reopenClosedTab;
+  }, [reopenClosedTab]);
+
+  // Initialize theme and register Cmd+, (Settings), Cmd+Shift+T (reopen closed
+  // tab) and Cmd+Shift+N (new private tab)
   useEffect(() => {
     initTheme().catch(() => {
       // Ignore theme init errors (e.g., localStorage)
@@ -315,6 +362,12 @@ function App() {
       if (e.metaKey && e.key === ",") {
         e.preventDefault();
         window.dispatchEvent(new CustomEvent("open-settings"));
+      } else if (e.metaKey && e.shiftKey && e.key.toLowerCase() === "t") {
+        e.preventDefault();
+        reopenClosedTabRef.current();
+      } else if (e.metaKey && e.shiftKey && e.key.toLowerCase() === "n") {
+        e.preventDefault();
+        createNewTabRef.current(undefined, true);
       }
     };
     window.addEventListener("keydown", onKeyDown);
@@ -323,57 +376,57 @@ function App() {
     };
   }, []);
 
-  // Handle deep link URL opening (always open in a new tab)
+  // Open links from other apps. Rust queues them until we subscribe, routes
+  // each one, and sends only those meant for a tab in this window
   useEffect(() => {
-    let unlisten: (() => void) | null = null;
-
-    const openUrls = (urls: string[]) => {
-      for (const raw of urls) {
-        const url = raw.trim();
-        // Always open incoming links in a fresh tab, per app policy
-        createNewTabRef.current(url);
-      }
-    };
-
-    (async () => {
-      try {
-        // If the app was launched by a link, handle it immediately:
-        const initial = await getCurrent(); // may be null
-        if (initial?.length) {
-          openUrls(initial);
-        }
-
-        // Handle subsequent links while the app is running:
-        unlisten = await onOpenUrl((urls) => openUrls(urls));
-      } catch (error) {
-        console.error("[Frontend] Deep link setup error:", error);
+    const unlisten = listen<RouteDecision[]>("open-urls", (event) => {
+      for (const { url, target } of event.payload) {
+        createNewTabRef.current(url, target.kind === "privateTab");
       }
-    })();
+    });
+    unlisten
+      .then(() => invoke("subscribe_open_urls"))
+      .catch((error) =>
+        console.error("[Frontend] Deep link setup error:", error)
+      );
 
     return () => {
-      if (unlisten) {
-        unlisten();
-      }
+      unlisten.then((fn) => fn());
     };
   }, []);
 
-  // Update navigation state based on history
+  // Update navigation state from the session history tracked in Rust
+  const activeLabel = activeTab?.webviewLabel ?? null;
+  const activeUrl = activeTab?.url;
   useEffect(() => {
-    if (!activeTab) {
+    if (!activeLabel) {
       setNavState({ canGoBack: false, canGoForward: false });
       return;
     }
 
-    const historyIndex = activeTab.historyIndex ?? 0;
-    const history = activeTab.history || [];
-
-    // We can go back if we're not at the beginning of history
-    const canGoBack = historyIndex > 0;
-    // We can go forward if we're not at the end of history
-    const canGoForward = historyIndex < history.length - 1;
+    invoke<[boolean, boolean]>("check_navigation_state", {
+      label: activeLabel,
+    })
+      .then(([canGoBack, canGoForward]) =>
+        setNavState({ canGoBack, canGoForward })
+      )
+      .catch(() => setNavState({ canGoBack: false, canGoForward: false }));
+
+    const unlisten = listen<{
+      label: string;
+      canGoBack: boolean;
+      canGoForward: boolean;
+    }>("webview-history-changed", (event) => {
+      if (event.payload.label === activeLabel) {
+        const { canGoBack, canGoForward } = event.payload;
+        setNavState({ canGoBack, canGoForward });
+      }
+    });
 
-    setNavState({ canGoBack, canGoForward });
-  }, [activeTab]);
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, [activeLabel, activeUrl]);
 
   return (
     <SidebarProvider>
@@ -381,9 +434,11 @@ function App() {
         <AppSidebar />
         <div className="flex w-full flex-1 flex-col">
           <TopBar
+            activeLabel={activeLabel}
             canGoBack={navState.canGoBack}
             canGoForward={navState.canGoForward}
             currentUrl={activeTab?.url || ""}
+            loadProgress={activeTab?.loading ? activeTab.loadProgress : null}
             onBack={handleBack}
             onForward={handleForward}
             onMakeDefaultBrowser={
@@ -426,7 +481,8 @@ function App() {
 
           <TabStrip
             activeTabId={state.activeTabId}
-            onNewTab={createNewTab}
+            onNewPrivateTab={() => createNewTab(undefined, true)}
+            onNewTab={() => createNewTab()}
             onTabClick={selectTab}
             onTabClose={closeTab}
             tabs={state.tabs}
@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Blocked requests in one tab.
+ */
+export type BlockedCounts = { 
+/**
+ * On the current document.
+ */
+page: number, 
+/**
+ * Since the tab was opened.
+ */
+total: number, };
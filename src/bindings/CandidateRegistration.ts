@@ -0,0 +1,16 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * What `register_as_browser_candidate` or `unregister_browser_candidate` did.
+ */
+export type CandidateRegistration = { desktopId: string, 
+/**
+ * Files written, or removed when unregistering. Files that were already
+ * up to date aren't listed.
+ */
+changed: Array<string>, 
+/**
+ * Whether `update-desktop-database` ran. Where it isn't installed,
+ * desktops pick the change up on their own next scan.
+ */
+databaseUpdated: boolean, };
@@ -0,0 +1,19 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * What a clear removed.
+ */
+export type ClearedData = { cookies: number, 
+/**
+ * Sites whose storage or caches were cleared.
+ */
+origins: number, 
+/**
+ * Whether storage or caches were cleared from the whole store at once,
+ * which doesn't count sites.
+ */
+wholeStore: boolean, historyVisits: number, 
+/**
+ * Origins that couldn't be cleared, with the reason.
+ */
+failed: Array<string>, };
@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type CookieInfo = { name: string, value: string, 
+/**
+ * Without the leading dot.
+ */
+domain: string, path: string, 
+/**
+ * Milliseconds since the epoch; `None` for session cookies.
+ */
+expires: number | null, secure: boolean, httpOnly: boolean, sameSite: string | null, };
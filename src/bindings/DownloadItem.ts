@@ -0,0 +1,24 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DownloadState } from "./DownloadState";
+
+export type DownloadItem = { id: number, url: string, 
+/**
+ * Page the download started from, sent as the referrer.
+ */
+referrer: string | null, 
+/**
+ * Tab the download started from; its cookies go with the request.
+ */
+label: string | null, path: string, fileName: string, state: DownloadState, receivedBytes: number, 
+/**
+ * Unknown until the server sends a length, and for some servers never.
+ */
+totalBytes: number | null, error: string | null, 
+/**
+ * Saved by the webview itself (`blob:` and `data:` URLs).
+ */
+native: boolean, 
+/**
+ * Started from a private tab: listed until the last one closes, never saved.
+ */
+private: boolean, startedAt: number, finishedAt: number | null, };
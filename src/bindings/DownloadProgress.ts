@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Payload of `download-progress`, sent a few times a second per transfer.
+ */
+export type DownloadProgress = { id: number, receivedBytes: number, totalBytes: number | null, bytesPerSecond: number, };
@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type DownloadSettings = { 
+/**
+ * Where downloads go; the OS downloads folder when unset.
+ */
+directory: string | null, 
+/**
+ * Show a save dialog for every download instead.
+ */
+askWhereToSave: boolean, };
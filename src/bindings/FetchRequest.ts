@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type FetchRequest = { url: string, method: string, status: number, duration: number, 
+/**
+ * First 2000 characters of a JSON or text response body.
+ */
+preview: string | null, contentType: string | null, };
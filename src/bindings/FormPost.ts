@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type FormPost = { 
+/**
+ * Where the form goes. A download answering it can't be fetched again.
+ */
+action: string, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * History change reported by the injected script.
+ */
+export type HistoryAction = "push" | "replace" | "pop" | "traverse";
@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { HistoryAction } from "./HistoryAction";
+
+export type HistoryChange = { action: HistoryAction, url: string, 
+/**
+ * Steps requested by a `traverse`.
+ */
+delta: number | null, };
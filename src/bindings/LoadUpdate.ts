@@ -0,0 +1,32 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { LoadPhase } from "./LoadPhase";
+
+/**
+ * Payload of the three load events.
+ */
+export type LoadUpdate = { label: string, 
+/**
+ * Counts the tab's loads, so events of a replaced load can be told apart.
+ */
+loadId: number, phase: LoadPhase, 
+/**
+ * 0 to 1, estimated from the phase.
+ */
+progress: number, 
+/**
+ * Milliseconds since the load started.
+ */
+elapsedMs: number, 
+/**
+ * The URL being loaded; once committed, the final one after redirects.
+ */
+url: string, 
+/**
+ * When DOMContentLoaded started, in milliseconds from navigation start,
+ * as the page measured it.
+ */
+domContentLoadedMs: number | null, 
+/**
+ * Why a `failed` load failed.
+ */
+error: string | null, };
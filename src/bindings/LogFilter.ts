@@ -0,0 +1,28 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ConsoleLevel } from "./ConsoleLevel";
+import type { LogKind } from "./LogKind";
+
+/**
+ * Which buffers `get_tab_logs` returns and how they are narrowed.
+ */
+export type LogFilter = { 
+/**
+ * Buffers to include; all of them when empty.
+ */
+kinds: Array<LogKind>, 
+/**
+ * Console levels to include; all when empty.
+ */
+levels: Array<ConsoleLevel>, 
+/**
+ * Case-insensitive substring of the message or URL.
+ */
+text: string | null, 
+/**
+ * Only entries with `ts` at or after this (page clock, ms since epoch).
+ */
+since: number | null, 
+/**
+ * Most recent N entries per buffer.
+ */
+limit: number | null, };
@@ -0,0 +1,12 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RequestKind } from "./RequestKind";
+
+export type NetworkEntry = { ts: number, kind: RequestKind, url: string, method: string, 
+/**
+ * `None` when the request never got a response.
+ */
+status: number | null, duration: number, error: string | null, contentType: string | null, 
+/**
+ * Response body preview, fetch only.
+ */
+preview: string | null, };
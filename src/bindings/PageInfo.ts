@@ -0,0 +1,9 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { PageHeading } from "./PageHeading";
+import type { PageLink } from "./PageLink";
+
+export type PageInfo = { title: string, url: string, lang: string | null, 
+/**
+ * `<meta name|property>` → `content`, first occurrence wins.
+ */
+metas: { [key in string]?: string }, icons: Array<string>, themeColor: string | null, links: Array<PageLink>, headings: Array<PageHeading>, };
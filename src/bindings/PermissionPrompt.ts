@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Permission } from "./Permission";
+
+export type PermissionPrompt = { 
+/**
+ * Pass to `resolve_permission_request`.
+ */
+requestId: number, label: string, origin: string, permission: Permission, };
@@ -0,0 +1,12 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Permission } from "./Permission";
+
+export type PermissionRequest = { 
+/**
+ * The page's id for the request, echoed back with the answer.
+ */
+id: number, permission: Permission, 
+/**
+ * `location.origin`; checked against the tab's committed URL.
+ */
+origin: string, };
@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Profile } from "./Profile";
+
+export type ProfileList = { profiles: Array<Profile>, 
+/**
+ * The profile this window runs.
+ */
+current: string, };
@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A `PerformanceResourceTiming` entry.
+ */
+export type ResourceTiming = { 
+/**
+ * `initiatorType`: `img`, `script`, `fetch`, …
+ */
+type: string, name: string, duration: number, startTime: number, 
+/**
+ * Missing on engines that don't expose it; reported as 0.
+ */
+transferSize: number, };
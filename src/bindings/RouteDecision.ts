@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RouteTarget } from "./RouteTarget";
+
+/**
+ * Where a link goes, and which rule sent it there.
+ */
+export type RouteDecision = { url: string, 
+/**
+ * Index of the matching rule; `None` when no rule matched.
+ */
+ruleIndex: number | null, ruleName: string | null, target: RouteTarget, };
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RouteTarget } from "./RouteTarget";
+
+export type RouteRule = { name: string, enabled: boolean, hosts: Array<string>, path: string | null, regex: string | null, sourceApps: Array<string>, target: RouteTarget, };
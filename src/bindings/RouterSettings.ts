@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RouteRule } from "./RouteRule";
+
+export type RouterSettings = { 
+/**
+ * When off, every link opens in a tab here.
+ */
+enabled: boolean, rules: Array<RouteRule>, };
@@ -0,0 +1,9 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Permission } from "./Permission";
+import type { PermissionState } from "./PermissionState";
+
+export type SitePermissions = { origin: string, 
+/**
+ * Every permission, including the ones still at "ask".
+ */
+permissions: { [key in Permission]?: PermissionState }, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ConsoleEntry } from "./ConsoleEntry";
+import type { ErrorEntry } from "./ErrorEntry";
+import type { NetworkEntry } from "./NetworkEntry";
+import type { ResourceEntry } from "./ResourceEntry";
+
+export type TabLogs = { console: Array<ConsoleEntry>, errors: Array<ErrorEntry>, network: Array<NetworkEntry>, resources: Array<ResourceEntry>, };
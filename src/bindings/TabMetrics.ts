@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Page-level numbers for the tab's current document.
+ */
+export type TabMetrics = { label: string, url: string | null, title: string | null, 
+/**
+ * When the current document's script started (`init`), page clock.
+ */
+documentStartedAt: number | null, 
+/**
+ * All times below are milliseconds since navigation start.
+ */
+firstPaint: number | null, firstContentfulPaint: number | null, largestContentfulPaint: number | null, domContentLoaded: number | null, loadEventEnd: number | null, longTaskCount: number, longTaskTotalMs: number, resourceCount: number, transferBytes: number, requestCount: number, failedRequests: number, consoleErrors: number, consoleWarnings: number, scriptErrors: number, };
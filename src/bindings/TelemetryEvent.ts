@@ -0,0 +1,23 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { BlockedRequest } from "./BlockedRequest";
+import type { ConsoleMessage } from "./ConsoleMessage";
+import type { DomReady } from "./DomReady";
+import type { FetchError } from "./FetchError";
+import type { FetchRequest } from "./FetchRequest";
+import type { FormPost } from "./FormPost";
+import type { Heartbeat } from "./Heartbeat";
+import type { HistoryChange } from "./HistoryChange";
+import type { LargestContentfulPaint } from "./LargestContentfulPaint";
+import type { LongTask } from "./LongTask";
+import type { NavigationTiming } from "./NavigationTiming";
+import type { PageInfo } from "./PageInfo";
+import type { Paint } from "./Paint";
+import type { PermissionRequest } from "./PermissionRequest";
+import type { Resource } from "./Resource";
+import type { ScriptError } from "./ScriptError";
+import type { Scroll } from "./Scroll";
+import type { Selection } from "./Selection";
+import type { UnhandledRejection } from "./UnhandledRejection";
+import type { XhrRequest } from "./XhrRequest";
+
+export type TelemetryEvent = { "kind": "init" } | { "kind": "heartbeat" } & Heartbeat | { "kind": "page-info" } & PageInfo | { "kind": "selection" } & Selection | { "kind": "resource" } & Resource | { "kind": "paint" } & Paint | { "kind": "lcp" } & LargestContentfulPaint | { "kind": "navigation" } & NavigationTiming | { "kind": "longtask" } & LongTask | { "kind": "fetch" } & FetchRequest | { "kind": "fetch-error" } & FetchError | { "kind": "xhr" } & XhrRequest | { "kind": "console" } & ConsoleMessage | { "kind": "error" } & ScriptError | { "kind": "unhandledrejection" } & UnhandledRejection | { "kind": "history" } & HistoryChange | { "kind": "scroll" } & Scroll | { "kind": "blocked" } & BlockedRequest | { "kind": "permission-request" } & PermissionRequest | { "kind": "dom-ready" } & DomReady | { "kind": "form-post" } & FormPost;
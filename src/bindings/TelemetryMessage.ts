@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { TelemetryEvent } from "./TelemetryEvent";
+
+/**
+ * One event as sent by the injected script: the envelope plus the event body.
+ */
+export type TelemetryMessage = { 
+/**
+ * Schema version the script was built against.
+ */
+v: number, label: string, 
+/**
+ * `Date.now()` in the page when the event was produced.
+ */
+ts: number, } & TelemetryEvent;
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type XhrRequest = { url: string, method: string, 
+/**
+ * 0 when the request failed or was aborted.
+ */
+status: number, duration: number, contentType: string | null, };
@@ -1,4 +1,6 @@
 import { Home, Settings } from "lucide-react";
+import { DownloadsPanel } from "@/components/downloads-panel";
+import { useProfiles } from "@/components/profiles-section";
 import { Button } from "@/components/ui/button";
 import {
   Sidebar,
@@ -14,11 +16,20 @@ import {
 } from "@/components/ui/sidebar";
 
 export function AppSidebar() {
+  const { current } = useProfiles();
+
   return (
     <Sidebar>
       <div className="h-12 w-full" data-tauri-drag-region />
       <SidebarHeader>
-        <div className="px-2 font-semibold">brsr</div>
+        <div className="flex items-baseline gap-2 px-2">
+          <span className="font-semibold">brsr</span>
+          {current && current.id !== "default" && (
+            <span className="truncate text-muted-foreground text-sm">
+              {current.name}
+            </span>
+          )}
+        </div>
       </SidebarHeader>
       <SidebarContent>
         <SidebarGroup>
@@ -34,6 +45,7 @@ export function AppSidebar() {
             </SidebarMenu>
           </SidebarGroupContent>
         </SidebarGroup>
+        <DownloadsPanel />
       </SidebarContent>
       <SidebarFooter>
         <div className="px-2 py-2">
@@ -1,120 +1,19 @@
+import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import { useEffect, useMemo, useState } from "react";
+import type { ConsoleLevel } from "@/bindings/ConsoleLevel";
+import type { PageInfo } from "@/bindings/PageInfo";
+import type { TabLogs } from "@/bindings/TabLogs";
+import type { TabMetrics } from "@/bindings/TabMetrics";
+import type { TelemetryMessage } from "@/bindings/TelemetryMessage";
 
-type TelemetryEvent =
-  | {
-      label: string;
-      ts: number;
-      kind: "init" | "heartbeat";
-      title?: string;
-      url?: string;
-    }
-  | {
-      label: string;
-      ts: number;
-      kind: "page-info";
-      title: string;
-      url: string;
-      lang: string | null;
-      metas: Record<string, string>;
-      icons: string[];
-      themeColor: string | null;
-      links: Array<{ href: string; text: string }>;
-      headings: Array<{ tag: string; text: string }>;
-    }
-  | { label: string; ts: number; kind: "selection"; text: string }
-  | {
-      label: string;
-      ts: number;
-      kind: "resource";
-      item: {
-        type: string;
-        name: string;
-        duration: number;
-        startTime: number;
-        transferSize: number;
-      };
-    }
-  | {
-      label: string;
-      ts: number;
-      kind: "fetch";
-      url: string;
-      method: string;
-      status: number;
-      duration: number;
-      preview: string | null;
-    }
-  | {
-      label: string;
-      ts: number;
-      kind: "fetch-error";
-      url: string;
-      method: string;
-      error: string;
-      duration: number;
-    }
-  | {
-      label: string;
-      ts: number;
-      kind: "xhr";
-      url: string;
-      method: string;
-      status: number;
-      duration: number;
-    }
-  | {
-      label: string;
-      ts: number;
-      kind: "console";
-      level: "log" | "info" | "warn" | "error";
-      message: string;
-    }
-  | {
-      label: string;
-      ts: number;
-      kind: "error";
-      message: string;
-      source: string | null;
-      lineno: number | null;
-      colno: number | null;
-    }
-  | { label: string; ts: number; kind: "unhandledrejection"; reason: string }
-  | {
-      label: string;
-      ts: number;
-      kind: "paint";
-      name: string;
-      startTime: number;
-    }
-  | {
-      label: string;
-      ts: number;
-      kind: "lcp";
-      startTime: number;
-      size: number;
-      url: string | null;
-    }
-  | {
-      label: string;
-      ts: number;
-      kind: "longtask";
-      startTime: number;
-      duration: number;
-    }
-  | {
-      label: string;
-      ts: number;
-      kind: "navigation";
-      domContentLoaded: number;
-      loadEventEnd: number;
-      type: string;
-    };
+// Events are validated in Rust before they reach us; the types are generated
+// from src-tauri/src/telemetry/event.rs (`npm run bindings`).
 
 type PanelState = {
   pageTitle: string;
   pageUrl: string;
-  metas: Record<string, string>;
+  metas: PageInfo["metas"];
   selection: string | null;
   requests: Array<{
     id: string;
@@ -126,7 +25,7 @@ type PanelState = {
   }>;
   logs: Array<{
     id: string;
-    level: "log" | "info" | "warn" | "error";
+    level: ConsoleLevel;
     message: string;
   }>;
   errors: Array<{ id: string; message: string }>; // includes unhandled rejections
@@ -146,7 +45,55 @@ function initialPanelState(): PanelState {
   };
 }
 
-function reduceEvent(cur: PanelState, payload: TelemetryEvent): PanelState {
+// Seed a tab's panel from what Rust aggregated before the panel was listening.
+async function loadPanelState(label: string): Promise<PanelState> {
+  const [metrics, logs] = await Promise.all([
+    invoke<TabMetrics>("get_tab_metrics", { label }),
+    invoke<TabLogs>("get_tab_logs", { label, filter: null }),
+  ]);
+  const requests = [
+    ...logs.resources.map((r) => ({
+      ts: r.ts,
+      item: {
+        id: `resource-${r.ts}`,
+        type: r.type,
+        url: r.name,
+        duration: r.duration,
+      },
+    })),
+    ...logs.network.map((n) => ({
+      ts: n.ts,
+      item: {
+        id: `${n.kind}-${n.ts}`,
+        type: n.error ? "fetch-error" : n.kind,
+        url: n.url,
+        status: n.status,
+        duration: n.duration,
+        method: n.method,
+      },
+    })),
+  ]
+    .sort((a, b) => a.ts - b.ts)
+    .slice(-MAX)
+    .map((r) => r.item);
+  return {
+    ...initialPanelState(),
+    pageTitle: metrics.title ?? "",
+    pageUrl: metrics.url ?? "",
+    requests,
+    logs: logs.console
+      .map((l) => ({ id: `console-${l.ts}`, level: l.level, message: l.message }))
+      .slice(-MAX),
+    errors: logs.errors
+      .map((e) => ({
+        id: `error-${e.ts}`,
+        message: `${e.message}${e.source ? ` @ ${e.source}` : ""}`,
+      }))
+      .slice(-MAX),
+  };
+}
+
+function reduceEvent(cur: PanelState, payload: TelemetryMessage): PanelState {
   const next: PanelState = { ...cur };
   const id = `${payload.kind}-${payload.ts}`;
   switch (payload.kind) {
@@ -260,7 +207,7 @@ export function AssistantPanel({
   const [byLabel, setByLabel] = useState<Record<string, PanelState>>({});
 
   useEffect(() => {
-    const unlisten = listen<TelemetryEvent>("webview-telemetry", (event) => {
+    const unlisten = listen<TelemetryMessage>("webview-telemetry", (event) => {
       const payload = event.payload;
       const label = payload.label;
       setByLabel((prev) => ({
@@ -274,6 +221,30 @@ export function AssistantPanel({
     };
   }, []);
 
+  useEffect(() => {
+    if (!activeWebviewLabel) {
+      return;
+    }
+    let cancelled = false;
+    loadPanelState(activeWebviewLabel)
+      .then((seed) => {
+        if (cancelled) {
+          return;
+        }
+        setByLabel((prev) =>
+          prev[activeWebviewLabel]
+            ? prev
+            : { ...prev, [activeWebviewLabel]: seed }
+        );
+      })
+      .catch((error) => {
+        console.error("[Frontend] Failed to load tab telemetry:", error);
+      });
+    return () => {
+      cancelled = true;
+    };
+  }, [activeWebviewLabel]);
+
   const state = useMemo<PanelState | null>(() => {
     if (!activeWebviewLabel) {
       return null;
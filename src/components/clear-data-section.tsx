@@ -0,0 +1,123 @@
+import { invoke } from "@tauri-apps/api/core";
+import { useState } from "react";
+import type { ClearedData } from "@/bindings/ClearedData";
+import type { DataCategory } from "@/bindings/DataCategory";
+import { Button } from "@/components/ui/button";
+import { Checkbox } from "@/components/ui/checkbox";
+import { Label } from "@/components/ui/label";
+import {
+  Select,
+  SelectContent,
+  SelectItem,
+  SelectTrigger,
+  SelectValue,
+} from "@/components/ui/select";
+import { errorMessage } from "@/lib/utils";
+
+const HOUR = 60 * 60 * 1000;
+
+const RANGES: { value: string; text: string; ms: number | null }[] = [
+  { value: "hour", text: "Last hour", ms: HOUR },
+  { value: "day", text: "Last 24 hours", ms: 24 * HOUR },
+  { value: "week", text: "Last 7 days", ms: 7 * 24 * HOUR },
+  { value: "month", text: "Last 4 weeks", ms: 28 * 24 * HOUR },
+  { value: "all", text: "All time", ms: null },
+];
+
+const CATEGORIES: { category: DataCategory; text: string }[] = [
+  { category: "history", text: "Browsing history" },
+  { category: "cookies", text: "Cookies" },
+  { category: "storage", text: "Site storage" },
+  { category: "cache", text: "Cached files" },
+];
+
+function summarize(cleared: ClearedData): string {
+  const parts = [
+    `${cleared.historyVisits} visits`,
+    `${cleared.cookies} cookies`,
+    cleared.wholeStore ? "site data" : `${cleared.origins} sites`,
+  ];
+  const failed = cleared.failed.length
+    ? ` · ${cleared.failed.length} sites couldn't be cleared`
+    : "";
+  return `Cleared ${parts.join(", ")}${failed}`;
+}
+
+export function ClearDataSection() {
+  const [range, setRange] = useState("hour");
+  const [categories, setCategories] = useState<DataCategory[]>([
+    "history",
+    "cookies",
+    "storage",
+    "cache",
+  ]);
+  const [busy, setBusy] = useState(false);
+  const [result, setResult] = useState<string | null>(null);
+
+  const toggle = (category: DataCategory, on: boolean) =>
+    setCategories((current) =>
+      on
+        ? [...current.filter((c) => c !== category), category]
+        : current.filter((c) => c !== category)
+    );
+
+  const clear = async () => {
+    const ms = RANGES.find((r) => r.value === range)?.ms ?? null;
+    setBusy(true);
+    setResult(null);
+    try {
+      const cleared = await invoke<ClearedData>("clear_browsing_data", {
+        range: { start: ms === null ? null : Date.now() - ms, end: null },
+        categories,
+      });
+      setResult(summarize(cleared));
+    } catch (error) {
+      console.error("[UI] Could not clear browsing data:", error);
+      setResult(errorMessage(error));
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  return (
+    <div className="space-y-1">
+      <Label htmlFor="clear-range">Clear browsing data</Label>
+      <Select onValueChange={setRange} value={range}>
+        <SelectTrigger aria-label="Time range" id="clear-range" type="button">
+          <SelectValue />
+        </SelectTrigger>
+        <SelectContent>
+          {RANGES.map((r) => (
+            <SelectItem key={r.value} value={r.value}>
+              {r.text}
+            </SelectItem>
+          ))}
+        </SelectContent>
+      </Select>
+      {CATEGORIES.map(({ category, text }) => (
+        <div className="flex items-center gap-2" key={category}>
+          <Checkbox
+            checked={categories.includes(category)}
+            id={`clear-${category}`}
+            onCheckedChange={(checked) => toggle(category, checked === true)}
+          />
+          <Label htmlFor={`clear-${category}`}>{text}</Label>
+        </div>
+      ))}
+      <div className="flex items-center gap-2">
+        <Button
+          disabled={busy || categories.length === 0}
+          onClick={clear}
+          size="sm"
+          type="button"
+          variant="outline"
+        >
+          {busy ? "Clearing…" : "Clear data"}
+        </Button>
+        {result && (
+          <span className="truncate text-muted-foreground text-xs">{result}</span>
+        )}
+      </div>
+    </div>
+  );
+}
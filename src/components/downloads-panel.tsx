@@ -0,0 +1,205 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import { FolderOpen, Pause, Play, RotateCw, X } from "lucide-react";
+import { useEffect, useState } from "react";
+import type { DownloadItem } from "@/bindings/DownloadItem";
+import type { DownloadProgress } from "@/bindings/DownloadProgress";
+import { Button } from "@/components/ui/button";
+import { Progress } from "@/components/ui/progress";
+import {
+  SidebarGroup,
+  SidebarGroupContent,
+  SidebarGroupLabel,
+} from "@/components/ui/sidebar";
+
+const UNITS = ["B", "KB", "MB", "GB", "TB"];
+
+function formatBytes(bytes: number): string {
+  let value = bytes;
+  let unit = 0;
+  while (value >= 1024 && unit < UNITS.length - 1) {
+    value /= 1024;
+    unit += 1;
+  }
+  return `${value.toFixed(unit === 0 ? 0 : 1)} ${UNITS[unit]}`;
+}
+
+function describe(item: DownloadItem, speed: number | undefined): string {
+  switch (item.state) {
+    case "in-progress": {
+      const total = item.totalBytes ? ` of ${formatBytes(item.totalBytes)}` : "";
+      const rate = speed ? ` · ${formatBytes(speed)}/s` : "";
+      return `${formatBytes(item.receivedBytes)}${total}${rate}`;
+    }
+    case "paused":
+      return `Paused · ${formatBytes(item.receivedBytes)}`;
+    case "completed":
+      return formatBytes(item.receivedBytes);
+    case "failed":
+      return item.error ?? "Failed";
+    case "cancelled":
+      return "Cancelled";
+    default:
+      return "";
+  }
+}
+
+// Most recent first; the sidebar only has room for a handful
+const VISIBLE = 8;
+
+export function DownloadsPanel() {
+  const [items, setItems] = useState<DownloadItem[]>([]);
+  const [speeds, setSpeeds] = useState<Record<number, number>>({});
+
+  useEffect(() => {
+    invoke<DownloadItem[]>("list_downloads")
+      .then(setItems)
+      .catch((error) => console.error("[UI] Could not list downloads:", error));
+
+    const upsert = (item: DownloadItem) =>
+      setItems((current) => {
+        const index = current.findIndex((i) => i.id === item.id);
+        if (index === -1) {
+          return [...current, item];
+        }
+        const next = [...current];
+        next[index] = item;
+        return next;
+      });
+
+    const unlisteners = [
+      listen<DownloadItem>("download-started", (event) => upsert(event.payload)),
+      listen<DownloadItem>("download-changed", (event) => upsert(event.payload)),
+      listen<DownloadProgress>("download-progress", (event) => {
+        const { id, receivedBytes, totalBytes, bytesPerSecond } = event.payload;
+        setItems((current) =>
+          current.map((i) =>
+            i.id === id ? { ...i, receivedBytes, totalBytes } : i
+          )
+        );
+        setSpeeds((current) => ({ ...current, [id]: bytesPerSecond }));
+      }),
+      listen<{ id: number }>("download-removed", (event) =>
+        setItems((current) => current.filter((i) => i.id !== event.payload.id))
+      ),
+    ];
+    return () => {
+      for (const unlisten of unlisteners) {
+        unlisten.then((fn) => fn());
+      }
+    };
+  }, []);
+
+  const run = (command: string, id: number) => {
+    invoke(command, { id }).catch((error) =>
+      console.error(`[UI] ${command} failed:`, error)
+    );
+  };
+
+  if (items.length === 0) {
+    return null;
+  }
+
+  return (
+    <SidebarGroup>
+      <SidebarGroupLabel>Downloads</SidebarGroupLabel>
+      <SidebarGroupContent className="space-y-2 px-2">
+        {items
+          .slice(-VISIBLE)
+          .reverse()
+          .map((item) => (
+            <div className="space-y-1" key={item.id}>
+              <div className="flex items-center gap-1">
+                <button
+                  className="min-w-0 flex-1 truncate text-left text-sm disabled:cursor-default"
+                  disabled={item.state !== "completed"}
+                  onClick={() => run("open_download", item.id)}
+                  title={item.path}
+                  type="button"
+                >
+                  {item.fileName}
+                </button>
+                {item.state === "in-progress" && !item.native && (
+                  <Button
+                    onClick={() => run("pause_download", item.id)}
+                    size="icon"
+                    title="Pause"
+                    type="button"
+                    variant="ghost"
+                  >
+                    <Pause className="h-3 w-3" />
+                  </Button>
+                )}
+                {item.state === "paused" && (
+                  <Button
+                    onClick={() => run("resume_download", item.id)}
+                    size="icon"
+                    title="Resume"
+                    type="button"
+                    variant="ghost"
+                  >
+                    <Play className="h-3 w-3" />
+                  </Button>
+                )}
+                {(item.state === "failed" || item.state === "cancelled") &&
+                  !item.native && (
+                    <Button
+                      onClick={() => run("retry_download", item.id)}
+                      size="icon"
+                      title="Retry"
+                      type="button"
+                      variant="ghost"
+                    >
+                      <RotateCw className="h-3 w-3" />
+                    </Button>
+                  )}
+                {item.state === "completed" && (
+                  <Button
+                    onClick={() => run("reveal_download", item.id)}
+                    size="icon"
+                    title="Show in folder"
+                    type="button"
+                    variant="ghost"
+                  >
+                    <FolderOpen className="h-3 w-3" />
+                  </Button>
+                )}
+                {(item.state === "in-progress" || item.state === "paused") &&
+                !item.native ? (
+                  <Button
+                    onClick={() => run("cancel_download", item.id)}
+                    size="icon"
+                    title="Cancel"
+                    type="button"
+                    variant="ghost"
+                  >
+                    <X className="h-3 w-3" />
+                  </Button>
+                ) : (
+                  item.state !== "in-progress" && (
+                    <Button
+                      onClick={() => run("remove_download", item.id)}
+                      size="icon"
+                      title="Remove from list"
+                      type="button"
+                      variant="ghost"
+                    >
+                      <X className="h-3 w-3" />
+                    </Button>
+                  )
+                )}
+              </div>
+              {item.state === "in-progress" && item.totalBytes ? (
+                <Progress
+                  value={(item.receivedBytes / item.totalBytes) * 100}
+                />
+              ) : null}
+              <div className="truncate text-muted-foreground text-xs">
+                {describe(item, speeds[item.id])}
+              </div>
+            </div>
+          ))}
+      </SidebarGroupContent>
+    </SidebarGroup>
+  );
+}
@@ -0,0 +1,164 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import { AppWindow, Check, Trash2 } from "lucide-react";
+import { useEffect, useState } from "react";
+import type { Profile } from "@/bindings/Profile";
+import type { ProfileList } from "@/bindings/ProfileList";
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+import { Label } from "@/components/ui/label";
+
+const DEFAULT_PROFILE = "default";
+
+export function useProfiles() {
+  const [list, setList] = useState<ProfileList | null>(null);
+
+  useEffect(() => {
+    invoke<ProfileList>("list_profiles")
+      .then(setList)
+      .catch((error) => console.error("[UI] Could not list profiles:", error));
+    const unlisten = listen<ProfileList>("profiles-changed", (event) =>
+      setList(event.payload)
+    );
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  const current = list?.profiles.find((p) => p.id === list.current) ?? null;
+  return { list, current };
+}
+
+function ProfileRow({
+  profile,
+  current,
+}: {
+  profile: Profile;
+  current: boolean;
+}) {
+  const [name, setName] = useState(profile.name);
+
+  useEffect(() => setName(profile.name), [profile.name]);
+
+  const run = async (command: string, args: Record<string, unknown>) => {
+    try {
+      await invoke(command, { id: profile.id, ...args });
+    } catch (error) {
+      console.error(`[UI] ${command} failed:`, error);
+      setName(profile.name);
+    }
+  };
+
+  return (
+    <div className="flex items-center gap-1">
+      <Input
+        aria-label="Profile name"
+        className="h-8 flex-1"
+        onBlur={() => {
+          if (name.trim() && name.trim() !== profile.name) {
+            run("rename_profile", { name });
+          } else {
+            setName(profile.name);
+          }
+        }}
+        onChange={(e) => setName(e.target.value)}
+        value={name}
+      />
+      {current ? (
+        <Check
+          aria-label="Current profile"
+          className="mx-2 h-4 w-4 text-muted-foreground"
+        />
+      ) : (
+        <>
+          <Button
+            onClick={() => run("switch_profile", {})}
+            size="sm"
+            type="button"
+            variant="ghost"
+          >
+            Switch
+          </Button>
+          <Button
+            onClick={() => run("open_profile_window", {})}
+            size="icon"
+            title="Open in new window"
+            type="button"
+            variant="ghost"
+          >
+            <AppWindow className="h-4 w-4" />
+          </Button>
+        </>
+      )}
+      <Button
+        disabled={current || profile.id === DEFAULT_PROFILE}
+        onClick={() => {
+          if (
+            window.confirm(
+              `Delete the "${profile.name}" profile with its history, bookmarks and site data?`
+            )
+          ) {
+            run("delete_profile", {});
+          }
+        }}
+        size="icon"
+        title="Delete profile"
+        type="button"
+        variant="ghost"
+      >
+        <Trash2 className="h-4 w-4" />
+      </Button>
+    </div>
+  );
+}
+
+export function ProfilesSection() {
+  const { list } = useProfiles();
+  const [newName, setNewName] = useState("");
+
+  const create = async () => {
+    if (!newName.trim()) {
+      return;
+    }
+    try {
+      await invoke<Profile>("create_profile", { name: newName });
+      setNewName("");
+    } catch (error) {
+      console.error("[UI] Could not create profile:", error);
+    }
+  };
+
+  if (!list) {
+    return null;
+  }
+
+  return (
+    <div className="space-y-1">
+      <Label htmlFor="new-profile">Profiles</Label>
+      {list.profiles.map((profile) => (
+        <ProfileRow
+          current={profile.id === list.current}
+          key={profile.id}
+          profile={profile}
+        />
+      ))}
+      <div className="flex items-center gap-1">
+        <Input
+          className="h-8 flex-1"
+          id="new-profile"
+          onChange={(e) => setNewName(e.target.value)}
+          onKeyDown={(e) => {
+            if (e.key === "Enter") {
+              create();
+            }
+          }}
+          placeholder="New profile name"
+          value={newName}
+        />
+        <Button onClick={create} size="sm" type="button" variant="outline">
+          Add
+        </Button>
+      </div>
+    </div>
+  );
+}
@@ -1,5 +1,10 @@
+import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import { useEffect, useState } from "react";
+import type { DownloadSettings } from "@/bindings/DownloadSettings";
+import { ClearDataSection } from "@/components/clear-data-section";
+import { ProfilesSection } from "@/components/profiles-section";
+import { Checkbox } from "@/components/ui/checkbox";
 import {
   Dialog,
   DialogContent,
@@ -7,6 +12,7 @@ import {
   DialogHeader,
   DialogTitle,
 } from "@/components/ui/dialog";
+import { Input } from "@/components/ui/input";
 import { Label } from "@/components/ui/label";
 import {
   Select,
@@ -21,15 +27,41 @@ import { applyTheme, getCurrentTheme } from "@/lib/theme";
 export function SettingsDialog() {
   const [open, setOpen] = useState(false);
   const [theme, setTheme] = useState<ThemeChoice>("system");
+  const [downloads, setDownloads] = useState<DownloadSettings>({
+    directory: null,
+    askWhereToSave: false,
+  });
+  const [folder, setFolder] = useState("");
 
-  // Keep local state in sync with stored theme when dialog opens
+  // Keep local state in sync with stored settings when dialog opens
   useEffect(() => {
     if (open) {
       const current = getCurrentTheme();
       setTheme(current);
+      invoke<DownloadSettings>("get_download_settings")
+        .then((settings) => {
+          setDownloads(settings);
+          setFolder(settings.directory ?? "");
+        })
+        .catch((error) =>
+          console.error("[UI] Could not load download settings:", error)
+        );
     }
   }, [open]);
 
+  const saveDownloads = async (next: DownloadSettings) => {
+    try {
+      setDownloads(
+        await invoke<DownloadSettings>("set_download_settings", {
+          settings: next,
+        })
+      );
+    } catch (error) {
+      console.error("[UI] Could not save download settings:", error);
+      setFolder(downloads.directory ?? "");
+    }
+  };
+
   // Open when backend emits "open-settings"
   useEffect(() => {
     const unlisten = listen("open-settings", () => setOpen(true));
@@ -76,6 +108,36 @@ export function SettingsDialog() {
               </SelectContent>
             </Select>
           </div>
+
+          <div className="space-y-1">
+            <Label htmlFor="download-folder">Download folder</Label>
+            <Input
+              id="download-folder"
+              onBlur={() => {
+                const directory = folder.trim() || null;
+                if (directory !== downloads.directory) {
+                  saveDownloads({ ...downloads, directory });
+                }
+              }}
+              onChange={(e) => setFolder(e.target.value)}
+              placeholder="System Downloads folder"
+              value={folder}
+            />
+          </div>
+          <div className="flex items-center gap-2">
+            <Checkbox
+              checked={downloads.askWhereToSave}
+              id="download-ask"
+              onCheckedChange={(checked) =>
+                saveDownloads({ ...downloads, askWhereToSave: checked === true })
+              }
+            />
+            <Label htmlFor="download-ask">Ask where to save each download</Label>
+          </div>
+
+          <ProfilesSection />
+
+          <ClearDataSection />
         </div>
       </DialogContent>
     </Dialog>
@@ -0,0 +1,235 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import { Info } from "lucide-react";
+import { useCallback, useEffect, useState } from "react";
+import type { Permission } from "@/bindings/Permission";
+import type { PermissionPrompt } from "@/bindings/PermissionPrompt";
+import type { PermissionState } from "@/bindings/PermissionState";
+import type { SitePermissions } from "@/bindings/SitePermissions";
+import { Button } from "@/components/ui/button";
+import { Checkbox } from "@/components/ui/checkbox";
+import { Label } from "@/components/ui/label";
+import {
+  Popover,
+  PopoverContent,
+  PopoverTrigger,
+} from "@/components/ui/popover";
+
+const PERMISSION_NAMES: Record<Permission, string> = {
+  camera: "Camera",
+  microphone: "Microphone",
+  geolocation: "Location",
+  notifications: "Notifications",
+  "clipboard-read": "Clipboard",
+  popups: "Pop-ups",
+};
+
+const STATES: { state: PermissionState; text: string }[] = [
+  { state: "allow", text: "Allow" },
+  { state: "ask", text: "Ask" },
+  { state: "deny", text: "Block" },
+];
+
+interface SiteInfoPopoverProps {
+  label: string | null;
+  url: string;
+}
+
+export function SiteInfoPopover({ label, url }: SiteInfoPopoverProps) {
+  const [open, setOpen] = useState(false);
+  const [site, setSite] = useState<SitePermissions | null>(null);
+  const [prompts, setPrompts] = useState<PermissionPrompt[]>([]);
+  const [remember, setRemember] = useState(true);
+
+  useEffect(() => {
+    setSite(null);
+    if (!url) {
+      return;
+    }
+    // Fails for pages without an origin (about:blank, data:), which have no permissions
+    invoke<SitePermissions>("get_site_permissions", { url })
+      .then(setSite)
+      .catch(() => setSite(null));
+  }, [url]);
+
+  useEffect(() => {
+    const requested = listen<PermissionPrompt>(
+      "site-permission-requested",
+      (event) => {
+        setPrompts((current) => [...current, event.payload]);
+        if (event.payload.label === label) {
+          setOpen(true);
+        }
+      }
+    );
+    const resolved = listen<{ requestId: number }>(
+      "site-permission-resolved",
+      (event) => {
+        setPrompts((current) =>
+          current.filter((p) => p.requestId !== event.payload.requestId)
+        );
+      }
+    );
+    return () => {
+      requested.then((fn) => fn());
+      resolved.then((fn) => fn());
+    };
+  }, [label]);
+
+  // Native webviews draw above the page, so the active one is hidden while
+  // the popover is open.
+  useEffect(() => {
+    if (!(open && label)) {
+      return;
+    }
+    invoke("hide_webview", { label }).catch(() => {});
+    return () => {
+      invoke("show_webview", { label }).catch(() => {});
+    };
+  }, [open, label]);
+
+  const setPermission = useCallback(
+    async (permission: Permission, state: PermissionState) => {
+      try {
+        setSite(
+          await invoke<SitePermissions>("set_site_permission", {
+            url,
+            permission,
+            state,
+          })
+        );
+      } catch (error) {
+        console.error("[UI] Could not change site permission:", error);
+      }
+    },
+    [url]
+  );
+
+  const answer = async (prompt: PermissionPrompt, allow: boolean) => {
+    try {
+      await invoke("resolve_permission_request", {
+        requestId: prompt.requestId,
+        allow,
+        remember,
+      });
+      if (remember) {
+        setSite(
+          await invoke<SitePermissions>("get_site_permissions", { url })
+        );
+      }
+    } catch (error) {
+      console.error("[UI] Could not answer permission request:", error);
+    }
+  };
+
+  // Cookies, storage and permissions for the origin; the page reloads so it
+  // doesn't keep running on what it had in memory.
+  const forgetSite = async () => {
+    try {
+      await invoke("clear_site_data", { url });
+      setSite(await invoke<SitePermissions>("get_site_permissions", { url }));
+      if (label) {
+        await invoke("refresh_webview", { label });
+      }
+      setOpen(false);
+    } catch (error) {
+      console.error("[UI] Could not forget site:", error);
+    }
+  };
+
+  const tabPrompts = prompts.filter((p) => p.label === label);
+
+  return (
+    <Popover onOpenChange={setOpen} open={open}>
+      <PopoverTrigger asChild>
+        <Button
+          className="no-drag relative"
+          disabled={!site}
+          size="icon"
+          title="Site information"
+          type="button"
+          variant="ghost"
+        >
+          <Info className="h-4 w-4" />
+          {tabPrompts.length > 0 && (
+            <span className="absolute top-1 right-1 h-2 w-2 rounded-full bg-primary" />
+          )}
+        </Button>
+      </PopoverTrigger>
+      <PopoverContent align="start" className="w-80 space-y-3">
+        {site && <div className="truncate font-medium text-sm">{site.origin}</div>}
+
+        {tabPrompts.map((prompt) => (
+          <div className="space-y-2 rounded-md border p-2" key={prompt.requestId}>
+            <div className="text-sm">
+              This site wants to use your{" "}
+              {PERMISSION_NAMES[prompt.permission].toLowerCase()}.
+            </div>
+            <div className="flex gap-2">
+              <Button onClick={() => answer(prompt, true)} size="sm" type="button">
+                Allow
+              </Button>
+              <Button
+                onClick={() => answer(prompt, false)}
+                size="sm"
+                type="button"
+                variant="outline"
+              >
+                Block
+              </Button>
+            </div>
+          </div>
+        ))}
+        {tabPrompts.length > 0 && (
+          <div className="flex items-center gap-2">
+            <Checkbox
+              checked={remember}
+              id="remember-permission"
+              onCheckedChange={(checked) => setRemember(checked === true)}
+            />
+            <Label htmlFor="remember-permission">Remember for this site</Label>
+          </div>
+        )}
+
+        {site && (
+          <div className="space-y-1">
+            {(Object.keys(PERMISSION_NAMES) as Permission[]).map((permission) => (
+              <div className="flex items-center justify-between" key={permission}>
+                <span className="text-sm">{PERMISSION_NAMES[permission]}</span>
+                <div className="flex gap-1">
+                  {STATES.map(({ state, text }) => (
+                    <Button
+                      key={state}
+                      onClick={() => setPermission(permission, state)}
+                      size="sm"
+                      type="button"
+                      variant={
+                        (site.permissions[permission] ?? "ask") === state
+                          ? "secondary"
+                          : "ghost"
+                      }
+                    >
+                      {text}
+                    </Button>
+                  ))}
+                </div>
+              </div>
+            ))}
+          </div>
+        )}
+
+        {site && (
+          <Button
+            className="w-full"
+            onClick={forgetSite}
+            size="sm"
+            type="button"
+            variant="outline"
+          >
+            Forget this site
+          </Button>
+        )}
+      </PopoverContent>
+    </Popover>
+  );
+}
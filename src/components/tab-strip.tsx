@@ -1,4 +1,4 @@
-import { Plus, X } from "lucide-react";
+import { Loader2, Plus, VenetianMask, X } from "lucide-react";
 import { useEffect, useState } from "react";
 import { Button } from "@/components/ui/button";
 import type { Tab } from "../types";
@@ -92,6 +92,7 @@ interface TabStripProps {
   onTabClick: (tabId: string) => void;
   onTabClose: (tabId: string) => void;
   onNewTab: () => void;
+  onNewPrivateTab: () => void;
 }
 
 export function TabStrip({
@@ -100,6 +101,7 @@ export function TabStrip({
   onTabClick,
   onTabClose,
   onNewTab,
+  onNewPrivateTab,
 }: TabStripProps) {
   return (
     <div className="flex shrink-0 items-center overflow-x-auto border-b px-2 py-1">
@@ -121,7 +123,20 @@ export function TabStrip({
             role="button"
             tabIndex={0}
           >
-            <Favicon title={tab.title} url={tab.url} />
+            {tab.private && (
+              <VenetianMask
+                aria-label="Private tab"
+                className="mr-2 h-4 w-4 shrink-0 text-muted-foreground"
+              />
+            )}
+            {tab.loading ? (
+              <Loader2
+                aria-label="Loading"
+                className="mr-2 h-4 w-4 shrink-0 animate-spin text-muted-foreground"
+              />
+            ) : (
+              <Favicon title={tab.title} url={tab.url} />
+            )}
             <span className="flex-1 truncate text-sm">
               {tab.title || "New Tab"}
             </span>
@@ -147,6 +162,15 @@ export function TabStrip({
         >
           <Plus className="h-4 w-4" />
         </Button>
+        <Button
+          onClick={() => onNewPrivateTab()}
+          size="icon"
+          title="New private tab"
+          type="button"
+          variant="ghost"
+        >
+          <VenetianMask className="h-4 w-4" />
+        </Button>
       </div>
     </div>
   );
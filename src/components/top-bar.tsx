@@ -1,29 +1,37 @@
+import { invoke } from "@tauri-apps/api/core";
 import { ArrowLeft, ArrowRight, RotateCw } from "lucide-react";
 import type React from "react";
 import { useEffect, useState } from "react";
 import { Button } from "@/components/ui/button";
 import { Input } from "@/components/ui/input";
 import { useIsMac } from "../hooks/use-is-mac";
+import { SiteInfoPopover } from "./site-info-popover";
+import type { OmniboxResolution } from "../types";
 
 interface TopBarProps {
   currentUrl: string;
+  activeLabel?: string | null;
   onNavigate: (url: string) => void;
   onBack: () => void;
   onForward: () => void;
   onReload: () => void;
   canGoBack?: boolean;
   canGoForward?: boolean;
+  // Set while the active tab is loading
+  loadProgress?: number | null;
   onMakeDefaultBrowser?: () => void | Promise<void>;
 }
 
 export function TopBar({
   currentUrl,
+  activeLabel = null,
   onNavigate,
   onBack,
   onForward,
   onReload,
   canGoBack = false,
   canGoForward = false,
+  loadProgress = null,
   onMakeDefaultBrowser,
 }: TopBarProps) {
   const [urlInput, setUrlInput] = useState(currentUrl);
@@ -33,13 +41,22 @@ export function TopBar({
     setUrlInput(currentUrl);
   }, [currentUrl]);
 
-  const handleSubmit = (e: React.FormEvent) => {
+  const handleSubmit = async (e: React.FormEvent) => {
     e.preventDefault();
-    let url = urlInput.trim();
-    if (!(url.startsWith("http://") || url.startsWith("https://"))) {
-      url = `https://${url}`;
+    const input = urlInput.trim();
+    if (!input) {
+      return;
+    }
+    try {
+      // URL vs. bare host vs. search query is decided in Rust (omnibox.rs)
+      const resolved = await invoke<OmniboxResolution>(
+        "resolve_omnibox_input",
+        { input }
+      );
+      onNavigate(resolved.url);
+    } catch (error) {
+      console.error("[UI] Omnibox resolution failed:", error);
     }
-    onNavigate(url);
   };
 
   // Inline layout with macOS traffic lights on the left, but avoid
@@ -87,6 +104,8 @@ export function TopBar({
         <RotateCw className="h-4 w-4" />
       </Button>
 
+      <SiteInfoPopover label={activeLabel} url={currentUrl} />
+
       <form className="no-drag flex-1" onSubmit={handleSubmit}>
         <Input
           className="no-drag bg-background/50"
@@ -119,6 +138,18 @@ export function TopBar({
 
       {/* Right drag spacer for grabbing the window when not interacting */}
       <div aria-hidden className="drag h-8 w-20" data-tauri-drag-region />
+
+      {loadProgress !== null && (
+        <div
+          aria-label="Loading"
+          aria-valuemax={100}
+          aria-valuemin={0}
+          aria-valuenow={Math.round(loadProgress * 100)}
+          className="absolute bottom-0 left-0 h-0.5 bg-primary transition-[width] duration-300"
+          role="progressbar"
+          style={{ width: `${loadProgress * 100}%` }}
+        />
+      )}
     </div>
   );
 }
@@ -35,6 +35,7 @@ export function WebviewContainer({
             )}
             key={tab.id}
             label={tab.webviewLabel}
+            private={tab.private}
             url={tab.url}
             visible={tab.id === activeTabId}
           />
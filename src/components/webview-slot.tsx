@@ -1,6 +1,6 @@
 import { invoke } from "@tauri-apps/api/core";
 import { useEffect, useRef } from "react";
-import { cn } from "@/lib/utils";
+import { cn, hasErrorCode } from "@/lib/utils";
 
 function measureContentBox(el: HTMLElement) {
   const rect = el.getBoundingClientRect();
@@ -34,10 +34,25 @@ function measureContentBox(el: HTMLElement) {
   };
 }
 
+// A `notFound` means the native webview went away underneath the slot; stop
+// sending it commands instead of failing on every resize.
+function handleSlotError(
+  error: unknown,
+  label: string,
+  created: { current: boolean }
+) {
+  if (hasErrorCode(error, "notFound")) {
+    created.current = false;
+  }
+  console.error(`[UI] Webview '${label}' command failed:`, error);
+}
+
 type WebviewSlotProps = {
   label: string;
   url: string;
   visible?: boolean;
+  // Only read when the webview is created; a tab can't change its data store
+  private?: boolean;
   className?: string;
   "data-testid"?: string;
 };
@@ -53,6 +68,7 @@ export function WebviewSlot({
   label,
   url,
   visible = true,
+  private: isPrivate = false,
   className,
   ...rest
 }: WebviewSlotProps) {
@@ -65,6 +81,13 @@ export function WebviewSlot({
     height: number;
   } | null>(null);
   const lastVisibleRef = useRef<boolean>(visible);
+  // Navigation happens inside the native webview after creation, so later
+  // `url`/`visible` changes must not tear it down; read them through refs.
+  const urlRef = useRef(url);
+  const visibleRef = useRef(visible);
+  urlRef.current = url;
+  visibleRef.current = visible;
+  const privateRef = useRef(isPrivate);
 
   useEffect(() => {
     const ensureCreated = async () => {
@@ -79,16 +102,17 @@ export function WebviewSlot({
 
       await invoke("create_browser_webview", {
         label,
-        url,
+        url: urlRef.current,
         x,
         y,
         width,
         height,
+        private: privateRef.current,
       });
       createdRef.current = true;
       lastRectRef.current = { x, y, width, height };
 
-      if (visible) {
+      if (visibleRef.current) {
         await invoke("show_webview", { label });
         lastVisibleRef.current = true;
       } else {
@@ -112,7 +136,7 @@ export function WebviewSlot({
       createdRef.current = false;
       lastRectRef.current = null;
     };
-  }, [label, url, visible]);
+  }, [label]);
 
   useEffect(() => {
     if (!ref.current) {
@@ -146,14 +170,18 @@ export function WebviewSlot({
         effectiveHeight !== prev.height;
 
       if (changed) {
-        await invoke("update_webview_bounds", {
-          label,
-          x: rect.x,
-          y: rect.y,
-          width: rect.width,
-          height: effectiveHeight,
-        });
-        lastRectRef.current = { ...rect, height: effectiveHeight };
+        try {
+          await invoke("update_webview_bounds", {
+            label,
+            x: rect.x,
+            y: rect.y,
+            width: rect.width,
+            height: effectiveHeight,
+          });
+          lastRectRef.current = { ...rect, height: effectiveHeight };
+        } catch (error) {
+          handleSlotError(error, label, createdRef);
+        }
       }
     };
 
@@ -191,12 +219,16 @@ export function WebviewSlot({
       if (!createdRef.current) {
         return;
       }
-      if (visible && !lastVisibleRef.current) {
-        await invoke("show_webview", { label });
-        lastVisibleRef.current = true;
-      } else if (!visible && lastVisibleRef.current) {
-        await invoke("hide_webview", { label });
-        lastVisibleRef.current = false;
+      try {
+        if (visible && !lastVisibleRef.current) {
+          await invoke("show_webview", { label });
+          lastVisibleRef.current = true;
+        } else if (!visible && lastVisibleRef.current) {
+          await invoke("hide_webview", { label });
+          lastVisibleRef.current = false;
+        }
+      } catch (error) {
+        handleSlotError(error, label, createdRef);
       }
     };
     flip();
@@ -1,6 +1,24 @@
 import { type ClassValue, clsx } from "clsx";
 import { twMerge } from "tailwind-merge";
+import type { BrsrError } from "@/types";
 
 export function cn(...inputs: ClassValue[]) {
   return twMerge(clsx(inputs));
 }
+
+/** Whether a rejected `invoke` carries a `BrsrError` with `code`. */
+export function hasErrorCode(error: unknown, code: BrsrError["code"]) {
+  return (
+    typeof error === "object" &&
+    error !== null &&
+    (error as BrsrError).code === code
+  );
+}
+
+/** Readable text for anything a command rejected with. */
+export function errorMessage(error: unknown) {
+  if (typeof error === "object" && error !== null && "message" in error) {
+    return String((error as BrsrError).message);
+  }
+  return String(error);
+}
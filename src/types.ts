@@ -4,11 +4,64 @@ export interface Tab {
   url: string;
   active: boolean;
   webviewLabel: string;
-  history?: string[]; // Track navigation history
-  historyIndex?: number; // Current position in history
+  pinned?: boolean;
+  private?: boolean; // Own ephemeral data store; never saved to history or the session
+  loading?: boolean; // A page load is in flight (webview-load-* events)
+  loadProgress?: number; // 0 to 1 while loading
 }
 
 export interface BrowserState {
   tabs: Tab[];
   activeTabId: string | null;
 }
+
+// Mirrors `TabState` in src-tauri/src/tabs.rs (the Rust tab registry)
+export interface NativeTabState {
+  label: string;
+  url: string;
+  title: string;
+  visible: boolean;
+  bounds: { x: number; y: number; width: number; height: number };
+  createdAt: number;
+  lastActiveAt: number;
+  pinned: boolean;
+  private: boolean;
+  scroll: { x: number; y: number };
+}
+
+// Mirrors `OmniboxResolution` in src-tauri/src/omnibox.rs
+export interface OmniboxResolution {
+  kind:
+    | "url"
+    | "host"
+    | "localhost"
+    | "ipAddress"
+    | "filePath"
+    | "search"
+    | "keyword";
+  url: string;
+  query: string | null;
+  engine: string | null;
+}
+
+// Mirrors `BrsrError` in src-tauri/src/error.rs: what every command rejects with.
+// Branch on `code`; `message` is for display. The other fields depend on the code.
+export interface BrsrError {
+  code:
+    | "notFound"
+    | "invalidUrl"
+    | "invalidInput"
+    | "conflict"
+    | "policy"
+    | "unsupported"
+    | "platform"
+    | "io"
+    | "network";
+  message: string;
+  kind?: string;
+  id?: string;
+  url?: string | null;
+  reason?: string;
+  feature?: string;
+  path?: string | null;
+}